@@ -2,7 +2,7 @@
 
 use anyrender::{PaintScene as _, render_to_buffer};
 use anyrender_vello_cpu::VelloCpuImageRenderer;
-use blitz_dom::{DocumentConfig, util::Color};
+use blitz_dom::{DocumentConfig, ImageDecoding, util::Color};
 use blitz_html::HtmlDocument;
 use blitz_net::Provider;
 use blitz_paint::paint_scene;
@@ -79,6 +79,9 @@ async fn main() {
                 scale as f32,
                 ColorScheme::Light,
             )),
+            // Decode images as they load, so that they have all been decoded by the time
+            // the network is idle
+            image_decoding: ImageDecoding::Inline,
             ..Default::default()
         },
     );
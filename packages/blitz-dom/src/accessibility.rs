@@ -1,44 +1,733 @@
-use crate::{BaseDocument, ElementData, Node as BlitzDomNode, local_name};
-use accesskit::{Node as AccessKitNode, NodeId, Role, Tree, TreeId, TreeUpdate};
+#[cfg(feature = "custom-widget")]
+use crate::WidgetAccessibilityTree;
+use crate::node::NodeData;
+use crate::select::select_is_drop_down;
+use crate::{BaseDocument, ElementData, Node as BlitzDomNode, NodeId as DomNodeId, local_name};
+use accesskit::{
+    Action, ActionData, ActionRequest, Affine, Node as AccessKitNode, NodeId, Rect, Role, Toggled,
+    Tree, TreeId, TreeUpdate,
+};
+use blitz_traits::events::{AccessibilityAction, BlitzAccessibilityActionEvent, UiEvent};
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+use style::values::specified::box_::{DisplayInside, DisplayOutside};
 
-impl BaseDocument {
-    pub fn build_accessibility_tree(&self) -> TreeUpdate {
-        let mut nodes = std::collections::HashMap::new();
-        let mut window = AccessKitNode::new(Role::Window);
+/// The id of the window node at the root of the tree
+const WINDOW_NODE_ID: NodeId = NodeId(u64::MAX);
 
-        self.visit(|node_id, node| {
-            let parent = node
-                .parent
-                .and_then(|parent_id| nodes.get_mut(&parent_id))
-                .map(|(_, parent)| parent)
-                .unwrap_or(&mut window);
-            let (id, builder) = self.build_accessibility_node(node, parent);
-
-            nodes.insert(node_id, (id, builder));
+/// The nodes of sub-documents and custom widgets are given ids from this value upwards, so
+/// that they don't clash with the ids of the document's own nodes
+const FIRST_EMBEDDED_NODE_ID: u64 = 1 << 63;
+
+/// The accessibility tree as last sent to assistive technology, which allows subsequent
+/// [`TreeUpdate`]s to contain only the nodes that have changed
+#[derive(Default)]
+pub(crate) struct AccessibilityCache {
+    /// Whether the whole tree has been built (if not, the next update contains the whole tree)
+    initialized: bool,
+    /// The last version sent of each of the document's nodes
+    nodes: HashMap<NodeId, AccessKitNode>,
+    /// Whether layout, scrolling or the top layer has changed since the last update (which may
+    /// have moved nodes, or made them inert)
+    pub(crate) layout_dirty: bool,
+    /// Whether focus has moved since the last update
+    pub(crate) focus_dirty: bool,
+    /// The nodes that have changed since the last update (see
+    /// [`BaseDocument::mark_node_changed`])
+    pub(crate) dirty_nodes: HashSet<DomNodeId>,
+    /// The ids given to the nodes of sub-documents and custom widgets, keyed by the node
+    /// containing the sub-document or widget and the id of the node in its own tree
+    embedded_ids: HashMap<(DomNodeId, NodeId), NodeId>,
+    /// The inverse of `embedded_ids` (plus the roots of custom widgets' subtrees, which use the
+    /// ids of the widgets' nodes), for forwarding action requests
+    embedded_targets: HashMap<NodeId, (DomNodeId, NodeId)>,
+    next_embedded_id: u64,
+    /// The [revision](crate::Widget::accessibility_revision) of each custom widget's subtree
+    /// as last sent
+    #[cfg(feature = "custom-widget")]
+    widget_revisions: HashMap<DomNodeId, u64>,
+}
+
+impl AccessibilityCache {
+    /// The id in this document's tree of a node of the sub-document or custom widget in
+    /// `container_id`
+    fn embedded_id(&mut self, container_id: DomNodeId, id: NodeId) -> NodeId {
+        *self
+            .embedded_ids
+            .entry((container_id, id))
+            .or_insert_with(|| {
+                let new_id = NodeId(FIRST_EMBEDDED_NODE_ID + self.next_embedded_id);
+                self.next_embedded_id += 1;
+                self.embedded_targets.insert(new_id, (container_id, id));
+                new_id
+            })
+    }
+
+    /// Forget the ids given to the nodes of a sub-document or custom widget that has been
+    /// replaced or removed
+    fn forget_embedded(&mut self, container_id: DomNodeId) {
+        self.embedded_ids.retain(|(container, _), id| {
+            if *container != container_id {
+                return true;
+            }
+            self.embedded_targets.remove(id);
+            self.nodes.remove(id);
+            false
         });
+        self.embedded_targets.remove(&accesskit_id(container_id));
+    }
+}
+
+/// The `<label>` elements of each labelable element (in tree order)
+type ControlLabels = HashMap<DomNodeId, Vec<DomNodeId>>;
+
+fn accesskit_id(node_id: DomNodeId) -> NodeId {
+    NodeId(node_id.as_u64())
+}
 
-        let mut nodes: Vec<_> = nodes
+impl BaseDocument {
+    /// Build the whole accessibility tree. This doesn't affect the updates returned by
+    /// [`accessibility_tree_update`](Self::accessibility_tree_update).
+    pub fn build_accessibility_tree(&self) -> TreeUpdate {
+        let sub_document_updates = self
+            .sub_document_node_ids()
             .into_iter()
-            .map(|(_, (id, node))| (id, node))
+            .filter(|node_id| self.nodes[*node_id].flags.is_in_document())
+            .filter_map(|node_id| {
+                let sub_doc = self.nodes[node_id].subdoc()?;
+                Some((node_id, sub_doc.inner().build_accessibility_tree()))
+            })
             .collect();
-        nodes.push((NodeId(u64::MAX), window));
+        self.build_whole_accessibility_tree(
+            &mut AccessibilityCache::default(),
+            sub_document_updates,
+        )
+    }
+
+    /// Make the next [`accessibility_tree_update`](Self::accessibility_tree_update) contain the
+    /// whole tree (e.g. when assistive technology first requests it)
+    pub fn reset_accessibility_tree(&mut self) {
+        self.accessibility = AccessibilityCache::default();
+    }
+
+    /// The changes to the accessibility tree since it was last updated. Only the nodes that
+    /// have changed are included (or the whole tree if it hasn't been sent yet, or has been
+    /// [reset](Self::reset_accessibility_tree)).
+    pub fn accessibility_tree_update(&mut self) -> TreeUpdate {
+        let initialized = self.accessibility.initialized;
+        let sub_document_updates = self.sub_document_accessibility_updates(!initialized);
+        if !initialized {
+            let mut cache = AccessibilityCache::default();
+            let update = self.build_whole_accessibility_tree(&mut cache, sub_document_updates);
+            self.accessibility = cache;
+            return update;
+        }
+
+        let structure_changed = !self.accessibility.dirty_nodes.is_empty();
+        let mut queue = self.take_accessibility_dirty_nodes();
+
+        let mut cache = std::mem::take(&mut self.accessibility);
+        cache.focus_dirty = false;
+        let mut nodes = Vec::new();
+        let mut sub_document_nodes = Vec::new();
+        let mut sub_document_focus = HashMap::new();
+        for (container_id, update) in sub_document_updates {
+            let rebuilt = self.stitch_sub_document(
+                &mut cache,
+                container_id,
+                update,
+                &mut nodes,
+                &mut sub_document_nodes,
+                &mut sub_document_focus,
+            );
+            // The sub-document's nodes have new ids, so its container's children have changed
+            if rebuilt {
+                queue.push(container_id);
+            }
+        }
+
+        if std::mem::take(&mut cache.layout_dirty) {
+            queue.extend(self.accessibility_nodes_moved(&cache));
+        }
+        // Custom widgets' subtrees are rebuilt when the widgets report that they have changed
+        #[cfg(feature = "custom-widget")]
+        queue.extend(
+            self.custom_widget_nodes
+                .iter()
+                .copied()
+                .filter(|node_id| self.widget_accessibility_changed(&cache, *node_id)),
+        );
+
+        if !queue.is_empty() {
+            self.build_accessibility_nodes(&mut cache, queue, &mut nodes);
+        }
+        nodes.push((WINDOW_NODE_ID, self.build_window_node()));
 
-        let tree = Tree::new(NodeId(u64::MAX));
+        // Only send the nodes that differ from the version last sent
+        nodes.retain(|(id, node)| cache.nodes.get(id) != Some(node));
+        cache.nodes.extend(nodes.iter().cloned());
+        self.accessibility = cache;
+        if structure_changed {
+            self.prune_accessibility_cache();
+        }
+
+        nodes.append(&mut sub_document_nodes);
         TreeUpdate {
             tree_id: TreeId::ROOT,
             nodes,
-            tree: Some(tree),
-            focus: NodeId(self.focus_node_id.map(|id| id.as_u64()).unwrap_or(u64::MAX)),
+            tree: None,
+            focus: self.accessibility_focus(&sub_document_focus),
+        }
+    }
+
+    /// Build the whole tree, recording the nodes sent in `cache`
+    fn build_whole_accessibility_tree(
+        &self,
+        cache: &mut AccessibilityCache,
+        sub_document_updates: Vec<(DomNodeId, TreeUpdate)>,
+    ) -> TreeUpdate {
+        let mut nodes = Vec::new();
+        let mut sub_document_nodes = Vec::new();
+        let mut sub_document_focus = HashMap::new();
+        for (container_id, update) in sub_document_updates {
+            self.stitch_sub_document(
+                cache,
+                container_id,
+                update,
+                &mut nodes,
+                &mut sub_document_nodes,
+                &mut sub_document_focus,
+            );
+        }
+
+        self.build_accessibility_nodes(cache, vec![self.root_node_id], &mut nodes);
+        nodes.push((WINDOW_NODE_ID, self.build_window_node()));
+
+        cache.nodes.extend(nodes.iter().cloned());
+        cache.initialized = true;
+
+        nodes.append(&mut sub_document_nodes);
+        TreeUpdate {
+            tree_id: TreeId::ROOT,
+            nodes,
+            tree: Some(Tree::new(WINDOW_NODE_ID)),
+            focus: self.accessibility_focus(&sub_document_focus),
+        }
+    }
+
+    /// The updates to the accessibility trees of the sub-documents in the document (which
+    /// contain their whole trees if `rebuild` is set)
+    fn sub_document_accessibility_updates(
+        &mut self,
+        rebuild: bool,
+    ) -> Vec<(DomNodeId, TreeUpdate)> {
+        let mut updates = Vec::new();
+        for container_id in self.sub_document_node_ids() {
+            let Some(sub_doc) = self
+                .get_node_mut(container_id)
+                .filter(|node| node.flags.is_in_document())
+                .and_then(|node| node.subdoc_mut())
+            else {
+                continue;
+            };
+            let mut sub_doc = sub_doc.inner_mut();
+            if rebuild {
+                sub_doc.reset_accessibility_tree();
+            }
+            updates.push((container_id, sub_doc.accessibility_tree_update()));
+        }
+        updates
+    }
+
+    /// Whether the accessibility tree may have changed since it was last built or updated
+    pub fn has_accessibility_changes(&self) -> bool {
+        #[cfg(feature = "custom-widget")]
+        let widgets_changed = self
+            .custom_widget_nodes
+            .iter()
+            .any(|node_id| self.widget_accessibility_changed(&self.accessibility, *node_id));
+        #[cfg(not(feature = "custom-widget"))]
+        let widgets_changed = false;
+
+        !self.accessibility.initialized
+            || self.accessibility.layout_dirty
+            || self.accessibility.focus_dirty
+            || !self.accessibility.dirty_nodes.is_empty()
+            || self.sub_document_nodes.iter().any(|node_id| {
+                self.nodes[*node_id]
+                    .subdoc()
+                    .is_some_and(|sub_doc| sub_doc.inner().has_accessibility_changes())
+            })
+            || widgets_changed
+    }
+
+    /// The [`UiEvent`] that performs an action requested by assistive technology. Returns `None`
+    /// for unsupported actions and for nodes that no longer exist (or belong to sub-documents
+    /// or custom widgets, see [`forward_accessibility_action`](Self::forward_accessibility_action)).
+    pub fn accessibility_action_event(&self, request: &ActionRequest) -> Option<UiEvent> {
+        let target = DomNodeId::from_u64(request.target_node.0);
+        self.get_node(target)?;
+
+        // Custom widgets perform the actions on the roots of their subtrees (other than focussing
+        // and scrolling their elements)
+        let is_widget_root = self
+            .accessibility
+            .embedded_targets
+            .contains_key(&request.target_node);
+        if is_widget_root && !matches!(request.action, Action::Focus | Action::ScrollIntoView) {
+            return None;
+        }
+
+        let action = match (request.action, &request.data) {
+            (Action::Click, _) => AccessibilityAction::Click,
+            (Action::Focus, _) => AccessibilityAction::Focus,
+            (Action::ScrollIntoView, _) => AccessibilityAction::ScrollIntoView,
+            (Action::SetValue, Some(ActionData::Value(value))) => {
+                AccessibilityAction::SetValue(value.to_string())
+            }
+            _ => return None,
+        };
+        Some(UiEvent::AccessibilityAction(
+            BlitzAccessibilityActionEvent { target, action },
+        ))
+    }
+
+    /// Perform an action requested by assistive technology on a node of a sub-document or of
+    /// a custom widget's subtree. Returns `false` if the target isn't such a node.
+    pub fn forward_accessibility_action(&mut self, request: &ActionRequest) -> bool {
+        let Some(&(container_id, target_node)) = self
+            .accessibility
+            .embedded_targets
+            .get(&request.target_node)
+        else {
+            return false;
+        };
+        let request = ActionRequest {
+            target_node,
+            ..request.clone()
+        };
+
+        #[cfg(feature = "custom-widget")]
+        if let Some(widget_data) = self
+            .get_node_mut(container_id)
+            .and_then(|node| node.element_data_mut())
+            .and_then(|el| el.custom_widget_data_mut())
+        {
+            widget_data.widget.accessibility_action(&request);
+            return true;
+        }
+
+        let Some(sub_doc) = self
+            .get_node_mut(container_id)
+            .and_then(|node| node.subdoc_mut())
+        else {
+            return false;
+        };
+        let event = sub_doc.inner().accessibility_action_event(&request);
+        match event {
+            Some(event) => {
+                sub_doc.handle_ui_event(event);
+                true
+            }
+            None => sub_doc.inner_mut().forward_accessibility_action(&request),
+        }
+    }
+
+    /// Take the nodes that have changed since the last update, along with the nodes whose
+    /// names or states are computed from them
+    fn take_accessibility_dirty_nodes(&mut self) -> Vec<DomNodeId> {
+        let changed = std::mem::take(&mut self.accessibility.dirty_nodes);
+        let mut dirty = HashSet::new();
+        let mut ids = HashSet::new();
+        for node_id in changed {
+            // Names are computed from the content of ancestors (buttons, links, labels, etc)
+            let mut next = Some(node_id);
+            while let Some(node) = next.and_then(|node_id| self.get_node(node_id)) {
+                if !dirty.insert(node.id) {
+                    break;
+                }
+                if let Some(id) = node.attr(local_name!("id")) {
+                    ids.insert(id.to_string());
+                }
+                // A `<summary>` is expanded if its `<details>` is open
+                if node.data.is_element_with_tag_name(&local_name!("details")) {
+                    dirty.extend(node.children.iter().copied().filter(|child_id| {
+                        self.nodes[*child_id]
+                            .data
+                            .is_element_with_tag_name(&local_name!("summary"))
+                    }));
+                }
+                next = node.parent;
+            }
+        }
+
+        // Controls are named by their labels
+        let label_changed = dirty.iter().any(|node_id| {
+            self.nodes[*node_id]
+                .data
+                .is_element_with_tag_name(&local_name!("label"))
+        });
+        if label_changed {
+            for (control_id, label_ids) in self.control_labels() {
+                if label_ids.iter().any(|label_id| dirty.contains(label_id)) {
+                    dirty.insert(control_id);
+                }
+            }
+        }
+
+        // Elements can be named and described by other elements (by id)
+        if !ids.is_empty() {
+            self.visit(|node_id, node| {
+                let references_changed = [
+                    local_name!("aria-labelledby"),
+                    local_name!("aria-describedby"),
+                ]
+                .into_iter()
+                .filter_map(|name| node.attr(name))
+                .flat_map(str::split_ascii_whitespace)
+                .any(|id| ids.contains(id));
+                if references_changed {
+                    dirty.insert(node_id);
+                }
+            });
+        }
+
+        dirty.into_iter().collect()
+    }
+
+    /// The elements whose bounds (or visibility) differ from those last sent
+    fn accessibility_nodes_moved(&self, cache: &AccessibilityCache) -> Vec<DomNodeId> {
+        let mut moved = Vec::new();
+        self.visit(|node_id, node| {
+            let (Some(element_data), Some(sent)) =
+                (node.element_data(), cache.nodes.get(&accesskit_id(node_id)))
+            else {
+                return;
+            };
+            let hidden = self.is_accessibility_hidden(node, element_data);
+            if sent.is_hidden() != hidden
+                || (!hidden && sent.bounds() != self.accessibility_bounds(node_id))
+            {
+                moved.push(node_id);
+            }
+        });
+        moved
+    }
+
+    /// Forget the nodes that have been removed from the document
+    fn prune_accessibility_cache(&mut self) {
+        let nodes = &self.nodes;
+        self.accessibility.nodes.retain(|id, _| {
+            *id == WINDOW_NODE_ID
+                || id.0 >= FIRST_EMBEDDED_NODE_ID
+                || nodes
+                    .get(DomNodeId::from_u64(id.0))
+                    .is_some_and(|node| node.flags.is_in_document())
+        });
+
+        let removed_containers: HashSet<DomNodeId> = self
+            .accessibility
+            .embedded_targets
+            .values()
+            .map(|(container_id, _)| *container_id)
+            .filter(|container_id| !self.contains_embedded_tree(*container_id))
+            .collect();
+        for container_id in removed_containers {
+            self.accessibility.forget_embedded(container_id);
+        }
+        #[cfg(feature = "custom-widget")]
+        self.accessibility
+            .widget_revisions
+            .retain(|node_id, _| self.custom_widget_nodes.contains(node_id));
+    }
+
+    /// Whether the node is in the document and contains a sub-document or custom widget
+    fn contains_embedded_tree(&self, node_id: DomNodeId) -> bool {
+        if !self
+            .get_node(node_id)
+            .is_some_and(|node| node.flags.is_in_document())
+        {
+            return false;
+        }
+        #[cfg(feature = "custom-widget")]
+        if self.custom_widget_nodes.contains(&node_id) {
+            return true;
+        }
+        self.sub_document_nodes.contains(&node_id)
+    }
+
+    /// Build the nodes in `queue` along with those of their children that haven't been sent.
+    /// The nodes of custom widgets' subtrees take the place of their elements' children.
+    fn build_accessibility_nodes(
+        &self,
+        cache: &mut AccessibilityCache,
+        mut queue: Vec<DomNodeId>,
+        nodes: &mut Vec<(NodeId, AccessKitNode)>,
+    ) {
+        // The subtrees of the custom widgets that are (re)built, keyed by their nodes
+        #[cfg(feature = "custom-widget")]
+        let mut widget_trees: HashMap<DomNodeId, Option<WidgetAccessibilityTree>> = queue
+            .iter()
+            .filter(|node_id| self.custom_widget_nodes.contains(*node_id))
+            .map(|node_id| (*node_id, self.widget_accessibility_tree(*node_id)))
+            .collect();
+        // Only needed when naming controls, so found on first use
+        let labels = OnceCell::new();
+        let mut built = HashSet::new();
+        while let Some(node_id) = queue.pop() {
+            let Some(node) = self.get_node(node_id) else {
+                continue;
+            };
+            if !node.flags.is_in_document() || !built.insert(node_id) {
+                continue;
+            }
+            #[cfg(feature = "custom-widget")]
+            if !self.custom_widget_nodes.is_empty() {
+                if self.custom_widget_nodes.contains(&node_id)
+                    && widget_trees
+                        .entry(node_id)
+                        .or_insert_with(|| self.widget_accessibility_tree(node_id))
+                        .is_some()
+                {
+                    continue;
+                }
+                // The contents of widgets with subtrees aren't exposed
+                let in_widget_subtree = std::iter::successors(node.parent, |id| {
+                    self.nodes[*id].parent
+                })
+                .any(|id| match widget_trees.get(&id) {
+                    Some(tree) => tree.is_some(),
+                    None => cache.embedded_targets.contains_key(&accesskit_id(id)),
+                });
+                if in_widget_subtree {
+                    continue;
+                }
+            }
+            // Nodes (re)attached to the document are sent along with their parent
+            queue.extend(
+                node.children
+                    .iter()
+                    .copied()
+                    .filter(|child_id| !cache.nodes.contains_key(&accesskit_id(*child_id))),
+            );
+            let builder = self.build_accessibility_node(cache, node, &labels);
+            nodes.push((accesskit_id(node_id), builder));
+        }
+
+        #[cfg(feature = "custom-widget")]
+        for (node_id, tree) in widget_trees {
+            let node = &self.nodes[node_id];
+            let Some(widget_data) = node.element_data().and_then(|el| el.custom_widget_data())
+            else {
+                continue;
+            };
+            cache
+                .widget_revisions
+                .insert(node_id, widget_data.widget.accessibility_revision());
+            match tree {
+                Some(tree) => {
+                    let element = self.build_accessibility_node(cache, node, &labels);
+                    self.stitch_widget(cache, node_id, element, tree, nodes);
+                }
+                // The element (built above) is exposed in place of the subtree last sent
+                None => cache.forget_embedded(node_id),
+            }
+        }
+    }
+
+    /// The accessibility subtree of the custom widget in the node (if it has one)
+    #[cfg(feature = "custom-widget")]
+    fn widget_accessibility_tree(&self, node_id: DomNodeId) -> Option<WidgetAccessibilityTree> {
+        let node = &self.nodes[node_id];
+        if !node.flags.is_in_document() {
+            return None;
+        }
+        let widget_data = node.element_data()?.custom_widget_data()?;
+        widget_data.widget.accessibility_tree()
+    }
+
+    /// Whether the accessibility subtree of the custom widget in the node has changed since it
+    /// was last sent
+    #[cfg(feature = "custom-widget")]
+    fn widget_accessibility_changed(&self, cache: &AccessibilityCache, node_id: DomNodeId) -> bool {
+        let node = &self.nodes[node_id];
+        node.flags.is_in_document()
+            && node
+                .element_data()
+                .and_then(|el| el.custom_widget_data())
+                .is_some_and(|widget_data| {
+                    cache.widget_revisions.get(&node_id)
+                        != Some(&widget_data.widget.accessibility_revision())
+                })
+    }
+
+    /// Add the nodes of a custom widget's accessibility subtree to the tree, giving them ids
+    /// that don't clash with this document's. The subtree's root takes the place of the node
+    /// built for the widget's element.
+    #[cfg(feature = "custom-widget")]
+    fn stitch_widget(
+        &self,
+        cache: &mut AccessibilityCache,
+        widget_id: DomNodeId,
+        element: AccessKitNode,
+        tree: WidgetAccessibilityTree,
+        nodes: &mut Vec<(NodeId, AccessKitNode)>,
+    ) {
+        let (x, y) = self.content_box_origin(widget_id);
+        let element_id = accesskit_id(widget_id);
+        cache
+            .embedded_targets
+            .insert(element_id, (widget_id, tree.root));
+        let mut map_id = |id: NodeId| match id == tree.root {
+            true => element_id,
+            false => cache.embedded_id(widget_id, id),
+        };
+
+        for (id, mut node) in tree.nodes {
+            let children: Vec<NodeId> = node.children().iter().map(|id| map_id(*id)).collect();
+            node.set_children(children);
+            let labelled_by: Vec<NodeId> =
+                node.labelled_by().iter().map(|id| map_id(*id)).collect();
+            node.set_labelled_by(labelled_by);
+            // Bounds are relative to the widget's content box
+            match node.bounds() {
+                Some(bounds) => node.set_bounds(Rect::new(
+                    bounds.x0 + x,
+                    bounds.y0 + y,
+                    bounds.x1 + x,
+                    bounds.y1 + y,
+                )),
+                None if id == tree.root => {
+                    if let Some(bounds) = element.bounds() {
+                        node.set_bounds(bounds);
+                    }
+                }
+                None => {}
+            }
+            if id == tree.root && element.is_hidden() {
+                node.set_hidden();
+            }
+            nodes.push((map_id(id), node));
+        }
+        if !nodes.iter().any(|(id, _)| *id == element_id) {
+            nodes.push((element_id, element));
+        }
+    }
+
+    /// Add the nodes of an `update` to the accessibility tree of the sub-document in
+    /// `container_id` to this document's tree, giving them ids that don't clash with this
+    /// document's. The sub-document's root is added to `nodes` (as it is positioned by this
+    /// document) and the rest to `sub_document_nodes`. Returns whether the sub-document's whole
+    /// tree was rebuilt.
+    fn stitch_sub_document(
+        &self,
+        cache: &mut AccessibilityCache,
+        container_id: DomNodeId,
+        update: TreeUpdate,
+        nodes: &mut Vec<(NodeId, AccessKitNode)>,
+        sub_document_nodes: &mut Vec<(NodeId, AccessKitNode)>,
+        sub_document_focus: &mut HashMap<DomNodeId, NodeId>,
+    ) -> bool {
+        let Some(sub_doc) = self.nodes[container_id].subdoc() else {
+            return false;
+        };
+        let scroll = sub_doc.inner().viewport_scroll();
+
+        let rebuilt = update.tree.is_some();
+        if rebuilt {
+            cache.forget_embedded(container_id);
+        }
+
+        // The sub-document's content box is positioned over its container's
+        let (x, y) = self.content_box_origin(container_id);
+        let transform = Affine::translate((x - scroll.x, y - scroll.y));
+
+        let window_id = cache.embedded_id(container_id, WINDOW_NODE_ID);
+        let mut window = None;
+        for (id, mut node) in update.nodes {
+            let children: Vec<NodeId> = node
+                .children()
+                .iter()
+                .map(|child_id| cache.embedded_id(container_id, *child_id))
+                .collect();
+            node.set_children(children);
+            let labelled_by: Vec<NodeId> = node
+                .labelled_by()
+                .iter()
+                .map(|label_id| cache.embedded_id(container_id, *label_id))
+                .collect();
+            node.set_labelled_by(labelled_by);
+
+            match id == WINDOW_NODE_ID {
+                true => window = Some(node),
+                false => {
+                    sub_document_nodes.push((cache.embedded_id(container_id, id), node));
+                }
+            }
+        }
+
+        // The sub-document's window becomes a container within this document's window
+        if let Some(mut window) = window.or_else(|| cache.nodes.get(&window_id).cloned()) {
+            window.set_role(Role::GenericContainer);
+            window.set_transform(transform);
+            nodes.push((window_id, window));
         }
+        if update.focus != WINDOW_NODE_ID {
+            let focus = cache.embedded_id(container_id, update.focus);
+            sub_document_focus.insert(container_id, focus);
+        }
+
+        rebuilt
+    }
+
+    fn build_window_node(&self) -> AccessKitNode {
+        let mut window = AccessKitNode::new(Role::Window);
+        // Bounds are in CSS pixels relative to the document, which the window scrolls and
+        // scales to physical pixels
+        let scroll = self.viewport_scroll;
+        window.set_transform(
+            Affine::scale(self.viewport.scale() as f64) * Affine::translate((-scroll.x, -scroll.y)),
+        );
+        window.push_child(accesskit_id(self.root_node().id));
+        window
+    }
+
+    /// The focussed node (which may be within a sub-document)
+    fn accessibility_focus(&self, sub_document_focus: &HashMap<DomNodeId, NodeId>) -> NodeId {
+        match self.focus_node_id {
+            Some(node_id) => sub_document_focus
+                .get(&node_id)
+                .copied()
+                .unwrap_or(accesskit_id(node_id)),
+            None => WINDOW_NODE_ID,
+        }
+    }
+
+    /// The position of the node's content box, relative to the document
+    fn content_box_origin(&self, node_id: DomNodeId) -> (f64, f64) {
+        let node = &self.nodes[node_id];
+        let layout = node.final_layout();
+        let position = node.absolute_position(0.0, 0.0);
+        (
+            (position.x + layout.content_box_x()) as f64,
+            (position.y + layout.content_box_y()) as f64,
+        )
+    }
+
+    /// The bounds of the node's border box, relative to the document
+    fn accessibility_bounds(&self, node_id: DomNodeId) -> Option<Rect> {
+        let rect = self.get_client_bounding_rect(node_id)?;
+        let x = rect.x + self.viewport_scroll.x;
+        let y = rect.y + self.viewport_scroll.y;
+        Some(Rect::new(x, y, x + rect.width, y + rect.height))
     }
 
     fn build_accessibility_node(
         &self,
+        cache: &AccessibilityCache,
         node: &BlitzDomNode,
-        parent: &mut AccessKitNode,
-    ) -> (NodeId, AccessKitNode) {
-        let id = NodeId(node.id.as_u64());
-
+        labels: &OnceCell<ControlLabels>,
+    ) -> AccessKitNode {
         let mut builder = AccessKitNode::default();
         if node.parent.is_none() {
             builder.set_role(Role::Window)
@@ -55,18 +744,486 @@ impl BaseDocument {
 
             builder.set_role(role);
             builder.set_html_tag(name);
+
+            let label = self.accessible_name(node, element_data, role, labels);
+            if let Some(description) = self.accessible_description(element_data, label.as_deref()) {
+                builder.set_description(description);
+            }
+            if let Some(label) = label {
+                builder.set_label(label);
+            }
+
+            if self.is_accessibility_hidden(node, element_data) {
+                builder.set_hidden();
+            } else if let Some(bounds) = self.accessibility_bounds(node.id) {
+                builder.set_bounds(bounds);
+                builder.add_action(Action::ScrollIntoView);
+            }
+
+            if element_data.select_data().is_some() {
+                let label = node
+                    .select_selected_options()
+                    .first()
+                    .map(|option_id| node.with(*option_id).option_label())
+                    .unwrap_or_default();
+                builder.set_value(label);
+                if select_is_drop_down(element_data) {
+                    builder.set_expanded(self.open_select_node_id == Some(node.id));
+                }
+            } else if element_data.name.local == local_name!("option") {
+                builder.set_selected(node.option_is_selected());
+            }
+
+            self.set_accessibility_states(node, element_data, &mut builder);
+            set_accessibility_actions(node, element_data, role, &mut builder);
         } else if node.is_text_node() {
             builder.set_role(Role::TextRun);
             builder.set_value(node.text_content());
-            parent.push_labelled_by(id)
         }
 
-        parent.push_child(id);
+        for child_id in node.children.iter().copied() {
+            builder.push_child(accesskit_id(child_id));
+            if self.nodes[child_id].is_text_node() {
+                builder.push_labelled_by(accesskit_id(child_id));
+            }
+        }
+        if let Some(window_id) = cache.embedded_ids.get(&(node.id, WINDOW_NODE_ID)) {
+            builder.push_child(*window_id);
+        }
+
+        builder
+    }
+
+    /// Whether the element is excluded from the accessibility tree, either by its own styles
+    /// and attributes (see [`is_hidden`]) or because it is blocked by a modal dialog
+    fn is_accessibility_hidden(&self, node: &BlitzDomNode, element_data: &ElementData) -> bool {
+        is_hidden(node, element_data) || self.is_inert_subtree(node.id)
+    }
+
+    /// Map each labelable element to the `<label>` elements that label it (in tree order)
+    fn control_labels(&self) -> ControlLabels {
+        let mut labels = ControlLabels::new();
+        self.visit(|node_id, node| {
+            let Some(element_data) = node.element_data() else {
+                return;
+            };
+            if element_data.name.local != local_name!("label") {
+                return;
+            }
+            let control = match element_data.attr(local_name!("for")) {
+                Some(for_id) => self
+                    .get_element_by_id(for_id)
+                    .filter(|id| self.nodes[*id].element_data().is_some_and(is_labelable)),
+                None => self.first_labelable_descendant(node),
+            };
+            if let Some(control) = control {
+                labels.entry(control).or_default().push(node_id);
+            }
+        });
+        labels
+    }
+
+    fn first_labelable_descendant(&self, node: &BlitzDomNode) -> Option<DomNodeId> {
+        node.children.iter().find_map(|child_id| {
+            let child = &self.nodes[*child_id];
+            match child.element_data() {
+                Some(el) if is_labelable(el) => Some(*child_id),
+                _ => self.first_labelable_descendant(child),
+            }
+        })
+    }
+
+    /// The element's accessible name (a simplified version of
+    /// <https://www.w3.org/TR/accname-1.2/#computation-steps>)
+    fn accessible_name(
+        &self,
+        node: &BlitzDomNode,
+        element_data: &ElementData,
+        role: Role,
+        labels: &OnceCell<ControlLabels>,
+    ) -> Option<String> {
+        if let Some(ids) = element_data.attr(local_name!("aria-labelledby")) {
+            let name = self.text_alternative_of_ids(ids);
+            if !name.is_empty() {
+                return Some(name);
+            }
+        }
+        if let Some(label) = element_data.attr(local_name!("aria-label")) {
+            let label = normalize_whitespace(label);
+            if !label.is_empty() {
+                return Some(label);
+            }
+        }
+        if let Some(name) = self.native_accessible_name(node, element_data, labels) {
+            return Some(name);
+        }
+        if name_from_content(role) {
+            let name = self.text_alternative_of_content(node);
+            if !name.is_empty() {
+                return Some(name);
+            }
+        }
+        element_data
+            .attr(local_name!("title"))
+            .or_else(|| element_data.attr(local_name!("placeholder")))
+            .map(normalize_whitespace)
+            .filter(|name| !name.is_empty())
+    }
+
+    /// The name given to the element by its own HTML semantics (labels, alt text, etc)
+    fn native_accessible_name(
+        &self,
+        node: &BlitzDomNode,
+        element_data: &ElementData,
+        labels: &OnceCell<ControlLabels>,
+    ) -> Option<String> {
+        let attr = |name| {
+            element_data
+                .attr(name)
+                .map(normalize_whitespace)
+                .filter(|value| !value.is_empty())
+        };
+        let child_content = |tag| {
+            node.children
+                .iter()
+                .map(|child_id| &self.nodes[*child_id])
+                .find(|child| child.data.is_element_with_tag_name(&tag))
+                .map(|child| self.text_alternative_of_content(child))
+                .filter(|name| !name.is_empty())
+        };
+
+        match element_data.name.local {
+            local_name!("input") => match element_data.attr(local_name!("type")) {
+                Some("button") => attr(local_name!("value")),
+                Some("submit") => attr(local_name!("value")).or(Some(String::from("Submit"))),
+                Some("reset") => attr(local_name!("value")).or(Some(String::from("Reset"))),
+                Some("image") => attr(local_name!("alt")).or(Some(String::from("Submit"))),
+                _ => self.label_text(node.id, labels),
+            },
+            local_name!("img") | local_name!("area") => attr(local_name!("alt")),
+            local_name!("fieldset") => child_content(local_name!("legend")),
+            local_name!("figure") => child_content(local_name!("figcaption")),
+            local_name!("table") => child_content(local_name!("caption")),
+            _ if is_labelable(element_data) => self.label_text(node.id, labels),
+            _ => None,
+        }
+    }
+
+    /// The text of the `<label>`s of a labelable element
+    fn label_text(&self, node_id: DomNodeId, labels: &OnceCell<ControlLabels>) -> Option<String> {
+        let text = labels
+            .get_or_init(|| self.control_labels())
+            .get(&node_id)?
+            .iter()
+            .map(|label_id| self.text_alternative_of_content(&self.nodes[*label_id]))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// The element's accessible description: the text of the elements referenced by
+    /// `aria-describedby`, or else its `title` or `placeholder` (unless that is already its
+    /// name)
+    fn accessible_description(
+        &self,
+        element_data: &ElementData,
+        name: Option<&str>,
+    ) -> Option<String> {
+        if let Some(ids) = element_data.attr(local_name!("aria-describedby")) {
+            let description = self.text_alternative_of_ids(ids);
+            if !description.is_empty() {
+                return Some(description);
+            }
+        }
+        [local_name!("title"), local_name!("placeholder")]
+            .into_iter()
+            .filter_map(|attr| element_data.attr(attr))
+            .map(normalize_whitespace)
+            .find(|description| !description.is_empty() && Some(description.as_str()) != name)
+    }
+
+    /// The text alternatives of the elements with the given (space separated) ids
+    fn text_alternative_of_ids(&self, ids: &str) -> String {
+        let text: Vec<String> = ids
+            .split_ascii_whitespace()
+            .filter_map(|id| self.get_element_by_id(id))
+            .map(|node_id| {
+                let node = &self.nodes[node_id];
+                match node.attr(local_name!("aria-label")) {
+                    Some(label) if !label.trim().is_empty() => normalize_whitespace(label),
+                    _ => self.text_alternative_of_content(node),
+                }
+            })
+            .filter(|text| !text.is_empty())
+            .collect();
+        text.join(" ")
+    }
+
+    /// The text of the node's (visible) descendants, using the alt text of images and the
+    /// `aria-label` of elements that have one
+    fn text_alternative_of_content(&self, node: &BlitzDomNode) -> String {
+        let mut text = String::new();
+        self.write_text_alternative_of_content(node, &mut text);
+        normalize_whitespace(&text)
+    }
+
+    fn write_text_alternative_of_content(&self, node: &BlitzDomNode, out: &mut String) {
+        for child_id in node.children.iter() {
+            let child = &self.nodes[*child_id];
+            match &child.data {
+                NodeData::Text(data) => out.push_str(&data.content),
+                NodeData::Element(element_data) => {
+                    if is_hidden(child, element_data) {
+                        continue;
+                    }
+                    // Block-level elements are separated from the surrounding text
+                    let is_block = child
+                        .display_style()
+                        .is_some_and(|display| display.outside() != DisplayOutside::Inline);
+                    if is_block {
+                        out.push(' ');
+                    }
+                    match element_data.attr(local_name!("aria-label")) {
+                        Some(label) if !label.trim().is_empty() => out.push_str(label),
+                        _ => match element_data.name.local {
+                            local_name!("img") => out.push_str(
+                                element_data.attr(local_name!("alt")).unwrap_or_default(),
+                            ),
+                            // The values of embedded controls aren't part of the name
+                            local_name!("input")
+                            | local_name!("select")
+                            | local_name!("textarea") => {}
+                            _ => self.write_text_alternative_of_content(child, out),
+                        },
+                    }
+                    if is_block {
+                        out.push(' ');
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Map the element's state (checked, disabled, expanded, etc) and value to AccessKit
+    fn set_accessibility_states(
+        &self,
+        node: &BlitzDomNode,
+        element_data: &ElementData,
+        builder: &mut AccessKitNode,
+    ) {
+        let aria_true = |name| element_data.attr(name) == Some("true");
+
+        if is_disabled(element_data) || aria_true(local_name!("aria-disabled")) {
+            builder.set_disabled();
+        }
+        if element_data.has_attr(local_name!("required")) || aria_true(local_name!("aria-required"))
+        {
+            builder.set_required();
+        }
+        if element_data.has_attr(local_name!("readonly")) || aria_true(local_name!("aria-readonly"))
+        {
+            builder.set_read_only();
+        }
+
+        let toggled = match element_data.checkbox_input_checked() {
+            Some(_) if element_data.indeterminate => Some(Toggled::Mixed),
+            Some(true) => Some(Toggled::True),
+            Some(false) => Some(Toggled::False),
+            None => match element_data
+                .attr(local_name!("aria-checked"))
+                .or_else(|| element_data.attr(local_name!("aria-pressed")))
+            {
+                Some("true") => Some(Toggled::True),
+                Some("mixed") => Some(Toggled::Mixed),
+                Some(_) => Some(Toggled::False),
+                None => None,
+            },
+        };
+        if let Some(toggled) = toggled {
+            builder.set_toggled(toggled);
+        }
+
+        if let Some(expanded) = element_data.attr(local_name!("aria-expanded")) {
+            builder.set_expanded(expanded == "true");
+        } else if element_data.name.local == local_name!("summary")
+            && let Some(details) = node.parent.map(|id| &self.nodes[id])
+            && details
+                .data
+                .is_element_with_tag_name(&local_name!("details"))
+        {
+            builder.set_expanded(details.has_attr(local_name!("open")));
+        }
+        if let Some(selected) = element_data.attr(local_name!("aria-selected")) {
+            builder.set_selected(selected == "true");
+        }
+
+        if let Some(level) = heading_level(element_data) {
+            builder.set_level(level);
+        }
+        if element_data.name.local == local_name!("a")
+            && let Some(href) = element_data.attr(local_name!("href"))
+        {
+            builder.set_url(href);
+        }
+
+        if let Some(input_data) = element_data.text_input_data() {
+            let text = input_data.editor.raw_text();
+            if element_data.attr(local_name!("type")) == Some("password") {
+                builder.set_value("•".repeat(text.chars().count()));
+            } else {
+                builder.set_value(text);
+            }
+            if let Some(placeholder) = element_data.attr(local_name!("placeholder")) {
+                builder.set_placeholder(placeholder);
+            }
+        }
+
+        let is_range = element_data.name.local == local_name!("progress")
+            || element_data.name.local == local_name!("meter")
+            || (element_data.name.local == local_name!("input")
+                && element_data.attr(local_name!("type")) == Some("range"));
+        if is_range {
+            let number = |name| {
+                element_data
+                    .attr(name)
+                    .and_then(|value| value.trim().parse::<f64>().ok())
+            };
+            if let Some(value) = number(local_name!("value")) {
+                builder.set_numeric_value(value);
+            }
+            if let Some(min) = number(local_name!("min")) {
+                builder.set_min_numeric_value(min);
+            }
+            if let Some(max) = number(local_name!("max")) {
+                builder.set_max_numeric_value(max);
+            }
+        }
+    }
+}
+
+/// The actions that assistive technology can perform on the element
+fn set_accessibility_actions(
+    node: &BlitzDomNode,
+    element_data: &ElementData,
+    role: Role,
+    builder: &mut AccessKitNode,
+) {
+    if is_disabled(element_data) {
+        return;
+    }
+    if node.is_focussable() {
+        builder.add_action(Action::Focus);
+    }
+    let is_clickable = matches!(
+        role,
+        Role::Button
+            | Role::Link
+            | Role::CheckBox
+            | Role::RadioButton
+            | Role::Switch
+            | Role::DisclosureTriangle
+            | Role::ListBoxOption
+            | Role::ComboBox
+            | Role::MenuItem
+            | Role::MenuItemCheckBox
+            | Role::MenuItemRadio
+            | Role::Tab
+            | Role::TreeItem
+    );
+    if is_clickable {
+        builder.add_action(Action::Click);
+    }
+    if element_data.text_input_data().is_some() && !element_data.has_attr(local_name!("readonly")) {
+        builder.add_action(Action::SetValue);
+    }
+}
+
+/// Whether the element is excluded from the accessibility tree (`display: none`,
+/// `aria-hidden="true"`)
+fn is_hidden(node: &BlitzDomNode, element_data: &ElementData) -> bool {
+    element_data.attr(local_name!("aria-hidden")) == Some("true")
+        || node
+            .display_style()
+            .is_some_and(|display| display.inside() == DisplayInside::None)
+}
+
+/// Whether the element is a disabled form control
+fn is_disabled(element_data: &ElementData) -> bool {
+    matches!(
+        element_data.name.local,
+        local_name!("button")
+            | local_name!("input")
+            | local_name!("select")
+            | local_name!("textarea")
+            | local_name!("option")
+            | local_name!("optgroup")
+            | local_name!("fieldset")
+    ) && element_data.has_attr(local_name!("disabled"))
+}
 
-        (id, builder)
+/// <https://html.spec.whatwg.org/multipage/forms.html#category-label>
+fn is_labelable(element_data: &ElementData) -> bool {
+    match element_data.name.local {
+        local_name!("input") => element_data.attr(local_name!("type")) != Some("hidden"),
+        local_name!("button")
+        | local_name!("meter")
+        | local_name!("output")
+        | local_name!("progress")
+        | local_name!("select")
+        | local_name!("textarea") => true,
+        _ => false,
     }
 }
 
+fn heading_level(element_data: &ElementData) -> Option<usize> {
+    if let Some(level) = element_data.attr(local_name!("aria-level")) {
+        return level.trim().parse().ok();
+    }
+    match element_data.name.local {
+        local_name!("h1") => Some(1),
+        local_name!("h2") => Some(2),
+        local_name!("h3") => Some(3),
+        local_name!("h4") => Some(4),
+        local_name!("h5") => Some(5),
+        local_name!("h6") => Some(6),
+        _ => None,
+    }
+}
+
+/// Whether elements with this role take their accessible name from their content
+/// (<https://www.w3.org/TR/wai-aria-1.2/#namefromcontent>)
+fn name_from_content(role: Role) -> bool {
+    matches!(
+        role,
+        Role::Button
+            | Role::Link
+            | Role::Heading
+            | Role::Cell
+            | Role::ColumnHeader
+            | Role::RowHeader
+            | Role::CheckBox
+            | Role::RadioButton
+            | Role::Switch
+            | Role::DisclosureTriangle
+            | Role::ListBoxOption
+            | Role::MenuItem
+            | Role::MenuItemCheckBox
+            | Role::MenuItemRadio
+            | Role::Tab
+            | Role::TreeItem
+            | Role::Tooltip
+            | Role::Label
+            | Role::Caption
+    )
+}
+
+/// Collapse runs of whitespace into single spaces, trimming the ends
+fn normalize_whitespace(text: &str) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
 fn role_from_name(name: &str) -> Option<Role> {
     match name {
         "alert" => Some(Role::Alert),
@@ -28,6 +28,19 @@ pub enum StyleThreading {
     Sequential,
 }
 
+/// Where images are decoded once their bytes have loaded.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ImageDecoding {
+    /// Decode images on rayon's global thread pool so that neither the
+    /// network threads nor the document's thread are blocked by decoding.
+    #[default]
+    WorkerPool,
+    /// Decode images synchronously on the thread which delivers their bytes
+    /// (and on the document's thread when they are re-decoded). Loading is
+    /// deterministic, which is useful for testing.
+    Inline,
+}
+
 /// Options used when constructing a [`BaseDocument`](crate::BaseDocument)
 #[derive(Default)]
 pub struct DocumentConfig {
@@ -50,6 +63,9 @@ pub struct DocumentConfig {
     /// The CSS media type used to evaluate `@media` rules.
     /// Defaults to [`MediaType::screen`].
     pub media_type: Option<MediaType>,
+    /// Whether the user prefers reduced motion: the `prefers-reduced-motion` media query
+    /// matches `reduce` and animated images aren't played. Defaults to `false`.
+    pub prefers_reduced_motion: bool,
     /// Strategy for Stylo's style traversal.
     /// Defaults to [`StyleThreading::Parallel`].
     pub style_threading: StyleThreading,
@@ -62,4 +78,11 @@ pub struct DocumentConfig {
     /// How deeply this document is nested within other documents
     /// (0 for a root document). Used to limit `<iframe>` nesting depth.
     pub subdocument_depth: usize,
+    /// Where images are decoded.
+    /// Defaults to [`ImageDecoding::WorkerPool`].
+    pub image_decoding: ImageDecoding,
+    /// The number of bytes of decoded image pixels to keep before the pixels of
+    /// `<img>` elements which are out of view are evicted (to be re-decoded when
+    /// they come back into view). Defaults to 256MiB.
+    pub image_memory_budget: Option<usize>,
 }
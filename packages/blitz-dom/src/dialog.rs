@@ -0,0 +1,326 @@
+//! The `<dialog>` element and the top layer.
+//!
+//! A dialog shown modally is added to the top layer: it is painted (along with its
+//! `::backdrop`) above all other content, and it makes the rest of the document inert, so
+//! that content can't be hit or focussed until the dialog is closed.
+//!
+//! <https://html.spec.whatwg.org/multipage/interactive-elements.html#the-dialog-element>
+//! <https://fullscreen.spec.whatwg.org/#top-layer>
+
+use blitz_traits::events::{BlitzDialogEvent, DomEventData, HitResult};
+use blitz_traits::node_id::NodeId;
+use markup5ever::local_name;
+use style::properties::ComputedValues;
+use style::selector_parser::PseudoElement;
+use style::servo_arc::Arc as ServoArc;
+use style::shared_lock::StylesheetGuards;
+use style::stylist::RuleInclusion;
+use style_dom::ElementState;
+
+use crate::traversal::TreeTraverser;
+use crate::{BaseDocument, DocumentMutator, qual_name};
+
+/// The state of a dialog which isn't reflected in its attributes
+#[derive(Debug, Default)]
+pub(crate) struct DialogState {
+    /// The dialog's return value, which is set when it is closed with a result
+    return_value: String,
+    /// The element which was focussed when the dialog was shown, and which is focussed again
+    /// when it is closed
+    previously_focussed: Option<NodeId>,
+}
+
+impl DocumentMutator<'_> {
+    /// Show a dialog non-modally (`dialog.show()`). It is opened in place and the rest of the
+    /// document stays interactive.
+    pub fn show_dialog(&mut self, dialog_id: NodeId) {
+        if !self.doc.is_closed_dialog(dialog_id) {
+            return;
+        }
+        let previously_focussed = self.doc.focus_node_id;
+        self.set_attribute(dialog_id, qual_name!("open"), "");
+        self.doc
+            .dialogs
+            .entry(dialog_id)
+            .or_default()
+            .previously_focussed = previously_focussed;
+        self.doc.focus_dialog(dialog_id);
+    }
+
+    /// Show a dialog modally (`dialog.showModal()`). It is added to the top layer, matches
+    /// `:modal`, and the rest of the document is inert until it is closed.
+    pub fn show_modal_dialog(&mut self, dialog_id: NodeId) {
+        if !self.doc.is_closed_dialog(dialog_id)
+            || !self.doc.nodes[dialog_id].flags.is_in_document()
+            || self.doc.is_popover_open(dialog_id)
+        {
+            return;
+        }
+        let previously_focussed = self.doc.focus_node_id;
+        // The attribute is set first, so that the snapshot taken for the restyle records the
+        // dialog's state from before it became modal
+        self.set_attribute(dialog_id, qual_name!("open"), "");
+        self.doc.snapshot_node_and(dialog_id, |node| {
+            if let Some(element) = node.element_data_mut() {
+                element.element_state.insert(ElementState::MODAL);
+            }
+        });
+        self.doc.add_to_top_layer(dialog_id);
+        self.doc
+            .dialogs
+            .entry(dialog_id)
+            .or_default()
+            .previously_focussed = previously_focussed;
+        self.doc.focus_dialog(dialog_id);
+    }
+
+    /// Close an open dialog (`dialog.close(returnValue)`), removing it from the top layer and
+    /// returning focus to the element which was focussed when it was shown. If a return value
+    /// is given, it becomes the dialog's [`dialog_return_value`](BaseDocument::dialog_return_value).
+    ///
+    /// A `close` event is queued at the dialog.
+    pub fn close_dialog(&mut self, dialog_id: NodeId, return_value: Option<&str>) {
+        let Some(node) = self.doc.get_node(dialog_id) else {
+            return;
+        };
+        if !node.data.is_element_with_tag_name(&local_name!("dialog"))
+            || !node.has_attr(local_name!("open"))
+        {
+            return;
+        }
+        let was_modal = self.doc.is_modal(dialog_id);
+
+        self.clear_attribute(dialog_id, qual_name!("open"));
+        let doc = &mut *self.doc;
+        if was_modal {
+            doc.snapshot_node_and(dialog_id, |node| {
+                if let Some(element) = node.element_data_mut() {
+                    element.element_state.remove(ElementState::MODAL);
+                }
+            });
+        }
+        doc.remove_from_top_layer(dialog_id);
+
+        let state = doc.dialogs.entry(dialog_id).or_default();
+        if let Some(return_value) = return_value {
+            state.return_value = return_value.to_string();
+        }
+        let previously_focussed = state.previously_focussed.take();
+
+        // Focus is restored if it was inside the dialog (which it always is for a modal dialog)
+        let focus_was_in_dialog = doc
+            .focus_node_id
+            .is_some_and(|id| id == dialog_id || doc.is_ancestor_of(dialog_id, id));
+        if was_modal || focus_was_in_dialog {
+            match previously_focussed.filter(|id| doc.get_node(*id).is_some()) {
+                Some(id) => {
+                    doc.set_focus_to(id);
+                }
+                None if focus_was_in_dialog => doc.clear_focus(),
+                None => {}
+            }
+        }
+
+        doc.queue_event(dialog_id, DomEventData::Close(BlitzDialogEvent));
+    }
+
+    /// Close the dialog that a form with `method=dialog` is in, using the submitter's value
+    /// as the dialog's return value. This takes the place of navigating when such a form is
+    /// submitted.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#submit-dialog>
+    pub(crate) fn submit_dialog_form(&mut self, form_id: NodeId, submitter_id: NodeId) {
+        let Some(dialog_id) = self.doc.nearest_ancestor_dialog(form_id) else {
+            return;
+        };
+        let return_value = (submitter_id != form_id)
+            .then(|| self.doc.nodes[submitter_id].attr(local_name!("value")))
+            .flatten()
+            .map(str::to_string);
+        self.close_dialog(dialog_id, return_value.as_deref());
+    }
+}
+
+impl BaseDocument {
+    /// The return value of a dialog: the value it was last closed with, or the empty string
+    pub fn dialog_return_value(&self, dialog_id: NodeId) -> &str {
+        self.dialogs
+            .get(&dialog_id)
+            .map(|state| state.return_value.as_str())
+            .unwrap_or("")
+    }
+
+    /// The elements in the top layer, from bottom to top
+    pub fn top_layer(&self) -> &[NodeId] {
+        &self.top_layer
+    }
+
+    /// Whether an element is a dialog which is shown modally (and so matches `:modal`)
+    pub fn is_modal(&self, node_id: NodeId) -> bool {
+        self.get_node(node_id)
+            .and_then(|node| node.element_data())
+            .is_some_and(|element| element.element_state.contains(ElementState::MODAL))
+    }
+
+    /// The topmost modal dialog in the top layer, which blocks interaction with everything
+    /// beneath it
+    pub fn topmost_modal_dialog(&self) -> Option<NodeId> {
+        self.top_layer
+            .iter()
+            .rev()
+            .copied()
+            .find(|&node_id| self.is_modal(node_id))
+    }
+
+    /// Whether a node is inert because it is blocked by a modal dialog: it is neither inside
+    /// the topmost modal dialog, nor inside an element above it in the top layer.
+    ///
+    /// Inert nodes can't be hit-tested or focussed.
+    pub fn is_inert(&self, node_id: NodeId) -> bool {
+        let modal_top_layer = self.modal_top_layer();
+        !modal_top_layer.is_empty()
+            && !modal_top_layer
+                .iter()
+                .any(|&top_id| top_id == node_id || self.is_ancestor_of(top_id, node_id))
+    }
+
+    /// Whether a node and all of its descendants are inert, which is the case unless it
+    /// contains the topmost modal dialog or an element above it in the top layer. Such
+    /// subtrees are hidden from assistive technologies.
+    pub fn is_inert_subtree(&self, node_id: NodeId) -> bool {
+        self.is_inert(node_id)
+            && !self
+                .modal_top_layer()
+                .iter()
+                .any(|&top_id| self.is_ancestor_of(node_id, top_id))
+    }
+
+    /// The topmost modal dialog and the elements above it in the top layer, which are the only
+    /// elements that aren't inert (along with their descendants). Empty if there is no modal
+    /// dialog.
+    fn modal_top_layer(&self) -> &[NodeId] {
+        match self
+            .top_layer
+            .iter()
+            .rposition(|&node_id| self.is_modal(node_id))
+        {
+            Some(modal_idx) => &self.top_layer[modal_idx..],
+            None => &[],
+        }
+    }
+
+    /// The computed styles of the `::backdrop` of an element in the top layer, which is
+    /// painted over the whole viewport beneath the element
+    pub fn backdrop_styles(&self, node_id: NodeId) -> Option<ServoArc<ComputedValues>> {
+        let node = self.get_node(node_id)?;
+        let primary_styles = node.primary_styles()?;
+        let read_guard = self.guard.read();
+        let guards = StylesheetGuards::same(&read_guard);
+        self.stylist.lazily_compute_pseudo_element_style(
+            &guards,
+            node,
+            &PseudoElement::Backdrop,
+            RuleInclusion::All,
+            &primary_styles,
+            /* is_probe */ false,
+            /* matching_fn */ None,
+        )
+    }
+
+    /// Add an element to the top of the top layer (moving it there if it is already in it)
+    pub(crate) fn add_to_top_layer(&mut self, node_id: NodeId) {
+        self.top_layer.retain(|&id| id != node_id);
+        self.top_layer.push(node_id);
+        self.top_layer_changed();
+    }
+
+    pub(crate) fn remove_from_top_layer(&mut self, node_id: NodeId) {
+        self.top_layer.retain(|&id| id != node_id);
+        self.top_layer_changed();
+    }
+
+    fn top_layer_changed(&mut self) {
+        // Which nodes are inert (and so hidden from assistive technologies) may have changed
+        #[cfg(feature = "accessibility")]
+        {
+            self.accessibility.layout_dirty = true;
+        }
+    }
+
+    /// Hit test the elements in the top layer, which are painted above all other content.
+    ///
+    /// A modal dialog blocks hits on everything beneath it, so a point outside of it (on its
+    /// `::backdrop`) hits the dialog itself. Returns `None` if the point should be hit-tested
+    /// against the rest of the document.
+    pub(crate) fn hit_top_layer(
+        &self,
+        x: f32,
+        y: f32,
+        scrollbar: &mut Option<crate::node::ScrollbarRef>,
+    ) -> Option<HitResult> {
+        let scale = self.viewport().scale_f64();
+        for &node_id in self.top_layer.iter().rev() {
+            let node = &self.nodes[node_id];
+            // Top layer elements are laid out in place, so hit testing starts from the
+            // position of their layout parent
+            if let Some(parent_id) = node.layout_parent.get() {
+                let origin = self.nodes[parent_id].absolute_position(0.0, 0.0);
+                if let Some(hit) = node.hit_inner(x - origin.x, y - origin.y, scale, scrollbar) {
+                    return Some(hit);
+                }
+            }
+
+            if self.is_modal(node_id) {
+                let pos = node.absolute_position(0.0, 0.0);
+                return Some(HitResult {
+                    node_id,
+                    is_text: false,
+                    x: x - pos.x,
+                    y: y - pos.y,
+                });
+            }
+        }
+        None
+    }
+
+    fn nearest_ancestor_dialog(&self, node_id: NodeId) -> Option<NodeId> {
+        let mut next = self.nodes[node_id].parent;
+        while let Some(id) = next {
+            let node = &self.nodes[id];
+            if node.data.is_element_with_tag_name(&local_name!("dialog")) {
+                return Some(id);
+            }
+            next = node.parent;
+        }
+        None
+    }
+
+    /// Whether a node is a `<dialog>` without the `open` attribute
+    fn is_closed_dialog(&self, node_id: NodeId) -> bool {
+        self.get_node(node_id).is_some_and(|node| {
+            node.data.is_element_with_tag_name(&local_name!("dialog"))
+                && !node.has_attr(local_name!("open"))
+        })
+    }
+
+    /// Focus a dialog which has just been shown: its first descendant with the `autofocus`
+    /// attribute, else its first focussable descendant, else the dialog itself
+    ///
+    /// <https://html.spec.whatwg.org/multipage/interactive-elements.html#dialog-focusing-steps>
+    fn focus_dialog(&mut self, dialog_id: NodeId) {
+        let focussable: Vec<NodeId> = TreeTraverser::new_with_root(self, dialog_id)
+            .filter(|&id| id != dialog_id && self.nodes[id].is_focussable())
+            .collect();
+        let control = if self.nodes[dialog_id].has_attr(local_name!("autofocus")) {
+            dialog_id
+        } else {
+            focussable
+                .iter()
+                .copied()
+                .find(|&id| self.nodes[id].has_attr(local_name!("autofocus")))
+                .or(focussable.first().copied())
+                .unwrap_or(dialog_id)
+        };
+        self.set_focus_to(control);
+    }
+}
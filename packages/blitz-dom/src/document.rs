@@ -1,11 +1,14 @@
 use crate::NodeTree;
-use crate::events::{DragMode, handle_dom_event};
+use crate::events::{DragMode, handle_cancelled_dom_event, handle_dom_event};
 use crate::font_metrics::BlitzFontMetricsProvider;
+use crate::image_decode::{DEFAULT_IMAGE_MEMORY_BUDGET, DecodedImages};
 use crate::layout::construct::ConstructionTask;
+use crate::layout::counter_style::CounterStyles;
 use crate::layout::damage::ALL_DAMAGE;
 use crate::mutator::ViewportMut;
 use crate::net::{
-    Resource, ResourceHandler, ResourceLoadResponse, StylesheetHandler, StylesheetLoader,
+    ImageHandler, Resource, ResourceHandler, ResourceLoadResponse, StylesheetHandler,
+    StylesheetLoader,
 };
 use crate::node::{ImageData, NodeFlags, RasterImageData, SpecialElementData, Status, TextBrush};
 use crate::scrolling::ScrollAnimationState;
@@ -16,13 +19,13 @@ use crate::url::DocumentUrl;
 use crate::util::ImageType;
 use crate::{
     DEFAULT_CSS, DocumentConfig, DocumentMutator, DummyHtmlParserProvider, ElementData,
-    EventDriver, HtmlParserProvider, Node, NodeData, NoopEventHandler, StyleThreading,
-    TextNodeData,
+    EventDriver, HtmlParserProvider, ImageDecoding, Node, NodeData, NoopEventHandler,
+    StyleThreading, TextNodeData,
 };
 use blitz_traits::devtools::DevtoolSettings;
-use blitz_traits::events::{DomEvent, HitResult, UiEvent};
+use blitz_traits::events::{BlitzLoadEvent, DomEvent, DomEventData, HitResult, UiEvent};
 use blitz_traits::navigation::{DummyNavigationProvider, NavigationProvider};
-use blitz_traits::net::{AbortSignal, DummyNetProvider, NetProvider, Request};
+use blitz_traits::net::{AbortSignal, Bytes, DummyNetProvider, NetProvider, Request};
 use blitz_traits::node_id::NodeId;
 use blitz_traits::shell::{ColorScheme, DummyShellProvider, ShellProvider, Viewport};
 use cursor_icon::CursorIcon;
@@ -65,6 +68,7 @@ use style::{
     stylesheets::{AllowImportRules, DocumentStyleSheet, Origin, Stylesheet},
     stylist::Stylist,
 };
+use style_dom::ElementState;
 use thin_vec::ThinVec;
 use url::Url;
 use web_time::Instant;
@@ -139,8 +143,11 @@ pub trait Document: Any + 'static {
 
     /// Poll any pending async operations, and flush changes to the underlying [`BaseDocument`]
     fn poll(&mut self, task_context: Option<TaskContext>) -> bool {
-        // Default implementation does nothing
+        // Default implementation just runs the default actions of pending events
         let _ = task_context;
+        let mut doc = self.inner_mut();
+        let mut driver = EventDriver::new(&mut *doc, NoopEventHandler);
+        driver.dispatch_pending_events();
         false
     }
 
@@ -187,6 +194,21 @@ pub enum DocumentEvent {
         node_id: NodeId,
         url: Url,
     },
+    /// The encoded bytes of an `<img>` raster image, sent just before the (undecoded) image
+    /// itself is loaded, which are kept so that it can be decoded at the size it is displayed at
+    EncodedImage {
+        url: String,
+        width: u32,
+        height: u32,
+        bytes: Bytes,
+    },
+    /// An image which has been decoded at `1 / 2^level` of its natural size on the
+    /// worker pool
+    ImageDecoded {
+        url: String,
+        level: u32,
+        result: Result<RasterImageData, String>,
+    },
 }
 
 pub struct BaseDocument {
@@ -204,6 +226,8 @@ pub struct BaseDocument {
     pub(crate) viewport_scroll: crate::Point<f64>,
     /// CSS media type used to evaluate `@media` rules.
     pub(crate) media_type: MediaType,
+    /// Whether the user prefers reduced motion (evaluated by `prefers-reduced-motion`)
+    pub(crate) prefers_reduced_motion: bool,
     /// Strategy for Stylo's style traversal during `resolve`.
     pub(crate) style_threading: StyleThreading,
     /// Whether incremental layout is enabled for this document.
@@ -258,6 +282,18 @@ pub struct BaseDocument {
     pub(crate) last_client_pointer_position: Option<taffy::Point<f32>>,
     /// The node which is currently focussed (if any)
     pub(crate) focus_node_id: Option<NodeId>,
+    /// Whether the user last interacted with the document using the keyboard (rather than a
+    /// pointer), in which case moving focus shows a focus ring (`:focus-visible`)
+    pub(crate) keyboard_modality: bool,
+    /// The `<select>` whose drop-down listbox is currently open (if any)
+    pub(crate) open_select_node_id: Option<NodeId>,
+    /// The elements in the top layer (modal dialogs and open popovers), from bottom to top.
+    /// They are painted and hit-tested above all other content.
+    pub(crate) top_layer: Vec<NodeId>,
+    /// The return values and previously focussed elements of dialogs
+    pub(crate) dialogs: HashMap<NodeId, crate::dialog::DialogState>,
+    /// The popovers which are currently open
+    pub(crate) open_popovers: HashMap<NodeId, crate::popover::PopoverState>,
     /// The node which is currently active (if any)
     pub(crate) active_node_id: Option<NodeId>,
     /// The node which recieved a mousedown event (if any)
@@ -288,6 +324,14 @@ pub struct BaseDocument {
     pub(crate) has_canvas: bool,
     /// Whether there are subdocuments that are animating (so we should re-render every frame)
     pub(crate) subdoc_is_animating: bool,
+    /// `<img>` elements displaying an animated image
+    pub(crate) animated_image_nodes: HashSet<NodeId>,
+    /// When the next frame of a playing animated image (in this document or a subdocument) is
+    /// due, in seconds on the animation clock
+    pub(crate) next_image_frame_time: Option<f64>,
+    /// Boxes with `position: sticky` or `position: fixed`, which are repositioned whenever a
+    /// scroll offset changes. Collected when styles are flushed to layout.
+    pub(crate) sticky_and_fixed_nodes: HashSet<NodeId>,
 
     /// Map of id attribute values to node IDs for fast lookups.
     /// May contain multiple nodes for the same id: `get_element_by_id`
@@ -298,15 +342,31 @@ pub struct BaseDocument {
     /// Stylesheets added by the useragent
     /// where the key is the hashed CSS
     pub(crate) ua_stylesheets: HashMap<String, DocumentStyleSheet>,
+    /// Counter styles defined by `@counter-style` rules in the document's stylesheets
+    pub(crate) counter_styles: CounterStyles,
+    /// Whether stylesheets have been added or removed since `counter_styles` was resolved
+    pub(crate) counter_styles_dirty: bool,
+    /// Whether the tree, or the counter properties or attributes of an element, changed since
+    /// counters were resolved
+    pub(crate) counters_dirty: bool,
     /// Map from form control node ID's to their associated forms node ID's
     pub(crate) controls_to_form: HashMap<NodeId, NodeId>,
+    /// Whether radio buttons may have been checked, added, removed or regrouped since the
+    /// state of their groups was updated
+    pub(crate) radio_groups_dirty: bool,
+    /// Forms and fieldsets whose form controls' validity may have changed since it was last
+    /// checked (see `update_form_validity`)
+    pub(crate) form_validity_dirty: HashSet<NodeId>,
     /// Nodes that contain sub documents
     pub(crate) sub_document_nodes: HashSet<NodeId>,
     /// Load state (abort controller and in-flight request id) for each
     /// `<iframe>` element whose sub-document is loaded automatically
     pub(crate) iframe_loads: HashMap<NodeId, crate::iframe::IframeLoad>,
-    /// Set of changed nodes for updating the accessibility tree
+    /// Set of changed nodes
     pub(crate) changed_nodes: HashSet<NodeId>,
+    /// The accessibility tree as last sent to assistive technology
+    #[cfg(feature = "accessibility")]
+    pub(crate) accessibility: crate::accessibility::AccessibilityCache,
     /// Set of changed nodes for updating the accessibility tree
     pub(crate) deferred_construction_nodes: Vec<ConstructionTask>,
 
@@ -320,16 +380,27 @@ pub struct BaseDocument {
     /// Cache of loaded images, keyed by URL. Allows reusing images across multiple
     /// elements without re-fetching from the network.
     pub(crate) image_cache: HashMap<String, ImageData>,
+    /// Encoded bytes and downscaled decodes of raster images displayed by `<img>` elements
+    pub(crate) decoded_images: DecodedImages,
+    /// Where raster images are decoded
+    pub(crate) image_decoding: ImageDecoding,
 
     /// Tracks in-flight image requests. When an image is being fetched, additional
     /// requests for the same URL are queued here instead of starting new fetches.
     /// Value is a list of (node_id, image_type) pairs waiting for the image.
     pub(crate) pending_images: HashMap<String, Vec<(NodeId, ImageType)>>,
+    /// `<img>` elements whose source depends on the viewport (those with a `srcset` attribute
+    /// or within a `<picture>` element), which are reselected when it changes
+    pub(crate) viewport_dependent_images: HashSet<NodeId>,
 
     // Tracks in-flight "critical" resources (e.g. stylesheets linked from the `<head>`),
     // keyed by request id
     pub(crate) pending_critical_resources: HashSet<usize>,
 
+    /// Events generated by the document itself (e.g. `load` and `error` events for
+    /// resources) which are waiting to be dispatched by an [`EventDriver`](crate::EventDriver)
+    pub(crate) pending_events: Vec<DomEvent>,
+
     // Service providers
     /// Network provider. Can be used to fetch assets.
     pub net_provider: Arc<dyn NetProvider>,
@@ -349,6 +420,7 @@ pub struct BaseDocument {
 pub(crate) fn make_device(
     viewport: &Viewport,
     media_type: MediaType,
+    prefers_reduced_motion: bool,
     font_ctx: Arc<Mutex<FontContext>>,
 ) -> Device {
     let width = viewport.window_size.0 as f32 / viewport.scale();
@@ -357,7 +429,7 @@ pub(crate) fn make_device(
     let device_size = euclid::Size2D::new(width, height) * viewport.scale();
     let device_pixel_ratio = euclid::Scale::new(viewport.scale());
 
-    Device::new(
+    let mut device = Device::new(
         media_type,
         selectors::matching::QuirksMode::NoQuirks,
         viewport_size,
@@ -371,7 +443,9 @@ pub(crate) fn make_device(
         },
         PointerCapabilities::default(),
         PointerCapabilities::default(),
-    )
+    );
+    device.set_prefers_reduced_motion(prefers_reduced_motion);
+    device
 }
 
 impl BaseDocument {
@@ -416,7 +490,13 @@ impl BaseDocument {
 
         let viewport = config.viewport.unwrap_or_default();
         let media_type = config.media_type.unwrap_or_else(MediaType::screen);
-        let device = make_device(&viewport, media_type.clone(), font_ctx.clone());
+        let prefers_reduced_motion = config.prefers_reduced_motion;
+        let device = make_device(
+            &viewport,
+            media_type.clone(),
+            prefers_reduced_motion,
+            font_ctx.clone(),
+        );
         let stylist = Stylist::new(device, QuirksMode::NoQuirks);
         let snapshots = SnapshotMap::new();
         let nodes = Box::new(NodeTree::new());
@@ -457,6 +537,7 @@ impl BaseDocument {
             nodes_to_id,
             viewport,
             media_type,
+            prefers_reduced_motion,
             style_threading: config.style_threading,
             incremental_layout: config.incremental.unwrap_or(true),
             subdocument_depth: config.subdocument_depth,
@@ -475,10 +556,18 @@ impl BaseDocument {
             hover_node_is_text: false,
             last_client_pointer_position: None,
             focus_node_id: None,
+            keyboard_modality: false,
+            open_select_node_id: None,
+            top_layer: Vec::new(),
+            dialogs: HashMap::new(),
+            open_popovers: HashMap::new(),
             active_node_id: None,
             mousedown_node_id: None,
             has_active_animations: false,
             subdoc_is_animating: false,
+            animated_image_nodes: HashSet::new(),
+            sticky_and_fixed_nodes: HashSet::new(),
+            next_image_frame_time: None,
             has_canvas: false,
             sub_document_nodes: HashSet::new(),
             iframe_loads: HashMap::new(),
@@ -489,11 +578,26 @@ impl BaseDocument {
             pending_resource_deallocations: Vec::new(),
 
             changed_nodes: HashSet::new(),
+            #[cfg(feature = "accessibility")]
+            accessibility: Default::default(),
             deferred_construction_nodes: Vec::new(),
             image_cache: HashMap::new(),
+            decoded_images: DecodedImages::new(
+                config
+                    .image_memory_budget
+                    .unwrap_or(DEFAULT_IMAGE_MEMORY_BUDGET),
+            ),
+            image_decoding: config.image_decoding,
             pending_images: HashMap::new(),
+            viewport_dependent_images: HashSet::new(),
             pending_critical_resources: HashSet::new(),
+            pending_events: Vec::new(),
             controls_to_form: HashMap::new(),
+            radio_groups_dirty: true,
+            form_validity_dirty: HashSet::new(),
+            counter_styles: CounterStyles::default(),
+            counter_styles_dirty: true,
+            counters_dirty: true,
             net_provider,
             navigation_provider,
             shell_provider,
@@ -629,6 +733,11 @@ impl BaseDocument {
         handle_dom_event(self, event, dispatch_event)
     }
 
+    /// Handle an event that was cancelled by its handlers (instead of running its default action)
+    pub fn handle_cancelled_dom_event(&mut self, event: &DomEvent) {
+        handle_cancelled_dom_event(self, event)
+    }
+
     pub fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
@@ -677,22 +786,33 @@ impl BaseDocument {
             return false;
         };
         *is_checked = !*is_checked;
+        let is_checked = *is_checked;
+        el.indeterminate = false;
 
-        *is_checked
+        is_checked
     }
 
     pub fn toggle_radio(&mut self, radio_set_name: String, target_radio_id: NodeId) {
+        let mut toggled = Vec::new();
         for (i, node) in self.nodes.iter_mut() {
             if let Some(node_data) = node.data.downcast_element_mut() {
-                if node_data.attr(local_name!("name")) == Some(&radio_set_name) {
+                if node_data.is_radio_button()
+                    && node_data.attr(local_name!("name")) == Some(&radio_set_name)
+                {
                     let was_clicked = i == target_radio_id;
                     let Some(is_checked) = node_data.checkbox_input_checked_mut() else {
                         continue;
                     };
-                    *is_checked = was_clicked;
+                    if *is_checked != was_clicked {
+                        *is_checked = was_clicked;
+                        toggled.push(i);
+                    }
                 }
             }
         }
+        for node_id in toggled {
+            self.invalidate_form_state(node_id);
+        }
     }
 
     /// Toggle the `open` attribute of a `<details>` element, expanding or
@@ -764,6 +884,7 @@ impl BaseDocument {
             .unwrap()
             .remove_sub_document();
         self.sub_document_nodes.remove(&node_id);
+        self.mark_node_changed(node_id);
         if let Some(load) = self.iframe_loads.remove(&node_id) {
             load.abort_controller.abort();
         }
@@ -807,6 +928,7 @@ impl BaseDocument {
             .unwrap()
             .set_custom_widget(widget);
         self.custom_widget_nodes.insert(node_id);
+        self.mark_node_changed(node_id);
     }
 
     #[cfg(feature = "custom-widget")]
@@ -818,6 +940,7 @@ impl BaseDocument {
         self.pending_resource_deallocations
             .extend_from_slice(&resources_to_deallocate);
         self.custom_widget_nodes.remove(&node_id);
+        self.mark_node_changed(node_id);
     }
 
     pub fn root_node(&self) -> &Node {
@@ -849,7 +972,7 @@ impl BaseDocument {
             .insert_with_key(|id| Node::new(tree_ptr, id, guard, node_data));
 
         // Mark the new node as changed.
-        self.changed_nodes.insert(id);
+        self.mark_node_changed(id);
         id
     }
 
@@ -858,6 +981,7 @@ impl BaseDocument {
     /// it so that stale NodeIds are never dereferenced after the slot is freed.
     pub(crate) fn remove_node_from_tree(&mut self, node_id: NodeId) -> Option<Node> {
         self.clear_interaction_state_for_removed_node(node_id);
+        self.dialogs.remove(&node_id);
         self.nodes.remove(node_id)
     }
 
@@ -914,9 +1038,28 @@ impl BaseDocument {
         }
         if self.focus_node_id == Some(node_id) {
             let shell_provider = self.shell_provider.clone();
-            self.nodes[node_id].blur(shell_provider);
+            let node = &mut self.nodes[node_id];
+            node.blur(shell_provider);
+            if let Some(data) = node.element_data_mut() {
+                data.element_state.remove(ElementState::FOCUS_WITHIN);
+            }
+            let parent_id = node.parent;
+            self.update_focus_within(parent_id, None);
             self.focus_node_id = None;
         }
+        if self.open_select_node_id == Some(node_id) {
+            self.open_select_node_id = None;
+        }
+        // An element which is removed from the document leaves the top layer, and is no
+        // longer modal or an open popover
+        if self.top_layer.contains(&node_id) {
+            self.remove_from_top_layer(node_id);
+            self.open_popovers.remove(&node_id);
+            if let Some(data) = self.nodes[node_id].element_data_mut() {
+                data.element_state
+                    .remove(ElementState::MODAL | ElementState::POPOVER_OPEN);
+            }
+        }
         if self.mousedown_node_id == Some(node_id) {
             self.mousedown_node_id = None;
         }
@@ -931,9 +1074,17 @@ impl BaseDocument {
         {
             self.hovered_scrollbar = None;
         }
-        let drag_references_node = match &self.drag_mode {
+        let drag_references_node = match &mut self.drag_mode {
             DragMode::Panning(state) => state.target == node_id,
             DragMode::ScrollbarDrag(state) => state.scrollbar.node_id == node_id,
+            DragMode::DragAndDrop(state) => {
+                // Dragging continues if the element under the pointer is removed
+                if state.target == Some(node_id) {
+                    state.target = None;
+                    state.accepted = false;
+                }
+                state.source == Some(node_id)
+            }
             DragMode::Selecting | DragMode::None => false,
         };
         if drag_references_node {
@@ -996,7 +1147,15 @@ impl BaseDocument {
 
     /// Whether the document has been mutated
     pub fn has_changes(&self) -> bool {
-        self.changed_nodes.is_empty()
+        !self.changed_nodes.is_empty()
+    }
+
+    /// Record that a node has changed in a way that may affect its accessibility node
+    /// (or those of the nodes whose names are computed from it)
+    pub(crate) fn mark_node_changed(&mut self, node_id: NodeId) {
+        self.changed_nodes.insert(node_id);
+        #[cfg(feature = "accessibility")]
+        self.accessibility.dirty_nodes.insert(node_id);
     }
 
     pub fn create_text_node(&mut self, text: &str) -> NodeId {
@@ -1125,6 +1284,7 @@ impl BaseDocument {
     pub fn remove_user_agent_stylesheet(&mut self, contents: &str) {
         if let Some(sheet) = self.ua_stylesheets.remove(contents) {
             self.stylist.remove_stylesheet(sheet, &self.guard.read());
+            self.counter_styles_dirty = true;
         }
     }
 
@@ -1148,6 +1308,7 @@ impl BaseDocument {
         let sheet = self.make_stylesheet(css, Origin::UserAgent);
         self.ua_stylesheets.insert(css.to_string(), sheet.clone());
         self.stylist.append_stylesheet(sheet, &self.guard.read());
+        self.counter_styles_dirty = true;
     }
 
     pub fn make_stylesheet(&self, css: impl AsRef<str>, origin: Origin) -> DocumentStyleSheet {
@@ -1184,6 +1345,7 @@ impl BaseDocument {
         if let Some(old) = old {
             self.stylist.remove_stylesheet(old, &self.guard.read())
         }
+        self.counter_styles_dirty = true;
 
         // Fetch @font-face fonts
         crate::net::fetch_font_face(
@@ -1237,6 +1399,17 @@ impl BaseDocument {
         match msg {
             DocumentEvent::ResourceLoad(resource) => self.load_resource(resource),
             DocumentEvent::NavigateIframe { node_id, url } => self.navigate_iframe(node_id, url),
+            DocumentEvent::EncodedImage {
+                url,
+                width,
+                height,
+                bytes,
+            } => self
+                .decoded_images
+                .insert_encoded(url, width, height, bytes),
+            DocumentEvent::ImageDecoded { url, level, result } => {
+                self.apply_decoded_image(url, level, result)
+            }
         }
     }
 
@@ -1245,6 +1418,18 @@ impl BaseDocument {
         !self.pending_critical_resources.is_empty()
     }
 
+    /// Queue an event generated by the document to be dispatched on the next
+    /// [`poll`](Document::poll)
+    pub(crate) fn queue_event(&mut self, target: NodeId, data: DomEventData) {
+        self.pending_events.push(DomEvent::new(target, data));
+    }
+
+    /// Take the events generated by the document (e.g. `load` and `error` events for resources)
+    /// which have not yet been dispatched
+    pub fn take_pending_events(&mut self) -> Vec<DomEvent> {
+        std::mem::take(&mut self.pending_events)
+    }
+
     pub fn load_resource(&mut self, res: ResourceLoadResponse) {
         self.pending_critical_resources.remove(&res.request_id);
 
@@ -1252,22 +1437,19 @@ impl BaseDocument {
             Ok(resource) => resource,
             Err(err) => {
                 if let Some(url) = res.resolved_url.as_ref() {
-                    let waiting_nodes = self.pending_images.remove(url).unwrap_or_default();
                     #[cfg(feature = "tracing")]
                     tracing::warn!(
                         url = url.as_str(),
-                        waiting_nodes = waiting_nodes.len(),
                         error = err.as_str(),
                         "Resource load failed"
                     );
-                    #[cfg(not(feature = "tracing"))]
-                    let _ = (waiting_nodes, err);
+                    self.apply_failed_resource(url, res.node_id);
                 } else {
                     #[cfg(feature = "tracing")]
                     tracing::warn!(error = err.as_str(), "Resource load failed (no url)");
-                    #[cfg(not(feature = "tracing"))]
-                    let _ = err;
                 }
+                #[cfg(not(feature = "tracing"))]
+                let _ = err;
                 return;
             }
         };
@@ -1276,10 +1458,30 @@ impl BaseDocument {
             Resource::Css(css) => {
                 let node_id = res.node_id.unwrap();
                 self.add_stylesheet_for_node(css, node_id);
+                if let Some(url) = res.resolved_url {
+                    self.queue_event(node_id, DomEventData::Load(BlitzLoadEvent { url }));
+                }
             }
             Resource::Image(_kind, width, height, image_data) => {
+                let Some(url) = res.resolved_url.as_ref() else {
+                    return;
+                };
+
+                // `<img>` images arrive without pixels, and are decoded once they have been
+                // laid out (see `resolve_image_decodes`)
+                if image_data.is_empty() {
+                    let image = RasterImageData::evicted(width, height);
+                    self.apply_undecoded_image(url, ImageData::Raster(image));
+                    return;
+                }
+
                 // Create the ImageData and cache it
-                let image = ImageData::Raster(RasterImageData::new(width, height, image_data));
+                let image = RasterImageData::new(width, height, image_data);
+                self.apply_loaded_image(url, ImageData::Raster(image));
+            }
+            Resource::AnimatedImage(_kind, width, height, first_frame, bytes) => {
+                let image =
+                    ImageData::Raster(RasterImageData::animated(width, height, first_frame, bytes));
 
                 let Some(url) = res.resolved_url.as_ref() else {
                     return;
@@ -1349,8 +1551,87 @@ impl BaseDocument {
         }
     }
 
+    /// Mark all nodes waiting on a failed resource as broken and fire `error` events
+    /// at the `<img>` and `<link>` elements among them.
+    fn apply_failed_resource(&mut self, url: &str, node_id: Option<NodeId>) {
+        let waiting_nodes = self.pending_images.remove(url).unwrap_or_default();
+        for (node_id, image_type) in waiting_nodes {
+            let Some(element) = self
+                .get_node_mut(node_id)
+                .and_then(|n| n.element_data_mut())
+            else {
+                continue;
+            };
+            match image_type {
+                ImageType::Image => {
+                    if element
+                        .image_source
+                        .as_ref()
+                        .is_some_and(|source| source.url != url)
+                    {
+                        continue;
+                    }
+                    element.image_status = Some(Status::Error);
+                    let url = url.to_string();
+                    self.queue_event(node_id, DomEventData::Error(BlitzLoadEvent { url }));
+                }
+                ImageType::Background(idx) => {
+                    if let Some(Some(layer_image)) = element.background_images.get_mut(idx) {
+                        layer_image.status = Status::Error;
+                    }
+                }
+                ImageType::Mask(idx) => {
+                    if let Some(Some(layer_image)) = element.mask_images.get_mut(idx) {
+                        layer_image.status = Status::Error;
+                    }
+                }
+            }
+        }
+
+        // A failed `<link rel="stylesheet">`
+        let is_link = node_id
+            .and_then(|node_id| self.get_node(node_id))
+            .is_some_and(|node| node.data.is_element_with_tag_name(&local_name!("link")));
+        if let (Some(node_id), true) = (node_id, is_link) {
+            let url = url.to_string();
+            self.queue_event(node_id, DomEventData::Error(BlitzLoadEvent { url }));
+        }
+    }
+
     /// Cache a loaded image and apply it to all nodes waiting on it
     /// (`<img>` elements, `background-image` layers and `mask-image` layers).
+    /// Apply an `<img>` image which hasn't been decoded yet to the `<img>` elements waiting
+    /// for it. Background and mask layers waiting for it fetch it again
+    /// so that it is decoded for them.
+    fn apply_undecoded_image(&mut self, url: &str, image: ImageData) {
+        let waiting_nodes = self.pending_images.remove(url).unwrap_or_default();
+        let (images, layers): (Vec<_>, Vec<_>) = waiting_nodes
+            .into_iter()
+            .partition(|(_, image_type)| matches!(image_type, ImageType::Image));
+
+        self.pending_images.insert(url.to_string(), images);
+        self.apply_loaded_image(url, image);
+
+        let Some((_, image_type)) = layers.first().copied() else {
+            return;
+        };
+        let Ok(parsed_url) = url::Url::parse(url) else {
+            return;
+        };
+        self.pending_images.insert(url.to_string(), layers);
+        self.net_provider.fetch(
+            self.id(),
+            self.build_request(parsed_url),
+            ResourceHandler::boxed(
+                self.tx.clone(),
+                self.id(),
+                None, // Don't pass node_id, we'll handle it via pending_images
+                self.shell_provider.clone(),
+                ImageHandler::new(image_type, url.to_string(), self.image_decoding),
+            ),
+        );
+    }
+
     fn apply_loaded_image(&mut self, url: &str, image: ImageData) {
         // Get all nodes waiting for this image
         let waiting_nodes = self.pending_images.remove(url).unwrap_or_default();
@@ -1361,8 +1642,11 @@ impl BaseDocument {
             waiting_nodes.len()
         );
 
-        // Cache the image
-        self.image_cache.insert(url.to_string(), image.clone());
+        // Cache the image (`<img>` images which haven't been decoded yet are found through
+        // `decoded_images` instead)
+        if !matches!(&image, ImageData::Raster(raster) if raster.is_evicted()) {
+            self.image_cache.insert(url.to_string(), image.clone());
+        }
 
         // Apply to all waiting nodes
         for (node_id, image_type) in waiting_nodes {
@@ -1372,12 +1656,29 @@ impl BaseDocument {
 
             match image_type {
                 ImageType::Image => {
-                    node.element_data_mut().unwrap().special_data =
-                        SpecialElementData::Image(Box::new(image.clone()));
+                    let element = node.element_data_mut().unwrap();
+
+                    // Skip `<img>` elements which have since selected a different image (or
+                    // no image at all)
+                    if element
+                        .image_source
+                        .as_ref()
+                        .is_none_or(|source| source.url != url)
+                    {
+                        continue;
+                    }
+
+                    element.special_data = SpecialElementData::Image(Box::new(image.clone()));
+                    element.image_status = Some(Status::Ok);
 
                     // Clear layout cache
                     node.cache_mut().clear();
                     node.insert_damage(ALL_DAMAGE);
+                    self.track_image_animation(node_id);
+                    self.track_image_decode(node_id);
+
+                    let url = url.to_string();
+                    self.queue_event(node_id, DomEventData::Load(BlitzLoadEvent { url }));
                 }
                 ImageType::Background(idx) | ImageType::Mask(idx) => {
                     let layer_image = node.element_data_mut().and_then(|el| {
@@ -1566,7 +1867,9 @@ impl BaseDocument {
 
     pub fn focus_next_node(&mut self) -> Option<NodeId> {
         let focussed_node_id = self.get_focussed_node_id()?;
-        let id = self.next_node(&self.nodes[focussed_node_id], |node| node.is_focussable())?;
+        let id = self.next_node(&self.nodes[focussed_node_id], |node| {
+            node.is_focussable() && !self.is_inert(node.id)
+        })?;
         self.set_focus_to(id);
         Some(id)
     }
@@ -1574,17 +1877,25 @@ impl BaseDocument {
     /// Move focus to the previous focussable node in the document
     pub fn focus_prev_node(&mut self) -> Option<NodeId> {
         let focussed_node_id = self.get_focussed_node_id()?;
-        let id = self.prev_node(&self.nodes[focussed_node_id], |node| node.is_focussable())?;
+        let id = self.prev_node(&self.nodes[focussed_node_id], |node| {
+            node.is_focussable() && !self.is_inert(node.id)
+        })?;
         self.set_focus_to(id);
         Some(id)
     }
 
     /// Clear the focussed node
     pub fn clear_focus(&mut self) {
+        self.close_select();
         if let Some(id) = self.focus_node_id {
             let shell_provider = self.shell_provider.clone();
             self.snapshot_node_and(id, |node| node.blur(shell_provider));
+            self.update_focus_within(Some(id), None);
             self.focus_node_id = None;
+            #[cfg(feature = "accessibility")]
+            {
+                self.accessibility.focus_dirty = true;
+            }
         }
     }
 
@@ -1598,25 +1909,76 @@ impl BaseDocument {
         if Some(focus_node_id) == self.focus_node_id {
             return false;
         }
+        // Nodes blocked by a modal dialog can't be focussed
+        if self.is_inert(focus_node_id) {
+            return false;
+        }
 
         #[cfg(feature = "tracing")]
         tracing::info!("Focussed node {focus_node_id}");
 
         let shell_provider = self.shell_provider.clone();
 
+        // Moving focus away from a select dismisses its drop-down listbox
+        if self.open_select_node_id != Some(focus_node_id) {
+            self.close_select();
+        }
+
         // Remove focus from the old node
         if let Some(id) = self.focus_node_id {
             self.snapshot_node_and(id, |node| node.blur(shell_provider.clone()));
         }
 
-        // Focus the new node
-        self.snapshot_node_and(focus_node_id, |node| node.focus(shell_provider));
+        // Focus the new node. Focus is shown when it is moved using the keyboard, and always
+        // for text inputs (which take keyboard input however they are focussed).
+        let focus_visible = self.keyboard_modality
+            || self.nodes[focus_node_id]
+                .element_data()
+                .is_some_and(|el| el.text_input_data().is_some());
+        self.snapshot_node_and(focus_node_id, |node| {
+            node.focus_with_visibility(focus_visible, shell_provider)
+        });
+        self.update_focus_within(self.focus_node_id, Some(focus_node_id));
 
         self.focus_node_id = Some(focus_node_id);
+        #[cfg(feature = "accessibility")]
+        {
+            self.accessibility.focus_dirty = true;
+        }
 
         true
     }
 
+    /// Move the `:focus-within` state from the old focussed node and its ancestors to the new
+    /// one's, restyling only the elements whose state changes
+    fn update_focus_within(&mut self, old_focus: Option<NodeId>, new_focus: Option<NodeId>) {
+        let ancestors = |mut next: Option<NodeId>| {
+            let mut path = Vec::new();
+            while let Some(node) = next.and_then(|id| self.get_node(id)) {
+                path.push(node.id);
+                next = node.parent;
+            }
+            path
+        };
+        let old_path = ancestors(old_focus);
+        let new_path = ancestors(new_focus);
+
+        for &id in old_path.iter().filter(|id| !new_path.contains(id)) {
+            self.snapshot_node_and(id, |node| {
+                if let Some(data) = node.element_data_mut() {
+                    data.element_state.remove(ElementState::FOCUS_WITHIN);
+                }
+            });
+        }
+        for &id in new_path.iter().filter(|id| !old_path.contains(id)) {
+            self.snapshot_node_and(id, |node| {
+                if let Some(data) = node.element_data_mut() {
+                    data.element_state.insert(ElementState::FOCUS_WITHIN);
+                }
+            });
+        }
+    }
+
     pub fn active_node(&mut self) -> bool {
         let Some(hover_node_id) = self.get_hover_node_id() else {
             return false;
@@ -1725,7 +2087,18 @@ impl BaseDocument {
             tracing::warn!("No DOM - not resolving hit test");
             return (None, None);
         }
+        // The open drop-down listbox of a <select> is painted above all other content
+        if let Some(hit) = self.hit_open_select_listbox(x, y) {
+            return (Some(hit), None);
+        }
+
         let mut scrollbar = None;
+        // Elements in the top layer are painted above everything else, and a modal dialog
+        // makes the content beneath it inert
+        if let Some(hit) = self.hit_top_layer(x, y, &mut scrollbar) {
+            return (Some(hit), scrollbar);
+        }
+
         let hit = self
             .root_element()
             .hit_inner(x, y, self.viewport().scale_f64(), &mut scrollbar);
@@ -1862,10 +2235,12 @@ impl BaseDocument {
 
     pub fn set_viewport(&mut self, viewport: Viewport) {
         let scale_has_changed = viewport.scale_f64() != self.viewport.scale_f64();
+        let size_has_changed = viewport.window_size != self.viewport.window_size;
         self.viewport = viewport;
         self.set_stylist_device(make_device(
             &self.viewport,
             self.media_type.clone(),
+            self.prefers_reduced_motion,
             self.font_ctx.clone(),
         ));
         self.scroll_viewport_by(0.0, 0.0); // Clamp scroll offset
@@ -1874,6 +2249,11 @@ impl BaseDocument {
             self.invalidate_inline_contexts();
             self.shell_provider.request_redraw();
         }
+
+        // `srcset` and `<picture>` selection depends on the viewport's scale and size
+        if scale_has_changed || size_has_changed {
+            self.reselect_image_sources();
+        }
     }
 
     /// Returns the current CSS media type used to evaluate `@media` rules.
@@ -1891,8 +2271,30 @@ impl BaseDocument {
         self.set_stylist_device(make_device(
             &self.viewport,
             self.media_type.clone(),
+            self.prefers_reduced_motion,
+            self.font_ctx.clone(),
+        ));
+    }
+
+    /// Whether the user prefers reduced motion
+    pub fn prefers_reduced_motion(&self) -> bool {
+        self.prefers_reduced_motion
+    }
+
+    /// Sets whether the user prefers reduced motion, which pauses animated images and is
+    /// evaluated by the `prefers-reduced-motion` media query
+    pub fn set_prefers_reduced_motion(&mut self, prefers_reduced_motion: bool) {
+        if self.prefers_reduced_motion == prefers_reduced_motion {
+            return;
+        }
+        self.prefers_reduced_motion = prefers_reduced_motion;
+        self.set_stylist_device(make_device(
+            &self.viewport,
+            self.media_type.clone(),
+            self.prefers_reduced_motion,
             self.font_ctx.clone(),
         ));
+        self.shell_provider.request_redraw();
     }
 
     pub fn viewport(&self) -> &Viewport {
@@ -1927,6 +2329,12 @@ impl BaseDocument {
         self.incremental_layout = enabled;
     }
 
+    /// Sets the number of bytes of decoded image pixels to keep before the pixels of
+    /// out of view `<img>` elements are evicted (`usize::MAX` for no limit).
+    pub fn set_image_memory_budget(&mut self, budget: usize) {
+        self.decoded_images.memory_budget = budget;
+    }
+
     pub fn devtools(&self) -> &DevtoolSettings {
         &self.devtool_settings
     }
@@ -1947,6 +2355,14 @@ impl BaseDocument {
             .and_then(|el| el.sub_doc_data_mut())
     }
 
+    /// The time (in seconds on the animation clock passed to [`BaseDocument::resolve`]) at
+    /// which the document next needs to be redrawn to show a new frame of an animated image,
+    /// if any are playing. Unlike [`BaseDocument::is_animating`], this doesn't require a
+    /// redraw every frame.
+    pub fn next_animation_frame_time(&self) -> Option<f64> {
+        self.next_image_frame_time
+    }
+
     pub fn is_animating(&self) -> bool {
         #[cfg(feature = "custom-widget")]
         let custom_widget_is_animating = self.custom_widget_nodes.iter().any(|&node_id| {
@@ -2065,6 +2481,7 @@ impl BaseDocument {
 
     pub fn set_viewport_scroll(&mut self, scroll: crate::Point<f64>) {
         self.viewport_scroll = scroll;
+        self.resolve_scroll_positions();
     }
 
     /// Find the node targeted by a URL fragment (the `#...` part of a URL).
@@ -2492,6 +2909,62 @@ impl BaseDocument {
         }
     }
 
+    /// Get the selected content as an HTML fragment, with one line per selected block. Blocks
+    /// whose text is selected in full are serialized with [`Node::outer_html`] (keeping their
+    /// markup), while partially selected blocks are serialized as their element containing
+    /// just the selected text. Anonymous blocks contribute just their selected text.
+    pub fn get_selected_html(&self) -> Option<String> {
+        let mut result = String::new();
+        for (node_id, start, end) in self.get_text_selection_ranges() {
+            let Some(node) = self.get_node(node_id) else {
+                continue;
+            };
+            let Some(element_data) = node.element_data() else {
+                continue;
+            };
+            let Some(text) = element_data
+                .inline_layout_data
+                .as_ref()
+                .map(|inline_layout| inline_layout.text.as_str())
+            else {
+                continue;
+            };
+            let Some(selected_text) = text.get(start..end) else {
+                continue;
+            };
+
+            if !result.is_empty() {
+                result.push('\n');
+            }
+            if node.is_anonymous() {
+                html_escape::encode_text_to_string(selected_text, &mut result);
+            } else if start == 0 && end == text.len() {
+                node.write_outer_html(&mut result);
+            } else {
+                result.push('<');
+                result.push_str(&element_data.name.local);
+                for attr in element_data.attrs() {
+                    result.push(' ');
+                    result.push_str(&attr.name.local);
+                    result.push_str("=\"");
+                    html_escape::encode_quoted_attribute_to_string(&attr.value, &mut result);
+                    result.push('"');
+                }
+                result.push('>');
+                html_escape::encode_text_to_string(selected_text, &mut result);
+                result.push_str("</");
+                result.push_str(&element_data.name.local);
+                result.push('>');
+            }
+        }
+
+        if result.is_empty() {
+            None
+        } else {
+            Some(result)
+        }
+    }
+
     /// Get all selection ranges as Vec<(node_id, start_offset, end_offset)>.
     /// Returns empty vec if no selection.
     pub fn get_text_selection_ranges(&self) -> Vec<(NodeId, usize, usize)> {
@@ -2758,7 +3231,7 @@ mod font_face_override_tests {
             node_id: None,
             resolved_url: Some(String::from("test://aliased-family")),
             result: Ok(Resource::Font(
-                blitz_traits::net::Bytes::from_static(crate::BULLET_FONT),
+                Bytes::from_static(crate::BULLET_FONT),
                 FontFaceOverrides {
                     family_name: Some(String::from(ALIAS)),
                     weight: Some(800.0),
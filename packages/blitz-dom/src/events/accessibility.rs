@@ -0,0 +1,86 @@
+use blitz_traits::events::{AccessibilityAction, DomEvent, InputType};
+use blitz_traits::node_id::NodeId;
+use keyboard_types::Modifiers;
+use markup5ever::local_name;
+
+use crate::{BaseDocument, ScrollBehavior, ScrollLogicalPosition, node::GeneratedTextInputEvent};
+
+use super::focus::generate_focus_events;
+
+/// Perform an action requested by assistive technology. Actions are performed the same way as
+/// the equivalent user input, so `Click` fires a `click` event (whose default action activates
+/// the element) and `SetValue` fires `beforeinput` and `input` events.
+pub(crate) fn handle_accessibility_action(
+    doc: &mut BaseDocument,
+    target: NodeId,
+    action: AccessibilityAction,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    // The node may have been removed since the accessibility tree was built
+    let Some(node) = doc.get_node(target) else {
+        return;
+    };
+
+    match action {
+        AccessibilityAction::Click => {
+            let event = node.synthetic_click_event(Modifiers::empty());
+            dispatch_event(DomEvent::new(target, event));
+        }
+        AccessibilityAction::Focus => {
+            if node.is_focussable() {
+                generate_focus_events(
+                    doc,
+                    &mut |doc| {
+                        doc.set_focus_to(target);
+                    },
+                    dispatch_event,
+                );
+                doc.shell_provider.request_redraw();
+            }
+        }
+        AccessibilityAction::ScrollIntoView => {
+            doc.scroll_into_view(
+                target,
+                ScrollBehavior::Instant,
+                ScrollLogicalPosition::Nearest,
+                ScrollLogicalPosition::Nearest,
+            );
+            doc.shell_provider.request_redraw();
+        }
+        AccessibilityAction::SetValue(value) => {
+            set_text_input_value(doc, target, &value, dispatch_event);
+        }
+    }
+}
+
+/// Replace the value of a text input as if the user had edited it
+fn set_text_input_value(
+    doc: &mut BaseDocument,
+    target: NodeId,
+    value: &str,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    let Some(el) = doc.nodes[target].element_data_mut() else {
+        return;
+    };
+    if el.has_attr(local_name!("disabled")) || el.has_attr(local_name!("readonly")) {
+        return;
+    }
+    let Some(input_data) = el.text_input_data_mut() else {
+        return;
+    };
+
+    // The new value replaces the whole of the old one
+    input_data
+        .editor
+        .driver(&mut doc.font_ctx.lock().unwrap(), &mut doc.layout_ctx)
+        .select_all();
+    doc.apply_generated_text_input_event(
+        target,
+        GeneratedTextInputEvent::BeforeInput(
+            InputType::InsertReplacementText,
+            Some(value.to_string()),
+        ),
+        dispatch_event,
+    );
+}
@@ -0,0 +1,168 @@
+use blitz_traits::events::{
+    BlitzClipboardEvent, BlitzKeyEvent, DataTransfer, DomEvent, DomEventData, InputType,
+};
+use blitz_traits::node_id::NodeId;
+use blitz_traits::shell::ClipboardContents;
+use keyboard_types::Key;
+use markup5ever::local_name;
+
+use crate::{BaseDocument, node::GeneratedTextInputEvent, util::ACTION_MOD};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ClipboardAction {
+    Copy,
+    Cut,
+    Paste,
+}
+
+/// The clipboard action triggered by a key press (Ctrl/Cmd + C, X or V)
+pub(crate) fn clipboard_shortcut(event: &BlitzKeyEvent) -> Option<ClipboardAction> {
+    if !event.state.is_pressed() || !event.modifiers.contains(ACTION_MOD) {
+        return None;
+    }
+    let Key::Character(c) = &event.key else {
+        return None;
+    };
+    match c.to_lowercase().as_str() {
+        "c" => Some(ClipboardAction::Copy),
+        "x" => Some(ClipboardAction::Cut),
+        "v" => Some(ClipboardAction::Paste),
+        _ => None,
+    }
+}
+
+/// Fire the `copy`, `cut` or `paste` event for `action` at `target`. The `DataTransfer` of
+/// a `paste` event holds the contents of the clipboard, although its image is only read if a
+/// handler asks for it.
+pub(crate) fn fire_clipboard_event(
+    doc: &mut BaseDocument,
+    target: NodeId,
+    action: ClipboardAction,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    let data = match action {
+        ClipboardAction::Copy | ClipboardAction::Cut => DataTransfer::new(),
+        ClipboardAction::Paste => {
+            let data = match doc.shell_provider.get_clipboard_contents() {
+                Ok(contents) => DataTransfer::from_clipboard_contents(contents),
+                Err(_) => DataTransfer::new(),
+            };
+            if data.image().is_none() {
+                let shell_provider = doc.shell_provider.clone();
+                data.set_image_with(move || shell_provider.get_clipboard_image().ok());
+            }
+            data
+        }
+    };
+    let event = BlitzClipboardEvent {
+        data_transfer: data,
+    };
+    let data = match action {
+        ClipboardAction::Copy => DomEventData::Copy(event),
+        ClipboardAction::Cut => DomEventData::Cut(event),
+        ClipboardAction::Paste => DomEventData::Paste(event),
+    };
+    dispatch_event(DomEvent::new(target, data));
+}
+
+/// Whether `node_id` is a text input that can be edited by cutting and pasting
+fn is_editable_text_input(doc: &BaseDocument, node_id: NodeId) -> bool {
+    doc.nodes[node_id].element_data().is_some_and(|el| {
+        el.text_input_data().is_some()
+            && !el.has_attr(local_name!("disabled"))
+            && !el.has_attr(local_name!("readonly"))
+    })
+}
+
+/// The content copied (or cut) from `target`: the selected text of a text input (except for
+/// password inputs, whose value can't be copied) or else the document's selection as both
+/// text and HTML.
+fn selected_contents(doc: &BaseDocument, target: NodeId) -> Option<ClipboardContents> {
+    if let Some(el) = doc.nodes[target].element_data()
+        && let Some(input_data) = el.text_input_data()
+    {
+        if el.attr(local_name!("type")) == Some("password") {
+            return None;
+        }
+        let text = input_data.editor.selected_text()?;
+        return Some(ClipboardContents {
+            text: Some(text.to_string()),
+            ..Default::default()
+        });
+    }
+
+    Some(ClipboardContents {
+        text: Some(doc.get_selected_text()?),
+        html: doc.get_selected_html(),
+        image: None,
+    })
+}
+
+/// The default action of `copy`: write the selection to the clipboard
+pub(crate) fn handle_copy(doc: &mut BaseDocument, target: NodeId) {
+    if let Some(contents) = selected_contents(doc, target) {
+        let _ = doc.shell_provider.set_clipboard_contents(contents);
+    }
+}
+
+/// The default action of `cut`: write the selection to the clipboard, and remove it from the
+/// text input it's in (if any) with a `deleteByCut` edit
+pub(crate) fn handle_cut(
+    doc: &mut BaseDocument,
+    target: NodeId,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    let Some(contents) = selected_contents(doc, target) else {
+        return;
+    };
+    let _ = doc.shell_provider.set_clipboard_contents(contents);
+
+    if !is_editable_text_input(doc, target) {
+        return;
+    }
+    doc.apply_generated_text_input_event(
+        target,
+        GeneratedTextInputEvent::BeforeInput(InputType::DeleteByCut, None),
+        dispatch_event,
+    );
+}
+
+/// The default action of `paste`: insert the event's `text/plain` data into the text input
+/// that it targets (with an `insertFromPaste` edit). Handlers can change what is inserted by
+/// replacing that data.
+pub(crate) fn handle_paste(
+    doc: &mut BaseDocument,
+    target: NodeId,
+    event: &BlitzClipboardEvent,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    if !is_editable_text_input(doc, target) {
+        return;
+    }
+    let Some(mut text) = event.data_transfer.get_data("text/plain") else {
+        return;
+    };
+    let is_multiline = doc.nodes[target]
+        .element_data()
+        .and_then(|el| el.text_input_data())
+        .is_some_and(|input_data| input_data.is_multiline);
+    // Line breaks can't be pasted into single line inputs
+    if !is_multiline {
+        text.retain(|c| c != '\n' && c != '\r');
+    }
+
+    doc.apply_generated_text_input_event(
+        target,
+        GeneratedTextInputEvent::BeforeInput(InputType::InsertFromPaste, Some(text)),
+        dispatch_event,
+    );
+}
+
+/// Cancelling a `copy` or `cut` event writes the data that its handlers added to the event's
+/// `DataTransfer` to the clipboard (instead of the selection)
+pub(crate) fn handle_cancelled_copy_or_cut(doc: &mut BaseDocument, event: &BlitzClipboardEvent) {
+    let contents = event.data_transfer.to_clipboard_contents();
+    if contents != ClipboardContents::default() {
+        let _ = doc.shell_provider.set_clipboard_contents(contents);
+    }
+}
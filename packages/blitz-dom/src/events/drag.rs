@@ -0,0 +1,296 @@
+use blitz_traits::events::{
+    BlitzDragEvent, BlitzFileDragEvent, BlitzPointerEvent, DataTransfer, DomEvent, DomEventData,
+    DropEffect, PointerCoords, UiEvent,
+};
+use blitz_traits::node_id::NodeId;
+use keyboard_types::Modifiers;
+use markup5ever::local_name;
+
+use crate::BaseDocument;
+
+use super::DragMode;
+
+/// The state of an in-progress drag-and-drop operation
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DragAndDropState {
+    /// The element being dragged, or `None` for files dragged in from outside of the application
+    pub(crate) source: Option<NodeId>,
+    pub(crate) data_transfer: DataTransfer,
+    /// Whether the drag has started. Set by the default action of `dragstart`, so a drag whose
+    /// `dragstart` event was cancelled never starts.
+    pub(crate) started: bool,
+    /// The element under the pointer (the target of the last `dragenter` event)
+    pub(crate) target: Option<NodeId>,
+    /// Whether the current target accepts the drop. This is set before each `dragover` event
+    /// (cancelling `dragover` accepts the drop) and reset by its default action unless the
+    /// target accepts drops by default.
+    pub(crate) accepted: bool,
+    /// The position of the pointer and the pressed modifiers at the last drag event
+    pub(crate) coords: PointerCoords,
+    pub(crate) mods: Modifiers,
+}
+
+impl DragAndDropState {
+    fn event_data(&self) -> BlitzDragEvent {
+        BlitzDragEvent {
+            coords: self.coords,
+            mods: self.mods,
+            data_transfer: self.data_transfer.clone(),
+        }
+    }
+}
+
+/// The element dragged when a drag starts on `node_id`: its nearest inclusive ancestor with
+/// `draggable="true"`, unless an element with `draggable="false"` is found first.
+///
+/// TODO: `<img>` elements and links are draggable by default
+pub(crate) fn draggable_element(doc: &BaseDocument, node_id: NodeId) -> Option<NodeId> {
+    let mut current = Some(node_id);
+    while let Some(id) = current {
+        let node = &doc.nodes[id];
+        if let Some(el) = node.element_data() {
+            match el.attr(local_name!("draggable")) {
+                Some("true") => return Some(id),
+                Some("false") => return None,
+                _ => {}
+            }
+        }
+        current = node.parent;
+    }
+    None
+}
+
+/// Start dragging `source`. The drag only proceeds if the `dragstart` event isn't cancelled.
+pub(crate) fn start_drag(
+    doc: &mut BaseDocument,
+    source: NodeId,
+    event: &BlitzPointerEvent,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    // The mousedown that started the drag may also have started a text selection
+    doc.clear_text_selection();
+
+    let state = DragAndDropState {
+        source: Some(source),
+        data_transfer: DataTransfer::new(),
+        started: false,
+        target: None,
+        accepted: false,
+        coords: event.coords,
+        mods: event.mods,
+    };
+    dispatch_event(DomEvent::new(
+        source,
+        DomEventData::DragStart(state.event_data()),
+    ));
+    doc.drag_mode = DragMode::DragAndDrop(state);
+}
+
+/// The element under page coordinates `(x, y)` that drag events are fired at
+fn drag_target_at(doc: &BaseDocument, x: f32, y: f32) -> Option<NodeId> {
+    match doc.hit(x, y) {
+        Some(hit) => doc.nearest_non_anonymous_ancestor(hit.node_id),
+        None => doc.try_root_element().map(|root| root.id),
+    }
+}
+
+/// Continue a drag as the pointer moves: fire `drag` at the source, `dragenter` and `dragleave`
+/// if the pointer moved onto a different element, and `dragover` at the element under the
+/// pointer.
+pub(crate) fn handle_drag_move(
+    doc: &mut BaseDocument,
+    coords: PointerCoords,
+    mods: Modifiers,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    let target = drag_target_at(doc, coords.page_x, coords.page_y);
+    let DragMode::DragAndDrop(state) = &mut doc.drag_mode else {
+        return;
+    };
+    if !state.started {
+        return;
+    }
+    state.coords = coords;
+    state.mods = mods;
+
+    if let Some(source) = state.source {
+        dispatch_event(DomEvent::new(
+            source,
+            DomEventData::Drag(state.event_data()),
+        ));
+    }
+
+    if state.target != target {
+        if let Some(new_target) = target {
+            dispatch_event(DomEvent::new(
+                new_target,
+                DomEventData::DragEnter(state.event_data()),
+            ));
+        }
+        if let Some(old_target) = state.target {
+            dispatch_event(DomEvent::new(
+                old_target,
+                DomEventData::DragLeave(state.event_data()),
+            ));
+        }
+        state.target = target;
+    }
+
+    if let Some(target) = target {
+        // Drops are accepted if `dragover` is cancelled, unless its handler sets the drop
+        // effect to `none`
+        state.data_transfer.set_drop_effect(DropEffect::Copy);
+        state.accepted = true;
+        dispatch_event(DomEvent::new(
+            target,
+            DomEventData::DragOver(state.event_data()),
+        ));
+    }
+}
+
+/// Finish a drag: fire `drop` at the current target if it accepts the drop (or `dragleave` if it
+/// doesn't), followed by `dragend` at the source.
+pub(crate) fn end_drag(
+    doc: &mut BaseDocument,
+    mut state: DragAndDropState,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    if !state.started {
+        return;
+    }
+
+    let drop_effect = match state.target {
+        Some(target) if state.accepted && state.data_transfer.drop_effect() != DropEffect::None => {
+            dispatch_event(DomEvent::new(
+                target,
+                DomEventData::Drop(state.event_data()),
+            ));
+            state.data_transfer.drop_effect()
+        }
+        Some(target) => {
+            dispatch_event(DomEvent::new(
+                target,
+                DomEventData::DragLeave(state.event_data()),
+            ));
+            DropEffect::None
+        }
+        None => DropEffect::None,
+    };
+    state.target = None;
+
+    if let Some(source) = state.source {
+        state.data_transfer.set_drop_effect(drop_effect);
+        dispatch_event(DomEvent::new(
+            source,
+            DomEventData::DragEnd(state.event_data()),
+        ));
+    }
+
+    doc.shell_provider.request_redraw();
+}
+
+/// Cancel the in-progress drag (if any) without dropping
+pub(crate) fn cancel_drag(doc: &mut BaseDocument, dispatch_event: &mut dyn FnMut(DomEvent)) {
+    if let DragMode::DragAndDrop(mut state) = doc.drag_mode.take() {
+        state.accepted = false;
+        end_drag(doc, state, dispatch_event);
+    }
+}
+
+/// Handle files being dragged over the window from outside of the application. These are
+/// delivered to the document as a drag with no source element.
+pub(crate) fn handle_file_drag(
+    doc: &mut BaseDocument,
+    event: UiEvent,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    match event {
+        UiEvent::FileDragEnter(BlitzFileDragEvent {
+            coords,
+            mods,
+            paths,
+        }) => {
+            doc.drag_mode = DragMode::DragAndDrop(DragAndDropState {
+                source: None,
+                data_transfer: DataTransfer::from_files(paths),
+                started: true,
+                target: None,
+                accepted: false,
+                coords,
+                mods,
+            });
+            handle_drag_move(doc, coords, mods, dispatch_event);
+        }
+        UiEvent::FileDragMove(event) => {
+            handle_drag_move(doc, event.coords, event.mods, dispatch_event);
+        }
+        UiEvent::FileDrop(event) => {
+            // The drop is delivered to the target of the last `dragover` event (if it accepted it)
+            if let DragMode::DragAndDrop(mut state) = doc.drag_mode.take() {
+                state.coords = event.coords;
+                state.mods = event.mods;
+                end_drag(doc, state, dispatch_event);
+            }
+        }
+        UiEvent::FileDragLeave => cancel_drag(doc, dispatch_event),
+        _ => {}
+    }
+}
+
+/// The `<input type=file>` that an event targeted at `node_id` (which may be one of the input's
+/// own button and label children) is dropped into
+fn file_input_for(doc: &BaseDocument, node_id: NodeId) -> Option<NodeId> {
+    let mut current = Some(node_id);
+    while let Some(id) = current {
+        let node = &doc.nodes[id];
+        if let Some(el) = node.element_data()
+            && el.name.local == local_name!("input")
+            && el.attr(local_name!("type")) == Some("file")
+        {
+            return (!el.has_attr(local_name!("disabled"))).then_some(id);
+        }
+        current = node.parent;
+    }
+    None
+}
+
+/// The default action of `dragstart`: start the drag
+pub(crate) fn handle_dragstart(doc: &mut BaseDocument, target: NodeId) {
+    if let DragMode::DragAndDrop(state) = &mut doc.drag_mode
+        && state.source == Some(target)
+    {
+        state.started = true;
+    }
+}
+
+/// The default action of `dragover`: reject the drop unless the target accepts it by default.
+/// File inputs accept dropped files.
+pub(crate) fn handle_dragover(doc: &mut BaseDocument, target: NodeId, event: &BlitzDragEvent) {
+    let accepts_drop = cfg!(feature = "file-input")
+        && event.data_transfer.has_files()
+        && file_input_for(doc, target).is_some();
+    if let DragMode::DragAndDrop(state) = &mut doc.drag_mode
+        && state.target == Some(target)
+    {
+        state.accepted = accepts_drop;
+    }
+}
+
+/// The default action of `drop`: files dropped on a file input are selected by it
+pub(crate) fn handle_drop(doc: &mut BaseDocument, target: NodeId, event: &BlitzDragEvent) {
+    let Some(input_id) = file_input_for(doc, target) else {
+        return;
+    };
+
+    #[cfg(feature = "file-input")]
+    {
+        let mut files = event.data_transfer.files();
+        if !doc.nodes[input_id].has_attr(local_name!("multiple")) {
+            files.truncate(1);
+        }
+        doc.set_file_input_files(input_id, files);
+        doc.shell_provider.request_redraw();
+    }
+    #[cfg(not(feature = "file-input"))]
+    let _ = (input_id, event);
+}
@@ -1,9 +1,12 @@
 use crate::Document;
+use crate::events::popover::light_dismiss_popovers;
+use crate::events::{handle_accessibility_action, handle_file_drag};
 use blitz_traits::events::{
-    BlitzPointerEvent, BlitzPointerId, DomEvent, DomEventData, EventState, Point, PointerCoords,
-    UiEvent,
+    BlitzAccessibilityActionEvent, BlitzPointerEvent, BlitzPointerId, DomEvent, DomEventData,
+    EventState, Point, PointerCoords, UiEvent,
 };
 use blitz_traits::node_id::NodeId;
+use keyboard_types::Modifiers;
 use std::collections::VecDeque;
 
 pub trait EventHandler {
@@ -163,6 +166,18 @@ impl<'doc, Handler: EventHandler> EventDriver<'doc, Handler> {
                 let mut doc = self.doc.inner_mut();
                 doc.active_node();
                 doc.set_mousedown_node_id(hover_node_id);
+                doc.keyboard_modality = false;
+                // Pressing outside of open popovers closes them, even if the `pointerdown`
+                // event is cancelled
+                light_dismiss_popovers(&mut doc, hover_node_id);
+            }
+            // Keyboard shortcuts don't count as keyboard interaction (for `:focus-visible`)
+            UiEvent::KeyDown(event)
+                if !event
+                    .modifiers
+                    .intersects(Modifiers::CONTROL | Modifiers::ALT | Modifiers::META) =>
+            {
+                self.doc.inner_mut().keyboard_modality = true;
             }
             UiEvent::PointerUp(event) => {
                 hover_node_id = self.handle_pointer_move(event);
@@ -195,6 +210,15 @@ impl<'doc, Handler: EventHandler> EventDriver<'doc, Handler> {
             UiEvent::KeyDown(_) => focussed_node_id,
             UiEvent::Ime(_) => focussed_node_id,
             UiEvent::AppleStandardKeybinding(_) => focussed_node_id,
+            // File drags are targeted at the element under the cursor when the drag events are
+            // generated
+            UiEvent::FileDragEnter(_)
+            | UiEvent::FileDragMove(_)
+            | UiEvent::FileDrop(_)
+            | UiEvent::FileDragLeave => hover_node_id,
+            UiEvent::AccessibilityAction(BlitzAccessibilityActionEvent { target, .. }) => {
+                Some(target)
+            }
         };
         // Fall back to the root element. A document without a root element (e.g. an
         // empty iframe sub-document) has no event target, so there is nothing to do.
@@ -260,6 +284,25 @@ impl<'doc, Handler: EventHandler> EventDriver<'doc, Handler> {
                 self.run_default_action(&mut dom_event);
                 self.process_queue();
             }
+            UiEvent::FileDragEnter(_)
+            | UiEvent::FileDragMove(_)
+            | UiEvent::FileDrop(_)
+            | UiEvent::FileDragLeave => {
+                let mut doc = self.doc.inner_mut();
+                handle_file_drag(&mut doc, event, &mut |new_evt| {
+                    self.queue.push_back(new_evt)
+                });
+                drop(doc);
+                self.process_queue();
+            }
+            UiEvent::AccessibilityAction(data) => {
+                let mut doc = self.doc.inner_mut();
+                handle_accessibility_action(&mut doc, target, data.action, &mut |new_evt| {
+                    self.queue.push_back(new_evt)
+                });
+                drop(doc);
+                self.process_queue();
+            }
         };
 
         // Update document input state (hover, focus, active, etc)
@@ -268,6 +311,19 @@ impl<'doc, Handler: EventHandler> EventDriver<'doc, Handler> {
         }
     }
 
+    /// Dispatch events generated by the document itself (e.g. `load` and `error` events
+    /// for resources). See [`BaseDocument::take_pending_events`](crate::BaseDocument::take_pending_events).
+    pub fn dispatch_pending_events(&mut self) {
+        let events = self.doc.inner_mut().take_pending_events();
+        for event in events {
+            // The target may have been removed since the event was queued
+            if self.doc.inner().get_node(event.target).is_none() {
+                continue;
+            }
+            self.handle_dom_event(event);
+        }
+    }
+
     pub fn handle_dom_event(&mut self, event: DomEvent) {
         self.queue.push_back(event);
         self.process_queue();
@@ -313,7 +369,9 @@ impl<'doc, Handler: EventHandler> EventDriver<'doc, Handler> {
     fn process_queue(&mut self) {
         while let Some(mut event) = self.queue.pop_front() {
             let event_state = self.run_handler_event(&mut event, EventState::default());
-            if !event_state.is_cancelled() {
+            if event_state.is_cancelled() {
+                self.doc.inner_mut().handle_cancelled_dom_event(&event);
+            } else {
                 self.run_default_action(&mut event);
             }
         }
@@ -0,0 +1,64 @@
+use blitz_traits::events::{BlitzFormEvent, DomEvent, DomEventData};
+use blitz_traits::node_id::NodeId;
+use markup5ever::local_name;
+
+use crate::BaseDocument;
+
+use super::focus::generate_focus_events;
+
+/// Submit a form on behalf of the user, unless its controls fail constraint validation (and
+/// neither the form's `novalidate` nor the submitter's `formnovalidate` attribute is set). In
+/// that case submission is blocked: an `invalid` event is fired at each invalid control and
+/// the first of them is focused.
+///
+/// Blitz doesn't show validation messages, so cancelling an `invalid` event has no effect.
+///
+/// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#interactively-validate-the-constraints>
+pub(crate) fn request_form_submission(
+    doc: &mut BaseDocument,
+    form_id: NodeId,
+    submitter_id: NodeId,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    let no_validate = doc.nodes[form_id].has_attr(local_name!("novalidate"))
+        || (submitter_id != form_id
+            && doc.nodes[submitter_id].has_attr(local_name!("formnovalidate")));
+
+    if !no_validate {
+        // Trying to submit a form shows the validity of all of its controls
+        let control_ids: Vec<NodeId> = doc
+            .controls_to_form
+            .iter()
+            .filter(|(_, owner_id)| **owner_id == form_id)
+            .map(|(control_id, _)| *control_id)
+            .collect();
+        for control_id in control_ids {
+            doc.set_user_validity(control_id);
+        }
+
+        let invalid_controls = doc.invalid_form_controls(form_id);
+        if let Some(&first_invalid) = invalid_controls.first() {
+            for &control_id in &invalid_controls {
+                dispatch_event(DomEvent::new(
+                    control_id,
+                    DomEventData::Invalid(BlitzFormEvent),
+                ));
+            }
+            generate_focus_events(
+                doc,
+                &mut |doc| {
+                    doc.set_focus_to(first_invalid);
+                },
+                dispatch_event,
+            );
+            doc.shell_provider.request_redraw();
+            return;
+        }
+    }
+
+    if doc.form_method_is_dialog(form_id, submitter_id) {
+        doc.mutate().submit_dialog_form(form_id, submitter_id);
+    } else {
+        doc.submit_form(form_id, submitter_id);
+    }
+}
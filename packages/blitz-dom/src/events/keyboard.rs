@@ -1,12 +1,18 @@
-use crate::{BaseDocument, node::GeneratedTextInputEvent, util::ACTION_MOD};
+use crate::{BaseDocument, node::GeneratedTextInputEvent};
 use blitz_traits::node_id::NodeId;
 use blitz_traits::{
     SmolStr,
-    events::{BlitzInputEvent, BlitzKeyEvent, DomEvent, DomEventData},
+    events::{
+        BlitzBeforeInputEvent, BlitzDialogEvent, BlitzInputEvent, BlitzKeyEvent, DomEvent,
+        DomEventData, InputType,
+    },
 };
 use keyboard_types::{Key, Modifiers};
 use markup5ever::local_name;
 
+use super::clipboard::{clipboard_shortcut, fire_clipboard_event};
+use super::form::request_form_submission;
+
 pub(super) enum KeyboardOrTextInputEvent {
     KeyPress(BlitzKeyEvent),
     AppleStandardKeyBinding(SmolStr),
@@ -16,7 +22,7 @@ pub(crate) fn handle_key_or_input_event<F: FnMut(DomEvent)>(
     doc: &mut BaseDocument,
     target: NodeId,
     event: KeyboardOrTextInputEvent,
-    dispatch_event: F,
+    mut dispatch_event: F,
 ) {
     if let KeyboardOrTextInputEvent::KeyPress(event) = &event {
         if event.key == Key::Tab {
@@ -28,29 +34,30 @@ pub(crate) fn handle_key_or_input_event<F: FnMut(DomEvent)>(
             return;
         }
 
-        // Handle copy (Ctrl+C/Cmd+C) for text selection when no text input is focused
-        if event.state.is_pressed() {
-            let action_mod = event.modifiers.contains(ACTION_MOD);
-            if action_mod {
-                if let Key::Character(c) = &event.key {
-                    if c.to_lowercase() == "c" {
-                        // Check if we have a text selection (and no focused text input)
-                        let has_focused_text_input = doc.focus_node_id.is_some_and(|id| {
-                            doc.get_node(id)
-                                .and_then(|n| n.element_data())
-                                .is_some_and(|e| e.text_input_data().is_some())
-                        });
-
-                        if !has_focused_text_input {
-                            if let Some(text) = doc.get_selected_text() {
-                                let _ = doc.shell_provider.set_clipboard_text(text);
-                                return;
-                            }
-                        }
-                    }
-                }
+        // Escape closes the topmost open popover, or asks the topmost modal dialog to close by
+        // firing a `cancel` event at it (unless it is dismissing the drop-down listbox of a
+        // select)
+        if event.key == Key::Escape && event.state.is_pressed() && doc.open_select_node_id.is_none()
+        {
+            if let Some(popover_id) = doc.topmost_light_dismissable_popover() {
+                doc.hide_popover(popover_id);
+                return;
+            }
+            if let Some(dialog_id) = doc.topmost_modal_dialog() {
+                dispatch_event(DomEvent::new(
+                    dialog_id,
+                    DomEventData::Cancel(BlitzDialogEvent),
+                ));
+                return;
             }
         }
+
+        // Clipboard shortcuts fire `copy`, `cut` and `paste` events, whose default actions
+        // read and write the clipboard
+        if let Some(action) = clipboard_shortcut(event) {
+            fire_clipboard_event(doc, target, action, &mut dispatch_event);
+            return;
+        }
     }
 
     if let Some(node_id) = doc.focus_node_id {
@@ -58,6 +65,18 @@ pub(crate) fn handle_key_or_input_event<F: FnMut(DomEvent)>(
             return;
         }
 
+        if doc.nodes[node_id]
+            .element_data()
+            .is_some_and(|el| el.select_data().is_some())
+        {
+            if let KeyboardOrTextInputEvent::KeyPress(event) = &event {
+                if event.state.is_pressed() {
+                    doc.handle_select_keypress(node_id, event, &mut dispatch_event);
+                }
+            }
+            return;
+        }
+
         let node = &mut doc.nodes[node_id];
         let Some(element_data) = node.element_data_mut() else {
             return;
@@ -69,7 +88,6 @@ pub(crate) fn handle_key_or_input_event<F: FnMut(DomEvent)>(
                     .apply_keypress_event(
                         &mut doc.font_ctx.lock().unwrap(),
                         &mut doc.layout_ctx,
-                        &*doc.shell_provider,
                         blitz_key_event,
                     ),
                 KeyboardOrTextInputEvent::AppleStandardKeyBinding(command) => input_data
@@ -88,6 +106,47 @@ pub(crate) fn handle_key_or_input_event<F: FnMut(DomEvent)>(
     }
 }
 
+/// The default action of a `beforeinput` event: making the edit it announced
+pub(crate) fn handle_before_input_event<F: FnMut(DomEvent)>(
+    doc: &mut BaseDocument,
+    target: NodeId,
+    event: &BlitzBeforeInputEvent,
+    dispatch_event: F,
+) {
+    let Some(input_data) = doc.nodes[target]
+        .element_data_mut()
+        .and_then(|el| el.text_input_data_mut())
+    else {
+        return;
+    };
+
+    let changed = {
+        let font_ctx = &mut doc.font_ctx.lock().unwrap();
+        let layout_ctx = &mut doc.layout_ctx;
+        match event.input_type {
+            InputType::HistoryUndo => input_data.undo(font_ctx, layout_ctx),
+            InputType::HistoryRedo => input_data.redo(font_ctx, layout_ctx),
+            input_type => {
+                let before = input_data.editor_state();
+                let changed =
+                    input_data.apply_edit(font_ctx, layout_ctx, input_type, event.data.as_deref());
+                if changed {
+                    input_data.record_edit(before, input_type);
+                }
+                changed
+            }
+        }
+    };
+
+    if changed {
+        doc.apply_generated_text_input_event(
+            target,
+            GeneratedTextInputEvent::Input,
+            dispatch_event,
+        );
+    }
+}
+
 impl BaseDocument {
     pub(crate) fn apply_generated_text_input_event<F: FnMut(DomEvent)>(
         &mut self,
@@ -105,33 +164,69 @@ impl BaseDocument {
 
         match event {
             GeneratedTextInputEvent::Input => {
+                input_data.user_edited = true;
                 let value = input_data.editor.raw_text().to_string();
                 dispatch_event(DomEvent::new(
                     node_id,
                     DomEventData::Input(BlitzInputEvent { value }),
                 ));
+                // The new value may be valid where the old one wasn't (or vice versa), and may
+                // show or hide the placeholder
+                self.invalidate_form_state(node_id);
                 self.shell_provider.request_redraw();
             }
-            GeneratedTextInputEvent::Select | GeneratedTextInputEvent::PreEditChange => {
+            GeneratedTextInputEvent::BeforeInput(input_type, data) => {
+                // History can't be traversed in the middle of an IME composition
+                let can_apply = match input_type {
+                    InputType::HistoryUndo => {
+                        input_data.can_undo() && !input_data.editor.is_composing()
+                    }
+                    InputType::HistoryRedo => {
+                        input_data.can_redo() && !input_data.editor.is_composing()
+                    }
+                    _ => true,
+                };
+                if can_apply {
+                    let value = input_data.editor.raw_text().to_string();
+                    dispatch_event(DomEvent::new(
+                        node_id,
+                        DomEventData::BeforeInput(BlitzBeforeInputEvent {
+                            value,
+                            input_type,
+                            data,
+                        }),
+                    ));
+                }
+            }
+            GeneratedTextInputEvent::Select => {
+                self.shell_provider.request_redraw();
+            }
+            GeneratedTextInputEvent::PreEditChange => {
+                // Starting or ending a composition hides or shows the placeholder
+                self.invalidate_form_state(node_id);
                 self.shell_provider.request_redraw();
             }
             GeneratedTextInputEvent::Submit => {
                 // TODO: Generate submit event that can be handled by script
-                implicit_form_submission(self, node_id);
+                implicit_form_submission(self, node_id, &mut dispatch_event);
             }
         }
     }
 }
 
 /// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#field-that-blocks-implicit-submission
-fn implicit_form_submission(doc: &BaseDocument, text_target: NodeId) {
-    let Some(form_owner_id) = doc.controls_to_form.get(&text_target) else {
+fn implicit_form_submission(
+    doc: &mut BaseDocument,
+    text_target: NodeId,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    let Some(form_owner_id) = doc.controls_to_form.get(&text_target).copied() else {
         return;
     };
     if doc
         .controls_to_form
         .iter()
-        .filter(|(_control_id, form_id)| *form_id == form_owner_id)
+        .filter(|(_control_id, form_id)| **form_id == form_owner_id)
         .filter_map(|(control_id, _)| doc.nodes[*control_id].element_data())
         .filter(|element_data| {
             element_data.attr(local_name!("type")).is_some_and(|t| {
@@ -158,5 +253,5 @@ fn implicit_form_submission(doc: &BaseDocument, text_target: NodeId) {
         return;
     }
 
-    doc.submit_form(*form_owner_id, *form_owner_id);
+    request_form_submission(doc, form_owner_id, form_owner_id, dispatch_event);
 }
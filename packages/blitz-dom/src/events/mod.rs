@@ -1,17 +1,27 @@
+mod accessibility;
+mod clipboard;
+mod drag;
 mod driver;
 mod focus;
+mod form;
 mod ime;
 mod keyboard;
 mod pointer;
+mod popover;
 
 use crate::util::Point;
+pub(crate) use accessibility::handle_accessibility_action;
 use blitz_traits::events::{DomEvent, DomEventData, PointerCoords, UiEvent};
+use clipboard::{handle_cancelled_copy_or_cut, handle_copy, handle_cut, handle_paste};
+pub(crate) use drag::handle_file_drag;
+use drag::{cancel_drag, handle_dragover, handle_dragstart, handle_drop};
 pub use driver::{EventDriver, EventHandler, NoopEventHandler};
 use focus::generate_focus_events;
 pub(crate) use ime::handle_ime_event;
-use keyboard::{KeyboardOrTextInputEvent, handle_key_or_input_event};
+use keyboard::{KeyboardOrTextInputEvent, handle_before_input_event, handle_key_or_input_event};
 pub(crate) use pointer::DragMode;
 use pointer::{handle_click, handle_pointerdown, handle_pointermove, handle_pointerup};
+use popover::handle_before_toggle;
 
 use crate::{BaseDocument, events::pointer::handle_wheel};
 
@@ -83,13 +93,35 @@ fn map_dom_event_to_ui_event(
         DomEventData::Click(_) => None,
         DomEventData::ContextMenu(_) => None,
         DomEventData::DoubleClick(_) => None,
+        DomEventData::BeforeInput(_) => None,
         DomEventData::Input(_) => None,
+        DomEventData::Change(_) => None,
+        DomEventData::Invalid(_) => None,
+        DomEventData::Reset(_) => None,
+        DomEventData::Cancel(_) => None,
+        DomEventData::Close(_) => None,
+        DomEventData::BeforeToggle(_) => None,
+        DomEventData::Toggle(_) => None,
+        // Clipboard events are fired by the sub-document in response to key presses
+        DomEventData::Copy(_) => None,
+        DomEventData::Cut(_) => None,
+        DomEventData::Paste(_) => None,
+        // Drags are tracked by the document they start in
+        DomEventData::DragStart(_) => None,
+        DomEventData::Drag(_) => None,
+        DomEventData::DragEnd(_) => None,
+        DomEventData::DragEnter(_) => None,
+        DomEventData::DragOver(_) => None,
+        DomEventData::DragLeave(_) => None,
+        DomEventData::Drop(_) => None,
         DomEventData::Wheel(data) => Some(UiEvent::Wheel(data)),
         DomEventData::Scroll(_) => None,
         DomEventData::Focus(_) => None,
         DomEventData::Blur(_) => None,
         DomEventData::FocusIn(_) => None,
         DomEventData::FocusOut(_) => None,
+        DomEventData::Load(_) => None,
+        DomEventData::Error(_) => None,
     }
 }
 
@@ -106,8 +138,11 @@ pub(crate) fn handle_dom_event<F: FnMut(DomEvent)>(
     // in which case we need to update the input's scroll offset afterwards.
     let may_move_text_input_caret = match &event.data {
         DomEventData::KeyDown(_)
+        | DomEventData::BeforeInput(_)
         | DomEventData::AppleStandardKeybinding(_)
         | DomEventData::Ime(_)
+        | DomEventData::Cut(_)
+        | DomEventData::Paste(_)
         | DomEventData::Click(_) => true,
         DomEventData::PointerDown(event) | DomEventData::PointerMove(event) => {
             !event.buttons.is_empty()
@@ -153,6 +188,11 @@ pub(crate) fn handle_dom_event<F: FnMut(DomEvent)>(
             DomEventData::PointerDown(_) | DomEventData::PointerUp(_)
         );
         let viewport_scroll = Point { x: 0.0, y: 0.0 };
+        match &event.data {
+            DomEventData::Focus(_) => widget_data.widget.focus(),
+            DomEventData::Blur(_) => widget_data.widget.blur(),
+            _ => {}
+        }
         let ui_event = map_dom_event_to_ui_event(event, pos, viewport_scroll);
 
         if let Some(ui_event) = ui_event {
@@ -203,7 +243,8 @@ pub(crate) fn handle_dom_event<F: FnMut(DomEvent)>(
             // Do nothing (handled in PointerUp)
         }
         DomEventData::PointerCancel(_) => {
-            // Do nothing (active state is reset in the event driver)
+            // Active state is reset in the event driver
+            cancel_drag(doc, &mut dispatch_event);
         }
         DomEventData::Click(event) => {
             handle_click(doc, target_node_id, event, &mut dispatch_event);
@@ -233,9 +274,63 @@ pub(crate) fn handle_dom_event<F: FnMut(DomEvent)>(
         DomEventData::Ime(event) => {
             handle_ime_event(doc, event.clone(), dispatch_event);
         }
+        DomEventData::BeforeInput(event) => {
+            handle_before_input_event(doc, target_node_id, event, dispatch_event);
+        }
         DomEventData::Input(_) => {
             // Do nothing (no default action)
         }
+        DomEventData::Change(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::Invalid(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::Reset(_) => {
+            doc.reset_form(target_node_id);
+        }
+        DomEventData::Cancel(_) => {
+            doc.mutate().close_dialog(target_node_id, None);
+        }
+        DomEventData::Close(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::BeforeToggle(event) => {
+            handle_before_toggle(doc, target_node_id, event);
+        }
+        DomEventData::Toggle(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::Copy(_) => {
+            handle_copy(doc, target_node_id);
+        }
+        DomEventData::Cut(_) => {
+            handle_cut(doc, target_node_id, &mut dispatch_event);
+        }
+        DomEventData::Paste(event) => {
+            handle_paste(doc, target_node_id, event, &mut dispatch_event);
+        }
+        DomEventData::DragStart(_) => {
+            handle_dragstart(doc, target_node_id);
+        }
+        DomEventData::Drag(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::DragEnd(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::DragEnter(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::DragOver(event) => {
+            handle_dragover(doc, target_node_id, event);
+        }
+        DomEventData::DragLeave(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::Drop(event) => {
+            handle_drop(doc, target_node_id, event);
+        }
         DomEventData::ContextMenu(_) => {
             // TODO: Open context menu
         }
@@ -296,6 +391,12 @@ pub(crate) fn handle_dom_event<F: FnMut(DomEvent)>(
         DomEventData::FocusOut(_) => {
             // Do nothing (no default action)
         }
+        DomEventData::Load(_) => {
+            // Do nothing (no default action)
+        }
+        DomEventData::Error(_) => {
+            // Do nothing (no default action)
+        }
     }
 
     // Keep the focused text input scrolled so that its caret stays visible. Keyboard/IME events
@@ -307,3 +408,15 @@ pub(crate) fn handle_dom_event<F: FnMut(DomEvent)>(
         }
     }
 }
+
+/// Handle an event whose default action was prevented. Most events do nothing in this case,
+/// but cancelling a `copy` or `cut` event writes the data added by its handlers to the
+/// clipboard.
+pub(crate) fn handle_cancelled_dom_event(doc: &mut BaseDocument, event: &DomEvent) {
+    match &event.data {
+        DomEventData::Copy(event) | DomEventData::Cut(event) => {
+            handle_cancelled_copy_or_cut(doc, event);
+        }
+        _ => {}
+    }
+}
@@ -5,8 +5,8 @@ use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use blitz_traits::{
     events::{
-        BlitzInputEvent, BlitzPointerEvent, BlitzPointerId, BlitzWheelDelta, BlitzWheelEvent,
-        DomEvent, DomEventData, MouseEventButton, MouseEventButtons,
+        BlitzFormEvent, BlitzInputEvent, BlitzPointerEvent, BlitzPointerId, BlitzWheelDelta,
+        BlitzWheelEvent, DomEvent, DomEventData, MouseEventButton, MouseEventButtons,
     },
     navigation::NavigationOptions,
 };
@@ -21,7 +21,10 @@ use crate::{
     scrolling::{FlingState, ScrollAnimationState},
 };
 
+use super::drag::{DragAndDropState, draggable_element, end_drag, handle_drag_move, start_drag};
 use super::focus::generate_focus_events;
+use super::form::request_form_submission;
+use super::popover::handle_popover_target_activation;
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct PanState {
@@ -60,6 +63,8 @@ pub(crate) enum DragMode {
     Panning(PanState),
     /// We are currently dragging a scrollbar thumb
     ScrollbarDrag(ScrollbarDragState),
+    /// We are currently dragging an element (or files dragged in from outside of the application)
+    DragAndDrop(DragAndDropState),
 }
 
 impl DragMode {
@@ -213,6 +218,7 @@ pub(crate) fn handle_pointermove<F: FnMut(DomEvent)>(
     let buttons = event.buttons;
 
     let mut changed = doc.set_hover_to(x, y);
+    changed |= doc.hover_open_select_listbox(x, y);
 
     // Check if we've moved enough to be considered a selection drag (2px threshold)
     if buttons != MouseEventButtons::None && doc.drag_mode == DragMode::None {
@@ -221,7 +227,13 @@ pub(crate) fn handle_pointermove<F: FnMut(DomEvent)>(
         if dx.abs() > 2.0 || dy.abs() > 2.0 {
             match event.id {
                 BlitzPointerId::Mouse | BlitzPointerId::Pen => {
-                    if let Some(mousedown_node_id) = doc.mousedown_node_id {
+                    // Dragging a `draggable` element takes precedence over selecting text
+                    if let Some(source) = doc
+                        .mousedown_node_id
+                        .and_then(|node_id| draggable_element(doc, node_id))
+                    {
+                        start_drag(doc, source, event, &mut dispatch_event);
+                    } else if let Some(mousedown_node_id) = doc.mousedown_node_id {
                         let node = &doc.nodes[mousedown_node_id];
                         if let Some(style) = node.primary_styles() {
                             let user_select = style.clone_user_select();
@@ -262,6 +274,11 @@ pub(crate) fn handle_pointermove<F: FnMut(DomEvent)>(
         }
     }
 
+    if matches!(doc.drag_mode, DragMode::DragAndDrop(_)) {
+        handle_drag_move(doc, event.coords, event.mods, &mut dispatch_event);
+        return changed;
+    }
+
     if let DragMode::Panning(state) = &mut doc.drag_mode {
         let time_ms = SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -411,9 +428,18 @@ pub(crate) fn handle_pointerdown(
     let Some(hit) = doc.hit(x, y) else {
         // Clear text selection when clicking outside any element
         doc.clear_text_selection();
+        doc.close_select();
         return;
     };
 
+    // Pressing anywhere outside of an open drop-down listbox (or its select) dismisses it
+    if doc
+        .open_select_node_id
+        .is_some_and(|select_id| select_id != hit.node_id)
+    {
+        doc.close_select();
+    }
+
     // Scrollbar thumb drags take precedence over content interactions and
     // are not dispatched to the page (matching native scrollbars). A
     // faded-out thumb doesn't capture: the click goes to the content.
@@ -572,10 +598,17 @@ pub(crate) fn handle_pointerup<F: FnMut(DomEvent)>(
     // locally for use within this function
     let drag_mode = doc.drag_mode.take();
 
-    // Don't dispatch click if we were doing a text selection drag or panning
-    // the document with a touch
+    // Don't dispatch click if we were doing a text selection drag, panning
+    // the document with a touch or dragging an element
     let do_click = drag_mode == DragMode::None;
 
+    if let DragMode::DragAndDrop(mut state) = drag_mode {
+        state.coords = event.coords;
+        state.mods = event.mods;
+        end_drag(doc, state, &mut dispatch_event);
+        return;
+    }
+
     // Repaint so a dragged scrollbar thumb drops its active styling, and
     // restart its fade-out delay now that the drag no longer holds it shown
     if let DragMode::ScrollbarDrag(state) = &drag_mode {
@@ -620,6 +653,12 @@ pub(crate) fn handle_click(
     let mut maybe_node_id = Some(target);
     let matched = 'matched: {
         while let Some(node_id) = maybe_node_id {
+            // Buttons with a `popovertarget` attribute show or hide their target popover
+            if let Some((popover_id, action)) = doc.popover_target_of(node_id) {
+                handle_popover_target_activation(doc, node_id, popover_id, action, dispatch_event);
+                break 'matched true;
+            }
+
             let maybe_element = {
                 let node = &mut doc.nodes[node_id];
                 node.data.downcast_element_mut()
@@ -647,6 +686,7 @@ pub(crate) fn handle_click(
                         node_id,
                         DomEventData::Input(BlitzInputEvent { value }),
                     ));
+                    doc.set_user_validity(node_id);
                     generate_focus_events(
                         doc,
                         &mut |doc| {
@@ -669,6 +709,7 @@ pub(crate) fn handle_click(
                         node_id,
                         DomEventData::Input(BlitzInputEvent { value }),
                     ));
+                    doc.set_user_validity(node_id);
 
                     generate_focus_events(
                         doc,
@@ -680,6 +721,23 @@ pub(crate) fn handle_click(
 
                     break 'matched true;
                 }
+                local_name!("select") => {
+                    generate_focus_events(
+                        doc,
+                        &mut |doc| {
+                            doc.set_focus_to(node_id);
+                        },
+                        dispatch_event,
+                    );
+                    doc.handle_select_click(
+                        node_id,
+                        event.page_x(),
+                        event.page_y(),
+                        event.mods,
+                        dispatch_event,
+                    );
+                    break 'matched true;
+                }
                 // Activating the first <summary> of a <details> element toggles
                 // the details' `open` attribute (expand/collapse).
                 local_name!("summary") => {
@@ -748,44 +806,27 @@ pub(crate) fn handle_click(
                 local_name!("input") | local_name!("button")
                     if el.is_submit_button() || el.attr(local_name!("type")) == Some("submit") =>
                 {
-                    if let Some(form_owner) = doc.controls_to_form.get(&node_id) {
-                        doc.submit_form(*form_owner, node_id);
+                    if let Some(form_owner) = doc.controls_to_form.get(&node_id).copied() {
+                        request_form_submission(doc, form_owner, node_id, dispatch_event);
+                    }
+                }
+                local_name!("input") | local_name!("button")
+                    if el.attr(local_name!("type")) == Some("reset") =>
+                {
+                    // The form is reset by the `reset` event's default action
+                    if let Some(form_owner) = doc.controls_to_form.get(&node_id).copied() {
+                        dispatch_event(DomEvent::new(
+                            form_owner,
+                            DomEventData::Reset(BlitzFormEvent),
+                        ));
                     }
                 }
                 #[cfg(feature = "file-input")]
                 local_name!("input") if el.attr(local_name!("type")) == Some("file") => {
-                    use crate::qual_name;
                     //TODO: Handle accept attribute https://developer.mozilla.org/en-US/docs/Web/HTML/Reference/Attributes/accept by passing an appropriate filter
                     let multiple = el.attr(local_name!("multiple")).is_some();
                     let files = doc.shell_provider.open_file_dialog(multiple, None);
-
-                    if let Some(file) = files.first() {
-                        el.attrs
-                            .set(qual_name!("value", html), &file.to_string_lossy());
-                    }
-                    let text_content = match files.len() {
-                        0 => "No Files Selected".to_string(),
-                        1 => files
-                            .first()
-                            .unwrap()
-                            .file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string(),
-                        x => format!("{x} Files Selected"),
-                    };
-
-                    if files.is_empty() {
-                        el.special_data = SpecialElementData::None;
-                    } else {
-                        el.special_data = SpecialElementData::FileInput(files.into())
-                    }
-                    let child_label_id = doc.nodes[node_id].children[1];
-                    let child_text_id = doc.nodes[child_label_id].children[0];
-                    let text_data = doc.nodes[child_text_id]
-                        .text_data_mut()
-                        .expect("Text data not found");
-                    text_data.content = text_content;
+                    doc.set_file_input_files(node_id, files);
                 }
                 _ => {}
             }
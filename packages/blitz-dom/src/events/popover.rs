@@ -0,0 +1,63 @@
+use blitz_traits::events::{BlitzToggleEvent, DomEvent, DomEventData, ToggleState};
+use blitz_traits::node_id::NodeId;
+
+use crate::BaseDocument;
+use crate::popover::PopoverTargetAction;
+
+/// A `beforetoggle` event announcing that a popover is about to be shown. Its default action
+/// shows the popover. (Popovers are hidden without being asked: `BaseDocument::hide_popover`
+/// queues its own `beforetoggle` event.)
+pub(crate) fn show_popover_event(popover_id: NodeId, source: Option<NodeId>) -> DomEvent {
+    DomEvent::new(
+        popover_id,
+        DomEventData::BeforeToggle(BlitzToggleEvent {
+            old_state: ToggleState::Closed,
+            new_state: ToggleState::Open,
+            source,
+        }),
+    )
+}
+
+/// Activating a button with a `popovertarget` attribute shows, hides or toggles its target
+/// popover (according to its `popovertargetaction` attribute)
+pub(crate) fn handle_popover_target_activation(
+    doc: &mut BaseDocument,
+    invoker_id: NodeId,
+    popover_id: NodeId,
+    action: PopoverTargetAction,
+    dispatch_event: &mut dyn FnMut(DomEvent),
+) {
+    match (action, doc.is_popover_open(popover_id)) {
+        (PopoverTargetAction::Toggle | PopoverTargetAction::Show, false) => {
+            dispatch_event(show_popover_event(popover_id, Some(invoker_id)));
+        }
+        (PopoverTargetAction::Toggle | PopoverTargetAction::Hide, true) => {
+            doc.hide_popover(popover_id);
+        }
+        _ => {}
+    }
+}
+
+/// Pressing the pointer outside of the open `auto` and `hint` popovers closes them, from the
+/// top of the stack down to the popover that was pressed in (if any). This happens even if
+/// the `pointerdown` event is cancelled.
+///
+/// <https://html.spec.whatwg.org/multipage/popover.html#popover-light-dismiss>
+pub(crate) fn light_dismiss_popovers(doc: &mut BaseDocument, target: Option<NodeId>) {
+    let ancestor = target.and_then(|node_id| doc.topmost_clicked_popover(node_id));
+    for popover_id in doc.light_dismissable_popovers_above(ancestor) {
+        doc.hide_popover(popover_id);
+    }
+}
+
+/// The default action of a `beforetoggle` event: showing the popover. An event announcing
+/// that a popover is being hidden has no default action.
+pub(crate) fn handle_before_toggle(
+    doc: &mut BaseDocument,
+    target: NodeId,
+    event: &BlitzToggleEvent,
+) {
+    if event.new_state == ToggleState::Open {
+        doc.show_popover(target, event.source);
+    }
+}
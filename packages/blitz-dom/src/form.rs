@@ -1,8 +1,11 @@
 use blitz_traits::node_id::NodeId;
 use markup5ever::{LocalName, local_name};
 
+#[cfg(feature = "file-input")]
+use crate::qual_name;
 use crate::{
     BaseDocument, ElementData,
+    node::SpecialElementData,
     traversal::{AncestorTraverser, TreeTraverser},
 };
 use blitz_traits::{
@@ -11,6 +14,8 @@ use blitz_traits::{
 };
 use core::str::FromStr;
 use std::fmt::Display;
+#[cfg(feature = "file-input")]
+use std::path::PathBuf;
 
 /// https://url.spec.whatwg.org/#default-encode-set
 const DEFAULT_ENCODE_SET: percent_encoding::AsciiSet = percent_encoding::CONTROLS
@@ -61,6 +66,23 @@ impl BaseDocument {
         }
     }
 
+    /// Whether submitting a form closes the dialog that it is in (rather than navigating),
+    /// because the submitter's `formmethod` or the form's `method` is `dialog`
+    pub(crate) fn form_method_is_dialog(&self, form_id: NodeId, submitter_id: NodeId) -> bool {
+        let Some(element) = self.nodes[form_id].element_data() else {
+            return false;
+        };
+        get_form_attr(
+            self,
+            element,
+            local_name!("method"),
+            submitter_id,
+            local_name!("formmethod"),
+        )
+        .and_then(|method| method.parse::<FormMethod>().ok())
+            == Some(FormMethod::Dialog)
+    }
+
     /// Submits a form with the given form node ID and submitter node ID
     ///
     /// # Arguments
@@ -168,6 +190,116 @@ impl BaseDocument {
 
         self.navigation_provider.navigate_to(navigation_options)
     }
+
+    /// Resets the controls of a form to their default values: text inputs to their `value`
+    /// attribute, checkboxes and radio buttons to their `checked` attribute, file inputs to
+    /// having no files selected, and selects to the options with the `selected` attribute
+    ///
+    /// This is the equivalent of `form.reset()`, except that no `reset` event is fired. To fire
+    /// a cancelable `reset` event (which resets the form unless it is cancelled), dispatch a
+    /// [`DomEventData::Reset`](blitz_traits::events::DomEventData::Reset) event at the form
+    /// using an [`EventDriver`](crate::EventDriver).
+    ///
+    /// # Arguments
+    /// * `form_id` - The ID of the form node to reset
+    ///
+    /// <https://html.spec.whatwg.org/multipage/forms.html#concept-form-reset>
+    pub fn reset_form(&mut self, form_id: NodeId) {
+        let control_ids: Vec<NodeId> = TreeTraverser::new(self)
+            .filter(|control_id| self.controls_to_form.get(control_id) == Some(&form_id))
+            .collect();
+        for control_id in control_ids {
+            self.reset_form_control(control_id);
+        }
+        self.shell_provider.request_redraw();
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#concept-form-reset-control>
+    fn reset_form_control(&mut self, control_id: NodeId) {
+        let Some(element) = self.nodes[control_id].element_data_mut() else {
+            return;
+        };
+        element.user_validity = false;
+
+        let default_value = element
+            .attr(local_name!("value"))
+            .unwrap_or_default()
+            .to_string();
+        let default_checked = element.has_attr(local_name!("checked"));
+        match &mut element.special_data {
+            SpecialElementData::TextInput(input_data) => {
+                input_data.set_text(
+                    &mut self.font_ctx.lock().unwrap(),
+                    &mut self.layout_ctx,
+                    &default_value,
+                );
+                input_data.history.clear();
+                input_data.user_edited = false;
+            }
+            SpecialElementData::CheckboxInput(checked) => *checked = default_checked,
+            SpecialElementData::Select(select_data) => select_data.user_selection = None,
+            _ => {}
+        }
+
+        #[cfg(feature = "file-input")]
+        if element.name.local == local_name!("input")
+            && element.attr(local_name!("type")) == Some("file")
+        {
+            element.special_data = SpecialElementData::None;
+            element
+                .attrs
+                .retain(|attr| attr.name.local != local_name!("value"));
+
+            let label_id = self.nodes[control_id].children[1];
+            let text_id = self.nodes[label_id].children[0];
+            if let Some(text_data) = self.nodes[text_id].text_data_mut() {
+                text_data.content = "No File Selected".to_string();
+            }
+        }
+
+        self.update_select_selectedness(control_id);
+        self.invalidate_form_state(control_id);
+    }
+
+    /// Select `files` in a `<input type=file>`, updating its `value` attribute and the label
+    /// showing the selected files
+    #[cfg(feature = "file-input")]
+    pub(crate) fn set_file_input_files(&mut self, input_id: NodeId, files: Vec<PathBuf>) {
+        let Some(element) = self.nodes[input_id].element_data_mut() else {
+            return;
+        };
+
+        if let Some(file) = files.first() {
+            element
+                .attrs
+                .set(qual_name!("value", html), &file.to_string_lossy());
+        }
+        let text_content = match files.len() {
+            0 => "No Files Selected".to_string(),
+            1 => files
+                .first()
+                .unwrap()
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            x => format!("{x} Files Selected"),
+        };
+
+        if files.is_empty() {
+            element.special_data = SpecialElementData::None;
+        } else {
+            element.special_data = SpecialElementData::FileInput(files.into())
+        }
+        let label_id = self.nodes[input_id].children[1];
+        let text_id = self.nodes[label_id].children[0];
+        let text_data = self.nodes[text_id]
+            .text_data_mut()
+            .expect("Text data not found");
+        text_data.content = text_content;
+
+        self.invalidate_form_state(input_id);
+    }
 }
 
 /// Constructs a list of form entries from form controls
@@ -267,11 +399,20 @@ fn construct_entry_list(doc: &BaseDocument, form_id: NodeId, submitter_id: NodeI
             continue;
         };
 
-        // TODO: If the field element is a select element,
+        // If the field element is a select element,
         //  then for each option element in the select element's
         //  list of options whose selectedness is true and that is not disabled,
         //  create an entry with name and the value of the option element,
         //  and append it to entry list.
+        if element.name.local == local_name!("select") {
+            for option_id in node.select_selected_options() {
+                let option = &doc.nodes[option_id];
+                if !option.option_is_disabled() {
+                    create_entry(name, option.option_value().as_str().into());
+                }
+            }
+            continue;
+        }
 
         // Otherwise, if the field element is an input element whose type attribute is in the Checkbox state or the Radio Button state, then:
         if element.name.local == local_name!("input")
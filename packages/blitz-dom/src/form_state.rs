@@ -0,0 +1,302 @@
+//! The states of form controls which are matched by pseudo-classes: `:placeholder-shown`,
+//! `:read-write`/`:read-only`, `:required`/`:optional`, `:indeterminate`, `:default` and
+//! `:in-range`/`:out-of-range`. They are derived from the controls' attributes and their live
+//! state (the contents of their text editor, checkedness, etc).
+//!
+//! <https://html.spec.whatwg.org/multipage/semantics-other.html#pseudo-classes>
+
+use std::collections::HashMap;
+
+use blitz_traits::node_id::NodeId;
+use markup5ever::local_name;
+use style::invalidation::element::restyle_hints::RestyleHint;
+
+use crate::traversal::TreeTraverser;
+use crate::{BaseDocument, ElementData, Node};
+
+/// The state of a radio button group, which is shared by all of the radio buttons in it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RadioGroupState {
+    /// Whether any of the radio buttons in the group are checked
+    pub has_checked: bool,
+    /// Whether any of the radio buttons in the group are `required`
+    pub required: bool,
+}
+
+impl Node {
+    /// Whether this is an input or textarea which is showing its placeholder (because it has a
+    /// `placeholder` attribute and is empty)
+    pub fn is_placeholder_shown(&self) -> bool {
+        let Some(element) = self.element_data() else {
+            return false;
+        };
+        matches!(
+            element.name.local,
+            local_name!("input") | local_name!("textarea")
+        ) && element.has_attr(local_name!("placeholder"))
+            && element
+                .text_input_data()
+                .is_some_and(|input| input.is_empty())
+    }
+
+    /// Whether the user can edit this element: a text control which is neither `readonly` nor
+    /// disabled, or an element which is editable due to `contenteditable`. Every other element
+    /// matches `:read-only`.
+    pub fn is_read_write(&self) -> bool {
+        let Some(element) = self.element_data() else {
+            return false;
+        };
+        match element.name.local {
+            local_name!("input") | local_name!("textarea") => {
+                element.text_input_data().is_some()
+                    && !element.has_attr(local_name!("readonly"))
+                    && !element.has_attr(local_name!("disabled"))
+            }
+            _ => self.is_content_editable(),
+        }
+    }
+
+    /// Whether this element is editable because of the `contenteditable` attribute of the
+    /// nearest ancestor (or itself) which has one
+    fn is_content_editable(&self) -> bool {
+        let mut node = self;
+        loop {
+            let value = node
+                .element_data()
+                .and_then(|element| element.attr(local_name!("contenteditable")));
+            if let Some(value) = value {
+                return value.is_empty()
+                    || value.eq_ignore_ascii_case("true")
+                    || value.eq_ignore_ascii_case("plaintext-only");
+            }
+            match node.parent {
+                Some(parent_id) => node = node.with(parent_id),
+                None => return false,
+            }
+        }
+    }
+
+    /// Whether the `required` attribute applies to this element, and so it matches either
+    /// `:required` or `:optional`
+    pub fn supports_required(&self) -> bool {
+        let Some(element) = self.element_data() else {
+            return false;
+        };
+        match element.name.local {
+            local_name!("input") => !matches!(
+                element.attr(local_name!("type")),
+                Some("hidden" | "range" | "color" | "submit" | "reset" | "button" | "image")
+            ),
+            local_name!("select") | local_name!("textarea") => true,
+            _ => false,
+        }
+    }
+
+    /// Whether this is a checkbox whose indeterminate flag is set, a radio button in a group in
+    /// which no radio button is checked, or a progress bar without a value
+    pub fn is_indeterminate(&self) -> bool {
+        let Some(element) = self.element_data() else {
+            return false;
+        };
+        match element.name.local {
+            local_name!("input") => match element.attr(local_name!("type")) {
+                Some("checkbox") => element.indeterminate,
+                Some("radio") => !element.radio_group.has_checked,
+                _ => false,
+            },
+            local_name!("progress") => !element.has_attr(local_name!("value")),
+            _ => false,
+        }
+    }
+
+    /// Whether this is a default option: a checkbox or radio button which is checked by
+    /// default, an option which is selected by default, or the default button of a form
+    pub fn is_default(&self) -> bool {
+        let Some(element) = self.element_data() else {
+            return false;
+        };
+        match element.name.local {
+            local_name!("input") if element.checkbox_input_checked().is_some() => {
+                element.has_attr(local_name!("checked"))
+            }
+            local_name!("option") => element.has_attr(local_name!("selected")),
+            _ => self.is_default_button(),
+        }
+    }
+
+    /// Whether this is the first submit button in the form that it is in, which is the button
+    /// that is activated by implicit submission
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#default-button>
+    fn is_default_button(&self) -> bool {
+        if !self.element_data().is_some_and(is_submit_button) {
+            return false;
+        }
+        let mut form_id = self.parent;
+        while let Some(id) = form_id {
+            let ancestor = self.with(id);
+            if ancestor.data.is_element_with_tag_name(&local_name!("form")) {
+                break;
+            }
+            form_id = ancestor.parent;
+        }
+        let Some(form_id) = form_id else {
+            return false;
+        };
+        self.first_submit_button(form_id) == Some(self.id)
+    }
+
+    /// The first submit button among the descendants of a node, in tree order
+    fn first_submit_button(&self, node_id: NodeId) -> Option<NodeId> {
+        self.with(node_id).children.iter().find_map(|&child_id| {
+            let child = self.with(child_id);
+            if child.element_data().is_some_and(is_submit_button) {
+                Some(child_id)
+            } else {
+                self.first_submit_button(child_id)
+            }
+        })
+    }
+}
+
+fn is_submit_button(element: &ElementData) -> bool {
+    element.is_submit_button()
+        || (element.name.local == local_name!("input")
+            && matches!(element.attr(local_name!("type")), Some("submit" | "image")))
+}
+
+impl BaseDocument {
+    /// Set the indeterminate flag of a checkbox, which makes it match `:indeterminate` (until
+    /// the user toggles it)
+    pub fn set_indeterminate(&mut self, node_id: NodeId, indeterminate: bool) {
+        let Some(element) = self.nodes[node_id].element_data_mut() else {
+            return;
+        };
+        if element.indeterminate == indeterminate {
+            return;
+        }
+        element.indeterminate = indeterminate;
+        self.invalidate_form_state(node_id);
+    }
+
+    /// Update the [`RadioGroupState`] of every radio button if radio buttons may have been
+    /// checked, added, removed or regrouped, restyling those whose group state changed. Like
+    /// [`BaseDocument::toggle_radio`], radio buttons are grouped by name across the document.
+    pub(crate) fn update_radio_groups(&mut self) {
+        if !self.radio_groups_dirty {
+            return;
+        }
+        self.radio_groups_dirty = false;
+
+        let mut groups: HashMap<&str, (Vec<NodeId>, RadioGroupState)> = HashMap::new();
+        let mut ungrouped = Vec::new();
+        for node_id in TreeTraverser::new(self) {
+            let Some(radio) = self.nodes[node_id].element_data() else {
+                continue;
+            };
+            if !radio.is_radio_button() {
+                continue;
+            }
+            let state = RadioGroupState {
+                has_checked: radio.checkbox_input_checked().unwrap_or(false),
+                required: radio.has_attr(local_name!("required")),
+            };
+            match radio
+                .attr(local_name!("name"))
+                .filter(|name| !name.is_empty())
+            {
+                Some(name) => {
+                    let (radio_ids, group) = groups.entry(name).or_default();
+                    radio_ids.push(node_id);
+                    group.has_checked |= state.has_checked;
+                    group.required |= state.required;
+                }
+                // A radio button without a name is in a group by itself
+                None => ungrouped.push((node_id, state)),
+            }
+        }
+
+        let states: Vec<(NodeId, RadioGroupState)> = groups
+            .into_values()
+            .flat_map(|(radio_ids, group)| radio_ids.into_iter().map(move |id| (id, group)))
+            .chain(ungrouped)
+            .collect();
+        for (node_id, state) in states {
+            let node = &mut self.nodes[node_id];
+            let Some(radio) = node.element_data_mut() else {
+                continue;
+            };
+            if radio.radio_group != state {
+                radio.radio_group = state;
+                node.set_restyle_hint(RestyleHint::restyle_subtree());
+                if let Some(parent_id) = node.parent {
+                    self.nodes[parent_id].set_restyle_hint(RestyleHint::restyle_subtree());
+                }
+                self.restyle_form_ancestors(node_id);
+            }
+        }
+    }
+
+    /// Restyle a form control whose state (its validity, whether its placeholder is shown,
+    /// etc) may have changed, along with the forms and fieldsets containing it (whose validity
+    /// depends on its validity). Siblings are restyled too, as they may be selected by their
+    /// state (e.g. `input:placeholder-shown + label`). The other radio buttons in a radio
+    /// button's group are restyled by [`update_radio_groups`](Self::update_radio_groups) if
+    /// their group's state changes.
+    pub(crate) fn invalidate_form_state(&mut self, node_id: NodeId) {
+        let node = &mut self.nodes[node_id];
+        let Some(element) = node.element_data() else {
+            return;
+        };
+        if element.is_radio_button() {
+            self.radio_groups_dirty = true;
+        }
+        node.set_restyle_hint(RestyleHint::restyle_subtree());
+        if let Some(parent_id) = node.parent {
+            self.nodes[parent_id].set_restyle_hint(RestyleHint::restyle_subtree());
+        }
+        // Its value or checkedness may also have changed
+        self.mark_node_changed(node_id);
+        self.restyle_form_ancestors(node_id);
+    }
+
+    /// Restyle the forms and fieldsets containing a node, which match `:valid`/`:invalid`
+    /// according to the validity of the form controls in them
+    pub(crate) fn restyle_form_ancestors(&mut self, node_id: NodeId) {
+        let mut ancestor_id = self.nodes[node_id].parent;
+        while let Some(id) = ancestor_id {
+            let ancestor = &mut self.nodes[id];
+            if ancestor.data.is_element_with_tag_name(&local_name!("form"))
+                || ancestor
+                    .data
+                    .is_element_with_tag_name(&local_name!("fieldset"))
+            {
+                ancestor.set_restyle_hint(RestyleHint::restyle_subtree());
+                self.form_validity_dirty.insert(id);
+            }
+            ancestor_id = ancestor.parent;
+        }
+    }
+
+    /// Check whether the forms and fieldsets whose form controls may have changed contain any
+    /// invalid form controls, so that matching `:valid`/`:invalid` against them doesn't need
+    /// to check all of their descendants
+    pub(crate) fn update_form_validity(&mut self) {
+        for node_id in std::mem::take(&mut self.form_validity_dirty) {
+            if self
+                .get_node(node_id)
+                .is_none_or(|node| node.element_data().is_none())
+            {
+                continue;
+            }
+            let has_invalid_control = TreeTraverser::new_with_root(self, node_id).any(|id| {
+                self.nodes[id]
+                    .validity()
+                    .is_some_and(|validity| !validity.valid())
+            });
+            if let Some(element) = self.nodes[node_id].element_data_mut() {
+                element.has_invalid_control = has_invalid_control;
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Playback of animated (GIF, APNG and WebP) images in `<img>` elements.
+//!
+//! Frames are decoded one at a time as playback reaches them, so an animated image only
+//! holds its encoded bytes and the frame which is displayed.
+
+use blitz_traits::net::Bytes;
+use blitz_traits::node_id::NodeId;
+
+use crate::BaseDocument;
+use crate::node::{ImageAnimation, ImageData, ImageFrame, SpecialElementData};
+
+/// Frames with a shorter duration than this are shown for [`DEFAULT_FRAME_DURATION`] instead,
+/// matching how browsers treat GIFs with (near) zero frame delays.
+#[cfg(feature = "animated-images")]
+const MIN_FRAME_DURATION: f64 = 0.011;
+#[cfg(feature = "animated-images")]
+const DEFAULT_FRAME_DURATION: f64 = 0.1;
+
+/// Create a decoder for the frames of an animated GIF, APNG or WebP image. Returns `None` for
+/// other formats and for images which aren't animated.
+#[cfg(feature = "animated-images")]
+pub(crate) fn decode_frames(bytes: &Bytes) -> Option<image::Frames<'static>> {
+    use image::codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder};
+    use image::{AnimationDecoder, ImageFormat};
+    use std::io::Cursor;
+
+    let frames = match image::guess_format(bytes).ok()? {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes.clone()))
+            .ok()?
+            .into_frames(),
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(Cursor::new(bytes.clone())).ok()?;
+            if !decoder.is_apng().ok()? {
+                return None;
+            }
+            decoder.apng().ok()?.into_frames()
+        }
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(Cursor::new(bytes.clone())).ok()?;
+            if !decoder.has_animation() {
+                return None;
+            }
+            decoder.into_frames()
+        }
+        _ => return None,
+    };
+    Some(frames)
+}
+
+#[cfg(not(feature = "animated-images"))]
+pub(crate) fn decode_frames(_bytes: &Bytes) -> Option<image::Frames<'static>> {
+    None
+}
+
+/// Convert a decoded frame to the RGBA8 data and duration it is displayed with
+#[cfg(feature = "animated-images")]
+pub(crate) fn image_frame(frame: image::Frame) -> ImageFrame {
+    use linebender_resource_handle::Blob;
+    use std::sync::Arc;
+
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    let duration = numer as f64 / denom.max(1) as f64 / 1000.0;
+    ImageFrame {
+        data: Blob::new(Arc::new(frame.into_buffer().into_raw())),
+        duration: if duration < MIN_FRAME_DURATION {
+            DEFAULT_FRAME_DURATION
+        } else {
+            duration
+        },
+    }
+}
+
+impl ImageAnimation {
+    /// Advance playback to `now`, returning the newly displayed frame if it changed.
+    ///
+    /// At most one frame is decoded per advance: playback which has fallen behind (because
+    /// the document wasn't resolved for a while) resumes from the next frame rather than
+    /// decoding every frame it missed.
+    pub(crate) fn advance(&mut self, now: f64) -> Option<ImageFrame> {
+        let elapsed = self
+            .last_tick
+            .map(|last| (now - last).max(0.0))
+            .unwrap_or(0.0);
+        self.last_tick = Some(now);
+        self.frame_elapsed += elapsed;
+        if self.frame_elapsed < self.frame_duration {
+            return None;
+        }
+
+        let Some(frame) = self.next_frame() else {
+            // A frame which fails to decode ends playback on the current frame
+            self.frame_duration = f64::INFINITY;
+            return None;
+        };
+        let overshoot = self.frame_elapsed - self.frame_duration;
+        self.frame_elapsed = if overshoot < frame.duration {
+            overshoot
+        } else {
+            0.0
+        };
+        self.frame_duration = frame.duration;
+        Some(frame)
+    }
+
+    /// The time (on the document's animation clock) at which the next frame is due, or
+    /// `None` if playback is paused
+    pub(crate) fn next_frame_time(&self) -> Option<f64> {
+        self.last_tick
+            .map(|last| last + (self.frame_duration - self.frame_elapsed).max(0.0))
+            .filter(|time| time.is_finite())
+    }
+
+    /// Decode the frame after the current one, looping back to the first frame after the
+    /// last
+    #[cfg(feature = "animated-images")]
+    fn next_frame(&mut self) -> Option<ImageFrame> {
+        if self.frames.is_none() {
+            // Skip up to the displayed frame (e.g. for a copy of a playing image)
+            let mut frames = decode_frames(&self.source)?;
+            frames.by_ref().take(self.current_frame + 1).for_each(drop);
+            self.frames = Some(frames);
+        }
+
+        let next = self.frames.as_mut()?.next();
+        let frame = match next {
+            Some(frame) => {
+                self.current_frame += 1;
+                frame
+            }
+            None => {
+                // Restart from the first frame
+                let mut frames = decode_frames(&self.source)?;
+                let frame = frames.next()?;
+                self.frames = Some(frames);
+                self.current_frame = 0;
+                frame
+            }
+        };
+        frame.ok().map(image_frame)
+    }
+
+    #[cfg(not(feature = "animated-images"))]
+    fn next_frame(&mut self) -> Option<ImageFrame> {
+        None
+    }
+}
+
+impl BaseDocument {
+    /// The animated raster image displayed by an `<img>` element (if any)
+    fn animated_image_mut(&mut self, node_id: NodeId) -> Option<&mut crate::node::RasterImageData> {
+        let element = self.get_node_mut(node_id)?.element_data_mut()?;
+        let SpecialElementData::Image(image) = &mut element.special_data else {
+            return None;
+        };
+        match &mut **image {
+            ImageData::Raster(raster) if raster.animation.is_some() => Some(raster),
+            _ => None,
+        }
+    }
+
+    /// Start tracking the playback of an `<img>` element's image if it is animated
+    pub(crate) fn track_image_animation(&mut self, node_id: NodeId) {
+        if self.animated_image_mut(node_id).is_some() {
+            self.animated_image_nodes.insert(node_id);
+        }
+    }
+
+    /// Whether any part of a node is within the viewport
+    pub(crate) fn is_in_viewport(&self, node_id: NodeId) -> bool {
+        let Some(rect) = self.get_client_bounding_rect(node_id) else {
+            return false;
+        };
+        let scale = self.viewport.scale_f64();
+        let (width, height) = self.viewport.window_size;
+        let (width, height) = (width as f64 / scale, height as f64 / scale);
+        rect.x + rect.width > 0.0 && rect.x < width && rect.y + rect.height > 0.0 && rect.y < height
+    }
+
+    /// Advance the frames of animated images to `now` (in seconds on the document's animation
+    /// clock). Images which are scrolled out of view are paused, as are all images when the
+    /// user prefers reduced motion.
+    ///
+    /// Playing images don't make the document animate every frame. Instead the time their
+    /// next frame is due is recorded, which [`BaseDocument::next_animation_frame_time`]
+    /// reports so that the shell only redraws when a frame changes.
+    pub(crate) fn resolve_image_animations(&mut self, now: f64) {
+        let node_ids: Vec<NodeId> = self.animated_image_nodes.iter().copied().collect();
+        let prefers_reduced_motion = self.prefers_reduced_motion;
+
+        let mut next_frame_time: Option<f64> = None;
+        for node_id in node_ids {
+            let is_playing = !prefers_reduced_motion
+                && self
+                    .get_node(node_id)
+                    .is_some_and(|node| node.flags.is_in_document())
+                && self.is_in_viewport(node_id);
+
+            let Some(image) = self.animated_image_mut(node_id) else {
+                // The image has been removed or replaced by a still image
+                self.animated_image_nodes.remove(&node_id);
+                continue;
+            };
+
+            if is_playing {
+                let frame_changed = image.advance_animation(now);
+                let frame_time = image.animation.as_ref().and_then(|a| a.next_frame_time());
+                next_frame_time = match (next_frame_time, frame_time) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+                if frame_changed {
+                    self.shell_provider.request_redraw();
+                }
+            } else {
+                image.pause_animation();
+            }
+        }
+        self.next_image_frame_time = next_frame_time;
+    }
+}
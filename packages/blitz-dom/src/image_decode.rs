@@ -0,0 +1,477 @@
+//! Decoding of `<img>` images at the size they are displayed at, and eviction of the
+//! decoded pixels of images which are out of view.
+//!
+//! Only the natural size of `<img>` raster images is read when they load (see
+//! [`ImageHandler`]). Once an `<img>` has been laid out, its image is decoded at the largest
+//! power-of-two fraction of its natural size which still covers the size it is painted at
+//! (accounting for `object-fit` and the viewport's scale). Decoded pixels are shared between
+//! all `<img>` elements which display the same url at the same size. Full-size pixels are
+//! released once the image is only displayed at smaller sizes, and the least recently used
+//! pixels are evicted when the decoded pixels exceed the document's memory budget.
+//!
+//! [`ImageHandler`]: crate::net::ImageHandler
+
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::sync::Arc;
+
+use blitz_traits::net::Bytes;
+use blitz_traits::node_id::NodeId;
+use style::properties::generated::longhands::object_fit::computed_value::T as ObjectFit;
+
+use crate::document::DocumentEvent;
+use crate::node::{ImageData, RasterImageData, SpecialElementData};
+use crate::{BaseDocument, ImageDecoding};
+
+/// Images are never decoded at less than `1 / 2^MAX_DECODE_LEVEL` of their natural size
+const MAX_DECODE_LEVEL: u32 = 6;
+
+/// The default number of bytes of decoded pixels to keep before evicting out of view images
+pub(crate) const DEFAULT_IMAGE_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+
+/// The encoded bytes and natural size of a raster image
+struct EncodedImage {
+    bytes: Bytes,
+    width: u32,
+    height: u32,
+    /// Whether the image has been decoded (at any size) since it loaded
+    decode_attempted: bool,
+}
+
+/// Decoded pixels at a given decode level, shared between the `<img>` elements using them
+struct DecodedPixels {
+    image: RasterImageData,
+    /// The resolve pass in which an `<img>` in view last used the pixels
+    last_used: u64,
+}
+
+/// What an image is re-decoded from
+enum DecodeSource {
+    /// Its full-size pixels, which are downsampled without decoding the image again
+    Pixels(RasterImageData),
+    /// Its encoded bytes
+    Encoded(Bytes),
+}
+
+/// Decoded images which can be re-decoded at other sizes or evicted
+#[derive(Default)]
+pub(crate) struct DecodedImages {
+    /// Encoded bytes by url
+    encoded: HashMap<String, EncodedImage>,
+    /// Decoded pixels by url and decode level (the image is decoded at `1 / 2^level` of
+    /// its natural size)
+    decoded: HashMap<(String, u32), DecodedPixels>,
+    /// The total size of the pixels in `decoded` (in bytes)
+    decoded_bytes: usize,
+    /// Decodes which are in progress on the worker pool
+    pending: HashSet<(String, u32)>,
+    /// `<img>` elements which display an image that can be re-decoded
+    nodes: HashSet<NodeId>,
+    /// The number of bytes of decoded pixels to keep before evicting out of view images
+    pub(crate) memory_budget: usize,
+    /// The number of resolve passes so far
+    generation: u64,
+}
+
+impl DecodedImages {
+    pub(crate) fn new(memory_budget: usize) -> Self {
+        Self {
+            memory_budget,
+            ..Default::default()
+        }
+    }
+
+    /// Keep the encoded bytes of a raster image so that it can be re-decoded at other sizes
+    pub(crate) fn insert_encoded(&mut self, url: String, width: u32, height: u32, bytes: Bytes) {
+        self.encoded.insert(
+            url,
+            EncodedImage {
+                bytes,
+                width,
+                height,
+                decode_attempted: false,
+            },
+        );
+    }
+
+    fn insert_pixels(&mut self, key: (String, u32), image: RasterImageData) {
+        self.decoded_bytes += image.data.data().len();
+        let pixels = DecodedPixels {
+            image,
+            last_used: self.generation,
+        };
+        if let Some(old) = self.decoded.insert(key, pixels) {
+            self.decoded_bytes -= old.image.data.data().len();
+        }
+    }
+
+    fn remove_pixels(&mut self, key: &(String, u32)) -> Option<DecodedPixels> {
+        let pixels = self.decoded.remove(key)?;
+        self.decoded_bytes -= pixels.image.data.data().len();
+        Some(pixels)
+    }
+
+    /// The image for a url which has been loaded before, if its bytes are still available.
+    /// The pixels are evicted if they are not currently decoded at full size.
+    pub(crate) fn get(&self, url: &str) -> Option<RasterImageData> {
+        let encoded = self.encoded.get(url)?;
+        let image = match self.decoded.get(&(url.to_string(), 0)) {
+            Some(pixels) => pixels.image.clone(),
+            None => RasterImageData::evicted(encoded.width, encoded.height),
+        };
+        Some(image)
+    }
+}
+
+/// Decode an image at `1 / 2^level` of its natural size.
+///
+/// The image crate's codecs can only decode at full size, so images are decoded in full and
+/// then downsampled (with a box filter, which is cheap for the power-of-two reductions used
+/// here), unless their full-size pixels are still held. The full-size buffer is dropped as
+/// soon as the downsampled copy exists.
+fn decode_at_level(source: DecodeSource, level: u32) -> Result<RasterImageData, String> {
+    use image::imageops::thumbnail;
+
+    let scaled = |width: u32, height: u32| ((width >> level).max(1), (height >> level).max(1));
+    match source {
+        DecodeSource::Pixels(image) => {
+            let (width, height) = scaled(image.width, image.height);
+            let pixels = image::ImageBuffer::<image::Rgba<u8>, &[u8]>::from_raw(
+                image.width,
+                image.height,
+                image.data.data(),
+            )
+            .ok_or("image data doesn't match its size")?;
+            let data = thumbnail(&pixels, width, height).into_raw();
+            Ok(RasterImageData::scaled(
+                image.natural_width,
+                image.natural_height,
+                width,
+                height,
+                Arc::new(data),
+            ))
+        }
+        DecodeSource::Encoded(bytes) => {
+            let image = image::ImageReader::new(Cursor::new(&bytes))
+                .with_guessed_format()
+                .expect("IO errors impossible with Cursor")
+                .decode()
+                .map_err(|err| err.to_string())?;
+            let (natural_width, natural_height) = (image.width(), image.height());
+            let (width, height) = scaled(natural_width, natural_height);
+            let data = match level {
+                0 => image.into_rgba8().into_raw(),
+                _ => {
+                    let data = thumbnail(&image, width, height).into_raw();
+                    drop(image);
+                    data
+                }
+            };
+            Ok(RasterImageData::scaled(
+                natural_width,
+                natural_height,
+                width,
+                height,
+                Arc::new(data),
+            ))
+        }
+    }
+}
+
+/// The decode level of an image's pixels (or `None` if they have been evicted)
+fn current_level(image: &RasterImageData) -> Option<u32> {
+    if image.is_evicted() {
+        return None;
+    }
+    Some((image.natural_width / image.width).max(1).ilog2())
+}
+
+/// The decode level which covers `needed` device pixels of an image with a natural size of
+/// `natural` pixels
+fn level_for_size(natural: (u32, u32), needed: (f32, f32)) -> u32 {
+    let mut level = 0;
+    while level < MAX_DECODE_LEVEL
+        && (natural.0 >> (level + 1)) as f32 >= needed.0
+        && (natural.1 >> (level + 1)) as f32 >= needed.1
+        && (natural.0 >> (level + 1)) > 0
+        && (natural.1 >> (level + 1)) > 0
+    {
+        level += 1;
+    }
+    level
+}
+
+impl BaseDocument {
+    /// The url and raster image displayed by an `<img>` element whose image can be re-decoded
+    fn decodable_image(&self, node_id: NodeId) -> Option<(&str, &RasterImageData)> {
+        let element = self.get_node(node_id)?.element_data()?;
+        let url = element.image_source.as_ref()?.url.as_str();
+        let SpecialElementData::Image(image) = &element.special_data else {
+            return None;
+        };
+        match &**image {
+            ImageData::Raster(raster)
+                if raster.animation.is_none() && self.decoded_images.encoded.contains_key(url) =>
+            {
+                Some((url, raster))
+            }
+            _ => None,
+        }
+    }
+
+    /// Start managing the decoded size of an `<img>` element's image if it can be re-decoded
+    pub(crate) fn track_image_decode(&mut self, node_id: NodeId) {
+        if self.decodable_image(node_id).is_some() {
+            self.decoded_images.nodes.insert(node_id);
+        }
+    }
+
+    /// The decode level for an `<img>` element's image given the size it is painted at
+    fn wanted_decode_level(&self, node_id: NodeId, image: &RasterImageData) -> u32 {
+        let node = &self.nodes[node_id];
+        let Some(styles) = node.primary_styles() else {
+            return 0;
+        };
+        let layout = node.final_layout();
+        let scale = self.viewport.scale();
+        let content_width = layout.content_box_width() * scale;
+        let content_height = layout.content_box_height() * scale;
+
+        let natural_width = image.natural_width as f32;
+        let natural_height = image.natural_height as f32;
+        let x_fit = content_width / natural_width;
+        let y_fit = content_height / natural_height;
+        let (x_scale, y_scale) = match styles.clone_object_fit() {
+            ObjectFit::Fill => (x_fit, y_fit),
+            ObjectFit::Contain => (x_fit.min(y_fit), x_fit.min(y_fit)),
+            ObjectFit::Cover => (x_fit.max(y_fit), x_fit.max(y_fit)),
+            ObjectFit::None => (scale, scale),
+            ObjectFit::ScaleDown => {
+                let fit = x_fit.min(y_fit).min(scale);
+                (fit, fit)
+            }
+        };
+
+        level_for_size(
+            (image.natural_width, image.natural_height),
+            (natural_width * x_scale, natural_height * y_scale),
+        )
+    }
+
+    /// Decode the images of `<img>` elements which are in view at the size they are displayed
+    /// at, and evict the pixels of images which are out of view if the decoded pixels exceed
+    /// the memory budget. Images which are out of view are only decoded if they haven't been
+    /// decoded before and their pixels fit within the memory budget.
+    pub(crate) fn resolve_image_decodes(&mut self) {
+        self.decoded_images.generation += 1;
+        let generation = self.decoded_images.generation;
+
+        let node_ids: Vec<NodeId> = self.decoded_images.nodes.iter().copied().collect();
+        for node_id in node_ids {
+            let Some((url, image)) = self.decodable_image(node_id) else {
+                // The image has been removed or replaced
+                self.decoded_images.nodes.remove(&node_id);
+                continue;
+            };
+            if !self.nodes[node_id].flags.is_in_document() {
+                continue;
+            }
+            let in_view = self.is_in_viewport(node_id);
+            if !in_view && self.decoded_images.encoded[url].decode_attempted {
+                continue;
+            }
+
+            let key = (url.to_string(), self.wanted_decode_level(node_id, image));
+            if !in_view {
+                let (width, height) = (image.natural_width >> key.1, image.natural_height >> key.1);
+                let size = width.max(1) as usize * height.max(1) as usize * 4;
+                if self.decoded_images.decoded_bytes + size > self.decoded_images.memory_budget {
+                    continue;
+                }
+            }
+            if current_level(image) == Some(key.1) {
+                if let Some(pixels) = self.decoded_images.decoded.get_mut(&key) {
+                    pixels.last_used = generation;
+                }
+                continue;
+            }
+
+            // Use the pixels if they have already been decoded at the wanted size...
+            if let Some(pixels) = self.decoded_images.decoded.get_mut(&key) {
+                pixels.last_used = generation;
+                let image = pixels.image.clone();
+                self.set_decoded_image(node_id, image);
+                continue;
+            }
+
+            // ...and otherwise decode them
+            if !self.decoded_images.pending.insert(key.clone()) {
+                continue;
+            }
+            let full_size = self.decoded_images.decoded.get(&(key.0.clone(), 0));
+            let source = match full_size {
+                Some(pixels) if !pixels.image.is_evicted() => {
+                    DecodeSource::Pixels(pixels.image.clone())
+                }
+                _ => DecodeSource::Encoded(self.decoded_images.encoded[&key.0].bytes.clone()),
+            };
+            match self.image_decoding {
+                ImageDecoding::Inline => {
+                    let result = decode_at_level(source, key.1);
+                    self.apply_decoded_image(key.0.clone(), key.1, result);
+                    if let Some(pixels) = self.decoded_images.decoded.get_mut(&key) {
+                        pixels.last_used = generation;
+                        let image = pixels.image.clone();
+                        self.set_decoded_image(node_id, image);
+                    }
+                }
+                ImageDecoding::WorkerPool => {
+                    let tx = self.tx.clone();
+                    let shell_provider = self.shell_provider.clone();
+                    rayon::spawn(move || {
+                        let (url, level) = key;
+                        let result = decode_at_level(source, level);
+                        let _ = tx.send(DocumentEvent::ImageDecoded { url, level, result });
+                        shell_provider.request_redraw();
+                    });
+                }
+            }
+        }
+
+        self.evict_decoded_images(generation);
+    }
+
+    /// Replace the pixels of an `<img>` element's image
+    fn set_decoded_image(&mut self, node_id: NodeId, image: RasterImageData) {
+        let element = self.nodes[node_id].element_data_mut().unwrap();
+        element.special_data = SpecialElementData::Image(Box::new(ImageData::Raster(image)));
+        self.shell_provider.request_redraw();
+    }
+
+    /// Store the result of re-decoding an image. It is applied to the `<img>` elements which
+    /// want it on the next resolve.
+    pub(crate) fn apply_decoded_image(
+        &mut self,
+        url: String,
+        level: u32,
+        result: Result<RasterImageData, String>,
+    ) {
+        let key = (url, level);
+        self.decoded_images.pending.remove(&key);
+        if let Some(encoded) = self.decoded_images.encoded.get_mut(&key.0) {
+            encoded.decode_attempted = true;
+        }
+        match result {
+            Ok(image) => self.decoded_images.insert_pixels(key, image),
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    url = key.0.as_str(),
+                    error = _err.as_str(),
+                    "Image decode failed"
+                );
+            }
+        }
+    }
+
+    /// Release the full-size pixels of images which are only displayed at smaller sizes in
+    /// view, then evict the least recently used decoded pixels which are not used by `<img>`
+    /// elements in view until the decoded pixels fit within the memory budget.
+    fn evict_decoded_images(&mut self, generation: u64) {
+        let images = &self.decoded_images;
+        let in_use: HashSet<&str> = images
+            .decoded
+            .iter()
+            .filter(|(_, pixels)| pixels.last_used == generation)
+            .map(|((url, _), _)| url.as_str())
+            .collect();
+        let mut to_evict: Vec<(String, u32)> = images
+            .decoded
+            .iter()
+            .filter(|((url, level), pixels)| {
+                *level == 0 && pixels.last_used < generation && in_use.contains(url.as_str())
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut total = images.decoded_bytes
+            - to_evict
+                .iter()
+                .map(|key| images.decoded[key].image.data.data().len())
+                .sum::<usize>();
+        if total > images.memory_budget {
+            let mut candidates: Vec<(u64, &(String, u32))> = images
+                .decoded
+                .iter()
+                .filter(|(key, pixels)| pixels.last_used < generation && !to_evict.contains(*key))
+                .map(|(key, pixels)| (pixels.last_used, key))
+                .collect();
+            candidates.sort_by_key(|(last_used, _)| *last_used);
+            for (_, key) in candidates {
+                if total <= images.memory_budget {
+                    break;
+                }
+                total -= images.decoded[key].image.data.data().len();
+                to_evict.push(key.clone());
+            }
+        }
+        if to_evict.is_empty() {
+            return;
+        }
+
+        let mut evicted_urls = HashSet::new();
+        for key in to_evict {
+            self.decoded_images.remove_pixels(&key);
+            if key.1 == 0 {
+                self.image_cache.remove(&key.0);
+            }
+            evicted_urls.insert(key);
+        }
+
+        // Drop the evicted pixels from the `<img>` elements displaying them
+        let node_ids: Vec<NodeId> = self.decoded_images.nodes.iter().copied().collect();
+        for node_id in node_ids {
+            let Some((url, image)) = self.decodable_image(node_id) else {
+                continue;
+            };
+            let Some(level) = current_level(image) else {
+                continue;
+            };
+            if evicted_urls.contains(&(url.to_string(), level)) {
+                let evicted = RasterImageData::evicted(image.natural_width, image.natural_height);
+                self.set_decoded_image(node_id, evicted);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_level_covers_needed_size() {
+        assert_eq!(level_for_size((1024, 512), (1024.0, 512.0)), 0);
+        assert_eq!(level_for_size((1024, 512), (600.0, 300.0)), 0);
+        assert_eq!(level_for_size((1024, 512), (512.0, 256.0)), 1);
+        assert_eq!(level_for_size((1024, 512), (64.0, 32.0)), 4);
+        // Both axes must be covered
+        assert_eq!(level_for_size((1024, 512), (64.0, 300.0)), 0);
+        // Tiny sizes are capped
+        assert_eq!(level_for_size((4096, 4096), (1.0, 1.0)), MAX_DECODE_LEVEL);
+    }
+
+    #[test]
+    fn full_size_pixels_are_downsampled_without_decoding() {
+        // A 4x2 image with a red left half and a blue right half
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let row = [red, red, blue, blue].concat();
+        let image = RasterImageData::new(4, 2, Arc::new([row.clone(), row].concat()));
+
+        let scaled = decode_at_level(DecodeSource::Pixels(image), 1).unwrap();
+        assert_eq!((scaled.natural_width, scaled.natural_height), (4, 2));
+        assert_eq!((scaled.width, scaled.height), (2, 1));
+        assert_eq!(scaled.data.data(), [red, blue].concat());
+    }
+}
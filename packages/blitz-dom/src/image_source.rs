@@ -0,0 +1,495 @@
+//! Selection of the image loaded by `<img>` elements from their `src`, `srcset` and `sizes`
+//! attributes and the `<source>` elements of a parent `<picture>` element.
+//!
+//! See: <https://html.spec.whatwg.org/multipage/images.html#selecting-an-image-source>
+
+use blitz_traits::events::{BlitzLoadEvent, DomEventData};
+use blitz_traits::node_id::NodeId;
+use cssparser::ParserInput;
+use selectors::matching::QuirksMode;
+use style::media_queries::MediaList;
+use style::parser::ParserContext;
+use style::stylesheets::{CssRuleType, CustomMediaEvaluator, Origin, UrlExtraData};
+use style::values::Parser;
+use style::values::specified::source_size_list::SourceSizeList;
+use style_traits::ParsingMode;
+
+use crate::layout::damage::ALL_DAMAGE;
+use crate::net::{ImageHandler, ResourceHandler};
+use crate::node::{ImageData, ImageSource, SpecialElementData, Status};
+use crate::util::ImageType;
+use crate::{BaseDocument, ElementData, local_name};
+
+/// The descriptor of an image candidate in a `srcset` attribute
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CandidateDescriptor {
+    /// A pixel density descriptor (e.g. `2x`)
+    Density(f32),
+    /// A width descriptor (e.g. `640w`), which is converted into a density using `sizes`
+    Width(u32),
+}
+
+/// An image candidate from a `srcset` attribute (or the `src` attribute)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ImageCandidate<'a> {
+    pub(crate) url: &'a str,
+    pub(crate) descriptor: CandidateDescriptor,
+}
+
+/// Parse a `srcset` attribute into a list of image candidates. Candidates with invalid
+/// descriptors are dropped.
+///
+/// See: <https://html.spec.whatwg.org/multipage/images.html#parsing-a-srcset-attribute>
+pub(crate) fn parse_srcset(srcset: &str) -> Vec<ImageCandidate<'_>> {
+    let mut candidates = Vec::new();
+    let mut rest = srcset;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            return candidates;
+        }
+
+        // The url runs until the next whitespace. Trailing commas end the candidate.
+        let url_end = rest
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let (url, after_url) = rest.split_at(url_end);
+        let trimmed_url = url.trim_end_matches(',');
+
+        let descriptors = if trimmed_url.len() != url.len() {
+            rest = after_url;
+            ""
+        } else {
+            // Descriptors run until the next comma that is not inside parentheses
+            let mut depth = 0;
+            let end = after_url
+                .find(|c: char| match c {
+                    '(' => {
+                        depth += 1;
+                        false
+                    }
+                    ')' => {
+                        depth -= 1;
+                        false
+                    }
+                    ',' => depth <= 0,
+                    _ => false,
+                })
+                .unwrap_or(after_url.len());
+            rest = &after_url[end..];
+            &after_url[..end]
+        };
+
+        if let Some(descriptor) = parse_descriptors(descriptors) {
+            candidates.push(ImageCandidate {
+                url: trimmed_url,
+                descriptor,
+            });
+        }
+    }
+}
+
+fn parse_descriptors(descriptors: &str) -> Option<CandidateDescriptor> {
+    let mut result = None;
+    let mut has_height = false;
+    for descriptor in descriptors.split_ascii_whitespace() {
+        let (value, kind) = descriptor.split_at(descriptor.len() - 1);
+        match kind {
+            "w" if result.is_none() => {
+                let width = value.parse::<u32>().ok().filter(|&w| w > 0)?;
+                result = Some(CandidateDescriptor::Width(width));
+            }
+            "x" if result.is_none() => {
+                let density = value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|d| d.is_finite() && *d >= 0.0)?;
+                result = Some(CandidateDescriptor::Density(density));
+            }
+            // Height descriptors are only valid alongside a width descriptor and are
+            // otherwise unused.
+            "h" if !has_height => {
+                value.parse::<u32>().ok().filter(|&h| h > 0)?;
+                has_height = true;
+            }
+            _ => return None,
+        }
+    }
+
+    if has_height && !matches!(result, Some(CandidateDescriptor::Width(_))) {
+        return None;
+    }
+    Some(result.unwrap_or(CandidateDescriptor::Density(1.0)))
+}
+
+/// Whether an image with the MIME type in a `<source type>` attribute can be decoded
+fn is_supported_image_type(mime_type: &str) -> bool {
+    let mime_type = mime_type.trim().to_ascii_lowercase();
+    if mime_type.is_empty() {
+        return true;
+    }
+    if cfg!(feature = "svg") && mime_type == "image/svg+xml" {
+        return true;
+    }
+    image::ImageFormat::from_mime_type(&mime_type).is_some_and(|format| format.reading_enabled())
+}
+
+/// Create a parser context for parsing the `media` and `sizes` attributes
+fn attr_parser_context(url_extra_data: &UrlExtraData) -> ParserContext<'_> {
+    ParserContext::new(
+        Origin::Author,
+        url_extra_data,
+        Some(CssRuleType::Media),
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+        /* namespaces = */ Default::default(),
+        None,
+        None,
+        /* attr_taint = */ Default::default(),
+    )
+}
+
+impl BaseDocument {
+    /// Whether a `<source media>` attribute matches the current viewport
+    fn media_attr_matches(&self, media: &str) -> bool {
+        let url_extra_data = self.url.url_extra_data();
+        let context = attr_parser_context(&url_extra_data);
+        let mut input = ParserInput::new(media);
+        let media_list = MediaList::parse(&context, &mut Parser::new(&mut input));
+        media_list.evaluate(
+            self.stylist.device(),
+            QuirksMode::NoQuirks,
+            &mut CustomMediaEvaluator::none(),
+        )
+    }
+
+    /// The width (in CSS pixels) that the image is expected to be displayed at according
+    /// to a `sizes` attribute. Defaults to `100vw`.
+    fn source_size(&self, sizes: Option<&str>) -> f32 {
+        let url_extra_data = self.url.url_extra_data();
+        let context = attr_parser_context(&url_extra_data);
+        let mut input = ParserInput::new(sizes.unwrap_or(""));
+        let source_sizes = SourceSizeList::parse(&context, &mut Parser::new(&mut input));
+        source_sizes
+            .evaluate(self.stylist.device(), QuirksMode::NoQuirks)
+            .to_f32_px()
+    }
+
+    /// Select the image that an `<img>` element should display from its `src` and `srcset`
+    /// attributes or from the `<source>` elements of its parent `<picture>` element, given
+    /// the viewport's scale and size.
+    ///
+    /// Returns `None` if there are no image candidates.
+    pub(crate) fn select_image_source(&self, img_id: NodeId) -> Option<ImageSource> {
+        let node = &self.nodes[img_id];
+        let img = node.element_data()?;
+
+        // The first `<source>` before the `<img>` in a `<picture>` which matches is used
+        let parent = node.parent.map(|parent_id| &self.nodes[parent_id]);
+        if let Some(picture) = parent.filter(|parent| {
+            parent
+                .data
+                .is_element_with_tag_name(&local_name!("picture"))
+        }) {
+            for &child_id in picture.children.iter() {
+                if child_id == img_id {
+                    break;
+                }
+                let Some(source) = self.nodes[child_id]
+                    .element_data()
+                    .filter(|el| el.name.local == local_name!("source"))
+                else {
+                    continue;
+                };
+                let Some(srcset) = source.attr(local_name!("srcset")) else {
+                    continue;
+                };
+                if source
+                    .attr(local_name!("media"))
+                    .is_some_and(|media| !self.media_attr_matches(media))
+                {
+                    continue;
+                }
+                if source
+                    .attr(local_name!("type"))
+                    .is_some_and(|mime_type| !is_supported_image_type(mime_type))
+                {
+                    continue;
+                }
+
+                let candidates = parse_srcset(srcset);
+                if !candidates.is_empty() {
+                    return self.pick_image_candidate(&candidates, source);
+                }
+            }
+        }
+
+        let mut candidates = img
+            .attr(local_name!("srcset"))
+            .map(parse_srcset)
+            .unwrap_or_default();
+
+        // `src` is a `1x` candidate unless `srcset` uses width descriptors or already has one
+        if let Some(src) = img.attr(local_name!("src")).filter(|src| !src.is_empty()) {
+            let has_src_equivalent =
+                candidates
+                    .iter()
+                    .any(|candidate| match candidate.descriptor {
+                        CandidateDescriptor::Width(_) => true,
+                        CandidateDescriptor::Density(density) => density == 1.0,
+                    });
+            if !has_src_equivalent {
+                candidates.push(ImageCandidate {
+                    url: src,
+                    descriptor: CandidateDescriptor::Density(1.0),
+                });
+            }
+        }
+
+        self.pick_image_candidate(&candidates, img)
+    }
+
+    /// Pick the candidate with the lowest density which is at least the viewport's scale
+    /// (or the highest density if there is no such candidate).
+    fn pick_image_candidate(
+        &self,
+        candidates: &[ImageCandidate<'_>],
+        element: &ElementData,
+    ) -> Option<ImageSource> {
+        let has_width_descriptors = candidates
+            .iter()
+            .any(|candidate| matches!(candidate.descriptor, CandidateDescriptor::Width(_)));
+        let source_size = match has_width_descriptors {
+            true => self.source_size(element.attr(local_name!("sizes"))),
+            false => 0.0,
+        };
+
+        let scale = self.viewport.scale();
+        let mut best: Option<(&str, f32)> = None;
+        for candidate in candidates {
+            let density = match candidate.descriptor {
+                CandidateDescriptor::Density(density) => density,
+                CandidateDescriptor::Width(width) if source_size > 0.0 => {
+                    width as f32 / source_size
+                }
+                CandidateDescriptor::Width(_) => f32::INFINITY,
+            };
+            let is_better = match best {
+                None => true,
+                Some((_, best_density)) if best_density < scale => density > best_density,
+                Some((_, best_density)) => density >= scale && density < best_density,
+            };
+            if is_better {
+                best = Some((candidate.url, density));
+            }
+        }
+
+        let (url, density) = best?;
+        let url = self.url.resolve_relative(url)?;
+        Some(ImageSource {
+            url: url.to_string(),
+            density: if density.is_finite() && density > 0.0 {
+                density
+            } else {
+                1.0
+            },
+        })
+    }
+
+    /// Select and load the image for an `<img>` element
+    pub(crate) fn load_image(&mut self, target_id: NodeId) {
+        if self.image_source_depends_on_viewport(target_id) {
+            self.viewport_dependent_images.insert(target_id);
+        } else {
+            self.viewport_dependent_images.remove(&target_id);
+        }
+
+        let source = self.select_image_source(target_id);
+        let node = &mut self.nodes[target_id];
+        let element = node.element_data_mut().unwrap();
+        let previous = std::mem::replace(&mut element.image_source, source.clone());
+
+        // Without a source the element no longer displays an image
+        let Some(source) = source else {
+            if element.image_status.is_some() {
+                element.special_data = SpecialElementData::None;
+                element.image_status = None;
+                node.cache_mut().clear();
+                node.insert_damage(ALL_DAMAGE);
+            }
+            return;
+        };
+
+        // The image is already loaded (or loading). If the density has changed then its
+        // natural size has too.
+        let is_loaded_or_loading = matches!(
+            element.image_status,
+            Some(Status::Ok) | Some(Status::Loading)
+        );
+        if let Some(previous) =
+            previous.filter(|previous| previous.url == source.url && is_loaded_or_loading)
+        {
+            if previous.density != source.density {
+                node.cache_mut().clear();
+                node.insert_damage(ALL_DAMAGE);
+            }
+            return;
+        }
+
+        let src_string = source.url.as_str();
+
+        // Check cache first (falling back to the encoded bytes of images whose pixels
+        // have been evicted)
+        let cached_image = self.image_cache.get(src_string).cloned().or_else(|| {
+            let image = self.decoded_images.get(src_string)?;
+            Some(ImageData::Raster(image))
+        });
+        if let Some(cached_image) = cached_image {
+            #[cfg(feature = "tracing")]
+            tracing::info!("Loading image {src_string} from cache");
+            let node = &mut self.nodes[target_id];
+            let element = node.element_data_mut().unwrap();
+            element.special_data = SpecialElementData::Image(Box::new(cached_image));
+            element.image_status = Some(Status::Ok);
+            node.cache_mut().clear();
+            node.insert_damage(ALL_DAMAGE);
+            self.track_image_animation(target_id);
+            self.track_image_decode(target_id);
+
+            let url = source.url;
+            self.queue_event(target_id, DomEventData::Load(BlitzLoadEvent { url }));
+            return;
+        }
+
+        let element = self.nodes[target_id].element_data_mut().unwrap();
+        element.image_status = Some(Status::Loading);
+
+        // Check if there's already a pending request for this URL
+        if let Some(waiting_list) = self.pending_images.get_mut(src_string) {
+            #[cfg(feature = "tracing")]
+            tracing::info!("Image {src_string} already pending, queueing node {target_id}");
+            waiting_list.push((target_id, ImageType::Image));
+            return;
+        }
+
+        // Start fetch and track as pending
+        #[cfg(feature = "tracing")]
+        tracing::info!("Fetching image {src_string}");
+        self.pending_images
+            .insert(src_string.to_string(), vec![(target_id, ImageType::Image)]);
+
+        let Ok(url) = url::Url::parse(src_string) else {
+            return;
+        };
+        self.net_provider.fetch(
+            self.id(),
+            self.build_request(url),
+            ResourceHandler::boxed(
+                self.tx.clone(),
+                self.id(),
+                None, // Don't pass node_id, we'll handle it via pending_images
+                self.shell_provider.clone(),
+                ImageHandler::new(
+                    ImageType::Image,
+                    src_string.to_string(),
+                    self.image_decoding,
+                ),
+            ),
+        );
+    }
+
+    /// Re-select the images of `<img>` elements which depend on the viewport (those with a
+    /// `srcset` attribute or within a `<picture>` element), loading any which have changed.
+    /// Called when the viewport's scale or size changes.
+    pub(crate) fn reselect_image_sources(&mut self) {
+        let mut img_ids: Vec<NodeId> = self.viewport_dependent_images.iter().copied().collect();
+        img_ids.retain(|&img_id| self.image_source_depends_on_viewport(img_id));
+        self.viewport_dependent_images = img_ids.iter().copied().collect();
+
+        for img_id in img_ids {
+            if !self.nodes[img_id].flags.is_in_document() {
+                continue;
+            }
+            let source = self.select_image_source(img_id);
+            if self.nodes[img_id].element_data().unwrap().image_source != source {
+                self.load_image(img_id);
+            }
+        }
+    }
+
+    /// Whether the image selected by an `<img>` element depends on the viewport: it has a
+    /// `srcset` attribute or is within a `<picture>` element
+    fn image_source_depends_on_viewport(&self, node_id: NodeId) -> bool {
+        let Some(node) = self.get_node(node_id) else {
+            return false;
+        };
+        node.data.is_element_with_tag_name(&local_name!("img"))
+            && (node.attr(local_name!("srcset")).is_some()
+                || node.parent.is_some_and(|parent_id| {
+                    self.nodes[parent_id]
+                        .data
+                        .is_element_with_tag_name(&local_name!("picture"))
+                }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptors(srcset: &str) -> Vec<(&str, CandidateDescriptor)> {
+        parse_srcset(srcset)
+            .into_iter()
+            .map(|candidate| (candidate.url, candidate.descriptor))
+            .collect()
+    }
+
+    #[test]
+    fn parses_density_descriptors() {
+        assert_eq!(
+            descriptors("a.png, b.png 2x,c.png 1.5x"),
+            vec![
+                ("a.png", CandidateDescriptor::Density(1.0)),
+                ("b.png", CandidateDescriptor::Density(2.0)),
+                ("c.png", CandidateDescriptor::Density(1.5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_width_descriptors() {
+        assert_eq!(
+            descriptors(
+                "  small.png 320w,
+ large.png 1280w 720h "
+            ),
+            vec![
+                ("small.png", CandidateDescriptor::Width(320)),
+                ("large.png", CandidateDescriptor::Width(1280)),
+            ]
+        );
+    }
+
+    #[test]
+    fn urls_may_contain_commas() {
+        assert_eq!(
+            descriptors("data:image/png;base64,AAAA 2x, b.png,"),
+            vec![
+                (
+                    "data:image/png;base64,AAAA",
+                    CandidateDescriptor::Density(2.0)
+                ),
+                ("b.png", CandidateDescriptor::Density(1.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_descriptors_drop_the_candidate() {
+        assert_eq!(
+            descriptors("a.png 2x 3x, b.png 0w, c.png 100h, d.png foo, e.png 3x"),
+            vec![("e.png", CandidateDescriptor::Density(3.0))]
+        );
+    }
+}
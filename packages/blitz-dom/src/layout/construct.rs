@@ -10,9 +10,11 @@ use parley::{
 use style::{
     computed_values::position::T as PositionProperty,
     data::ElementData as StyloElementData,
+    properties::ComputedValues,
+    servo_arc::Arc as ServoArc,
     shared_lock::StylesheetGuards,
     values::{
-        computed::{Content, ContentItem, Display, Float, TextTransform},
+        computed::{Display, Float, TextTransform},
         specified::box_::{DisplayInside, DisplayOutside},
     },
 };
@@ -22,8 +24,9 @@ use crate::{
     BaseDocument, ElementData, Node, NodeData,
     layout::damage::{CONSTRUCT_BOX, CONSTRUCT_DESCENDENT, CONSTRUCT_FC},
     node::{
-        ListItemLayout, ListItemLayoutPosition, Marker, NodeFlags, NodeKind, SpecialElementData,
-        TextBrush, TextInputData, TextLayout,
+        Attribute, GeneratedContentItem, ListItemLayout, ListItemLayoutPosition, Marker, NodeFlags,
+        NodeKind, SelectOptionLabel, SpecialElementData, TextBrush, TextInputData,
+        TextInputPlaceholder, TextLayout,
     },
     qual_name, stylo_to_parley,
     traversal::{iter_children, iter_children_and_pseudos},
@@ -31,7 +34,7 @@ use crate::{
 
 use super::{
     damage::ALL_DAMAGE,
-    list::{BULLET_FONT_FAMILY, collect_list_item_children},
+    list::{BULLET_FONT_FAMILY, update_list_item_marker},
     replaced::is_replaced_element,
     table::build_table_context,
 };
@@ -436,6 +439,7 @@ fn collect_layout_children_with_wrap(
     }
 
     flush_pseudo_elements(doc, container_node_id);
+    update_list_item_marker(doc, container_node_id);
 
     if let Some(el) = doc.nodes[container_node_id].data.downcast_element() {
         // Handle text inputs
@@ -460,6 +464,11 @@ fn collect_layout_children_with_wrap(
             }
         }
 
+        if tag_name == "select" {
+            create_select(doc, container_node_id);
+            return;
+        }
+
         #[cfg(feature = "svg")]
         if matches!(tag_name, "svg") {
             let mut outer_html = doc.get_node(container_node_id).unwrap().outer_html();
@@ -499,19 +508,6 @@ fn collect_layout_children_with_wrap(
             };
             return;
         }
-
-        //Only ol tags have start and reversed attributes
-        let (mut index, reversed) = if tag_name == "ol" {
-            (
-                el.attr_parsed(local_name!("start"))
-                    .map(|start: usize| start - 1)
-                    .unwrap_or(0),
-                el.attr_parsed(local_name!("reversed")).unwrap_or(false),
-            )
-        } else {
-            (1, false)
-        };
-        collect_list_item_children(doc, &mut index, reversed, container_node_id);
     }
 
     // Skip further construction if the node has no children or psuedo-children
@@ -658,24 +654,6 @@ fn collect_layout_children_with_wrap(
     }
 }
 
-/// Extract the text generated by a pseudo-element's `content` property
-/// (only string content items are currently supported).
-fn pe_content_text(style: &style::properties::ComputedValues) -> Option<&str> {
-    match &style.get_counters().content {
-        Content::Items(item_data) => {
-            let items = &item_data.items[0..item_data.alt_start];
-            match items.first() {
-                Some(ContentItem::String(owned_str)) => Some(owned_str),
-                _ => {
-                    // TODO: other types of content
-                    None
-                }
-            }
-        }
-        _ => None,
-    }
-}
-
 fn flush_pseudo_elements(doc: &mut BaseDocument, node_id: NodeId) {
     let (before_style, after_style, before_node_id, after_node_id) = {
         let node = &doc.nodes[node_id];
@@ -695,12 +673,21 @@ fn flush_pseudo_elements(doc: &mut BaseDocument, node_id: NodeId) {
         (before_style, after_style, before_node_id, after_node_id)
     };
 
+    // The content generated by each pseudo-element's `content` (resolved along with counters)
+    let (before_content, after_content) = doc.nodes[node_id]
+        .element_data()
+        .and_then(|el| el.generated_content.as_deref())
+        .map(|content| (content.before.clone(), content.after.clone()))
+        .unwrap_or_default();
+
     // Sync pseudo element
     // TODO: Make incremental
-    for (idx, pe_style, pe_node_id) in [
-        (1, before_style, before_node_id),
-        (0, after_style, after_node_id),
+    for (idx, pe_style, pe_node_id, pe_content) in [
+        (1, before_style, before_node_id, before_content),
+        (0, after_style, after_node_id, after_content),
     ] {
+        let pe_content = pe_content.unwrap_or_default();
+
         // Delete psuedo element if it exists but shouldn't
         if let (Some(pe_node_id), None) = (pe_node_id, &pe_style) {
             doc.remove_and_drop_pe(pe_node_id);
@@ -722,13 +709,6 @@ fn flush_pseudo_elements(doc: &mut BaseDocument, node_id: NodeId) {
                     .insert(NodeFlags::IS_IN_DOCUMENT);
             }
 
-            if let Some(text) = pe_content_text(pe_style) {
-                let text = text.to_string();
-                let text_node_id = doc.create_text_node(&text);
-                doc.nodes[text_node_id].parent = Some(new_node_id);
-                doc.nodes[new_node_id].children.push(text_node_id);
-            }
-
             let mut element_data = StyloElementData::default();
             element_data.styles.primary = Some(pe_style.clone());
             element_data.set_restyled();
@@ -737,6 +717,8 @@ fn flush_pseudo_elements(doc: &mut BaseDocument, node_id: NodeId) {
                 .stylo_element_data_mut()
                 .ensure_init_mut() = element_data;
 
+            sync_pseudo_element_content(doc, new_node_id, pe_style, &pe_content);
+
             let node = &mut doc.nodes[node_id];
             node.set_pe_by_index(idx, Some(new_node_id));
             node.insert_damage(ALL_DAMAGE);
@@ -744,41 +726,16 @@ fn flush_pseudo_elements(doc: &mut BaseDocument, node_id: NodeId) {
 
         // Else: Update psuedo element
         if let (Some(pe_node_id), Some(pe_style)) = (pe_node_id, pe_style) {
-            // Sync the pseudo-element's text node with its `content` style, which
-            // may have changed (e.g. `details[open] summary::after { content: ... }`).
+            // Sync the pseudo-element's children with its generated content, which
+            // may have changed (e.g. `details[open] summary::after { content: ... }`,
+            // or a change to a counter or attribute used by `content`).
             //
-            // Note: this deliberately compares the text itself rather than relying on
+            // Note: this deliberately compares the content itself rather than relying on
             // the style-pointer comparison below, as the pseudo-element's style may
             // already have been updated by `sync_pseudo_element_styles` during damage
-            // propagation without the text having been updated.
-            let new_text = pe_content_text(&pe_style).map(str::to_string);
-            let existing_text_node_id = doc.nodes[pe_node_id]
-                .children
-                .first()
-                .copied()
-                .filter(|&child_id| doc.nodes[child_id].is_text_node());
-            match (existing_text_node_id, new_text) {
-                (Some(text_node_id), Some(new_text)) => {
-                    let text_data = doc.nodes[text_node_id].text_data_mut().unwrap();
-                    if text_data.content != new_text {
-                        text_data.content = new_text;
-                        doc.nodes[node_id].insert_damage(ALL_DAMAGE);
-                    }
-                }
-                (None, Some(new_text)) => {
-                    let text_node_id = doc.create_text_node(&new_text);
-                    doc.nodes[text_node_id].parent = Some(pe_node_id);
-                    doc.nodes[pe_node_id].children.push(text_node_id);
-                    doc.nodes[node_id].insert_damage(ALL_DAMAGE);
-                }
-                (Some(text_node_id), None) => {
-                    doc.nodes[pe_node_id]
-                        .children
-                        .retain(|&child_id| child_id != text_node_id);
-                    doc.remove_node_from_tree(text_node_id);
-                    doc.nodes[node_id].insert_damage(ALL_DAMAGE);
-                }
-                (None, None) => {}
+            // propagation without the content having been updated.
+            if sync_pseudo_element_content(doc, pe_node_id, &pe_style, &pe_content) {
+                doc.nodes[node_id].insert_damage(ALL_DAMAGE);
             }
 
             let mut node_styles = doc.nodes[pe_node_id]
@@ -796,6 +753,88 @@ fn flush_pseudo_elements(doc: &mut BaseDocument, node_id: NodeId) {
     }
 }
 
+/// Replace the children of a pseudo-element with text nodes and anonymous images for its
+/// generated content (if its existing children don't already match). Returns whether the
+/// children were replaced.
+fn sync_pseudo_element_content(
+    doc: &mut BaseDocument,
+    pe_node_id: NodeId,
+    pe_style: &ServoArc<ComputedValues>,
+    content: &[GeneratedContentItem],
+) -> bool {
+    use style::selector_parser::PseudoElement;
+
+    const IMG_NAME: QualName = qual_name!("img", html);
+
+    let existing_content: Vec<GeneratedContentItem> = doc.nodes[pe_node_id]
+        .children
+        .iter()
+        .filter_map(|&child_id| match &doc.nodes[child_id].data {
+            NodeData::Text(text) => Some(GeneratedContentItem::Text(text.content.clone())),
+            NodeData::Element(el) => Some(GeneratedContentItem::Image(
+                el.attr(local_name!("src")).unwrap_or_default().to_string(),
+            )),
+            _ => None,
+        })
+        .collect();
+    if existing_content == content {
+        return false;
+    }
+
+    for child_id in std::mem::take(&mut doc.nodes[pe_node_id].children) {
+        doc.remove_and_drop_pe(child_id);
+    }
+
+    let is_in_document = doc.nodes[pe_node_id]
+        .flags
+        .contains(NodeFlags::IS_IN_DOCUMENT);
+    for item in content {
+        let child_id = match item {
+            GeneratedContentItem::Text(text) => doc.create_text_node(text),
+            GeneratedContentItem::Image(url) => {
+                let src = Attribute {
+                    name: qual_name!("src"),
+                    value: url.clone(),
+                };
+                let image_id = doc.create_node(NodeData::Element(Box::new(ElementData::new(
+                    IMG_NAME,
+                    vec![src],
+                ))));
+
+                // The image is an anonymous inline box which inherits from the pseudo-element
+                let read_guard = doc.guard.read();
+                let guards = StylesheetGuards::same(&read_guard);
+                let style = doc.stylist.style_for_anonymous::<&Node>(
+                    &guards,
+                    &PseudoElement::ServoAnonymousBox,
+                    pe_style,
+                );
+                drop(read_guard);
+                let mut element_data = StyloElementData::default();
+                element_data.styles.primary = Some(style);
+                element_data.set_restyled();
+                element_data.damage = ALL_DAMAGE;
+                *doc.nodes[image_id]
+                    .stylo_element_data_mut()
+                    .ensure_init_mut() = element_data;
+
+                image_id
+            }
+        };
+        doc.nodes[child_id].parent = Some(pe_node_id);
+        if is_in_document {
+            doc.nodes[child_id].flags.insert(NodeFlags::IS_IN_DOCUMENT);
+        }
+        doc.nodes[pe_node_id].children.push(child_id);
+
+        if matches!(item, GeneratedContentItem::Image(_)) {
+            doc.load_image(child_id);
+        }
+    }
+
+    true
+}
+
 /// Handles the cases where there are text nodes or inline nodes that need to be wrapped in an anonymous block node
 fn collect_complex_layout_children(
     doc: &mut BaseDocument,
@@ -854,6 +893,8 @@ fn collect_complex_layout_children(
 }
 
 fn create_text_editor(doc: &mut BaseDocument, input_element_id: NodeId, is_multiline: bool) {
+    let placeholder = create_placeholder(doc, input_element_id, is_multiline);
+
     let node = &mut doc.nodes[input_element_id];
     let parley_style = node
         .primary_styles()
@@ -867,6 +908,7 @@ fn create_text_editor(doc: &mut BaseDocument, input_element_id: NodeId, is_multi
         let editor = &mut text_input_data.editor;
         editor.set_text(element.attr(local_name!("value")).unwrap_or(""));
         element.special_data = SpecialElementData::TextInput(text_input_data);
+        element.update_input_pattern();
     }
 
     let SpecialElementData::TextInput(text_input_data) = &mut element.special_data else {
@@ -884,6 +926,64 @@ fn create_text_editor(doc: &mut BaseDocument, input_element_id: NodeId, is_multi
     styles.insert(StyleProperty::Brush(parley_style.brush));
 
     editor.refresh_layout(&mut doc.font_ctx.lock().unwrap(), &mut doc.layout_ctx);
+    text_input_data.placeholder = placeholder;
+}
+
+/// Lay out a text input's `placeholder` attribute using the styles of its `::placeholder`
+/// pseudo-element. Like the input's value, the placeholder isn't wrapped.
+pub(crate) fn create_placeholder(
+    doc: &mut BaseDocument,
+    input_element_id: NodeId,
+    is_multiline: bool,
+) -> Option<TextInputPlaceholder> {
+    use style::selector_parser::PseudoElement;
+    use style::stylist::RuleInclusion;
+
+    let node = &doc.nodes[input_element_id];
+    let placeholder = node.attr(local_name!("placeholder"))?;
+    // Line breaks are stripped from the placeholders of single-line inputs
+    let text: String = match is_multiline {
+        true => placeholder.to_string(),
+        false => placeholder
+            .chars()
+            .filter(|c| !matches!(c, '\n' | '\r'))
+            .collect(),
+    };
+    if text.is_empty() {
+        return None;
+    }
+
+    let primary_styles = node.primary_styles()?;
+    let read_guard = doc.guard.read();
+    let guards = StylesheetGuards::same(&read_guard);
+    let styles = doc.stylist.lazily_compute_pseudo_element_style(
+        &guards,
+        node,
+        &PseudoElement::Placeholder,
+        RuleInclusion::All,
+        &primary_styles,
+        /* is_probe */ false,
+        /* matching_fn */ None,
+    )?;
+    drop(read_guard);
+    let input_styles = (*primary_styles).clone();
+    drop(primary_styles);
+
+    let parley_style = stylo_to_parley::style(input_element_id, &styles);
+    let scale = doc.viewport.scale();
+    let mut font_ctx = doc.font_ctx.lock().unwrap();
+    let mut builder = doc
+        .layout_ctx
+        .tree_builder(&mut font_ctx, scale, true, &parley_style);
+    builder.push_text(&text);
+    let mut layout = builder.build().0;
+    layout.break_all_lines(None);
+
+    Some(TextInputPlaceholder {
+        layout: Box::new(layout),
+        styles,
+        input_styles,
+    })
 }
 
 fn create_checkbox_input(doc: &mut BaseDocument, input_element_id: NodeId) {
@@ -896,6 +996,67 @@ fn create_checkbox_input(doc: &mut BaseDocument, input_element_id: NodeId) {
     }
 }
 
+fn create_select(doc: &mut BaseDocument, select_element_id: NodeId) {
+    let node = &doc.nodes[select_element_id];
+    let parley_style = node
+        .primary_styles()
+        .as_ref()
+        .map(|s| stylo_to_parley::style(node.id, s))
+        .unwrap_or_default();
+
+    // The options themselves don't generate boxes: the select is laid out as a single box
+    // sized to fit its widest option label.
+    let labels: Vec<(NodeId, String)> = node
+        .select_options()
+        .into_iter()
+        .map(|option_id| (option_id, node.with(option_id).option_label()))
+        .collect();
+
+    let scale = doc.viewport.scale();
+    let mut font_ctx = doc.font_ctx.lock().unwrap();
+    let option_labels: Vec<SelectOptionLabel> = labels
+        .into_iter()
+        .map(|(option_id, label)| {
+            let mut builder =
+                doc.layout_ctx
+                    .tree_builder(&mut font_ctx, scale, true, &parley_style);
+            builder.push_text(&label);
+            let mut layout = builder.build().0;
+            layout.break_all_lines(None);
+            SelectOptionLabel {
+                option_id,
+                layout: Box::new(layout),
+            }
+        })
+        .collect();
+    drop(font_ctx);
+
+    let label_width = option_labels
+        .iter()
+        .map(|label| label.layout.full_width() / scale)
+        .fold(0.0, f32::max);
+    let row_height = option_labels
+        .iter()
+        .map(|label| label.layout.height() / scale)
+        .fold(0.0, f32::max);
+    let row_height = match row_height > 0.0 {
+        true => row_height,
+        false => parley_style.font_size * 1.2,
+    };
+
+    let element = doc.nodes[select_element_id]
+        .data
+        .downcast_element_mut()
+        .unwrap();
+    if !matches!(element.special_data, SpecialElementData::Select(_)) {
+        element.special_data = SpecialElementData::Select(Box::default());
+    }
+    let select_data = element.select_data_mut().unwrap();
+    select_data.option_labels = option_labels;
+    select_data.label_width = label_width;
+    select_data.row_height = row_height;
+}
+
 /// Find and return the "layout_children" (inline boxes) for an inline layout
 /// without actually constructing the layout. This allows us to defer the expensive
 /// construction of the Parley layout (which invokes text shaping) to a paralell phase.
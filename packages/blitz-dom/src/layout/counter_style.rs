@@ -0,0 +1,959 @@
+//! Counter styles (<https://drafts.csswg.org/css-counter-styles-3/>): the predefined counter
+//! styles, author `@counter-style` rules, and the algorithms which represent a counter value
+//! using them.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use style::Atom;
+use style::counter_style::{
+    CounterBound, CounterStyle, CounterStyleRule, Symbol, Symbols, SymbolsType,
+    System as RuleSystem,
+};
+
+use crate::node::Marker;
+
+/// How a counter style turns a value into a representation
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum System {
+    Cyclic,
+    Numeric,
+    Alphabetic,
+    Symbolic,
+    Additive,
+    /// A fixed system whose first symbol represents the given value
+    Fixed(i32),
+    /// One of the CJK longhand algorithms which can't be expressed using the other systems
+    Cjk(&'static CjkLonghand),
+}
+
+/// The maximum number of symbols which the `symbolic` and `additive` systems repeat, and which
+/// `pad` pads a representation to. Values which would need more symbols can't be represented
+/// (and so fall back), which keeps huge values from producing huge strings.
+///
+/// <https://drafts.csswg.org/css-counter-styles-3/#counter-style-symbols>
+const MAX_REPEATED_SYMBOLS: usize = 60;
+
+/// A fully resolved counter style
+#[derive(Debug, Clone)]
+struct Definition {
+    system: System,
+    symbols: Vec<String>,
+    additive_symbols: Vec<(i32, String)>,
+    negative: (String, String),
+    prefix: String,
+    suffix: String,
+    /// The ranges of values which the style can represent (`None` for `auto`)
+    range: Option<Vec<(i32, i32)>>,
+    pad: (usize, String),
+    fallback: Atom,
+}
+
+impl Definition {
+    fn new(system: System, symbols: &[&str], suffix: &str) -> Self {
+        Self {
+            system,
+            symbols: symbols.iter().map(|symbol| symbol.to_string()).collect(),
+            additive_symbols: Vec::new(),
+            negative: ("-".to_string(), String::new()),
+            prefix: String::new(),
+            suffix: suffix.to_string(),
+            range: None,
+            pad: (0, String::new()),
+            fallback: Atom::from("decimal"),
+        }
+    }
+
+    fn additive(symbols: &[(i32, &str)], range: (i32, i32)) -> Self {
+        Self {
+            additive_symbols: symbols
+                .iter()
+                .map(|(weight, symbol)| (*weight, symbol.to_string()))
+                .collect(),
+            range: Some(vec![range]),
+            ..Self::new(System::Additive, &[], ". ")
+        }
+    }
+
+    fn cjk(longhand: &'static CjkLonghand) -> Self {
+        Self {
+            range: Some(vec![(-9999, 9999)]),
+            fallback: Atom::from("cjk-decimal"),
+            ..Self::new(System::Cjk(longhand), &[], longhand.suffix)
+        }
+    }
+
+    fn with_pad(mut self, len: usize, symbol: &str) -> Self {
+        self.pad = (len, symbol.to_string());
+        self
+    }
+
+    fn with_fallback(mut self, fallback: &str) -> Self {
+        self.fallback = Atom::from(fallback);
+        self
+    }
+
+    fn in_range(&self, value: i32) -> bool {
+        match &self.range {
+            Some(ranges) => ranges
+                .iter()
+                .any(|(start, end)| *start <= value && value <= *end),
+            None => match self.system {
+                System::Cyclic | System::Numeric | System::Fixed(_) | System::Cjk(_) => true,
+                System::Alphabetic | System::Symbolic => value >= 1,
+                System::Additive => value >= 0,
+            },
+        }
+    }
+
+    /// Represent a value using this style's algorithm (without falling back), or `None` if
+    /// the algorithm can't represent the value
+    fn generate(&self, value: i32) -> Option<String> {
+        let uses_negative = matches!(
+            self.system,
+            System::Symbolic | System::Alphabetic | System::Numeric | System::Additive
+        );
+        let is_negative = uses_negative && value < 0;
+        let abs = value.unsigned_abs();
+
+        let symbols = &self.symbols;
+        let representation = match self.system {
+            System::Cjk(longhand) => return Some(longhand.represent(value)),
+            System::Cyclic => {
+                let index = (value as i64 - 1).rem_euclid(symbols.len() as i64);
+                symbols.get(index as usize)?.clone()
+            }
+            System::Fixed(first) => {
+                let index = value as i64 - first as i64;
+                symbols.get(usize::try_from(index).ok()?)?.clone()
+            }
+            System::Symbolic => {
+                if abs == 0 || symbols.is_empty() {
+                    return None;
+                }
+                let len = symbols.len() as u32;
+                let count = abs.div_ceil(len) as usize;
+                if count > MAX_REPEATED_SYMBOLS {
+                    return None;
+                }
+                let symbol = &symbols[((abs - 1) % len) as usize];
+                symbol.repeat(count)
+            }
+            System::Alphabetic => {
+                if abs == 0 || symbols.len() < 2 {
+                    return None;
+                }
+                let len = symbols.len() as u32;
+                let mut digits = Vec::new();
+                let mut value = abs;
+                while value > 0 {
+                    value -= 1;
+                    digits.push(&symbols[(value % len) as usize]);
+                    value /= len;
+                }
+                digits.into_iter().rev().map(String::as_str).collect()
+            }
+            System::Numeric => {
+                if symbols.len() < 2 {
+                    return None;
+                }
+                let len = symbols.len() as u32;
+                let mut digits = vec![&symbols[(abs % len) as usize]];
+                let mut value = abs / len;
+                while value > 0 {
+                    digits.push(&symbols[(value % len) as usize]);
+                    value /= len;
+                }
+                digits.into_iter().rev().map(String::as_str).collect()
+            }
+            System::Additive => {
+                if abs == 0 {
+                    let (_, symbol) = self.additive_symbols.iter().find(|(w, _)| *w == 0)?;
+                    symbol.clone()
+                } else {
+                    let mut representation = String::new();
+                    let mut value = abs;
+                    let mut count = 0;
+                    for (weight, symbol) in &self.additive_symbols {
+                        let weight = *weight as u32;
+                        if weight == 0 || weight > value {
+                            continue;
+                        }
+                        count += (value / weight) as usize;
+                        if count > MAX_REPEATED_SYMBOLS {
+                            return None;
+                        }
+                        representation.push_str(&symbol.repeat((value / weight) as usize));
+                        value %= weight;
+                        if value == 0 {
+                            break;
+                        }
+                    }
+                    if value != 0 {
+                        return None;
+                    }
+                    representation
+                }
+            }
+        };
+
+        // Pad the representation (including any negative sign) to the minimum length
+        let (pad_len, pad_symbol) = &self.pad;
+        let mut len = representation.chars().count();
+        if is_negative {
+            len += self.negative.0.chars().count() + self.negative.1.chars().count();
+        }
+        let padding = pad_symbol.repeat((*pad_len).min(MAX_REPEATED_SYMBOLS).saturating_sub(len));
+
+        Some(match is_negative {
+            true => format!(
+                "{}{padding}{representation}{}",
+                self.negative.0, self.negative.1
+            ),
+            false => format!("{padding}{representation}"),
+        })
+    }
+}
+
+/// The CJK longhand counter styles (<https://drafts.csswg.org/css-counter-styles-3/#limited-chinese>)
+#[derive(Debug)]
+struct CjkLonghand {
+    digits: [&'static str; 10],
+    /// The markers for tens, hundreds and thousands
+    markers: [&'static str; 3],
+    negative: &'static str,
+    suffix: &'static str,
+    /// Zero digits are dropped entirely, rather than collapsed into a single zero
+    drop_zeros: bool,
+    /// Which `1` digits before a marker are dropped
+    drop_ones: DropOnes,
+}
+
+#[derive(Debug)]
+enum DropOnes {
+    Never,
+    /// Only before the tens marker of values between ten and nineteen (Chinese informal)
+    Teens,
+    /// Before every marker (Japanese and Korean informal)
+    Always,
+}
+
+impl CjkLonghand {
+    fn represent(&self, value: i32) -> String {
+        if value == 0 {
+            return self.digits[0].to_string();
+        }
+
+        let abs = value.unsigned_abs();
+        let mut representation = String::new();
+        let mut pending_zero = false;
+        for position in (0..4).rev() {
+            let digit = (abs / 10u32.pow(position)) % 10;
+            if digit == 0 {
+                // Leading and trailing zeros are never written
+                pending_zero = !representation.is_empty();
+                continue;
+            }
+            if pending_zero && !self.drop_zeros {
+                representation.push_str(self.digits[0]);
+            }
+            pending_zero = false;
+
+            let drop_one = digit == 1
+                && position > 0
+                && match self.drop_ones {
+                    DropOnes::Never => false,
+                    DropOnes::Teens => position == 1 && abs < 20,
+                    DropOnes::Always => true,
+                };
+            if !drop_one {
+                representation.push_str(self.digits[digit as usize]);
+            }
+            if position > 0 {
+                representation.push_str(self.markers[position as usize - 1]);
+            }
+        }
+
+        match value < 0 {
+            true => format!("{}{representation}", self.negative),
+            false => representation,
+        }
+    }
+}
+
+const CHINESE_DIGITS: [&str; 10] = ["零", "一", "二", "三", "四", "五", "六", "七", "八", "九"];
+
+static SIMP_CHINESE_INFORMAL: CjkLonghand = CjkLonghand {
+    digits: CHINESE_DIGITS,
+    markers: ["十", "百", "千"],
+    negative: "负",
+    suffix: "、",
+    drop_zeros: false,
+    drop_ones: DropOnes::Teens,
+};
+static SIMP_CHINESE_FORMAL: CjkLonghand = CjkLonghand {
+    digits: ["零", "壹", "贰", "叁", "肆", "伍", "陆", "柒", "捌", "玖"],
+    markers: ["拾", "佰", "仟"],
+    negative: "负",
+    suffix: "、",
+    drop_zeros: false,
+    drop_ones: DropOnes::Never,
+};
+static TRAD_CHINESE_INFORMAL: CjkLonghand = CjkLonghand {
+    digits: CHINESE_DIGITS,
+    markers: ["十", "百", "千"],
+    negative: "負",
+    suffix: "、",
+    drop_zeros: false,
+    drop_ones: DropOnes::Teens,
+};
+static TRAD_CHINESE_FORMAL: CjkLonghand = CjkLonghand {
+    digits: ["零", "壹", "貳", "參", "肆", "伍", "陸", "柒", "捌", "玖"],
+    markers: ["拾", "佰", "仟"],
+    negative: "負",
+    suffix: "、",
+    drop_zeros: false,
+    drop_ones: DropOnes::Never,
+};
+static JAPANESE_INFORMAL: CjkLonghand = CjkLonghand {
+    digits: ["〇", "一", "二", "三", "四", "五", "六", "七", "八", "九"],
+    markers: ["十", "百", "千"],
+    negative: "マイナス",
+    suffix: "、",
+    drop_zeros: true,
+    drop_ones: DropOnes::Always,
+};
+static JAPANESE_FORMAL: CjkLonghand = CjkLonghand {
+    digits: ["零", "壱", "弐", "参", "四", "伍", "六", "七", "八", "九"],
+    markers: ["拾", "百", "阡"],
+    negative: "マイナス",
+    suffix: "、",
+    drop_zeros: true,
+    drop_ones: DropOnes::Never,
+};
+static KOREAN_HANGUL_FORMAL: CjkLonghand = CjkLonghand {
+    digits: ["영", "일", "이", "삼", "사", "오", "육", "칠", "팔", "구"],
+    markers: ["십", "백", "천"],
+    negative: "마이너스 ",
+    suffix: ", ",
+    drop_zeros: true,
+    drop_ones: DropOnes::Never,
+};
+static KOREAN_HANJA_INFORMAL: CjkLonghand = CjkLonghand {
+    digits: CHINESE_DIGITS,
+    markers: ["十", "百", "千"],
+    negative: "마이너스 ",
+    suffix: ", ",
+    drop_zeros: true,
+    drop_ones: DropOnes::Always,
+};
+static KOREAN_HANJA_FORMAL: CjkLonghand = CjkLonghand {
+    digits: ["零", "壹", "貳", "參", "四", "五", "六", "七", "八", "九"],
+    markers: ["拾", "百", "仟"],
+    negative: "마이너스 ",
+    suffix: ", ",
+    drop_zeros: true,
+    drop_ones: DropOnes::Never,
+};
+
+const ROMAN: [(i32, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+const ARMENIAN: [(i32, &str); 36] = [
+    (9000, "Ք"),
+    (8000, "Փ"),
+    (7000, "Ւ"),
+    (6000, "Ց"),
+    (5000, "Ր"),
+    (4000, "Տ"),
+    (3000, "Վ"),
+    (2000, "Ս"),
+    (1000, "Ռ"),
+    (900, "Ջ"),
+    (800, "Պ"),
+    (700, "Չ"),
+    (600, "Ո"),
+    (500, "Շ"),
+    (400, "Ն"),
+    (300, "Յ"),
+    (200, "Մ"),
+    (100, "Ճ"),
+    (90, "Ղ"),
+    (80, "Ձ"),
+    (70, "Հ"),
+    (60, "Կ"),
+    (50, "Ծ"),
+    (40, "Խ"),
+    (30, "Լ"),
+    (20, "Ի"),
+    (10, "Ժ"),
+    (9, "Թ"),
+    (8, "Ը"),
+    (7, "Է"),
+    (6, "Զ"),
+    (5, "Ե"),
+    (4, "Դ"),
+    (3, "Գ"),
+    (2, "Բ"),
+    (1, "Ա"),
+];
+
+const GEORGIAN: [(i32, &str); 37] = [
+    (10000, "ჵ"),
+    (9000, "ჰ"),
+    (8000, "ჯ"),
+    (7000, "ჴ"),
+    (6000, "ხ"),
+    (5000, "ჭ"),
+    (4000, "წ"),
+    (3000, "ძ"),
+    (2000, "ც"),
+    (1000, "ჩ"),
+    (900, "შ"),
+    (800, "ყ"),
+    (700, "ღ"),
+    (600, "ქ"),
+    (500, "ფ"),
+    (400, "ჳ"),
+    (300, "ტ"),
+    (200, "ს"),
+    (100, "რ"),
+    (90, "ჟ"),
+    (80, "პ"),
+    (70, "ო"),
+    (60, "ჲ"),
+    (50, "ნ"),
+    (40, "მ"),
+    (30, "ლ"),
+    (20, "კ"),
+    (10, "ი"),
+    (9, "თ"),
+    (8, "ჱ"),
+    (7, "ზ"),
+    (6, "ვ"),
+    (5, "ე"),
+    (4, "დ"),
+    (3, "გ"),
+    (2, "ბ"),
+    (1, "ა"),
+];
+
+const HEBREW: [(i32, &str); 37] = [
+    (10000, "י׳"),
+    (9000, "ט׳"),
+    (8000, "ח׳"),
+    (7000, "ז׳"),
+    (6000, "ו׳"),
+    (5000, "ה׳"),
+    (4000, "ד׳"),
+    (3000, "ג׳"),
+    (2000, "ב׳"),
+    (1000, "א׳"),
+    (400, "ת"),
+    (300, "ש"),
+    (200, "ר"),
+    (100, "ק"),
+    (90, "צ"),
+    (80, "פ"),
+    (70, "ע"),
+    (60, "ס"),
+    (50, "נ"),
+    (40, "מ"),
+    (30, "ל"),
+    (20, "כ"),
+    (19, "יט"),
+    (18, "יח"),
+    (17, "יז"),
+    (16, "טז"),
+    (15, "טו"),
+    (10, "י"),
+    (9, "ט"),
+    (8, "ח"),
+    (7, "ז"),
+    (6, "ו"),
+    (5, "ה"),
+    (4, "ד"),
+    (3, "ג"),
+    (2, "ב"),
+    (1, "א"),
+];
+
+/// The digits of a decimal-like numeric counter style
+fn digits(digits: &str) -> Vec<&str> {
+    digits
+        .char_indices()
+        .map(|(idx, c)| &digits[idx..idx + c.len_utf8()])
+        .collect()
+}
+
+fn lowercase(symbols: &[(i32, &'static str)]) -> Vec<(i32, String)> {
+    symbols
+        .iter()
+        .map(|(weight, symbol)| (*weight, symbol.to_lowercase()))
+        .collect()
+}
+
+/// The counter styles predefined by <https://drafts.csswg.org/css-counter-styles-3/#predefined-counters>
+static PREDEFINED: LazyLock<HashMap<&'static str, Definition>> = LazyLock::new(|| {
+    let numeric = |name: &'static str, symbols: &str| {
+        (
+            name,
+            Definition::new(System::Numeric, &digits(symbols), ". "),
+        )
+    };
+    let alphabetic = |name: &'static str, symbols: &str, suffix: &str| {
+        let symbols: Vec<&str> = symbols.split(' ').collect();
+        (name, Definition::new(System::Alphabetic, &symbols, suffix))
+    };
+    let bullet =
+        |name: &'static str, symbol: &str| (name, Definition::new(System::Cyclic, &[symbol], " "));
+    let latin_lower = "a b c d e f g h i j k l m n o p q r s t u v w x y z";
+    let latin_upper = "A B C D E F G H I J K L M N O P Q R S T U V W X Y Z";
+
+    let lower_roman = Definition {
+        additive_symbols: lowercase(&ROMAN),
+        ..Definition::additive(&ROMAN, (1, 3999))
+    };
+    let lower_armenian = Definition {
+        additive_symbols: lowercase(&ARMENIAN),
+        ..Definition::additive(&ARMENIAN, (1, 9999))
+    };
+
+    HashMap::from([
+        numeric("decimal", "0123456789"),
+        (
+            "decimal-leading-zero",
+            Definition::new(System::Numeric, &digits("0123456789"), ". ").with_pad(2, "0"),
+        ),
+        numeric("arabic-indic", "٠١٢٣٤٥٦٧٨٩"),
+        numeric("bengali", "০১২৩৪৫৬৭৮৯"),
+        numeric("cambodian", "០១២៣៤៥៦៧៨៩"),
+        numeric("khmer", "០១២៣៤៥៦៧៨៩"),
+        numeric("devanagari", "०१२३४५६७८९"),
+        numeric("gujarati", "૦૧૨૩૪૫૬૭૮૯"),
+        numeric("gurmukhi", "੦੧੨੩੪੫੬੭੮੯"),
+        numeric("kannada", "೦೧೨೩೪೫೬೭೮೯"),
+        numeric("lao", "໐໑໒໓໔໕໖໗໘໙"),
+        numeric("malayalam", "൦൧൨൩൪൫൬൭൮൯"),
+        numeric("mongolian", "᠐᠑᠒᠓᠔᠕᠖᠗᠘᠙"),
+        numeric("myanmar", "၀၁၂၃၄၅၆၇၈၉"),
+        numeric("oriya", "୦୧୨୩୪୫୬୭୮୯"),
+        numeric("persian", "۰۱۲۳۴۵۶۷۸۹"),
+        numeric("tamil", "௦௧௨௩௪௫௬௭௮௯"),
+        numeric("telugu", "౦౧౨౩౪౫౬౭౮౯"),
+        numeric("thai", "๐๑๒๓๔๕๖๗๘๙"),
+        numeric("tibetan", "༠༡༢༣༤༥༦༧༨༩"),
+        (
+            "cjk-decimal",
+            Definition::new(System::Numeric, &digits("〇一二三四五六七八九"), "、"),
+        ),
+        ("upper-roman", Definition::additive(&ROMAN, (1, 3999))),
+        ("lower-roman", lower_roman),
+        ("armenian", Definition::additive(&ARMENIAN, (1, 9999))),
+        ("upper-armenian", Definition::additive(&ARMENIAN, (1, 9999))),
+        ("lower-armenian", lower_armenian),
+        ("georgian", Definition::additive(&GEORGIAN, (1, 19999))),
+        ("hebrew", Definition::additive(&HEBREW, (1, 10999))),
+        alphabetic("lower-alpha", latin_lower, ". "),
+        alphabetic("lower-latin", latin_lower, ". "),
+        alphabetic("upper-alpha", latin_upper, ". "),
+        alphabetic("upper-latin", latin_upper, ". "),
+        alphabetic(
+            "lower-greek",
+            "α β γ δ ε ζ η θ ι κ λ μ ν ξ ο π ρ σ τ υ φ χ ψ ω",
+            ". ",
+        ),
+        alphabetic(
+            "hiragana",
+            "あ い う え お か き く け こ さ し す せ そ た ち つ て と な に ぬ ね の は ひ ふ へ ほ ま み む め も や ゆ よ ら り る れ ろ わ ゐ ゑ を ん",
+            "、",
+        ),
+        alphabetic(
+            "hiragana-iroha",
+            "い ろ は に ほ へ と ち り ぬ る を わ か よ た れ そ つ ね な ら む う ゐ の お く や ま け ふ こ え て あ さ き ゆ め み し ゑ ひ も せ す",
+            "、",
+        ),
+        alphabetic(
+            "katakana",
+            "ア イ ウ エ オ カ キ ク ケ コ サ シ ス セ ソ タ チ ツ テ ト ナ ニ ヌ ネ ノ ハ ヒ フ ヘ ホ マ ミ ム メ モ ヤ ユ ヨ ラ リ ル レ ロ ワ ヰ ヱ ヲ ン",
+            "、",
+        ),
+        alphabetic(
+            "katakana-iroha",
+            "イ ロ ハ ニ ホ ヘ ト チ リ ヌ ル ヲ ワ カ ヨ タ レ ソ ツ ネ ナ ラ ム ウ ヰ ノ オ ク ヤ マ ケ フ コ エ テ ア サ キ ユ メ ミ シ ヱ ヒ モ セ ス",
+            "、",
+        ),
+        {
+            let (name, definition) = alphabetic(
+                "cjk-earthly-branch",
+                "子 丑 寅 卯 辰 巳 午 未 申 酉 戌 亥",
+                "、",
+            );
+            (name, definition.with_fallback("cjk-decimal"))
+        },
+        {
+            let (name, definition) =
+                alphabetic("cjk-heavenly-stem", "甲 乙 丙 丁 戊 己 庚 辛 壬 癸", "、");
+            (name, definition.with_fallback("cjk-decimal"))
+        },
+        (
+            "simp-chinese-informal",
+            Definition::cjk(&SIMP_CHINESE_INFORMAL),
+        ),
+        ("simp-chinese-formal", Definition::cjk(&SIMP_CHINESE_FORMAL)),
+        (
+            "trad-chinese-informal",
+            Definition::cjk(&TRAD_CHINESE_INFORMAL),
+        ),
+        ("trad-chinese-formal", Definition::cjk(&TRAD_CHINESE_FORMAL)),
+        ("cjk-ideographic", Definition::cjk(&TRAD_CHINESE_INFORMAL)),
+        ("japanese-informal", Definition::cjk(&JAPANESE_INFORMAL)),
+        ("japanese-formal", Definition::cjk(&JAPANESE_FORMAL)),
+        (
+            "korean-hangul-formal",
+            Definition::cjk(&KOREAN_HANGUL_FORMAL),
+        ),
+        (
+            "korean-hanja-informal",
+            Definition::cjk(&KOREAN_HANJA_INFORMAL),
+        ),
+        ("korean-hanja-formal", Definition::cjk(&KOREAN_HANJA_FORMAL)),
+        bullet("disc", "•"),
+        bullet("circle", "◦"),
+        bullet("square", "▪"),
+        bullet("disclosure-open", "▾"),
+        bullet("disclosure-closed", "▸"),
+    ])
+});
+
+fn symbol_text(symbol: &Symbol) -> String {
+    match symbol {
+        Symbol::String(string) => string.to_string(),
+        Symbol::Ident(ident) => ident.0.to_string(),
+    }
+}
+
+fn symbols_text(symbols: &Symbols) -> Vec<String> {
+    symbols.0.iter().map(symbol_text).collect()
+}
+
+/// The counter styles defined by a document's `@counter-style` rules, falling back to the
+/// predefined counter styles
+#[derive(Default)]
+pub(crate) struct CounterStyles {
+    defined: HashMap<Atom, Definition>,
+}
+
+impl CounterStyles {
+    /// Resolve `@counter-style` rules (in cascade order, so that later rules with the same name
+    /// take precedence)
+    pub(crate) fn from_rules<'a>(rules: impl IntoIterator<Item = &'a CounterStyleRule>) -> Self {
+        let rules: HashMap<Atom, &CounterStyleRule> = rules
+            .into_iter()
+            .map(|rule| (rule.name().0.clone(), rule))
+            .collect();
+
+        let mut styles = Self::default();
+        for name in rules.keys() {
+            styles.resolve_rule(name, &rules, &mut HashSet::new());
+        }
+        styles
+    }
+
+    /// Resolve the definition of a `@counter-style` rule, first resolving the counter style it
+    /// extends (if any). Returns `None` if the rule is invalid.
+    fn resolve_rule(
+        &mut self,
+        name: &Atom,
+        rules: &HashMap<Atom, &CounterStyleRule>,
+        resolving: &mut HashSet<Atom>,
+    ) -> Option<Definition> {
+        if let Some(definition) = self.defined.get(name) {
+            return Some(definition.clone());
+        }
+        let rule = rules.get(name)?;
+
+        let mut definition = match rule.system() {
+            Some(RuleSystem::Extends(base)) => {
+                let base = &base.0;
+                // Extending a counter style which (indirectly) extends this one is treated as
+                // extending `decimal`
+                let definition = match resolving.insert(name.clone()) {
+                    true => self.resolve_rule(base, rules, resolving),
+                    false => None,
+                };
+                resolving.remove(name);
+                definition
+                    .or_else(|| PREDEFINED.get(&**base).cloned())
+                    .unwrap_or_else(|| PREDEFINED["decimal"].clone())
+            }
+            system => {
+                let system = match system {
+                    None | Some(RuleSystem::Symbolic) => System::Symbolic,
+                    Some(RuleSystem::Cyclic) => System::Cyclic,
+                    Some(RuleSystem::Numeric) => System::Numeric,
+                    Some(RuleSystem::Alphabetic) => System::Alphabetic,
+                    Some(RuleSystem::Additive) => System::Additive,
+                    Some(RuleSystem::Fixed { first_symbol_value }) => {
+                        System::Fixed(first_symbol_value.map_or(1, |value| value.value()))
+                    }
+                    Some(RuleSystem::Extends(_)) => unreachable!(),
+                };
+                let symbols = rule.symbols().map(symbols_text).unwrap_or_default();
+                let additive_symbols: Vec<(i32, String)> = rule
+                    .additive_symbols()
+                    .map(|symbols| {
+                        symbols
+                            .0
+                            .iter()
+                            .map(|tuple| (tuple.weight.value(), symbol_text(&tuple.symbol)))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                let is_valid = match system {
+                    System::Cyclic | System::Fixed(_) | System::Symbolic => !symbols.is_empty(),
+                    System::Alphabetic | System::Numeric => symbols.len() >= 2,
+                    System::Additive => !additive_symbols.is_empty(),
+                    System::Cjk(_) => false,
+                };
+                if !is_valid {
+                    return None;
+                }
+
+                Definition {
+                    additive_symbols,
+                    ..Definition::new(system, &[], ". ")
+                }
+                .with_symbols(symbols)
+            }
+        };
+
+        if let Some(negative) = rule.negative() {
+            definition.negative = (
+                symbol_text(&negative.0),
+                negative.1.as_ref().map(symbol_text).unwrap_or_default(),
+            );
+        }
+        if let Some(prefix) = rule.prefix() {
+            definition.prefix = symbol_text(prefix);
+        }
+        if let Some(suffix) = rule.suffix() {
+            definition.suffix = symbol_text(suffix);
+        }
+        if let Some(ranges) = rule.range() {
+            // An empty list of ranges is `auto`
+            definition.range = (!ranges.0.is_empty()).then(|| {
+                ranges
+                    .0
+                    .iter()
+                    .map(|range| {
+                        let bound = |bound: &CounterBound, infinite: i32| match bound {
+                            CounterBound::Integer(value) => value.value(),
+                            CounterBound::Infinite => infinite,
+                        };
+                        (bound(&range.start, i32::MIN), bound(&range.end, i32::MAX))
+                    })
+                    .collect()
+            });
+        }
+        if let Some(pad) = rule.pad() {
+            definition.pad = (pad.0.value().max(0) as usize, symbol_text(&pad.1));
+        }
+        if let Some(fallback) = rule.fallback() {
+            definition.fallback = fallback.0.0.clone();
+        }
+
+        self.defined.insert(name.clone(), definition.clone());
+        Some(definition)
+    }
+
+    /// The definition of a named counter style. Unknown counter styles are treated as `decimal`.
+    fn definition(&self, name: &Atom) -> &Definition {
+        self.defined
+            .get(name)
+            .or_else(|| PREDEFINED.get(&**name))
+            .unwrap_or(&PREDEFINED["decimal"])
+    }
+
+    /// Represent a value using a counter style definition, using its fallback style (and
+    /// ultimately `decimal`) if the value can't be represented.
+    fn represent(&self, definition: &Definition, value: i32) -> String {
+        let mut definition = definition;
+        // Guard against cycles of fallback styles
+        for _ in 0..8 {
+            if definition.in_range(value) {
+                if let Some(representation) = definition.generate(value) {
+                    return representation;
+                }
+            }
+            definition = self.definition(&definition.fallback);
+        }
+        PREDEFINED["decimal"].generate(value).unwrap()
+    }
+
+    /// The definition of a `<counter-style>` value
+    fn style_definition(&self, style: &CounterStyle) -> Option<Definition> {
+        Some(match style {
+            CounterStyle::None | CounterStyle::String(_) => return None,
+            CounterStyle::Name(name) => self.definition(&name.0).clone(),
+            // An anonymous counter style from the `symbols()` function
+            CounterStyle::Symbols { ty, symbols } => {
+                let system = match ty {
+                    SymbolsType::Cyclic => System::Cyclic,
+                    SymbolsType::Numeric => System::Numeric,
+                    SymbolsType::Alphabetic => System::Alphabetic,
+                    SymbolsType::Symbolic => System::Symbolic,
+                    SymbolsType::Fixed => System::Fixed(1),
+                };
+                Definition::new(system, &[], " ").with_symbols(symbols_text(symbols))
+            }
+        })
+    }
+
+    /// The representation of a counter value in a counter style (as used by the `counter()`
+    /// and `counters()` functions)
+    pub(crate) fn format(&self, style: &CounterStyle, value: i32) -> String {
+        match style {
+            CounterStyle::String(string) => string.to_string(),
+            _ => match self.style_definition(style) {
+                Some(definition) => self.represent(&definition, value),
+                None => String::new(),
+            },
+        }
+    }
+
+    /// The marker of a list item whose `list-item` counter has the given value
+    pub(crate) fn marker(&self, style: &CounterStyle, value: i32) -> Option<Marker> {
+        if let CounterStyle::String(string) = style {
+            return Some(Marker::String(string.to_string()));
+        }
+        let definition = self.style_definition(style)?;
+
+        // The predefined bullets are drawn as a single character
+        if style.is_bullet() && !self.defined.contains_key(&style_name(style)?) {
+            let symbol = definition.symbols.first()?;
+            return symbol.chars().next().map(Marker::Char);
+        }
+
+        let representation = self.represent(&definition, value);
+        Some(Marker::String(format!(
+            "{}{representation}{}",
+            definition.prefix, definition.suffix
+        )))
+    }
+}
+
+fn style_name(style: &CounterStyle) -> Option<Atom> {
+    match style {
+        CounterStyle::Name(name) => Some(name.0.clone()),
+        _ => None,
+    }
+}
+
+impl Definition {
+    fn with_symbols(mut self, symbols: Vec<String>) -> Self {
+        self.symbols = symbols;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(name: &str, value: i32) -> String {
+        let style = CounterStyle::Name(style::values::CustomIdent(Atom::from(name)));
+        CounterStyles::default().format(&style, value)
+    }
+
+    #[test]
+    fn numeric_styles() {
+        assert_eq!(format("decimal", 0), "0");
+        assert_eq!(format("decimal", -12), "-12");
+        assert_eq!(format("decimal-leading-zero", 7), "07");
+        assert_eq!(format("decimal-leading-zero", 12), "12");
+        assert_eq!(format("decimal-leading-zero", -7), "-7");
+        assert_eq!(format("arabic-indic", 42), "٤٢");
+        assert_eq!(format("cjk-decimal", 2024), "二〇二四");
+    }
+
+    #[test]
+    fn additive_styles() {
+        assert_eq!(format("lower-roman", 4), "iv");
+        assert_eq!(format("upper-roman", 1994), "MCMXCIV");
+        assert_eq!(format("upper-roman", 3999), "MMMCMXCIX");
+        // Out of range values fall back to decimal
+        assert_eq!(format("upper-roman", 4000), "4000");
+        assert_eq!(format("upper-roman", 0), "0");
+        assert_eq!(format("hebrew", 15), "טו");
+        assert_eq!(format("lower-armenian", 1), "ա");
+    }
+
+    #[test]
+    fn alphabetic_styles() {
+        assert_eq!(format("lower-alpha", 1), "a");
+        assert_eq!(format("lower-alpha", 27), "aa");
+        assert_eq!(format("upper-latin", 28), "AB");
+        assert_eq!(format("lower-greek", 3), "γ");
+        // Alphabetic styles can't represent zero
+        assert_eq!(format("lower-alpha", 0), "0");
+        assert_eq!(format("cjk-heavenly-stem", 11), "甲甲");
+    }
+
+    #[test]
+    fn cjk_longhand_styles() {
+        assert_eq!(format("simp-chinese-informal", 0), "零");
+        assert_eq!(format("simp-chinese-informal", 10), "十");
+        assert_eq!(format("simp-chinese-informal", 15), "十五");
+        assert_eq!(format("simp-chinese-informal", 105), "一百零五");
+        assert_eq!(format("simp-chinese-informal", 1010), "一千零一十");
+        assert_eq!(format("trad-chinese-formal", 12), "壹拾貳");
+        assert_eq!(format("japanese-informal", 1111), "千百十一");
+        assert_eq!(format("japanese-informal", 1001), "千一");
+        assert_eq!(format("korean-hangul-formal", 21), "이십일");
+        assert_eq!(format("simp-chinese-informal", -3), "负三");
+        // Out of range values fall back to cjk-decimal
+        assert_eq!(format("simp-chinese-informal", 10000), "一〇〇〇〇");
+    }
+
+    #[test]
+    fn huge_values_fall_back() {
+        let styles = CounterStyles::default();
+        let symbolic = Definition::new(System::Symbolic, &["*"], " ");
+        assert_eq!(symbolic.generate(60).map(|s| s.len()), Some(60));
+        assert_eq!(symbolic.generate(i32::MAX), None);
+        assert_eq!(styles.represent(&symbolic, i32::MAX), i32::MAX.to_string());
+
+        let additive = Definition {
+            range: None,
+            ..Definition::additive(&[(10, "X"), (1, "I")], (0, 0))
+        };
+        assert_eq!(additive.generate(i32::MAX), None);
+        assert_eq!(styles.represent(&additive, i32::MIN), i32::MIN.to_string());
+
+        let padded = Definition::new(System::Numeric, &["0", "1"], " ").with_pad(usize::MAX, "0");
+        assert_eq!(
+            padded.generate(1).map(|s| s.len()),
+            Some(MAX_REPEATED_SYMBOLS)
+        );
+    }
+
+    #[test]
+    fn unknown_styles_are_decimal() {
+        assert_eq!(format("not-a-counter-style", 3), "3");
+    }
+}
@@ -0,0 +1,450 @@
+//! Evaluate CSS counters (<https://drafts.csswg.org/css-lists-3/#auto-numbering>) and
+//! generated content (<https://drafts.csswg.org/css-content-3/#content-property>)
+//!
+//! Counters and quotes depend on an element's position in the tree rather than just its own
+//! style, so they are resolved by a tree-order walk over the document after styles are
+//! resolved. The resulting `list-item` values and `::before`/`::after` content are stored in
+//! each element's [`GeneratedContent`] and read when the element's layout children are
+//! constructed.
+
+use blitz_traits::node_id::NodeId;
+use markup5ever::local_name;
+use style::Atom;
+use style::context::QuirksMode;
+use style::counter_style::CounterStyleRule;
+use style::properties::ComputedValues;
+use style::servo_arc::Arc;
+use style::shared_lock::{Locked, SharedRwLockReadGuard};
+use style::stylesheets::{CssRule, CustomMediaEvaluator};
+use style::url::ComputedUrl;
+use style::values::computed::list::Quotes;
+use style::values::computed::{Content, ContentItem};
+use style::values::generics::image::Image as StyloImage;
+
+use super::counter_style::CounterStyles;
+use crate::BaseDocument;
+use crate::layout::damage::ALL_DAMAGE;
+use crate::node::{GeneratedContent, GeneratedContentItem};
+
+/// An instance of a counter. Its scope is the element which created it, that element's
+/// following siblings, and all of their descendants.
+struct Counter {
+    name: Atom,
+    value: i32,
+    /// The depth in the tree of the element which created the counter
+    depth: usize,
+    /// Whether the counter was reset with `reversed()` (so `list-item` counts down)
+    reversed: bool,
+}
+
+/// The quotes used by `open-quote` and `close-quote` when `quotes` is `auto`
+const DEFAULT_QUOTES: [(&str, &str); 2] = [("\u{201c}", "\u{201d}"), ("\u{2018}", "\u{2019}")];
+
+struct CounterWalker<'a> {
+    doc: &'a BaseDocument,
+    counters: Vec<Counter>,
+    /// The nesting level of quotes (which, unlike counters, isn't scoped)
+    quote_depth: usize,
+    results: Vec<(NodeId, Option<GeneratedContent>)>,
+}
+
+impl CounterWalker<'_> {
+    fn innermost(&self, name: &Atom) -> Option<usize> {
+        self.counters
+            .iter()
+            .rposition(|counter| counter.name == *name)
+    }
+
+    fn value(&self, name: &Atom) -> Option<i32> {
+        self.innermost(name).map(|idx| self.counters[idx].value)
+    }
+
+    fn reset(&mut self, name: &Atom, value: i32, reversed: bool, depth: usize) {
+        // A counter created by a preceding sibling is replaced rather than nested
+        if let Some(idx) = self.innermost(name)
+            && self.counters[idx].depth == depth
+        {
+            self.counters.remove(idx);
+        }
+        self.counters.push(Counter {
+            name: name.clone(),
+            value,
+            depth,
+            reversed,
+        });
+    }
+
+    fn increment(&mut self, name: &Atom, by: i32, depth: usize) {
+        match self.innermost(name) {
+            Some(idx) => {
+                let counter = &mut self.counters[idx];
+                counter.value = counter.value.wrapping_add(by);
+            }
+            None => self.reset(name, by, false, depth),
+        }
+    }
+
+    fn set(&mut self, name: &Atom, value: i32, depth: usize) {
+        match self.innermost(name) {
+            Some(idx) => self.counters[idx].value = value,
+            None => self.reset(name, value, false, depth),
+        }
+    }
+
+    /// Apply the `counter-reset`, `counter-increment` and `counter-set` properties (in that
+    /// order) of an element or pseudo-element at the given depth
+    fn apply_counter_properties(
+        &mut self,
+        style: &ComputedValues,
+        is_list_item: bool,
+        depth: usize,
+        node_id: NodeId,
+    ) {
+        let counters = style.get_counters();
+        let list_item = Atom::from("list-item");
+
+        for pair in counters.counter_reset.iter() {
+            let name = &pair.name.0;
+            let (mut value, mut reversed) = (pair.value, pair.is_reversed);
+            if *name == list_item && !reversed && value == 0 {
+                // The `counter-reset: list-item` from the UA stylesheet is adjusted by the
+                // `start` and `reversed` attributes of `<ol>` elements
+                if let Some((start, ol_reversed)) = self.ol_attributes(node_id) {
+                    reversed = ol_reversed;
+                    value = match (start, reversed) {
+                        (Some(start), false) => start.wrapping_sub(1),
+                        (Some(start), true) => start.wrapping_add(1),
+                        (None, false) => 0,
+                        (None, true) => self.count_list_items(node_id) + 1,
+                    };
+                }
+            } else if reversed && value == 0 {
+                // `reversed(<counter-name>)` without a value counts down to 1
+                value = self.count_list_items(node_id) + 1;
+            }
+            self.reset(name, value, reversed, depth);
+        }
+
+        // List items implicitly increment `list-item` (unless `counter-increment` mentions it)
+        let increments_list_item = counters
+            .counter_increment
+            .iter()
+            .any(|pair| pair.name.0 == list_item);
+        if is_list_item && !increments_list_item {
+            let reversed = self
+                .innermost(&list_item)
+                .is_some_and(|idx| self.counters[idx].reversed);
+            self.increment(&list_item, if reversed { -1 } else { 1 }, depth);
+        }
+        for pair in counters.counter_increment.iter() {
+            self.increment(&pair.name.0, pair.value, depth);
+        }
+
+        for pair in counters.counter_set.iter() {
+            self.set(&pair.name.0, pair.value, depth);
+        }
+
+        // The `value` attribute of `<li>` elements sets `list-item`
+        if is_list_item {
+            let value = self.doc.nodes[node_id]
+                .element_data()
+                .filter(|el| el.name.local == local_name!("li"))
+                .and_then(|el| el.attr_parsed::<i32>(local_name!("value")));
+            if let Some(value) = value {
+                self.set(&list_item, value, depth);
+            }
+        }
+    }
+
+    /// The `start` and `reversed` attributes of an `<ol>` element
+    fn ol_attributes(&self, node_id: NodeId) -> Option<(Option<i32>, bool)> {
+        let el = self.doc.nodes[node_id].element_data()?;
+        if el.name.local != local_name!("ol") {
+            return None;
+        }
+        let start = el.attr_parsed::<i32>(local_name!("start"));
+        let reversed = el.attr(local_name!("reversed")).is_some();
+        Some((start, reversed))
+    }
+
+    /// The number of list items owned by a list (not counting those in nested lists), which
+    /// is the initial value of a reversed list's `list-item` counter
+    fn count_list_items(&self, node_id: NodeId) -> i32 {
+        let mut count = 0;
+        for &child_id in &self.doc.nodes[node_id].children {
+            let child = &self.doc.nodes[child_id];
+            let Some(display) = child.primary_styles().map(|s| s.get_box().display) else {
+                continue;
+            };
+            if display.is_none() {
+                continue;
+            }
+            if display.is_list_item() {
+                count += 1;
+            }
+            let is_list = child.element_data().is_some_and(|el| {
+                matches!(
+                    el.name.local,
+                    local_name!("ol") | local_name!("ul") | local_name!("menu")
+                )
+            });
+            if !is_list {
+                count += self.count_list_items(child_id);
+            }
+        }
+        count
+    }
+
+    /// The opening or closing quote for the current quote depth
+    fn quote(style: &ComputedValues, depth: usize, open: bool) -> String {
+        let pair = match &style.get_list().quotes {
+            Quotes::QuoteList(list) => {
+                let Some(pair) = list.0.get(depth).or(list.0.last()) else {
+                    return String::new();
+                };
+                (&*pair.opening, &*pair.closing)
+            }
+            // TODO: use the quotes appropriate for the element's language
+            Quotes::Auto => DEFAULT_QUOTES[depth.min(DEFAULT_QUOTES.len() - 1)],
+        };
+        match open {
+            true => pair.0.to_string(),
+            false => pair.1.to_string(),
+        }
+    }
+
+    /// The content generated by a pseudo-element's `content` property. Counters used by
+    /// `counter()` and `counters()` which don't exist are instantiated at the given depth.
+    fn content_items(
+        &mut self,
+        style: &ComputedValues,
+        counter_styles: &CounterStyles,
+        depth: usize,
+        originating_node_id: NodeId,
+    ) -> Option<Vec<GeneratedContentItem>> {
+        let Content::Items(item_data) = &style.get_counters().content else {
+            return None;
+        };
+
+        let mut items = Vec::new();
+        let mut text = String::new();
+        for item in &item_data.items[0..item_data.alt_start] {
+            match item {
+                ContentItem::String(string) => text.push_str(string),
+                ContentItem::Counter(name, counter_style) => {
+                    if self.innermost(&name.0).is_none() {
+                        self.reset(&name.0, 0, false, depth);
+                    }
+                    let value = self.value(&name.0).unwrap_or(0);
+                    text.push_str(&counter_styles.format(counter_style, value));
+                }
+                ContentItem::Counters(name, separator, counter_style) => {
+                    if self.innermost(&name.0).is_none() {
+                        self.reset(&name.0, 0, false, depth);
+                    }
+                    let values: Vec<String> = self
+                        .counters
+                        .iter()
+                        .filter(|counter| counter.name == name.0)
+                        .map(|counter| counter_styles.format(counter_style, counter.value))
+                        .collect();
+                    text.push_str(&values.join(separator));
+                }
+                ContentItem::OpenQuote => {
+                    text.push_str(&Self::quote(style, self.quote_depth, true));
+                    self.quote_depth += 1;
+                }
+                ContentItem::CloseQuote => {
+                    // A close quote without a matching open quote generates nothing
+                    if self.quote_depth > 0 {
+                        self.quote_depth -= 1;
+                        text.push_str(&Self::quote(style, self.quote_depth, false));
+                    }
+                }
+                ContentItem::NoOpenQuote => self.quote_depth += 1,
+                ContentItem::NoCloseQuote => self.quote_depth = self.quote_depth.saturating_sub(1),
+                ContentItem::Attr(attr) => {
+                    // `attr()` only matches an attribute in the namespace it names, and
+                    // generates its fallback value if the element doesn't have the attribute
+                    let value = self.doc.nodes[originating_node_id]
+                        .element_data()
+                        .and_then(|el| {
+                            el.attrs().iter().find(|a| {
+                                a.name.ns == attr.namespace_url && *a.name.local == *attr.attribute
+                            })
+                        })
+                        .map(|a| a.value.as_str());
+                    text.push_str(value.unwrap_or(&attr.fallback));
+                }
+                ContentItem::Image(image) => {
+                    // TODO: gradients and other non-url images
+                    if let StyloImage::Url(ComputedUrl::Valid(url)) = image {
+                        if !text.is_empty() {
+                            items.push(GeneratedContentItem::Text(std::mem::take(&mut text)));
+                        }
+                        items.push(GeneratedContentItem::Image(url.as_str().to_string()));
+                    }
+                }
+            }
+        }
+        if !text.is_empty() {
+            items.push(GeneratedContentItem::Text(text));
+        }
+
+        Some(items)
+    }
+
+    fn visit(&mut self, node_id: NodeId, depth: usize, counter_styles: &CounterStyles) {
+        let doc = self.doc;
+        let node = &doc.nodes[node_id];
+        if !node.is_element() {
+            return;
+        }
+        let Some(style) = node.primary_styles().map(|style| (*style).clone()) else {
+            return;
+        };
+        let display = style.get_box().display;
+        // Elements which aren't rendered don't affect counters
+        if display.is_none() {
+            return;
+        }
+
+        // Note: yes these are kinda backwards
+        let (before_style, after_style) = node
+            .stylo_element_data_opt()
+            .and_then(|data| data.get())
+            .map(|data| {
+                let pseudos = data.styles.pseudos.as_array();
+                (pseudos[1].clone(), pseudos[0].clone())
+            })
+            .unwrap_or_default();
+
+        let is_list_item = display.is_list_item();
+        self.apply_counter_properties(&style, is_list_item, depth, node_id);
+        let list_item_value = is_list_item
+            .then(|| self.value(&Atom::from("list-item")))
+            .flatten();
+
+        // The `::before` pseudo-element is the element's first child...
+        let before = before_style.and_then(|style| {
+            self.apply_counter_properties(&style, false, depth + 1, node_id);
+            self.content_items(&style, counter_styles, depth + 1, node_id)
+        });
+
+        let children = node.children.clone();
+        for child_id in children {
+            self.visit(child_id, depth + 1, counter_styles);
+        }
+
+        // ...and the `::after` pseudo-element is its last child
+        let after = after_style.and_then(|style| {
+            self.apply_counter_properties(&style, false, depth + 1, node_id);
+            self.content_items(&style, counter_styles, depth + 1, node_id)
+        });
+
+        // Counters created by children go out of scope
+        while self.counters.last().is_some_and(|c| c.depth > depth) {
+            self.counters.pop();
+        }
+
+        let generated_content = GeneratedContent {
+            list_item_value,
+            before,
+            after,
+        };
+        let generated_content =
+            (generated_content != GeneratedContent::default()).then_some(generated_content);
+        self.results.push((node_id, generated_content));
+    }
+}
+
+impl BaseDocument {
+    /// Resolve the counters and quotes of every element in the document, storing the values
+    /// used by list markers and `::before`/`::after` content in each element's
+    /// [`GeneratedContent`]. Elements whose generated content changes are damaged so that they
+    /// are reconstructed.
+    pub(crate) fn resolve_generated_content(&mut self) {
+        if self.counter_styles_dirty {
+            self.counter_styles = self.collect_counter_styles();
+            self.counter_styles_dirty = false;
+            self.counters_dirty = true;
+        }
+        if !self.counters_dirty {
+            return;
+        }
+        self.counters_dirty = false;
+
+        let mut walker = CounterWalker {
+            doc: self,
+            counters: Vec::new(),
+            quote_depth: 0,
+            results: Vec::new(),
+        };
+        let root_children = self.root_node().children.clone();
+        for child_id in root_children {
+            walker.visit(child_id, 0, &self.counter_styles);
+        }
+        let results = walker.results;
+
+        for (node_id, generated_content) in results {
+            let node = &mut self.nodes[node_id];
+            let element_data = node.element_data_mut().unwrap();
+            if element_data.generated_content.as_deref() != generated_content.as_ref() {
+                element_data.generated_content = generated_content.map(Box::new);
+                node.insert_damage(ALL_DAMAGE);
+            }
+        }
+    }
+
+    /// Resolve the `@counter-style` rules in the document's user-agent and author
+    /// stylesheets
+    fn collect_counter_styles(&self) -> CounterStyles {
+        let guard = self.guard.read();
+        let mut rules = Vec::new();
+        for sheet in self
+            .useragent_stylesheets()
+            .chain(self.author_stylesheets())
+        {
+            let sheet_rules = sheet.0.contents(&guard).rules(&guard);
+            self.collect_counter_style_rules(sheet_rules, &guard, &mut rules);
+        }
+        let rules: Vec<_> = rules.iter().map(|rule| rule.read_with(&guard)).collect();
+        CounterStyles::from_rules(rules)
+    }
+
+    /// Collect the `@counter-style` rules which apply to the document, including those
+    /// nested in matching `@media` and `@supports` rules
+    fn collect_counter_style_rules(
+        &self,
+        rules: &[CssRule],
+        guard: &SharedRwLockReadGuard,
+        out: &mut Vec<Arc<Locked<CounterStyleRule>>>,
+    ) {
+        for rule in rules {
+            match rule {
+                CssRule::CounterStyle(rule) => out.push(rule.clone()),
+                CssRule::Media(rule) => {
+                    let matches = rule.media_queries.read_with(guard).evaluate(
+                        self.stylist.device(),
+                        QuirksMode::NoQuirks,
+                        &mut CustomMediaEvaluator::none(),
+                    );
+                    if matches {
+                        self.collect_counter_style_rules(
+                            &rule.rules.read_with(guard).0,
+                            guard,
+                            out,
+                        );
+                    }
+                }
+                CssRule::Supports(rule) if rule.enabled => {
+                    self.collect_counter_style_rules(&rule.rules.read_with(guard).0, guard, out);
+                }
+                CssRule::LayerBlock(rule) => {
+                    self.collect_counter_style_rules(&rule.rules.read_with(guard).0, guard, out);
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use blitz_traits::node_id::NodeId;
 use std::ops::Range;
 
+use super::construct::create_placeholder;
+use super::resolve_calc_value;
 use crate::Node;
 use crate::net::ResourceHandler;
 use crate::node::NodeFlags;
@@ -10,6 +12,7 @@ use crate::{
 use style::properties::ComputedValues;
 use style::properties::generated::longhands::position::computed_value::T as Position;
 use style::selector_parser::RestyleDamage;
+use style::servo_arc::Arc as ServoArc;
 use style::url::ComputedUrl;
 use style::values::computed::Float;
 use style::values::generics::image::Image as StyloImage;
@@ -17,6 +20,8 @@ use style::values::specified::align::AlignFlags;
 use style::values::specified::box_::DisplayInside;
 use style::values::specified::box_::DisplayOutside;
 use taffy::Rect;
+use taffy::style_helpers::{FromLength, length};
+use taffy::{MaybeResolve as _, ResolveOrZero as _};
 use thin_vec::ThinVec;
 
 pub(crate) const CONSTRUCT_BOX: RestyleDamage =
@@ -32,12 +37,20 @@ pub(crate) const ONLY_RELAYOUT: RestyleDamage =
 pub(crate) const ALL_DAMAGE: RestyleDamage =
     RestyleDamage::from_bits_retain(0b_0000_0000_0111_1111);
 
+/// A restyle changed a property which counters and generated content depend on, so they need
+/// to be resolved again. This isn't part of [`ALL_DAMAGE`]: it is consumed (and removed) at the
+/// end of each style traversal.
+pub(crate) const RESOLVE_COUNTERS: RestyleDamage =
+    RestyleDamage::from_bits_retain(0b_0000_0000_1000_0000);
+
 impl BaseDocument {
     pub(crate) fn propagate_damage_flags(
         &mut self,
         node_id: NodeId,
         damage_from_parent: RestyleDamage,
     ) -> RestyleDamage {
+        self.sync_placeholder_styles(node_id);
+
         let mut damage = if let Some(data) = self.nodes[node_id]
             .stylo_element_data_opt_mut()
             .and_then(|s| s.get_mut())
@@ -182,6 +195,40 @@ impl BaseDocument {
             pe_data.set_restyled();
         }
     }
+
+    /// Lay out a text input's placeholder again if the input has been restyled.
+    ///
+    /// Placeholders are laid out during box construction (see `create_placeholder`), but
+    /// restyles which change the `::placeholder` styles (e.g. `input:focus::placeholder`)
+    /// don't necessarily reconstruct the input's box.
+    fn sync_placeholder_styles(&mut self, node_id: NodeId) {
+        let node = &self.nodes[node_id];
+        let Some(input) = node.element_data().and_then(|el| el.text_input_data()) else {
+            return;
+        };
+        let Some(placeholder) = &input.placeholder else {
+            return;
+        };
+        let Some(primary_styles) = node.primary_styles() else {
+            return;
+        };
+        if ServoArc::ptr_eq(&placeholder.input_styles, &primary_styles) {
+            return;
+        }
+        let is_multiline = input.is_multiline;
+        drop(primary_styles);
+
+        let placeholder = create_placeholder(self, node_id, is_multiline);
+        let node = &mut self.nodes[node_id];
+        if let Some(input) = node
+            .data
+            .downcast_element_mut()
+            .and_then(|el| el.text_input_data_mut())
+        {
+            input.placeholder = placeholder;
+        }
+        node.insert_damage(RestyleDamage::REPAINT);
+    }
 }
 
 // fn is_fc_root(style: &ComputedValues) -> bool {
@@ -291,11 +338,18 @@ pub(crate) fn compute_layout_damage(old: &ComputedValues, new: &ComputedValues)
         false
     };
 
+    let counters_need_resolve = || {
+        old.get_box().display != new.get_box().display
+            || old.get_counters() != new.get_counters()
+            || old.get_list().quotes != new.get_list().quotes
+            || old.get_list().list_style_type != new.get_list().list_style_type
+    };
+
     #[allow(
         clippy::if_same_then_else,
         reason = "these branches will soon be different"
     )]
-    if box_tree_needs_rebuild() {
+    let damage = if box_tree_needs_rebuild() {
         ALL_DAMAGE
     } else if text_shaping_needs_recollect() {
         ALL_DAMAGE
@@ -304,6 +358,12 @@ pub(crate) fn compute_layout_damage(old: &ComputedValues, new: &ComputedValues)
         // its box. In the future, we will distinguish between types of damage to the
         // fragment as well.
         RestyleDamage::RELAYOUT
+    };
+
+    if counters_need_resolve() {
+        damage | RESOLVE_COUNTERS
+    } else {
+        damage
     }
 }
 
@@ -422,7 +482,15 @@ impl BaseDocument {
                 input.editor.set_scale(scale);
                 let mut font_ctx = font_ctx.lock().unwrap();
                 input.editor.refresh_layout(&mut font_ctx, layout_ctx);
-                node.insert_damage(ONLY_RELAYOUT);
+                if input.placeholder.is_some() {
+                    // Rebuild the placeholder layout at the new scale
+                    node.insert_damage(ALL_DAMAGE);
+                } else {
+                    node.insert_damage(ONLY_RELAYOUT);
+                }
+            } else if element.select_data().is_some() {
+                // Rebuild the option label layouts at the new scale
+                node.insert_damage(ALL_DAMAGE);
             }
         }
 
@@ -434,6 +502,7 @@ impl BaseDocument {
     }
 
     pub fn flush_styles_to_layout(&mut self, node_id: NodeId) {
+        self.sticky_and_fixed_nodes.clear();
         self.flush_styles_to_layout_impl(node_id, None);
     }
 
@@ -514,7 +583,11 @@ impl BaseDocument {
                                 doc_id,
                                 None, // Don't pass node_id, we'll handle via pending_images
                                 self.shell_provider.clone(),
-                                ImageHandler::new(kind.image_type(idx)),
+                                ImageHandler::new(
+                                    kind.image_type(idx),
+                                    url_str.to_string(),
+                                    self.image_decoding,
+                                ),
                             ),
                         );
 
@@ -543,14 +616,19 @@ impl BaseDocument {
         self.flush_image_layers_from_style(node_id, ImageLayerKind::Mask);
 
         let incremental = self.incremental_layout;
-        let display = {
+        let scale = self.viewport.scale();
+        let viewport_size = taffy::Size {
+            width: self.viewport.window_size.0 as f32 / scale,
+            height: self.viewport.window_size.1 as f32 / scale,
+        };
+        let (display, position) = {
             let node = self.nodes.get_mut(node_id).unwrap();
             let _damage = node.damage().unwrap_or(ALL_DAMAGE);
 
             // Compute the owned taffy style and display in an inner scope so the
             // immutable borrow of `node` (held by the stylo element data guard)
             // is released before we mutably access `node` below.
-            let (mut taffy_style, display_constructed_as) = {
+            let (mut taffy_style, display_constructed_as, mut position) = {
                 let stylo_element_data = node.stylo_element_data_opt().and_then(|s| s.get());
                 let primary_styles = stylo_element_data
                     .as_ref()
@@ -560,8 +638,21 @@ impl BaseDocument {
                     return;
                 };
 
-                (stylo_taffy::to_taffy_style(style), style.clone_display())
+                (
+                    stylo_taffy::to_taffy_style(style),
+                    style.clone_display(),
+                    style.clone_position(),
+                )
             };
+            // Elements in the top layer are positioned against the viewport (rather than their
+            // ancestors) whatever their `position`
+            if self.top_layer.contains(&node_id) {
+                taffy_style.position = taffy::Position::Absolute;
+                position = Position::Fixed;
+            }
+            if position == Position::Fixed {
+                resolve_fixed_style_against_viewport(&mut taffy_style, viewport_size);
+            }
             taffy_style.item_is_replaced = node
                 .data
                 .downcast_element()
@@ -585,9 +676,16 @@ impl BaseDocument {
                 }
             }
 
-            node.style().display
+            (node.style().display, position)
         };
 
+        // Sticky and fixed boxes are repositioned whenever a scroll offset changes
+        if matches!(position, Position::Sticky | Position::Fixed) {
+            self.sticky_and_fixed_nodes.insert(node_id);
+        } else {
+            *self.nodes[node_id].position_offset_mut() = crate::util::Point::ZERO;
+        }
+
         // If the node has children, then take those children and...
         let children = self.nodes[node_id].layout_children.borrow_mut().take();
         if let Some(mut children) = children {
@@ -624,6 +722,12 @@ impl BaseDocument {
 
             // Push children to either paint_children or layout_children depending on
             for &child_id in children.iter() {
+                // Elements in the top layer are laid out in place, but painted (and hit-tested)
+                // above all other content rather than as part of their parent
+                if self.top_layer.contains(&child_id) {
+                    continue;
+                }
+
                 let child = &self.nodes[child_id];
 
                 let Some(style) = child.primary_styles() else {
@@ -678,6 +782,91 @@ impl BaseDocument {
     }
 }
 
+/// Taffy lays out fixed boxes like absolutely positioned boxes, within their parent. Their
+/// containing block is the viewport, so resolve their sizes and insets against the viewport
+/// instead. Where both insets of an axis are set, the size is whatever the viewport leaves
+/// between them. The box is then moved into place by
+/// [`resolve_scroll_positions`](BaseDocument::resolve_scroll_positions).
+fn resolve_fixed_style_against_viewport(style: &mut taffy::Style, viewport: taffy::Size<f32>) {
+    fn resolved<T: FromLength>(value: Option<f32>, unresolved: T) -> T {
+        value.map_or(unresolved, length)
+    }
+
+    let basis = viewport.map(Some);
+    let size = style.size.maybe_resolve(basis, resolve_calc_value);
+    let min_size = style.min_size.maybe_resolve(basis, resolve_calc_value);
+    let max_size = style.max_size.maybe_resolve(basis, resolve_calc_value);
+    let inset = Rect {
+        left: style
+            .inset
+            .left
+            .maybe_resolve(basis.width, resolve_calc_value),
+        right: style
+            .inset
+            .right
+            .maybe_resolve(basis.width, resolve_calc_value),
+        top: style
+            .inset
+            .top
+            .maybe_resolve(basis.height, resolve_calc_value),
+        bottom: style
+            .inset
+            .bottom
+            .maybe_resolve(basis.height, resolve_calc_value),
+    };
+    let margin = style.margin.map(|margin| {
+        margin
+            .maybe_resolve(basis.width, resolve_calc_value)
+            .unwrap_or(0.0)
+    });
+    let padding = style
+        .padding
+        .resolve_or_zero(basis.width, resolve_calc_value);
+    let border = style
+        .border
+        .resolve_or_zero(basis.width, resolve_calc_value);
+    let box_sizing_adjustment = match style.box_sizing {
+        taffy::BoxSizing::ContentBox => taffy::Size {
+            width: padding.left + padding.right + border.left + border.right,
+            height: padding.top + padding.bottom + border.top + border.bottom,
+        },
+        taffy::BoxSizing::BorderBox => taffy::Size::ZERO,
+    };
+    let stretched =
+        |start: Option<f32>, end: Option<f32>, margins: f32, adjustment: f32, available: f32| {
+            Some((available - start? - end? - margins - adjustment).max(0.0))
+        };
+    let width = size.width.or_else(|| {
+        stretched(
+            inset.left,
+            inset.right,
+            margin.left + margin.right,
+            box_sizing_adjustment.width,
+            viewport.width,
+        )
+    });
+    let height = size.height.or_else(|| {
+        stretched(
+            inset.top,
+            inset.bottom,
+            margin.top + margin.bottom,
+            box_sizing_adjustment.height,
+            viewport.height,
+        )
+    });
+
+    style.size.width = resolved(width, style.size.width);
+    style.size.height = resolved(height, style.size.height);
+    style.min_size.width = resolved(min_size.width, style.min_size.width);
+    style.min_size.height = resolved(min_size.height, style.min_size.height);
+    style.max_size.width = resolved(max_size.width, style.max_size.width);
+    style.max_size.height = resolved(max_size.height, style.max_size.height);
+    style.inset.left = resolved(inset.left, style.inset.left);
+    style.inset.right = resolved(inset.right, style.inset.right);
+    style.inset.top = resolved(inset.top, style.inset.top);
+    style.inset.bottom = resolved(inset.bottom, style.inset.bottom);
+}
+
 #[inline(always)]
 fn position_to_order(pos: Position) -> i32 {
     match pos {
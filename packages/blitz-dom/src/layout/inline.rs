@@ -1,6 +1,8 @@
 use blitz_traits::node_id::NodeId;
-use parley::{AlignmentOptions, IndentOptions};
-use style::values::specified::box_::DisplayOutside;
+use parley::{AlignmentOptions, IndentOptions, PositionedLayoutItem};
+use style::properties::generated::longhands::_moz_box_orient::computed_value::T as BoxOrient;
+use style::values::computed::text::TextOverflowSide;
+use style::values::specified::box_::{DisplayInside, DisplayOutside};
 use style::values::{computed::CSSPixelLength, generics::text::GenericTextIndent};
 use taffy::{
     AvailableSpace, BlockContext, BlockFormattingContext, BoxSizing, CollapsibleMarginSet,
@@ -16,6 +18,11 @@ use taffy::{Clear, Float, prelude::TaffyMaxContent};
 
 use super::resolve_calc_value;
 use crate::BaseDocument;
+use crate::node::{TextLayout, TextTruncation, TruncatedLine};
+use crate::stylo_to_parley;
+
+/// The ellipsis used for `text-overflow: ellipsis` and `-webkit-line-clamp`
+const ELLIPSIS: &str = "\u{2026}";
 
 impl BaseDocument {
     pub(crate) fn compute_inline_layout(
@@ -625,8 +632,19 @@ impl BaseDocument {
             },
         );
 
+        // Apply `-webkit-line-clamp` and `text-overflow`
+        self.truncate_inline_layout(node_id, &mut inline_layout, width);
+
+        // Lines hidden by `-webkit-line-clamp` don't contribute to the height
         #[allow(unused_mut)]
-        let mut height = inline_layout.layout.height();
+        let mut height = match inline_layout.truncation.visible_lines {
+            Some(count) => inline_layout
+                .layout
+                .get(count - 1)
+                .map(|line| line.metrics().block_max_coord)
+                .unwrap_or(0.0),
+            None => inline_layout.layout.height(),
+        };
 
         // HACK. TODO: fix in Parley.
         //
@@ -849,6 +867,114 @@ impl BaseDocument {
     }
 }
 
+impl BaseDocument {
+    /// Compute how the lines of an inline layout are truncated for display: lines after
+    /// the `-webkit-line-clamp` limit of a vertical `-webkit-box` are hidden and the last displayed line ends in an
+    /// ellipsis, as do lines which overflow `max_advance` in a block with clipped overflow
+    /// and `text-overflow: ellipsis` (or a custom string).
+    ///
+    /// TODO: support `text-overflow` at the start edge and in right-to-left text
+    fn truncate_inline_layout(
+        &mut self,
+        node_id: NodeId,
+        inline_layout: &mut TextLayout,
+        max_advance: f32,
+    ) {
+        inline_layout.truncation = TextTruncation::default();
+
+        let node = &self.nodes[node_id];
+        let Some(styles) = node.primary_styles() else {
+            return;
+        };
+
+        // `-webkit-line-clamp` only applies to a legacy `display: -webkit-box` with
+        // `-webkit-box-orient: vertical`
+        let line_count = inline_layout.layout.len();
+        let line_clamp = styles.clone__webkit_line_clamp();
+        let is_vertical_webkit_box = styles.clone_display().inside() == DisplayInside::WebkitBox
+            && styles.clone__moz_box_orient() == BoxOrient::Vertical;
+        let visible_lines = (is_vertical_webkit_box && !line_clamp.is_none())
+            .then_some(line_clamp.0 as usize)
+            .filter(|&count| count < line_count);
+
+        // `text-overflow` only applies to blocks which clip their inline overflow
+        let text_overflow = match node.style().overflow().x {
+            Overflow::Visible => None,
+            _ => match styles.clone_text_overflow().second {
+                TextOverflowSide::Clip => None,
+                TextOverflowSide::Ellipsis => Some(String::from(ELLIPSIS)),
+                TextOverflowSide::String(string) => Some(String::from(&*string)),
+            },
+        };
+
+        let truncated_lines: Vec<usize> = inline_layout
+            .layout
+            .lines()
+            .take(visible_lines.unwrap_or(line_count))
+            .enumerate()
+            .filter(|(line_index, line)| {
+                Some(line_index + 1) == visible_lines
+                    || (text_overflow.is_some() && line.metrics().advance > max_advance)
+            })
+            .map(|(line_index, _)| line_index)
+            .collect();
+
+        inline_layout.truncation.visible_lines = visible_lines;
+        if truncated_lines.is_empty() {
+            return;
+        }
+
+        // Lay out the ellipsis with the inline root's style
+        let ellipsis_text = text_overflow.unwrap_or_else(|| String::from(ELLIPSIS));
+        let parley_style = stylo_to_parley::style(node_id, &styles);
+        let mut font_ctx = self.font_ctx.lock().unwrap();
+        let mut builder =
+            self.layout_ctx
+                .tree_builder(&mut font_ctx, self.viewport.scale(), true, &parley_style);
+        builder.push_text(&ellipsis_text);
+        let mut ellipsis = builder.build().0;
+        ellipsis.break_all_lines(None);
+        let available = max_advance - ellipsis.full_width();
+
+        for line_index in truncated_lines {
+            let line = inline_layout.layout.get(line_index).unwrap();
+
+            // Keep as many whole clusters (and inline boxes) as fit alongside the ellipsis,
+            // dropping any whitespace before the ellipsis.
+            let mut clip_x = line.metrics().offset;
+            'items: for item in line.items() {
+                match item {
+                    PositionedLayoutItem::GlyphRun(glyph_run) => {
+                        let mut x = glyph_run.offset();
+                        for cluster in glyph_run.run().visual_clusters() {
+                            x += cluster.advance();
+                            if x > available {
+                                break 'items;
+                            }
+                            if !cluster.is_space_or_nbsp() {
+                                clip_x = x;
+                            }
+                        }
+                    }
+                    PositionedLayoutItem::InlineBox(ibox) => {
+                        if ibox.x + ibox.width > available {
+                            break 'items;
+                        }
+                        clip_x = ibox.x + ibox.width;
+                    }
+                }
+            }
+
+            inline_layout.truncation.lines.push(TruncatedLine {
+                line_index,
+                clip_x,
+                ellipsis_x: clip_x,
+            });
+        }
+        inline_layout.truncation.ellipsis = Some(Box::new(ellipsis));
+    }
+}
+
 #[inline(always)]
 fn f32_max(a: f32, b: f32) -> f32 {
     a.max(b)
@@ -5,6 +5,7 @@
 //! This is slower, yes, but happens fast enough that it's not a huge issue.
 
 use crate::node::{ImageData, NodeData, SpecialElementData};
+use crate::select::{SELECT_ARROW_WIDTH, select_display_size, select_is_drop_down};
 use crate::{document::BaseDocument, dom_node_id, node::Node, taffy_node_id};
 use markup5ever::{LocalName, local_name};
 use std::cell::Ref;
@@ -19,6 +20,8 @@ use taffy::{
 };
 
 pub(crate) mod construct;
+pub(crate) mod counter_style;
+pub(crate) mod counters;
 pub(crate) mod damage;
 pub(crate) mod inline;
 pub(crate) mod list;
@@ -139,6 +142,16 @@ impl BaseDocument {
                 // })
             }
             NodeData::Element(element_data) | NodeData::AnonymousBlock(element_data) => {
+                // Custom widgets can lay themselves out, which requires their computed styles
+                #[cfg(feature = "custom-widget")]
+                if let SpecialElementData::CustomWidget(widget_data) =
+                    &mut element_data.special_data
+                    && let Some(styles) = element_data.stylo_element_data.primary_styles()
+                    && let Some(output) = widget_data.widget.layout(&inputs, &styles)
+                {
+                    return output;
+                }
+
                 // TODO: deduplicate with single-line text input
                 if *element_data.name.local == *"textarea" {
                     let rows = element_data
@@ -163,6 +176,24 @@ impl BaseDocument {
                     );
                 }
 
+                if let Some(select_data) = element_data.select_data() {
+                    let (width, height) = if select_is_drop_down(element_data) {
+                        (
+                            select_data.label_width + SELECT_ARROW_WIDTH,
+                            select_data.row_height,
+                        )
+                    } else {
+                        let rows = select_display_size(element_data) as f32;
+                        (select_data.label_width, select_data.row_height * rows)
+                    };
+                    return compute_leaf_layout(
+                        inputs,
+                        node.style(),
+                        resolve_calc_value,
+                        |_known_size, _available_space| taffy::Size { width, height },
+                    );
+                }
+
                 if *element_data.name.local == *"input" {
                     match element_data.attr(local_name!("type")) {
                         // if the input type is hidden, hide it
@@ -227,11 +258,21 @@ impl BaseDocument {
                             .and_then(|val| val.parse::<f32>().ok()),
                     };
 
+                    // The natural size of images selected from a `srcset` is scaled by their density
+                    let density = element_data
+                        .image_source
+                        .as_ref()
+                        .map(|source| source.density)
+                        .unwrap_or(1.0);
+
                     // Get the element's intrinsic dimensions and default object size
                     let (intrinsic_sizes, default_object_size) = match &element_data.special_data {
                         SpecialElementData::Image(image_data) => match &**image_data {
                             ImageData::Raster(image) => {
-                                let (width, height) = (image.width as f32, image.height as f32);
+                                let (width, height) = (
+                                    image.natural_width as f32 / density,
+                                    image.natural_height as f32 / density,
+                                );
                                 (
                                     IntrinsicSizes {
                                         width: Some(width),
@@ -256,6 +297,8 @@ impl BaseDocument {
                                     width = Some(size.width());
                                     height = Some(size.height());
                                 }
+                                let width = width.map(|width| width / density);
+                                let height = height.map(|height| height / density);
                                 (
                                     IntrinsicSizes {
                                         width,
@@ -551,7 +594,17 @@ impl RoundTree for BaseDocument {
     }
 
     fn set_final_layout(&mut self, node_id: NodeId, layout: &Layout) {
-        *self.node_from_id_mut(node_id).final_layout_mut() = *layout;
+        let final_layout = self.node_from_id_mut(node_id).final_layout_mut();
+        if *final_layout == *layout {
+            return;
+        }
+        *final_layout = *layout;
+
+        // The node has moved, so its bounds in the accessibility tree need checking
+        #[cfg(feature = "accessibility")]
+        {
+            self.accessibility.layout_dirty = true;
+        }
     }
 }
 
@@ -41,19 +41,29 @@ pub mod node;
 
 mod config;
 mod debug;
+mod dialog;
 mod events;
 mod font_metrics;
 mod form;
+mod form_state;
 mod html;
 /// Loading of `<iframe>` elements into sub-documents.
 mod iframe;
+/// Playback of animated images.
+mod image_animation;
+/// Downscaled decoding and eviction of `<img>` images.
+mod image_decode;
+/// Selection of images from `srcset`, `sizes` and `<picture>` elements.
+mod image_source;
 /// Integration of taffy and the DOM.
 mod layout;
 mod mutator;
+mod popover;
 mod query_selector;
 mod resolve;
 /// Scrolling of nodes and the viewport, and scroll animations.
 mod scrolling;
+mod select;
 mod selection;
 /// Implementations that interact with servo's style engine
 mod stylo;
@@ -65,6 +75,8 @@ mod traversal;
 mod tree;
 
 mod url;
+/// Constraint validation of form controls.
+mod validation;
 
 pub use stylo_to_kurbo::resolve_2d_transform;
 
@@ -76,20 +88,25 @@ mod accessibility;
 
 pub use crate::layout::replaced::IntrinsicSizes;
 #[cfg(feature = "custom-widget")]
-pub use crate::node::Widget;
+pub use crate::node::{Widget, WidgetAccessibilityTree};
 
 pub use blitz_traits::node_id::NodeId;
-pub use config::{DocumentConfig, StyleThreading};
+pub use config::{DocumentConfig, ImageDecoding, StyleThreading};
 pub use document::{BaseDocument, DocGuard, DocGuardMut, Document, PlainDocument};
 pub use markup5ever::{
     LocalName, Namespace, NamespaceStaticSet, Prefix, PrefixStaticSet, QualName, local_name,
     namespace_prefix, namespace_url, ns,
 };
-pub use mutator::DocumentMutator;
+pub use mutator::{DocumentMutator, PendingMutations};
 pub use node::{Attribute, DocumentData, ElementData, Node, NodeData, TextNodeData};
 pub use parley::FontContext;
 pub use scrolling::{ScrollBehavior, ScrollLogicalPosition};
+pub use select::{
+    SELECT_ARROW_WIDTH, SELECT_LISTBOX_BORDER, SELECT_LISTBOX_PADDING, select_display_size,
+    select_is_drop_down,
+};
 pub use tree::NodeTree;
+pub use validation::ValidityState;
 
 /// Convert a Blitz [`NodeId`] into a [`taffy::NodeId`] (which wraps a `u64`).
 #[inline]
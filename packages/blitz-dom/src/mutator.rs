@@ -5,9 +5,8 @@ use std::ops::{Deref, DerefMut};
 
 use crate::document::make_device;
 use crate::layout::damage::ALL_DAMAGE;
-use crate::net::{ImageHandler, ResourceHandler, StylesheetHandler};
+use crate::net::{ResourceHandler, StylesheetHandler};
 use crate::node::{CanvasData, NodeFlags, SpecialElementData};
-use crate::util::ImageType;
 use crate::{
     Attribute, BaseDocument, Document, ElementData, Node, NodeData, QualName, local_name, qual_name,
 };
@@ -33,6 +32,7 @@ pub enum AppendTextErr {
 /// function for borrow-checker reasons.
 enum SpecialOp {
     LoadImage(NodeId),
+    LoadPictureImage(NodeId),
     LoadIframe(NodeId),
     LoadStylesheet(NodeId),
     UnloadStylesheet(NodeId),
@@ -54,6 +54,7 @@ pub struct DocumentMutator<'doc> {
     title_node: Option<NodeId>,
     style_nodes: HashSet<NodeId>,
     form_nodes: HashSet<NodeId>,
+    select_nodes: HashSet<NodeId>,
 
     /// Whether an element/attribute that affect animation status has been seen
     recompute_is_animating: bool,
@@ -66,6 +67,24 @@ pub struct DocumentMutator<'doc> {
     node_to_autofocus: Option<NodeId>,
 }
 
+/// The processing that a [`DocumentMutator`] defers until it is flushed, detached from the
+/// document by [`DocumentMutator::suspend`].
+///
+/// This allows a batch of mutations which can't hold a mutator for its whole duration (such as
+/// the nodes inserted by a streaming HTML parser while parsing a chunk) to be flushed once,
+/// by [`resume`](DocumentMutator::resume)ing the mutator and then dropping it.
+#[derive(Default)]
+pub struct PendingMutations {
+    title_node: Option<NodeId>,
+    style_nodes: HashSet<NodeId>,
+    form_nodes: HashSet<NodeId>,
+    select_nodes: HashSet<NodeId>,
+    recompute_is_animating: bool,
+    mutations_occurred: bool,
+    #[cfg(feature = "autofocus")]
+    node_to_autofocus: Option<NodeId>,
+}
+
 impl Drop for DocumentMutator<'_> {
     fn drop(&mut self) {
         self.flush(); // Defined at bottom of file
@@ -83,6 +102,7 @@ impl DocumentMutator<'_> {
             title_node: None,
             style_nodes: HashSet::new(),
             form_nodes: HashSet::new(),
+            select_nodes: HashSet::new(),
             recompute_is_animating: false,
             mutations_occurred: false,
             #[cfg(feature = "autofocus")]
@@ -90,6 +110,41 @@ impl DocumentMutator<'_> {
         }
     }
 
+    /// Create a mutator which continues the batch of mutations suspended by
+    /// [`suspend`](Self::suspend)
+    pub fn resume<'doc>(
+        doc: &'doc mut BaseDocument,
+        pending: PendingMutations,
+    ) -> DocumentMutator<'doc> {
+        DocumentMutator {
+            doc,
+            eager_op_queue: Vec::new(),
+            title_node: pending.title_node,
+            style_nodes: pending.style_nodes,
+            form_nodes: pending.form_nodes,
+            select_nodes: pending.select_nodes,
+            recompute_is_animating: pending.recompute_is_animating,
+            mutations_occurred: pending.mutations_occurred,
+            #[cfg(feature = "autofocus")]
+            node_to_autofocus: pending.node_to_autofocus,
+        }
+    }
+
+    /// Release the document without flushing, returning the processing which has been
+    /// deferred so far so that the batch can later be [`resume`](Self::resume)d
+    pub fn suspend(mut self) -> PendingMutations {
+        PendingMutations {
+            title_node: self.title_node.take(),
+            style_nodes: mem::take(&mut self.style_nodes),
+            form_nodes: mem::take(&mut self.form_nodes),
+            select_nodes: mem::take(&mut self.select_nodes),
+            recompute_is_animating: mem::take(&mut self.recompute_is_animating),
+            mutations_occurred: mem::take(&mut self.mutations_occurred),
+            #[cfg(feature = "autofocus")]
+            node_to_autofocus: self.node_to_autofocus.take(),
+        }
+    }
+
     // Query methods
 
     pub fn node_has_parent(&self, node_id: NodeId) -> bool {
@@ -191,6 +246,7 @@ impl DocumentMutator<'_> {
                 parent.insert_damage(ALL_DAMAGE);
             }
 
+            self.doc.mark_node_changed(node_id);
             self.maybe_record_node(parent_id);
         }
     }
@@ -208,6 +264,7 @@ impl DocumentMutator<'_> {
             Some(data) => {
                 data.content += text;
                 self.mutations_occurred |= node_is_in_document;
+                self.doc.mark_node_changed(node_id);
                 Ok(())
             }
             None => Err(AppendTextErr::NotTextNode),
@@ -237,6 +294,7 @@ impl DocumentMutator<'_> {
         let node_is_in_document = self.doc.nodes[node_id].flags.is_in_document();
         if node_is_in_document {
             self.doc.snapshot_node(node_id);
+            self.doc.mark_node_changed(node_id);
 
             let node = &mut self.doc.nodes[node_id];
             if let Some(mut data) = node.stylo_element_data_opt_mut().and_then(|s| s.get_mut()) {
@@ -260,8 +318,17 @@ impl DocumentMutator<'_> {
             // Without this, the traversal may skip nodes with pending RestyleHint/damage
             // because it uses dirty_descendants flags to determine which subtrees to visit.
             self.doc.nodes[node_id].mark_ancestors_dirty();
+
+            self.counter_attribute_changed(node_id, &name);
+            self.form_control_attribute_changed(node_id, &name);
         }
 
+        if *name.local == *"popover" {
+            self.doc.popover_attribute_changed(node_id, Some(value));
+        }
+
+        self.select_attribute_changed(node_id, &name);
+
         if name.local == local_name!("id") && node_is_in_document {
             if let Some(old_id) = self.doc.nodes[node_id]
                 .element_data()
@@ -303,6 +370,10 @@ impl DocumentMutator<'_> {
             element.flush_is_focussable();
         }
 
+        if name.local == local_name!("pattern") {
+            element.update_input_pattern();
+        }
+
         let tag = &element.name.local;
         let attr = &name.local;
 
@@ -318,6 +389,10 @@ impl DocumentMutator<'_> {
                     &mut self.doc.layout_ctx,
                     value,
                 );
+            } else if let Some(select_data) = element.select_data_mut() {
+                // Setting a select's `value` attribute (as frameworks do) overrides any
+                // previous user selection
+                select_data.user_selection = None;
             }
             return;
         }
@@ -341,8 +416,26 @@ impl DocumentMutator<'_> {
 
         if (tag, attr) == tag_and_attr!("input", "checked") {
             set_input_checked_state(element, value.to_string());
-        } else if (tag, attr) == tag_and_attr!("img", "src") {
+            // Checking a radio button unchecks the others in its group
+            let group = element
+                .attr(local_name!("name"))
+                .filter(|name| !name.is_empty() && element.is_radio_button())
+                .filter(|_| element.checkbox_input_checked() == Some(true))
+                .map(str::to_string);
+            if let Some(group) = group {
+                self.doc.toggle_radio(group, node_id);
+            }
+        } else if (tag, attr) == tag_and_attr!("img", "src")
+            || (tag, attr) == tag_and_attr!("img", "srcset")
+            || (tag, attr) == tag_and_attr!("img", "sizes")
+        {
             self.load_image(node_id);
+        } else if (tag, attr) == tag_and_attr!("source", "srcset")
+            || (tag, attr) == tag_and_attr!("source", "sizes")
+            || (tag, attr) == tag_and_attr!("source", "media")
+            || (tag, attr) == tag_and_attr!("source", "type")
+        {
+            self.load_picture_image(node_id);
         } else if (tag, attr) == tag_and_attr!("canvas", "src") {
             self.load_custom_paint_src(node_id);
         } else if (tag, attr) == tag_and_attr!("link", "href") {
@@ -358,6 +451,7 @@ impl DocumentMutator<'_> {
         let node_is_in_document = self.doc.nodes[node_id].flags.is_in_document();
         if node_is_in_document {
             self.doc.snapshot_node(node_id);
+            self.doc.mark_node_changed(node_id);
 
             let node = &mut self.doc.nodes[node_id];
 
@@ -369,8 +463,17 @@ impl DocumentMutator<'_> {
             // Mark ancestors dirty so the style traversal visits this subtree.
             // Without this, the traversal may skip nodes with pending RestyleHint/damage.
             node.mark_ancestors_dirty();
+
+            self.counter_attribute_changed(node_id, &name);
+            self.form_control_attribute_changed(node_id, &name);
         }
 
+        if *name.local == *"popover" {
+            self.doc.popover_attribute_changed(node_id, None);
+        }
+
+        self.select_attribute_changed(node_id, &name);
+
         if name.local == local_name!("id") && node_is_in_document {
             if let Some(old_id) = self.doc.nodes[node_id]
                 .element_data()
@@ -406,6 +509,10 @@ impl DocumentMutator<'_> {
             element.id = None;
         }
 
+        if name.local == local_name!("pattern") {
+            element.update_input_pattern();
+        }
+
         // As in `set_attribute`: taking one of these away can make the element
         // unfocusable again.
         if name.local == local_name!("tabindex")
@@ -459,6 +566,13 @@ impl DocumentMutator<'_> {
         self.mutations_occurred |= node_is_in_document;
     }
 
+    /// Set the indeterminate flag of a checkbox (the `indeterminate` IDL attribute)
+    pub fn set_indeterminate(&mut self, node_id: NodeId, indeterminate: bool) {
+        let node_is_in_document = self.doc.nodes[node_id].flags.is_in_document();
+        self.doc.set_indeterminate(node_id, indeterminate);
+        self.mutations_occurred |= node_is_in_document;
+    }
+
     pub fn set_sub_document(&mut self, node_id: NodeId, sub_document: Box<dyn Document>) {
         let node_is_in_document = self.doc.nodes[node_id].flags.is_in_document();
         self.doc.set_sub_document(node_id, sub_document);
@@ -843,6 +957,11 @@ impl<'doc> DocumentMutator<'doc> {
             self.doc.reset_form_owner(id);
         }
 
+        // Sync the selectedness of options whose select's options or attributes changed
+        for id in self.select_nodes.drain() {
+            self.doc.update_select_selectedness(id);
+        }
+
         #[cfg(feature = "autofocus")]
         if let Some(node_id) = self.node_to_autofocus.take() {
             if self.doc.get_node(node_id).is_some() {
@@ -864,6 +983,7 @@ impl<'doc> DocumentMutator<'doc> {
         for op in ops.drain(0..) {
             match op {
                 SpecialOp::LoadImage(node_id) => self.load_image(node_id),
+                SpecialOp::LoadPictureImage(node_id) => self.load_picture_image(node_id),
                 SpecialOp::LoadIframe(node_id) => self.load_iframe(node_id),
                 SpecialOp::LoadStylesheet(node_id) => self.load_linked_stylesheet(node_id),
                 SpecialOp::UnloadStylesheet(node_id) => self.unload_stylesheet(node_id),
@@ -880,6 +1000,8 @@ impl<'doc> DocumentMutator<'doc> {
     }
 
     fn process_added_subtree(&mut self, node_id: NodeId) {
+        self.doc.counters_dirty = true;
+        let mut has_form_control = false;
         self.doc.iter_subtree_mut(node_id, |node_id, doc| {
             let node = &mut doc.nodes[node_id];
             node.flags.set(NodeFlags::IS_IN_DOCUMENT, true);
@@ -897,10 +1019,16 @@ impl<'doc> DocumentMutator<'doc> {
 
             // Custom post-processing by element tag name
             let tag = element.name.local.as_ref();
+            if matches!(tag, "form" | "fieldset") {
+                doc.form_validity_dirty.insert(node_id);
+            }
             match tag {
                 "title" => self.title_node = Some(node_id),
                 "link" => self.eager_op_queue.push(SpecialOp::LoadStylesheet(node_id)),
                 "img" => self.eager_op_queue.push(SpecialOp::LoadImage(node_id)),
+                "source" => self
+                    .eager_op_queue
+                    .push(SpecialOp::LoadPictureImage(node_id)),
                 "iframe" => self.eager_op_queue.push(SpecialOp::LoadIframe(node_id)),
                 "canvas" => self
                     .eager_op_queue
@@ -912,6 +1040,13 @@ impl<'doc> DocumentMutator<'doc> {
                     self.eager_op_queue
                         .push(SpecialOp::ProcessButtonInput(node_id));
                     self.form_nodes.insert(node_id);
+                    has_form_control = true;
+                    if tag == "select" {
+                        self.select_nodes.insert(node_id);
+                    }
+                    if element.is_radio_button() {
+                        doc.radio_groups_dirty = true;
+                    }
                 }
                 _ => {}
             }
@@ -928,10 +1063,17 @@ impl<'doc> DocumentMutator<'doc> {
             }
         });
 
+        // Forms and fieldsets match `:invalid` if any of their controls do
+        if has_form_control {
+            self.doc.restyle_form_ancestors(node_id);
+        }
+
         self.flush_eager_ops();
     }
 
     fn process_removed_subtree(&mut self, node_id: NodeId) {
+        self.doc.counters_dirty = true;
+        let mut has_form_control = false;
         self.doc.iter_subtree_mut(node_id, |node_id, doc| {
             doc.nodes[node_id]
                 .flags
@@ -970,6 +1112,15 @@ impl<'doc> DocumentMutator<'doc> {
                 return;
             };
 
+            // Removing a submit button may also change the default button of its form
+            has_form_control |= matches!(
+                element.name.local,
+                local_name!("button")
+                    | local_name!("input")
+                    | local_name!("select")
+                    | local_name!("textarea")
+            );
+
             match &element.special_data {
                 SpecialElementData::SubDocument(_) => {
                     self.eager_op_queue
@@ -989,20 +1140,102 @@ impl<'doc> DocumentMutator<'doc> {
                 }
                 SpecialElementData::TableRoot(_) => {}
                 SpecialElementData::TextInput(_) => {}
-                SpecialElementData::CheckboxInput(_) => {}
+                SpecialElementData::CheckboxInput(_) => {
+                    if element.is_radio_button() {
+                        doc.radio_groups_dirty = true;
+                    }
+                }
+                SpecialElementData::Select(_) => {}
                 #[cfg(feature = "file-input")]
                 SpecialElementData::FileInput(_) => {}
                 SpecialElementData::None => {}
             }
         });
 
+        if has_form_control {
+            self.doc.restyle_form_ancestors(node_id);
+        }
+
         self.flush_eager_ops();
     }
 
+    /// Mark the document's counters as needing to be resolved again when an attribute that
+    /// they depend on changes: `<ol start>`/`<ol reversed>`, `<li value>` and attributes read
+    /// by `attr()` in `::before`/`::after` content. Attributes that change counters through
+    /// the element's styles are picked up when it is restyled.
+    fn counter_attribute_changed(&mut self, node_id: NodeId, name: &QualName) {
+        let Some(element) = self.doc.nodes[node_id].element_data() else {
+            return;
+        };
+        let tag_and_attr = (&element.name.local, &name.local);
+        if tag_and_attr == tag_and_attr!("ol", "start")
+            || tag_and_attr == tag_and_attr!("ol", "reversed")
+            || tag_and_attr == tag_and_attr!("li", "value")
+            || element
+                .generated_content
+                .as_ref()
+                .is_some_and(|content| content.before.is_some() || content.after.is_some())
+        {
+            self.doc.counters_dirty = true;
+        }
+    }
+
+    /// Update the forms and radio button groups that a form control is in when one of its
+    /// attributes changes, as its attributes affect its validity (and so that of its form),
+    /// which radio button group it is in and which button is its form's default button
+    fn form_control_attribute_changed(&mut self, node_id: NodeId, name: &QualName) {
+        let Some(element) = self.doc.nodes[node_id].element_data() else {
+            return;
+        };
+        // Disabling a fieldset bars the form controls in it from constraint validation
+        if element.name.local == local_name!("fieldset") && name.local == local_name!("disabled") {
+            self.doc.form_validity_dirty.insert(node_id);
+            self.doc.restyle_form_ancestors(node_id);
+            return;
+        }
+        if !matches!(
+            element.name.local,
+            local_name!("button")
+                | local_name!("input")
+                | local_name!("select")
+                | local_name!("textarea")
+        ) {
+            return;
+        }
+        if element.name.local == local_name!("input")
+            && matches!(
+                name.local,
+                local_name!("type")
+                    | local_name!("name")
+                    | local_name!("checked")
+                    | local_name!("required")
+            )
+        {
+            self.doc.radio_groups_dirty = true;
+        }
+        self.doc.restyle_form_ancestors(node_id);
+    }
+
+    /// Record that the selectedness of a select's options needs updating when one of the
+    /// attributes it depends on changes on the select or one of its options
+    fn select_attribute_changed(&mut self, node_id: NodeId, name: &QualName) {
+        if matches!(
+            name.local,
+            local_name!("selected")
+                | local_name!("value")
+                | local_name!("disabled")
+                | local_name!("multiple")
+                | local_name!("size")
+        ) {
+            self.record_select_change(node_id);
+        }
+    }
+
     fn maybe_record_node(&mut self, node_id: impl Into<Option<NodeId>>) {
         let Some(node_id) = node_id.into() else {
             return;
         };
+        self.doc.mark_node_changed(node_id);
 
         let Some(tag_name) = self.doc.nodes[node_id]
             .data
@@ -1017,10 +1250,32 @@ impl<'doc> DocumentMutator<'doc> {
             "style" => {
                 self.style_nodes.insert(node_id);
             }
+            "select" | "optgroup" | "option" => self.record_select_change(node_id),
             _ => {}
         }
     }
 
+    /// Record that the selectedness of the options of the select containing `node_id` (a
+    /// select, or one of its optgroups or options) needs updating when the mutator flushes
+    fn record_select_change(&mut self, node_id: NodeId) {
+        let node = &self.doc.nodes[node_id];
+        let Some(element) = node.element_data() else {
+            return;
+        };
+        let select = match element.name.local {
+            local_name!("select") => Some(node),
+            local_name!("option") => node.option_owner_select(),
+            local_name!("optgroup") => node
+                .parent
+                .map(|parent_id| &self.doc.nodes[parent_id])
+                .filter(|parent| parent.data.is_element_with_tag_name(&local_name!("select"))),
+            _ => None,
+        };
+        if let Some(select) = select {
+            self.select_nodes.insert(select.id);
+        }
+    }
+
     fn load_linked_stylesheet(&mut self, target_id: NodeId) {
         let node = &self.doc.nodes[target_id];
 
@@ -1087,54 +1342,38 @@ impl<'doc> DocumentMutator<'doc> {
             .force_stylesheet_origins_dirty(OriginSet::all());
 
         self.doc.nodes_to_stylesheet.remove(&node_id);
+        self.doc.counter_styles_dirty = true;
     }
 
     fn load_image(&mut self, target_id: NodeId) {
-        let node = &self.doc.nodes[target_id];
-        if let Some(raw_src) = node.attr(local_name!("src")) {
-            if !raw_src.is_empty() {
-                let src = self.doc.resolve_url(raw_src);
-                let src_string = src.as_str();
-
-                // Check cache first
-                if let Some(cached_image) = self.doc.image_cache.get(src_string) {
-                    #[cfg(feature = "tracing")]
-                    tracing::info!("Loading image {src_string} from cache");
-                    let node = &mut self.doc.nodes[target_id];
-                    node.element_data_mut().unwrap().special_data =
-                        SpecialElementData::Image(Box::new(cached_image.clone()));
-                    node.cache_mut().clear();
-                    node.insert_damage(ALL_DAMAGE);
-                    return;
-                }
-
-                // Check if there's already a pending request for this URL
-                if let Some(waiting_list) = self.doc.pending_images.get_mut(src_string) {
-                    #[cfg(feature = "tracing")]
-                    tracing::info!("Image {src_string} already pending, queueing node {target_id}");
-                    waiting_list.push((target_id, ImageType::Image));
-                    return;
-                }
+        self.doc.load_image(target_id);
+    }
 
-                // Start fetch and track as pending
-                #[cfg(feature = "tracing")]
-                tracing::info!("Fetching image {src_string}");
-                self.doc
-                    .pending_images
-                    .insert(src_string.to_string(), vec![(target_id, ImageType::Image)]);
-
-                self.doc.net_provider.fetch(
-                    self.doc.id(),
-                    self.doc.build_request(src),
-                    ResourceHandler::boxed(
-                        self.doc.tx.clone(),
-                        self.doc.id(),
-                        None, // Don't pass node_id, we'll handle it via pending_images
-                        self.doc.shell_provider.clone(),
-                        ImageHandler::new(ImageType::Image),
-                    ),
-                );
-            }
+    /// Reload the image of the \<img\> element in a \<picture\> element (if any) after one
+    /// of the picture's \<source\> elements changes
+    fn load_picture_image(&mut self, source_id: NodeId) {
+        let Some(parent_id) = self.doc.nodes[source_id].parent else {
+            return;
+        };
+        let picture = &self.doc.nodes[parent_id];
+        if !picture
+            .data
+            .is_element_with_tag_name(&local_name!("picture"))
+        {
+            return;
+        }
+        let img_id = picture.children.iter().copied().find(|&child_id| {
+            self.doc.nodes[child_id]
+                .data
+                .is_element_with_tag_name(&local_name!("img"))
+        });
+        // Images which haven't been loaded yet will select their source when they are
+        let Some(img_id) = img_id else {
+            return;
+        };
+        let img = self.doc.nodes[img_id].element_data().unwrap();
+        if img.image_status.is_some() && img.image_source != self.doc.select_image_source(img_id) {
+            self.load_image(img_id);
         }
     }
 
@@ -1287,6 +1526,7 @@ impl Drop for ViewportMut<'_> {
         self.doc.set_stylist_device(make_device(
             &self.doc.viewport,
             self.doc.media_type.clone(),
+            self.doc.prefers_reduced_motion,
             self.doc.font_ctx.clone(),
         ));
         self.doc.scroll_viewport_by(0.0, 0.0); // Clamp scroll offset
@@ -19,12 +19,12 @@ use style::{
     values::{CssUrl, SourceLocation},
 };
 
-use blitz_traits::net::{AbortSignal, Bytes, NetHandler, NetProvider, Request};
+use blitz_traits::net::{AbortSignal, Bytes, NetError, NetHandler, NetProvider, Request, Response};
 use blitz_traits::shell::ShellProvider;
 
 use url::Url;
 
-use crate::{document::DocumentEvent, util::ImageType};
+use crate::{ImageDecoding, document::DocumentEvent, util::ImageType};
 
 pub(crate) fn stamped_request(url: Url, signal: Option<&AbortSignal>) -> Request {
     let mut req = Request::get(url);
@@ -57,6 +57,9 @@ pub struct FontFaceOverrides {
 #[derive(Clone, Debug)]
 pub enum Resource {
     Image(ImageType, u32, u32, Arc<Vec<u8>>),
+    /// An image with multiple frames (animated GIF, APNG or WebP): its first frame, along with
+    /// its encoded bytes (which later frames are decoded from as it plays)
+    AnimatedImage(ImageType, u32, u32, crate::node::ImageFrame, Bytes),
     #[cfg(feature = "svg")]
     Svg(ImageType, crate::node::SvgImageData),
     Css(DocumentStyleSheet),
@@ -121,6 +124,14 @@ impl<T: Send + Sync + 'static> ResourceHandler<T> {
         let _ = self.tx.send(DocumentEvent::ResourceLoad(response));
         self.shell_provider.request_redraw();
     }
+
+    /// Report a failed request. Aborted requests are not reported as the resource is no
+    /// longer wanted (e.g. the document has navigated away).
+    fn respond_failed(&self, url: String, error: NetError) {
+        if !matches!(error, NetError::Aborted) {
+            self.respond(url, Err(error.to_string()));
+        }
+    }
 }
 
 #[allow(unused)]
@@ -138,7 +149,34 @@ pub struct StylesheetHandler {
     pub abort_signal: Option<AbortSignal>,
 }
 
+/// Reject stylesheets served with an error status or, as browsers do in standards mode,
+/// with a `Content-Type` other than `text/css`.
+///
+/// <https://html.spec.whatwg.org/multipage/links.html#link-type-stylesheet>
+fn check_stylesheet_response(response: &Response) -> Result<(), String> {
+    if !response.is_success() {
+        return Err(NetError::HttpStatus(response.status).to_string());
+    }
+    match response.mime_type() {
+        Some(mime_type) if mime_type != "text/css" => Err(format!(
+            "Stylesheet has MIME type {mime_type} (expected text/css)"
+        )),
+        _ => Ok(()),
+    }
+}
+
 impl NetHandler for ResourceHandler<StylesheetHandler> {
+    fn response(self: Box<Self>, response: Response, bytes: Bytes) {
+        match check_stylesheet_response(&response) {
+            Ok(()) => self.bytes(response.url, bytes),
+            Err(err) => self.respond(response.url, Err(err)),
+        }
+    }
+
+    fn failed(self: Box<Self>, url: String, error: NetError) {
+        self.respond_failed(url, error)
+    }
+
     fn bytes(self: Box<Self>, resolved_url: String, bytes: Bytes) {
         let Ok(css) = std::str::from_utf8(&bytes) else {
             return self.respond(resolved_url, Err(String::from("Invalid UTF8")));
@@ -243,6 +281,17 @@ struct NestedStylesheetHandler {
 }
 
 impl NetHandler for ResourceHandler<NestedStylesheetHandler> {
+    fn response(self: Box<Self>, response: Response, bytes: Bytes) {
+        match check_stylesheet_response(&response) {
+            Ok(()) => self.bytes(response.url, bytes),
+            Err(err) => self.respond(response.url, Err(err)),
+        }
+    }
+
+    fn failed(self: Box<Self>, url: String, error: NetError) {
+        self.respond_failed(url, error)
+    }
+
     fn bytes(self: Box<Self>, resolved_url: String, bytes: Bytes) {
         let Ok(css) = std::str::from_utf8(&bytes) else {
             return self.respond(resolved_url, Err(String::from("Invalid UTF8")));
@@ -292,6 +341,14 @@ impl NetHandler for ResourceHandler<FontFaceHandler> {
         let result = self.data.parse(bytes);
         self.respond(resolved_url, result)
     }
+
+    fn failed(self: Box<Self>, url: String, error: NetError) {
+        // The node is the one whose stylesheet declared the `@font-face` rule, and it didn't
+        // fail to load itself (so no `error` event is fired at it)
+        let mut handler = *self;
+        handler.node_id = None;
+        handler.respond_failed(url, error)
+    }
 }
 impl FontFaceHandler {
     fn parse(&mut self, bytes: Bytes) -> Result<Resource, String> {
@@ -508,6 +565,15 @@ fn stylo_to_fontique_style(style: &FontStyleRange) -> parley::fontique::FontStyl
 pub(crate) struct DocumentSrcHandler;
 
 impl NetHandler for ResourceHandler<DocumentSrcHandler> {
+    fn response(self: Box<Self>, response: Response, bytes: Bytes) {
+        // Like browsers, display the body of error responses (e.g. a server's 404 page)
+        self.bytes(response.url, bytes)
+    }
+
+    fn failed(self: Box<Self>, url: String, error: NetError) {
+        self.respond_failed(url, error)
+    }
+
     fn bytes(self: Box<Self>, resolved_url: String, bytes: Bytes) {
         let html = String::from_utf8_lossy(&bytes).into_owned();
         self.respond(resolved_url, Ok(Resource::DocumentSrc(html)));
@@ -516,22 +582,106 @@ impl NetHandler for ResourceHandler<DocumentSrcHandler> {
 
 pub struct ImageHandler {
     kind: ImageType,
+    /// The requested url. Responses are reported against this url (rather than the
+    /// final url after any redirects) as that is what nodes waiting on the image and the
+    /// image cache are keyed by.
+    url: String,
+    /// Where the image is decoded
+    decoding: ImageDecoding,
 }
 impl ImageHandler {
-    pub fn new(kind: ImageType) -> Self {
-        Self { kind }
+    pub fn new(kind: ImageType, url: String, decoding: ImageDecoding) -> Self {
+        Self {
+            kind,
+            url,
+            decoding,
+        }
+    }
+}
+
+impl ResourceHandler<ImageHandler> {
+    /// Decode the image and send it to the document. `<img>` raster images are not decoded
+    /// here: their encoded bytes are sent first, and the document decodes them once it knows
+    /// the size they are displayed at.
+    fn decode_and_respond(&self, bytes: Bytes) {
+        let result = self.data.parse(bytes.clone());
+        if let Ok(Resource::Image(ImageType::Image, width, height, data)) = &result
+            && data.is_empty()
+        {
+            let _ = self.tx.send(DocumentEvent::EncodedImage {
+                url: self.data.url.clone(),
+                width: *width,
+                height: *height,
+                bytes,
+            });
+        }
+        self.respond(self.data.url.clone(), result)
     }
 }
 
 impl NetHandler for ResourceHandler<ImageHandler> {
-    fn bytes(self: Box<Self>, resolved_url: String, bytes: Bytes) {
-        let result = self.data.parse(bytes);
-        self.respond(resolved_url, result)
+    fn bytes(self: Box<Self>, _resolved_url: String, bytes: Bytes) {
+        match self.data.decoding {
+            ImageDecoding::Inline => self.decode_and_respond(bytes),
+            ImageDecoding::WorkerPool => rayon::spawn(move || self.decode_and_respond(bytes)),
+        }
+    }
+
+    fn failed(self: Box<Self>, _url: String, error: NetError) {
+        self.respond_failed(self.data.url.clone(), error)
     }
 }
 
 impl ImageHandler {
+    /// Decode the first frame of an animated GIF, APNG or WebP image. The remaining frames
+    /// are decoded as the image plays. Returns `None` for other formats and for images with
+    /// a single frame.
+    #[cfg(feature = "animated-images")]
+    fn parse_animated(&self, bytes: &Bytes) -> Option<Resource> {
+        use crate::image_animation::{decode_frames, image_frame};
+
+        let mut frames = decode_frames(bytes)?;
+        let first_frame = frames.next()?.ok()?;
+        if frames.next().is_none() {
+            return None;
+        }
+
+        let (width, height) = first_frame.buffer().dimensions();
+        Some(Resource::AnimatedImage(
+            self.kind,
+            width,
+            height,
+            image_frame(first_frame),
+            bytes.clone(),
+        ))
+    }
+
     fn parse(&self, bytes: Bytes) -> Result<Resource, String> {
+        #[cfg(feature = "animated-images")]
+        if let Some(resource) = self.parse_animated(&bytes) {
+            return Ok(resource);
+        }
+
+        // Only read the size of `<img>` images, which are decoded at the size they are
+        // displayed at (an image without pixels is sent, see `BaseDocument::load_resource`)
+        if matches!(self.kind, ImageType::Image) {
+            let dimensions = image::ImageReader::new(Cursor::new(&bytes))
+                .with_guessed_format()
+                .expect("IO errors impossible with Cursor")
+                .into_dimensions();
+            if let Ok((width, height)) = dimensions
+                && width > 0
+                && height > 0
+            {
+                return Ok(Resource::Image(
+                    self.kind,
+                    width,
+                    height,
+                    Arc::new(Vec::new()),
+                ));
+            }
+        }
+
         let image_err = match image::ImageReader::new(Cursor::new(&bytes))
             .with_guessed_format()
             .expect("IO errors impossible with Cursor")
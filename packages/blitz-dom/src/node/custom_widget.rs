@@ -1,10 +1,10 @@
 use std::any::Any;
 
+use accesskit::{ActionRequest, Node as AccessKitNode, NodeId as AccessKitNodeId};
 use anyrender::ResourceId;
 use blitz_traits::events::UiEvent;
 pub use style::properties::ComputedValues as ComputedStyles;
-// use accesskit::Node as AccessKitNode;
-// use taffy::{LayoutInput, LayoutOutput};
+pub use taffy::{LayoutInput, LayoutOutput};
 
 pub use anyrender::{RenderContext, Scene};
 
@@ -106,9 +106,15 @@ pub trait Widget {
     }
 
     /// Handle input events (mouse, keyboard, etc)
+    ///
+    /// Keyboard events are received while the widget's node is focussed.
     fn handle_event(&mut self, event: &UiEvent) {
         let _ = event;
     }
+    /// The widget's node gained focus
+    fn focus(&mut self) {}
+    /// The widget's node lost focus
+    fn blur(&mut self) {}
 
     /// The widget's intrinsic dimensions: an intrinsic width, height and
     /// aspect ratio, each of which may independently be absent.
@@ -139,11 +145,50 @@ pub trait Widget {
         Scene::new()
     }
 
-    // TODO: allow for multiple nodes per widget
-    // fn accessibility_tree(&mut self) -> AccessKitNode;
+    /// Lay out the widget's node within the constraints in `inputs`.
+    ///
+    /// Returning `None` (the default) lays the node out as a replaced element sized by
+    /// [`intrinsic_sizes`](Self::intrinsic_sizes). The node's children are not laid out when
+    /// the widget lays itself out. The output is cached by Taffy until the node is marked dirty.
+    fn layout(&mut self, inputs: &LayoutInput, styles: &ComputedStyles) -> Option<LayoutOutput> {
+        let _ = (inputs, styles);
+        None
+    }
+
+    /// The widget's accessibility subtree, which replaces the node's children in the
+    /// accessibility tree. Returning `None` (the default) exposes the node as a normal element.
+    ///
+    /// This is queried when the widget's [revision](Self::accessibility_revision) changes (and
+    /// when its node changes or moves), but only the nodes that have changed are sent to
+    /// assistive technology.
+    fn accessibility_tree(&self) -> Option<WidgetAccessibilityTree> {
+        None
+    }
+
+    /// A number that the widget changes whenever its [accessibility
+    /// subtree](Self::accessibility_tree) changes, which tells the document to query it again.
+    fn accessibility_revision(&self) -> u64 {
+        0
+    }
+
+    /// Perform an action requested by assistive technology on one of the nodes of the
+    /// widget's [accessibility subtree](Self::accessibility_tree). `request.target_node` is
+    /// the widget's own id for the node.
+    fn accessibility_action(&mut self, request: &ActionRequest) {
+        let _ = request;
+    }
+}
 
-    // TODO: simpler layout mode?
-    // fn layout(&mut self, inputs: LayoutInput, styles: &ComputedStyles) -> LayoutOutput;
+/// The accessibility subtree of a custom widget
+///
+/// Bounds are relative to the top-left corner of the widget's content box. The ids of nodes
+/// are chosen by the widget, and are mapped to ids that are unique within the document's tree.
+pub struct WidgetAccessibilityTree {
+    /// The id of the root node, which takes the place of the widget's element (and defaults
+    /// to the element's bounds)
+    pub root: AccessKitNodeId,
+    /// The nodes of the subtree (including the root), in any order
+    pub nodes: Vec<(AccessKitNodeId, AccessKitNode)>,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -1,3 +1,4 @@
+use blitz_traits::net::Bytes;
 use blitz_traits::node_id::NodeId;
 use cssparser::ParserInput;
 use kurbo::{Affine, Rect as KurboRect};
@@ -26,12 +27,14 @@ use taffy::{
     prelude::{Layout, Style},
 };
 use url::Url;
+use web_time::Instant;
 
 use super::stylo_data::StyloData;
 #[cfg(feature = "svg")]
 use super::svg::SvgImageData;
 use super::{Attribute, Attributes};
 use crate::Document;
+use crate::form_state::RadioGroupState;
 use crate::layout::table::TableContext;
 use crate::node::{TextBrush, TextInputData, TextLayout};
 
@@ -57,6 +60,24 @@ pub struct ElementData {
     /// Whether the element is focussable
     pub is_focussable: bool,
 
+    /// Whether the user has interacted with this form control (or tried to submit its form),
+    /// after which its validity is reflected by `:user-valid`/`:user-invalid`
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#user-validity>
+    pub user_validity: bool,
+
+    /// The indeterminate flag of a checkbox, which is set by script and cleared when the user
+    /// toggles the checkbox. It only affects the checkbox's appearance (`:indeterminate`).
+    pub indeterminate: bool,
+
+    /// The state of the group that a radio button is in, which the document keeps up to date
+    /// as radio buttons are checked, added and removed
+    pub radio_group: RadioGroupState,
+
+    /// Whether a form or fieldset contains any invalid form controls (and so matches
+    /// `:invalid`), which the document keeps up to date as the controls' validity changes
+    pub(crate) has_invalid_control: bool,
+
     /// The element's parsed style attribute (used by stylo)
     pub style_attribute: Option<ServoArc<Locked<PropertyDeclarationBlock>>>,
 
@@ -71,6 +92,13 @@ pub struct ElementData {
 
     pub mask_images: Vec<Option<ImageResourceData>>,
 
+    /// The loading status of the element's image (\<img\> elements only)
+    pub image_status: Option<Status>,
+
+    /// The image selected from the element's `src`/`srcset` attributes or its parent
+    /// \<picture\> element's \<source\> elements (\<img\> elements only)
+    pub image_source: Option<ImageSource>,
+
     /// Parley text layout (elements with inline inner display mode only)
     pub inline_layout_data: Option<Box<TextLayout>>,
 
@@ -104,6 +132,10 @@ pub struct ElementData {
     pub before: Option<NodeId>,
     pub after: Option<NodeId>,
 
+    /// Counter values and pseudo-element content resolved from the `counter-*` and `content`
+    /// properties (which depend on the element's position in the tree)
+    pub generated_content: Option<Box<GeneratedContent>>,
+
     /// Detailed grid track sizing information from the most recent layout
     /// (grid containers only). Used by devtools grid inspection.
     pub detailed_grid_info: Option<Box<taffy::DetailedGridInfo<Atom>>>,
@@ -115,6 +147,9 @@ pub struct ElementData {
     pub unrounded_layout: Layout,
     pub final_layout: Layout,
     pub scroll_offset: crate::Point<f64>,
+    /// Offset from the laid out location of a `position: sticky` or `position: fixed` box
+    /// to where it is painted and hit-tested, which depends on scroll offsets.
+    pub position_offset: crate::Point<f64>,
     pub scrollable_overflow: KurboRect,
     pub transform: Option<Affine>,
 }
@@ -143,6 +178,9 @@ pub struct DocumentData {
     pub unrounded_layout: Layout,
     pub final_layout: Layout,
     pub scroll_offset: crate::Point<f64>,
+    /// Offset from the laid out location of a `position: sticky` or `position: fixed` box
+    /// to where it is painted and hit-tested, which depends on scroll offsets.
+    pub position_offset: crate::Point<f64>,
     pub scrollable_overflow: KurboRect,
     pub transform: Option<Affine>,
 }
@@ -164,6 +202,7 @@ impl std::fmt::Debug for DocumentData {
             .field("unrounded_layout", &self.unrounded_layout)
             .field("final_layout", &self.final_layout)
             .field("scroll_offset", &self.scroll_offset)
+            .field("position_offset", &self.position_offset)
             .field("scrollable_overflow", &self.scrollable_overflow)
             .field("transform", &self.transform)
             .finish_non_exhaustive()
@@ -186,6 +225,7 @@ impl DocumentData {
             unrounded_layout: Layout::new(),
             final_layout: Layout::new(),
             scroll_offset: crate::Point::ZERO,
+            position_offset: crate::Point::ZERO,
             scrollable_overflow: KurboRect::ZERO,
             transform: None,
         }
@@ -216,12 +256,19 @@ impl std::fmt::Debug for ElementData {
             .field("id", &self.id)
             .field("attrs", &self.attrs)
             .field("is_focussable", &self.is_focussable)
+            .field("user_validity", &self.user_validity)
+            .field("indeterminate", &self.indeterminate)
+            .field("radio_group", &self.radio_group)
+            .field("has_invalid_control", &self.has_invalid_control)
             .field("style_attribute", &self.style_attribute)
             .field("special_data", &self.special_data)
             .field("background_images", &self.background_images)
             .field("mask_images", &self.mask_images)
+            .field("image_status", &self.image_status)
+            .field("image_source", &self.image_source)
             .field("inline_layout_data", &self.inline_layout_data)
             .field("list_item_data", &self.list_item_data)
+            .field("generated_content", &self.generated_content)
             .field("template_contents", &self.template_contents)
             .field("element_state", &self.element_state)
             .field("display_constructed_as", &self.display_constructed_as)
@@ -241,10 +288,16 @@ impl Clone for ElementData {
             id: self.id.clone(),
             attrs: self.attrs.clone(),
             is_focussable: self.is_focussable,
+            user_validity: false,
+            indeterminate: false,
+            radio_group: RadioGroupState::default(),
+            has_invalid_control: false,
             style_attribute: self.style_attribute.clone(),
             special_data: self.special_data.clone(),
             background_images: self.background_images.clone(),
             mask_images: self.mask_images.clone(),
+            image_status: self.image_status,
+            image_source: self.image_source.clone(),
             inline_layout_data: self.inline_layout_data.clone(),
             list_item_data: self.list_item_data.clone(),
             template_contents: self.template_contents,
@@ -259,6 +312,7 @@ impl Clone for ElementData {
             dirty_descendants: AtomicBool::new(true),
             before: None,
             after: None,
+            generated_content: None,
             detailed_grid_info: None,
             style: Default::default(),
             display_constructed_as: StyloDisplay::Block,
@@ -266,6 +320,7 @@ impl Clone for ElementData {
             unrounded_layout: Layout::new(),
             final_layout: Layout::new(),
             scroll_offset: crate::Point::ZERO,
+            position_offset: crate::Point::ZERO,
             scrollable_overflow: KurboRect::ZERO,
             transform: None,
         }
@@ -281,6 +336,7 @@ pub enum SpecialElementType {
     TableRoot,
     TextInput,
     CheckboxInput,
+    Select,
     #[cfg(feature = "file-input")]
     FileInput,
     #[default]
@@ -307,6 +363,8 @@ pub enum SpecialElementData {
     TextInput(TextInputData),
     /// Checkbox checked state
     CheckboxInput(bool),
+    /// A \<select\> element's selection state and option labels
+    Select(Box<SelectData>),
     /// Selected files
     #[cfg(feature = "file-input")]
     FileInput(FileData),
@@ -327,6 +385,7 @@ impl Clone for SpecialElementData {
             Self::TableRoot(data) => Self::TableRoot(data.clone()),
             Self::TextInput(data) => Self::TextInput(data.clone()),
             Self::CheckboxInput(data) => Self::CheckboxInput(*data),
+            Self::Select(data) => Self::Select(data.clone()),
             #[cfg(feature = "file-input")]
             Self::FileInput(data) => Self::FileInput(data.clone()),
             Self::None => Self::None,
@@ -353,6 +412,10 @@ impl ElementData {
             id: id_attr_atom,
             attrs: Attributes::new(attrs),
             is_focussable: false,
+            user_validity: false,
+            indeterminate: false,
+            radio_group: RadioGroupState::default(),
+            has_invalid_control: false,
             style_attribute: Default::default(),
             inline_layout_data: None,
             list_item_data: None,
@@ -360,6 +423,8 @@ impl ElementData {
             template_contents: None,
             background_images: Vec::new(),
             mask_images: Vec::new(),
+            image_status: None,
+            image_source: None,
 
             stylo_element_data: Default::default(),
             selector_flags: Cell::new(ElementSelectorFlags::empty()),
@@ -370,6 +435,7 @@ impl ElementData {
             dirty_descendants: AtomicBool::new(true),
             before: None,
             after: None,
+            generated_content: None,
             detailed_grid_info: None,
             style: Default::default(),
             display_constructed_as: StyloDisplay::Block,
@@ -377,6 +443,7 @@ impl ElementData {
             unrounded_layout: Layout::new(),
             final_layout: Layout::new(),
             scroll_offset: crate::Point::ZERO,
+            position_offset: crate::Point::ZERO,
             scrollable_overflow: KurboRect::ZERO,
             transform: None,
         };
@@ -518,6 +585,20 @@ impl ElementData {
         }
     }
 
+    pub fn select_data(&self) -> Option<&SelectData> {
+        match &self.special_data {
+            SpecialElementData::Select(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn select_data_mut(&mut self) -> Option<&mut SelectData> {
+        match &mut self.special_data {
+            SpecialElementData::Select(data) => Some(data),
+            _ => None,
+        }
+    }
+
     #[cfg(feature = "file-input")]
     pub fn file_data(&self) -> Option<&FileData> {
         match &self.special_data {
@@ -704,25 +785,173 @@ impl ElementData {
             && self.attr(LocalName::from("commandfor")).is_none();
         is_submit || is_auto_submit
     }
+
+    pub fn is_radio_button(&self) -> bool {
+        self.name.local == local_name!("input") && self.attr(local_name!("type")) == Some("radio")
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct RasterImageData {
-    /// The width of the image
+    /// The width of the image data
     pub width: u32,
-    /// The height of the image
+    /// The height of the image data
     pub height: u32,
-    /// The raw image data in RGBA8 format
+    /// The natural width of the image. Larger than `width` if the image was decoded at a
+    /// reduced size (or its pixels have been evicted).
+    pub natural_width: u32,
+    /// The natural height of the image. Larger than `height` if the image was decoded at a
+    /// reduced size (or its pixels have been evicted).
+    pub natural_height: u32,
+    /// The raw image data in RGBA8 format (the current frame for animated images)
     pub data: Blob<u8>,
+    /// The frames and playback state of animated images (GIF, APNG and WebP)
+    pub animation: Option<Box<ImageAnimation>>,
 }
 impl RasterImageData {
     pub fn new(width: u32, height: u32, data: Arc<Vec<u8>>) -> Self {
         Self {
             width,
             height,
+            natural_width: width,
+            natural_height: height,
+            data: Blob::new(data),
+            animation: None,
+        }
+    }
+
+    /// Create an image whose pixels were decoded at a reduced size
+    pub fn scaled(
+        natural_width: u32,
+        natural_height: u32,
+        width: u32,
+        height: u32,
+        data: Arc<Vec<u8>>,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            natural_width,
+            natural_height,
             data: Blob::new(data),
+            animation: None,
+        }
+    }
+
+    /// Create an image whose pixels are not currently decoded
+    pub fn evicted(natural_width: u32, natural_height: u32) -> Self {
+        Self::scaled(natural_width, natural_height, 0, 0, Arc::new(Vec::new()))
+    }
+
+    /// Whether the image has no pixels to draw (because they have been evicted)
+    pub fn is_evicted(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Create an animated image which starts at its first frame. Later frames are decoded
+    /// from `source` (the encoded image) as playback reaches them.
+    pub fn animated(width: u32, height: u32, first_frame: ImageFrame, source: Bytes) -> Self {
+        Self {
+            width,
+            height,
+            natural_width: width,
+            natural_height: height,
+            data: first_frame.data,
+            animation: Some(Box::new(ImageAnimation {
+                source,
+                frames: None,
+                current_frame: 0,
+                frame_duration: first_frame.duration,
+                frame_elapsed: 0.0,
+                last_tick: None,
+            })),
+        }
+    }
+
+    /// Advance an animated image's playback to `now` (in seconds on the document's animation
+    /// clock). Returns `true` if the displayed frame changed.
+    pub fn advance_animation(&mut self, now: f64) -> bool {
+        let Some(animation) = self.animation.as_deref_mut() else {
+            return false;
+        };
+        match animation.advance(now) {
+            Some(frame) => {
+                self.data = frame.data;
+                true
+            }
+            None => false,
         }
     }
+
+    /// Pause an animated image's playback. It resumes from the same point in the current
+    /// frame the next time it is advanced.
+    pub fn pause_animation(&mut self) {
+        if let Some(animation) = self.animation.as_deref_mut() {
+            animation.last_tick = None;
+        }
+    }
+}
+
+/// A single frame of an animated image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageFrame {
+    /// The raw frame data in RGBA8 format (composited onto the full image canvas)
+    pub data: Blob<u8>,
+    /// How long the frame is displayed for (in seconds)
+    pub duration: f64,
+}
+
+/// The playback state of an animated image. Frames are decoded one at a time as playback
+/// reaches them, so only the displayed frame is held in memory.
+pub struct ImageAnimation {
+    /// The encoded image. Shared between all copies of the image.
+    pub source: Bytes,
+    /// Decodes the frames after the current one. Created when playback first advances.
+    pub(crate) frames: Option<image::Frames<'static>>,
+    /// The index of the frame which is currently displayed
+    pub current_frame: usize,
+    /// How long the current frame is displayed for (in seconds)
+    pub frame_duration: f64,
+    /// How long the current frame has been displayed for (in seconds)
+    pub frame_elapsed: f64,
+    /// The time of the last advance while playing (`None` when paused)
+    pub last_tick: Option<f64>,
+}
+
+impl Clone for ImageAnimation {
+    fn clone(&self) -> Self {
+        // A decoder can't be cloned, so the copy creates its own when it next advances
+        Self {
+            source: self.source.clone(),
+            frames: None,
+            current_frame: self.current_frame,
+            frame_duration: self.frame_duration,
+            frame_elapsed: self.frame_elapsed,
+            last_tick: self.last_tick,
+        }
+    }
+}
+
+impl PartialEq for ImageAnimation {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+            && self.current_frame == other.current_frame
+            && self.frame_duration == other.frame_duration
+            && self.frame_elapsed == other.frame_elapsed
+            && self.last_tick == other.last_tick
+    }
+}
+
+impl std::fmt::Debug for ImageAnimation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageAnimation")
+            .field("source_len", &self.source.len())
+            .field("current_frame", &self.current_frame)
+            .field("frame_duration", &self.frame_duration)
+            .field("frame_elapsed", &self.frame_elapsed)
+            .field("last_tick", &self.last_tick)
+            .finish_non_exhaustive()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -733,13 +962,23 @@ pub enum ImageData {
     None,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Status {
     Ok,
     Error,
     Loading,
 }
 
+/// An image candidate selected for an \<img\> element
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageSource {
+    /// The resolved url of the image
+    pub url: String,
+    /// The pixel density of the image (image pixels per CSS pixel), which scales its
+    /// natural size
+    pub density: f32,
+}
+
 #[derive(Debug, Clone)]
 pub struct ImageResourceData {
     /// The url of the background image
@@ -782,6 +1021,7 @@ impl std::fmt::Debug for SpecialElementData {
             SpecialElementData::TableRoot(_) => f.write_str("NodeSpecificData::TableRoot"),
             SpecialElementData::TextInput(_) => f.write_str("NodeSpecificData::TextInput"),
             SpecialElementData::CheckboxInput(_) => f.write_str("NodeSpecificData::CheckboxInput"),
+            SpecialElementData::Select(_) => f.write_str("NodeSpecificData::Select"),
             #[cfg(feature = "file-input")]
             SpecialElementData::FileInput(_) => f.write_str("NodeSpecificData::FileInput"),
             SpecialElementData::None => f.write_str("NodeSpecificData::None"),
@@ -789,6 +1029,27 @@ impl std::fmt::Debug for SpecialElementData {
     }
 }
 
+/// Content generated for an element from counters and the `content` property. See
+/// [`ElementData::generated_content`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeneratedContent {
+    /// The value of the `list-item` counter (`display: list-item` elements only)
+    pub list_item_value: Option<i32>,
+    /// The content generated by the `::before` pseudo-element's `content`
+    pub before: Option<Vec<GeneratedContentItem>>,
+    /// The content generated by the `::after` pseudo-element's `content`
+    pub after: Option<Vec<GeneratedContentItem>>,
+}
+
+/// An item of a pseudo-element's generated content
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeneratedContentItem {
+    /// A run of text (from strings, counters, quotes and `attr()`)
+    Text(String),
+    /// An image (from `url()`), which is displayed as an anonymous replaced element
+    Image(String),
+}
+
 #[derive(Clone)]
 pub struct ListItemLayout {
     pub marker: Marker,
@@ -816,6 +1077,47 @@ impl std::fmt::Debug for ListItemLayout {
     }
 }
 
+/// A \<select\> element's selection state, along with the text layouts used to paint it.
+///
+/// The select's list of options is not stored here. It is derived from the DOM on demand
+/// (see [`Node::select_options`](crate::Node::select_options)).
+#[derive(Clone, Default)]
+pub struct SelectData {
+    /// The options selected by the user. This is `None` until the user first changes the
+    /// selection, and until then the options' `selected` attributes determine selectedness.
+    pub user_selection: Option<Vec<NodeId>>,
+    /// The option highlighted by keyboard or pointer navigation within an open drop-down
+    /// listbox (or within an inline listbox)
+    pub highlighted: Option<NodeId>,
+    /// Text layouts for each option's label, in the order of the select's list of options
+    pub option_labels: Vec<SelectOptionLabel>,
+    /// The width of the widest option label in CSS pixels
+    pub label_width: f32,
+    /// The height of a single option row in CSS pixels
+    pub row_height: f32,
+    /// The characters typed so far in the current typeahead search
+    pub(crate) typeahead: String,
+    /// When the last character of the current typeahead search was typed
+    pub(crate) typeahead_time: Option<Instant>,
+}
+
+impl SelectData {
+    /// The label layout for a given option (if it has been laid out)
+    pub fn option_label(&self, option_id: NodeId) -> Option<&parley::Layout<TextBrush>> {
+        self.option_labels
+            .iter()
+            .find(|label| label.option_id == option_id)
+            .map(|label| &*label.layout)
+    }
+}
+
+/// The laid out label of a single \<option\> within a \<select\>
+#[derive(Clone)]
+pub struct SelectOptionLabel {
+    pub option_id: NodeId,
+    pub layout: Box<parley::Layout<TextBrush>>,
+}
+
 #[cfg(feature = "file-input")]
 mod file_data {
     use std::ops::{Deref, DerefMut};
@@ -10,19 +10,25 @@ mod stylo_data;
 #[cfg(feature = "svg")]
 mod svg;
 mod text;
+mod undo;
 
 pub use attributes::{Attribute, Attributes};
 #[cfg(feature = "custom-widget")]
 pub use custom_widget::{
-    ComputedStyles, CustomWidgetData, CustomWidgetStatus, ProxyRenderContext, Widget,
+    ComputedStyles, CustomWidgetData, CustomWidgetStatus, LayoutInput, LayoutOutput,
+    ProxyRenderContext, Widget, WidgetAccessibilityTree,
 };
 pub use element::{
-    CanvasData, DocumentData, ElementData, ImageData, ImageResourceData, ListItemLayout,
-    ListItemLayoutPosition, Marker, RasterImageData, SpecialElementData, SpecialElementType,
+    CanvasData, DocumentData, ElementData, GeneratedContent, GeneratedContentItem, ImageAnimation,
+    ImageData, ImageFrame, ImageResourceData, ImageSource, ListItemLayout, ListItemLayoutPosition,
+    Marker, RasterImageData, SelectData, SelectOptionLabel, SpecialElementData, SpecialElementType,
     Status,
 };
 pub use node::*;
 pub use scrollbar::{ScrollbarColor, ScrollbarRef, ScrollbarWidth};
 #[cfg(feature = "svg")]
 pub use svg::{SvgImageData, SvgIntrinsicDimensions};
-pub use text::{GeneratedTextInputEvent, TextBrush, TextInputData, TextLayout};
+pub use text::{
+    GeneratedTextInputEvent, TextBrush, TextInputData, TextInputPlaceholder, TextLayout,
+    TextTruncation, TruncatedLine,
+};
@@ -164,6 +164,7 @@ universal_accessors! {
     unrounded_layout / unrounded_layout_mut: Layout,
     final_layout / final_layout_mut: Layout,
     scroll_offset / scroll_offset_mut: crate::Point<f64>,
+    position_offset / position_offset_mut: crate::Point<f64>,
     scrollable_overflow / scrollable_overflow_mut: KurboRect,
     transform / transform_mut: Option<Affine>,
     display_constructed_as / display_constructed_as_mut: StyloDisplay,
@@ -514,12 +515,24 @@ impl Node {
             .is_some_and(|data| data.element_state.contains(ElementState::HOVER))
     }
 
+    /// Focus the node, showing a focus ring (`:focus-visible`)
     pub fn focus(&mut self, shell_provider: Arc<dyn ShellProvider>) {
+        self.focus_with_visibility(true, shell_provider);
+    }
+
+    /// Focus the node, showing a focus ring (`:focus-visible`) only if `focus_visible` is true
+    pub fn focus_with_visibility(
+        &mut self,
+        focus_visible: bool,
+        shell_provider: Arc<dyn ShellProvider>,
+    ) {
         if let Some(data) = self.element_data_mut() {
+            data.element_state.insert(ElementState::FOCUS);
             data.element_state
-                .insert(ElementState::FOCUS | ElementState::FOCUSRING);
+                .set(ElementState::FOCUSRING, focus_visible);
         }
-        self.set_restyle_hint(RestyleHint::restyle_subtree());
+        // Selectors that depend on the state are invalidated from the node's snapshot
+        self.set_restyle_hint(RestyleHint::RESTYLE_SELF);
 
         // If focussing a text input, enable IME and set IME area
         if self
@@ -541,6 +554,14 @@ impl Node {
         if let Some(data) = self.element_data_mut() {
             data.element_state
                 .remove(ElementState::FOCUS | ElementState::FOCUSRING);
+
+            // Once the user has edited a text input and moved on, its validity is shown
+            if data
+                .text_input_data()
+                .is_some_and(|input| input.user_edited)
+            {
+                data.user_validity = true;
+            }
         }
         self.set_restyle_hint(RestyleHint::restyle_subtree());
 
@@ -1022,6 +1043,10 @@ impl Node {
         Some(&attr.value)
     }
 
+    pub fn has_attr(&self, name: impl PartialEq<LocalName>) -> bool {
+        self.data.has_attr(name)
+    }
+
     pub fn primary_styles(&self) -> Option<impl Deref<Target = ServoArc<ComputedValues>>> {
         self.stylo_element_data_opt()
             .and_then(|stylo| stylo.primary_styles())
@@ -1146,8 +1171,9 @@ impl Node {
             .primary_styles()
             .is_some_and(|style| style.clone_pointer_events() == PointerEvents::None);
 
-        let mut x = x - self.final_layout().location.x + self.scroll_offset().x as f32;
-        let mut y = y - self.final_layout().location.y + self.scroll_offset().y as f32;
+        let location = self.painted_location();
+        let mut x = x - location.x + self.scroll_offset().x as f32;
+        let mut y = y - location.y + self.scroll_offset().y as f32;
 
         if let Some(t) = *self.transform() {
             let p = t.inverse() * kurbo::Point::new(x as f64 * scale, y as f64 * scale);
@@ -1343,10 +1369,23 @@ impl Node {
         Some(offset)
     }
 
+    /// The location of the `Node` relative to its layout parent where it is painted and
+    /// hit-tested: its laid out location shifted by its
+    /// [`position_offset`](Self::position_offset) (for sticky and fixed boxes).
+    pub fn painted_location(&self) -> taffy::Point<f32> {
+        let location = self.final_layout().location;
+        let offset = self.position_offset();
+        taffy::Point {
+            x: location.x + offset.x as f32,
+            y: location.y + offset.y as f32,
+        }
+    }
+
     /// Computes the Document-relative coordinates of the `Node`
     pub fn absolute_position(&self, x: f32, y: f32) -> crate::util::Point<f32> {
-        let x = x + self.final_layout().location.x - self.scroll_offset().x as f32;
-        let y = y + self.final_layout().location.y - self.scroll_offset().y as f32;
+        let location = self.painted_location();
+        let x = x + location.x - self.scroll_offset().x as f32;
+        let y = y + location.y - self.scroll_offset().y as f32;
 
         // Recurse up the layout hierarchy
         self.layout_parent
@@ -1,11 +1,16 @@
 use blitz_traits::{
-    events::{BlitzImeEvent, BlitzKeyEvent},
+    events::{BlitzImeEvent, BlitzKeyEvent, InputType},
     node_id::NodeId,
     shell::ShellProvider,
 };
 use keyboard_types::{Key, Modifiers};
 use parley::{ContentWidths, FontContext, LayoutContext};
+use regex::Regex;
+use style::properties::ComputedValues;
+use style::servo_arc::Arc as ServoArc;
+use web_time::Instant;
 
+use super::undo::{EditorState, UndoHistory};
 use crate::util::ACTION_MOD;
 
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
@@ -26,6 +31,40 @@ pub struct TextLayout {
     pub text: String,
     pub content_widths: Option<ContentWidths>,
     pub layout: parley::layout::Layout<TextBrush>,
+    /// How `layout` is truncated for display (by `text-overflow` and `-webkit-line-clamp`)
+    pub truncation: TextTruncation,
+}
+
+/// How the lines of an inline layout are truncated when they are displayed.
+///
+/// The layout itself is never modified, so selection, hit-testing and text extraction all
+/// operate on the original text.
+#[derive(Clone, Default)]
+pub struct TextTruncation {
+    /// The number of lines which are displayed, if `-webkit-line-clamp` hides some of them
+    pub visible_lines: Option<usize>,
+    /// The displayed lines which end in an ellipsis
+    pub lines: Vec<TruncatedLine>,
+    /// The ellipsis string, laid out with the inline root's style
+    pub ellipsis: Option<Box<parley::layout::Layout<TextBrush>>>,
+}
+
+impl TextTruncation {
+    /// The truncation of the line at `line_index` (if it is truncated)
+    pub fn line(&self, line_index: usize) -> Option<&TruncatedLine> {
+        self.lines.iter().find(|line| line.line_index == line_index)
+    }
+}
+
+/// A line which is cut off and followed by an ellipsis. Positions are in the same (scaled)
+/// coordinates as the layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TruncatedLine {
+    pub line_index: usize,
+    /// The line's content is only drawn to the left of this position
+    pub clip_x: f32,
+    /// Where the ellipsis is drawn
+    pub ellipsis_x: f32,
 }
 
 impl TextLayout {
@@ -48,7 +87,11 @@ impl std::fmt::Debug for TextLayout {
 
 // TODO: support keypress events
 pub enum GeneratedTextInputEvent {
+    /// The input's value was edited
     Input,
+    /// An edit (and the text it inserts), which is only made if its `beforeinput` event isn't
+    /// cancelled
+    BeforeInput(InputType, Option<String>),
     Select,
     PreEditChange,
     Submit,
@@ -65,12 +108,35 @@ pub struct TextInputData {
     /// vertical offset. It is kept up to date so that the caret remains visible within the
     /// input's content box.
     pub scroll_offset: f32,
+    /// The input's undo/redo history
+    pub(crate) history: UndoHistory,
+    /// Whether the input's value was last changed by the user (rather than programmatically).
+    /// The `minlength` and `maxlength` constraints only apply to values edited by the user.
+    pub user_edited: bool,
+    /// The laid out `placeholder` attribute, which is shown while the input is empty
+    pub placeholder: Option<TextInputPlaceholder>,
+    /// The compiled `pattern` attribute (or `None` if it is missing or invalid), which the
+    /// input's value must match
+    pub(crate) pattern: Option<Regex>,
+}
+
+/// The laid out `placeholder` of a text input, along with the computed styles of its
+/// `::placeholder` pseudo-element (which determine its colour and opacity)
+#[derive(Clone)]
+pub struct TextInputPlaceholder {
+    pub layout: Box<parley::Layout<TextBrush>>,
+    pub styles: ServoArc<ComputedValues>,
+    /// The input's primary styles when the placeholder was laid out, so that it can be laid
+    /// out again when the input is restyled
+    pub(crate) input_styles: ServoArc<ComputedValues>,
 }
 
 // FIXME: Implement Clone for PlainEditor
 impl Clone for TextInputData {
     fn clone(&self) -> Self {
-        TextInputData::new(self.is_multiline)
+        let mut data = TextInputData::new(self.is_multiline);
+        data.pattern = self.pattern.clone();
+        data
     }
 }
 
@@ -81,9 +147,19 @@ impl TextInputData {
             editor,
             is_multiline,
             scroll_offset: 0.0,
+            history: UndoHistory::default(),
+            user_edited: false,
+            placeholder: None,
+            pattern: None,
         }
     }
 
+    /// Whether the input is empty (including of any IME composition), and so its placeholder
+    /// is shown (if it has one)
+    pub fn is_empty(&self) -> bool {
+        self.editor.raw_text().is_empty() && !self.editor.is_composing()
+    }
+
     pub fn set_text(
         &mut self,
         font_ctx: &mut FontContext,
@@ -93,7 +169,125 @@ impl TextInputData {
         if self.editor.text() != text {
             self.editor.set_text(text);
             self.editor.driver(font_ctx, layout_ctx).refresh_layout();
+            // Undoing past a programmatic change would clobber it
+            self.history.clear();
+            self.user_edited = false;
+        }
+    }
+
+    /// Whether there is an edit which can be undone
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    /// Whether there is an undone edit which can be redone
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// The input's current value and selection
+    pub(crate) fn editor_state(&self) -> EditorState {
+        let selection = self.editor.raw_selection();
+        EditorState {
+            text: self.editor.raw_text().to_string(),
+            anchor: selection.anchor().index(),
+            focus: selection.focus().index(),
+        }
+    }
+
+    /// Record an edit made since the input was in the `before` state in the undo history
+    pub(crate) fn record_edit(&mut self, before: EditorState, input_type: InputType) {
+        let after = self.editor_state();
+        self.history
+            .record(before, &after, input_type, Instant::now());
+    }
+
+    /// Undo the last edit. Returns false if there was nothing to undo.
+    pub(crate) fn undo(
+        &mut self,
+        font_ctx: &mut FontContext,
+        layout_ctx: &mut LayoutContext<TextBrush>,
+    ) -> bool {
+        let current = self.editor_state();
+        let Some(state) = self.history.undo(current) else {
+            return false;
+        };
+        self.restore_editor_state(font_ctx, layout_ctx, state);
+        true
+    }
+
+    /// Redo the last undone edit. Returns false if there was nothing to redo.
+    pub(crate) fn redo(
+        &mut self,
+        font_ctx: &mut FontContext,
+        layout_ctx: &mut LayoutContext<TextBrush>,
+    ) -> bool {
+        let current = self.editor_state();
+        let Some(state) = self.history.redo(current) else {
+            return false;
+        };
+        self.restore_editor_state(font_ctx, layout_ctx, state);
+        true
+    }
+
+    /// Make an edit announced by a `beforeinput` event, inserting `data` for the edits which
+    /// insert text. Returns false if the edit couldn't be made.
+    pub(crate) fn apply_edit(
+        &mut self,
+        font_ctx: &mut FontContext,
+        layout_ctx: &mut LayoutContext<TextBrush>,
+        input_type: InputType,
+        data: Option<&str>,
+    ) -> bool {
+        let mut driver = self.editor.driver(font_ctx, layout_ctx);
+        let is_collapsed = driver.editor.raw_selection().is_collapsed();
+        match input_type {
+            InputType::InsertText
+            | InputType::InsertFromPaste
+            | InputType::InsertCompositionText
+            | InputType::InsertReplacementText => {
+                let Some(data) = data else {
+                    return false;
+                };
+                driver.insert_or_replace_selection(data);
+            }
+            InputType::InsertLineBreak => driver.insert_or_replace_selection("\n"),
+            InputType::DeleteContentBackward => driver.backdelete(),
+            InputType::DeleteContentForward => driver.delete(),
+            InputType::DeleteWordBackward => driver.backdelete_word(),
+            InputType::DeleteWordForward => driver.delete_word(),
+            InputType::DeleteSoftLineBackward
+            | InputType::DeleteSoftLineForward
+            | InputType::DeleteHardLineBackward
+            | InputType::DeleteHardLineForward => {
+                // These delete the selection, or up to the line boundary if there isn't one
+                if is_collapsed {
+                    match input_type {
+                        InputType::DeleteSoftLineBackward => driver.select_to_line_start(),
+                        InputType::DeleteSoftLineForward => driver.select_to_line_end(),
+                        InputType::DeleteHardLineBackward => driver.select_to_hard_line_start(),
+                        _ => driver.select_to_hard_line_end(),
+                    }
+                }
+                driver.delete_selection();
+            }
+            InputType::DeleteByCut => driver.delete_selection(),
+            // Undo and redo restore the input's history (see `undo` and `redo`)
+            InputType::HistoryUndo | InputType::HistoryRedo => return false,
         }
+        true
+    }
+
+    fn restore_editor_state(
+        &mut self,
+        font_ctx: &mut FontContext,
+        layout_ctx: &mut LayoutContext<TextBrush>,
+        state: EditorState,
+    ) {
+        self.editor.set_text(&state.text);
+        self.editor
+            .driver(font_ctx, layout_ctx)
+            .select_byte_range(state.anchor, state.focus);
     }
 
     /// Recompute [`Self::scroll_offset`] so that the caret stays visible within the input's
@@ -196,7 +390,6 @@ impl TextInputData {
         &mut self,
         font_ctx: &mut FontContext,
         layout_ctx: &mut LayoutContext<TextBrush>,
-        shell_provider: &dyn ShellProvider,
         event: BlitzKeyEvent,
     ) -> Option<GeneratedTextInputEvent> {
         // Do nothing if it is a keyup event
@@ -212,27 +405,20 @@ impl TextInputData {
         let editor = &mut self.editor;
         let mut driver = editor.driver(font_ctx, layout_ctx);
         match event.key {
-            Key::Character(c) if action_mod && matches!(c.as_str(), "c" | "x" | "v") => {
-                match c.to_lowercase().as_str() {
-                    "c" => {
-                        if let Some(text) = driver.editor.selected_text() {
-                            let _ = shell_provider.set_clipboard_text(text.to_owned());
-                        }
-                    }
-                    "x" => {
-                        if let Some(text) = driver.editor.selected_text() {
-                            let _ = shell_provider.set_clipboard_text(text.to_owned());
-                            driver.delete_selection()
-                        }
-                    }
-                    "v" => {
-                        let text = shell_provider.get_clipboard_text().unwrap_or_default();
-                        driver.insert_or_replace_selection(&text)
-                    }
-                    _ => unreachable!(),
-                }
-
-                return Some(GeneratedTextInputEvent::Input);
+            Key::Character(c) if action_mod && c.to_lowercase() == "z" => {
+                let input_type = if shift {
+                    InputType::HistoryRedo
+                } else {
+                    InputType::HistoryUndo
+                };
+                return Some(GeneratedTextInputEvent::BeforeInput(input_type, None));
+            }
+            #[cfg(not(target_os = "macos"))]
+            Key::Character(c) if action_mod && c.to_lowercase() == "y" => {
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::HistoryRedo,
+                    None,
+                ));
             }
             Key::Character(c) if action_mod && matches!(c.to_lowercase().as_str(), "a") => {
                 if shift {
@@ -315,37 +501,41 @@ impl TextInputData {
                 return Some(GeneratedTextInputEvent::Select);
             }
             Key::Delete => {
-                if action_mod {
-                    driver.delete_word()
+                let input_type = if action_mod {
+                    InputType::DeleteWordForward
                 } else {
-                    driver.delete()
-                }
-                return Some(GeneratedTextInputEvent::Input);
+                    InputType::DeleteContentForward
+                };
+                return Some(GeneratedTextInputEvent::BeforeInput(input_type, None));
             }
 
             // On macOS this is handled by the apple standard keybindings
             #[cfg(not(target_os = "macos"))]
             Key::Backspace => {
-                if action_mod {
-                    driver.backdelete_word()
+                let input_type = if action_mod {
+                    InputType::DeleteWordBackward
                 } else {
-                    driver.backdelete()
-                }
-                return Some(GeneratedTextInputEvent::Input);
+                    InputType::DeleteContentBackward
+                };
+                return Some(GeneratedTextInputEvent::BeforeInput(input_type, None));
             }
 
             Key::Character(c) if c == "\n" => {
                 if is_multiline {
-                    driver.insert_or_replace_selection("\n");
-                    return Some(GeneratedTextInputEvent::Input);
+                    return Some(GeneratedTextInputEvent::BeforeInput(
+                        InputType::InsertLineBreak,
+                        None,
+                    ));
                 } else {
                     return Some(GeneratedTextInputEvent::Submit);
                 }
             }
             Key::Enter => {
                 if is_multiline {
-                    driver.insert_or_replace_selection("\n");
-                    return Some(GeneratedTextInputEvent::Input);
+                    return Some(GeneratedTextInputEvent::BeforeInput(
+                        InputType::InsertLineBreak,
+                        None,
+                    ));
                 } else {
                     return Some(GeneratedTextInputEvent::Submit);
                 }
@@ -353,8 +543,10 @@ impl TextInputData {
             Key::Character(s)
                 if !mods.contains(Modifiers::CONTROL) && !mods.contains(Modifiers::SUPER) =>
             {
-                driver.insert_or_replace_selection(&s);
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::InsertText,
+                    Some(s.to_string()),
+                ));
             }
             _ => {}
         };
@@ -382,36 +574,48 @@ impl TextInputData {
             "insertContainerBreak:" => {}
             // Inserts a double quotation mark without substituting a curly quotation mark.
             "insertDoubleQuoteIgnoringSubstitution:" => {
-                driver.insert_or_replace_selection("\"");
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::InsertText,
+                    Some("\"".to_string()),
+                ));
             }
             // Inserts a line break character.
             "insertLineBreak:" => {
-                driver.insert_or_replace_selection("\n");
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::InsertLineBreak,
+                    None,
+                ));
             }
             // Inserts a newline character.
             "insertNewline:" => {
                 if is_multiline {
-                    driver.insert_or_replace_selection("\n");
-                    return Some(GeneratedTextInputEvent::Input);
+                    return Some(GeneratedTextInputEvent::BeforeInput(
+                        InputType::InsertLineBreak,
+                        None,
+                    ));
                 } else {
                     return Some(GeneratedTextInputEvent::Submit);
                 }
             }
             // Inserts a newline character without invoking the field editor’s normal handling to end editing.
             "insertNewlineIgnoringFieldEditor:" => {
-                driver.insert_or_replace_selection("\n");
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::InsertLineBreak,
+                    None,
+                ));
             }
             // Inserts a paragraph separator.
             "insertParagraphSeparator:" => {
-                driver.insert_or_replace_selection("\n");
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::InsertLineBreak,
+                    None,
+                ));
             }
             "insertSingleQuoteIgnoringSubstitution:" => {
-                driver.insert_or_replace_selection("'");
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::InsertText,
+                    Some("'".to_string()),
+                ));
             }
             // Inserts a tab character.
             "insertTab:" | "insertTabIgnoringFieldEditor:" => {
@@ -425,61 +629,67 @@ impl TextInputData {
             // Deletes content moving backward from the current insertion point.
             // TODO: handle deleteBackwardByDecomposingPreviousCharacter separately
             "deleteBackward:" | "deleteBackwardByDecomposingPreviousCharacter:" => {
-                driver.backdelete();
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::DeleteContentBackward,
+                    None,
+                ));
             }
             "deleteForward:" => {
-                driver.delete();
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::DeleteContentForward,
+                    None,
+                ));
             }
             // Deletes content from the insertion point to the beginning of the current line.
             "deleteToBeginningOfLine:" => {
-                if driver.editor.raw_selection().is_collapsed() {
-                    driver.select_to_line_start();
-                }
-                driver.delete_selection();
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::DeleteSoftLineBackward,
+                    None,
+                ));
             }
             // Deletes content from the insertion point to the beginning of the current paragraph.
             "deleteToEndOfLine:" => {
-                if driver.editor.raw_selection().is_collapsed() {
-                    driver.select_to_line_end();
-                }
-                driver.delete_selection();
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::DeleteSoftLineForward,
+                    None,
+                ));
             }
             "deleteToBeginningOfParagraph:" => {
-                if driver.editor.raw_selection().is_collapsed() {
-                    driver.select_to_hard_line_start();
-                }
-                driver.delete_selection();
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::DeleteHardLineBackward,
+                    None,
+                ));
             }
 
             // Deletes content from the insertion point to the end of the current line.
             "deleteToEndOfParagraph:" => {
-                if driver.editor.raw_selection().is_collapsed() {
-                    driver.select_to_hard_line_end();
-                }
-                driver.delete_selection();
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::DeleteHardLineForward,
+                    None,
+                ));
             }
             // Deletes content from the insertion point to the end of the current paragraph.
             "deleteWordBackward:" => {
-                driver.backdelete_word();
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::DeleteWordBackward,
+                    None,
+                ));
             }
             // Deletes the word preceding the current insertion point.
             "deleteWordForward:" => {
-                driver.delete_word();
-                return Some(GeneratedTextInputEvent::Input);
+                return Some(GeneratedTextInputEvent::BeforeInput(
+                    InputType::DeleteWordForward,
+                    None,
+                ));
             }
             // Deletes the current selection, placing it in a temporary buffer, such as the Clipboard.
             "yank:" => {
                 if let Some(text) = driver.editor.selected_text() {
                     let _ = shell_provider.set_clipboard_text(text.to_owned());
-                    driver.delete_selection();
-                    return Some(GeneratedTextInputEvent::Input);
+                    return Some(GeneratedTextInputEvent::BeforeInput(
+                        InputType::DeleteByCut,
+                        None,
+                    ));
                 }
             }
 
@@ -759,6 +969,20 @@ impl TextInputData {
         layout_ctx: &mut LayoutContext<TextBrush>,
         event: BlitzImeEvent,
     ) -> Option<GeneratedTextInputEvent> {
+        // The preedit text is only part of the undo history once it is committed, at which
+        // point the whole composition is recorded as a single edit
+        let mut commit_start = None;
+        match &event {
+            BlitzImeEvent::Preedit(text, _) if !text.is_empty() && !self.editor.is_composing() => {
+                self.history.start_composition(self.editor_state());
+            }
+            BlitzImeEvent::Commit(_) => {
+                let start = self.history.take_composition_start();
+                commit_start = Some(start.unwrap_or_else(|| self.editor_state()));
+            }
+            _ => {}
+        }
+
         let editor = &mut self.editor;
         let mut driver = editor.driver(font_ctx, layout_ctx);
 
@@ -769,10 +993,14 @@ impl TextInputData {
             }
             BlitzImeEvent::Disabled => {
                 driver.clear_compose();
+                self.history.take_composition_start();
                 Some(GeneratedTextInputEvent::PreEditChange)
             }
             BlitzImeEvent::Commit(text) => {
                 driver.insert_or_replace_selection(&text);
+                if let Some(before) = commit_start {
+                    self.record_edit(before, InputType::InsertCompositionText);
+                }
                 Some(GeneratedTextInputEvent::Input)
             }
             BlitzImeEvent::Preedit(text, cursor) => {
@@ -0,0 +1,257 @@
+//! Undo/redo history for text inputs
+
+use blitz_traits::events::InputType;
+use web_time::{Duration, Instant};
+
+/// Consecutive edits of the same kind are merged into a single undo step unless they are
+/// separated by a pause of at least this long
+const COALESCE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The maximum number of undo steps kept for each input
+const MAX_UNDO_STEPS: usize = 100;
+
+/// The value and selection (as byte offsets) of a text input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct EditorState {
+    pub(crate) text: String,
+    pub(crate) anchor: usize,
+    pub(crate) focus: usize,
+}
+
+/// Which edits can be merged into a single undo step
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EditKind {
+    Insert,
+    DeleteBackward,
+    DeleteForward,
+    /// Edits which are always undone on their own (pastes, cuts, line breaks, etc)
+    Other,
+}
+
+impl EditKind {
+    fn of(input_type: InputType) -> Self {
+        match input_type {
+            InputType::InsertText => Self::Insert,
+            InputType::DeleteContentBackward => Self::DeleteBackward,
+            InputType::DeleteContentForward => Self::DeleteForward,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// The most recent edit, which the next edit may be merged with
+#[derive(Clone, Debug)]
+struct LastEdit {
+    kind: EditKind,
+    /// The selection after the edit
+    anchor: usize,
+    focus: usize,
+    time: Instant,
+}
+
+/// The undo and redo stacks of a text input.
+///
+/// Each undo step stores the whole state of the input before the step, which is cheap
+/// enough for the size of text that inputs usually hold.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UndoHistory {
+    undo_stack: Vec<EditorState>,
+    redo_stack: Vec<EditorState>,
+    last_edit: Option<LastEdit>,
+    /// The state of the input before the current IME composition started
+    composition_start: Option<EditorState>,
+}
+
+impl UndoHistory {
+    /// Record an edit from `before` to `after`.
+    ///
+    /// Typing (or deleting) consecutive characters is merged into the previous undo step as
+    /// long as the caret hasn't moved in between and the user hasn't paused.
+    pub(crate) fn record(
+        &mut self,
+        before: EditorState,
+        after: &EditorState,
+        input_type: InputType,
+        now: Instant,
+    ) {
+        if before.text == after.text {
+            return;
+        }
+
+        self.redo_stack.clear();
+        // Any composition which hasn't been committed by now was cancelled
+        self.composition_start = None;
+
+        let kind = EditKind::of(input_type);
+        let coalesce = kind != EditKind::Other
+            && self.last_edit.as_ref().is_some_and(|last| {
+                last.kind == kind
+                    && last.anchor == before.anchor
+                    && last.focus == before.focus
+                    && now.duration_since(last.time) < COALESCE_TIMEOUT
+            });
+        if !coalesce {
+            self.undo_stack.push(before);
+            if self.undo_stack.len() > MAX_UNDO_STEPS {
+                self.undo_stack.remove(0);
+            }
+        }
+
+        self.last_edit = Some(LastEdit {
+            kind,
+            anchor: after.anchor,
+            focus: after.focus,
+            time: now,
+        });
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Pop the last undo step, returning the state to restore
+    pub(crate) fn undo(&mut self, current: EditorState) -> Option<EditorState> {
+        let state = self.undo_stack.pop()?;
+        self.redo_stack.push(current);
+        self.last_edit = None;
+        Some(state)
+    }
+
+    /// Pop the last redo step, returning the state to restore
+    pub(crate) fn redo(&mut self, current: EditorState) -> Option<EditorState> {
+        let state = self.redo_stack.pop()?;
+        self.undo_stack.push(current);
+        self.last_edit = None;
+        Some(state)
+    }
+
+    /// Remember the state before an IME composition, so that the committed text is undone
+    /// in one step (the intermediate preedit text is never recorded)
+    pub(crate) fn start_composition(&mut self, state: EditorState) {
+        self.composition_start = Some(state);
+    }
+
+    /// The state before the current IME composition (if there is one)
+    pub(crate) fn take_composition_start(&mut self) -> Option<EditorState> {
+        self.composition_start.take()
+    }
+
+    /// Forget all undo and redo steps
+    pub(crate) fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(text: &str) -> EditorState {
+        EditorState {
+            text: text.to_string(),
+            anchor: text.len(),
+            focus: text.len(),
+        }
+    }
+
+    /// Record typing each of `texts` in turn, `gap` apart, starting from `start`
+    fn type_states(history: &mut UndoHistory, start: &str, texts: &[&str], gap: Duration) {
+        let mut now = Instant::now();
+        let mut before = state(start);
+        for text in texts {
+            let after = state(text);
+            history.record(before, &after, InputType::InsertText, now);
+            before = after;
+            now += gap;
+        }
+    }
+
+    #[test]
+    fn typing_is_coalesced() {
+        let mut history = UndoHistory::default();
+        type_states(&mut history, "", &["a", "ab", "abc"], Duration::ZERO);
+
+        assert_eq!(history.undo(state("abc")), Some(state("")));
+        assert!(!history.can_undo());
+        assert_eq!(history.redo(state("")), Some(state("abc")));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn pauses_split_typing() {
+        let mut history = UndoHistory::default();
+        type_states(&mut history, "", &["a", "ab"], COALESCE_TIMEOUT);
+
+        assert_eq!(history.undo(state("ab")), Some(state("a")));
+        assert_eq!(history.undo(state("a")), Some(state("")));
+    }
+
+    #[test]
+    fn different_kinds_are_not_coalesced() {
+        let mut history = UndoHistory::default();
+        let now = Instant::now();
+        history.record(state(""), &state("ab"), InputType::InsertText, now);
+        history.record(
+            state("ab"),
+            &state("a"),
+            InputType::DeleteContentBackward,
+            now,
+        );
+        history.record(state("a"), &state("ax"), InputType::InsertFromPaste, now);
+        history.record(state("ax"), &state("axy"), InputType::InsertFromPaste, now);
+
+        assert_eq!(history.undo(state("axy")), Some(state("ax")));
+        assert_eq!(history.undo(state("ax")), Some(state("a")));
+        assert_eq!(history.undo(state("a")), Some(state("ab")));
+        assert_eq!(history.undo(state("ab")), Some(state("")));
+    }
+
+    #[test]
+    fn moving_the_caret_splits_typing() {
+        let mut history = UndoHistory::default();
+        let now = Instant::now();
+        history.record(state(""), &state("a"), InputType::InsertText, now);
+        let moved = EditorState {
+            text: "a".to_string(),
+            anchor: 0,
+            focus: 0,
+        };
+        let after = EditorState {
+            text: "ba".to_string(),
+            anchor: 1,
+            focus: 1,
+        };
+        history.record(moved.clone(), &after, InputType::InsertText, now);
+
+        assert_eq!(history.undo(after), Some(moved));
+        assert_eq!(history.undo(state("a")), Some(state("")));
+    }
+
+    #[test]
+    fn new_edits_clear_redo_stack() {
+        let mut history = UndoHistory::default();
+        type_states(&mut history, "", &["a"], Duration::ZERO);
+        history.undo(state("a"));
+        assert!(history.can_redo());
+
+        type_states(&mut history, "", &["b"], Duration::ZERO);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = UndoHistory::default();
+        let now = Instant::now();
+        for i in 0..(MAX_UNDO_STEPS + 10) {
+            let before = state(&"a".repeat(i));
+            let after = state(&"a".repeat(i + 1));
+            history.record(before, &after, InputType::InsertFromPaste, now);
+        }
+        assert_eq!(history.undo_stack.len(), MAX_UNDO_STEPS);
+        assert_eq!(history.undo_stack[0], state(&"a".repeat(10)));
+    }
+}
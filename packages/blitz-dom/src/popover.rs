@@ -0,0 +1,357 @@
+//! The popover API: elements with a `popover` attribute.
+//!
+//! An open popover is added to the top layer, so it is painted above all other content, and
+//! matches `:popover-open`. Popovers are opened and closed by buttons with a `popovertarget`
+//! attribute. `auto` and `hint` popovers are also "light dismissed": they are closed by
+//! pressing Escape or by pressing the pointer outside of them. Opening an `auto` popover
+//! closes any other open `auto` popovers which aren't its ancestors, so nested popovers (e.g.
+//! submenus) form a stack.
+//!
+//! <https://html.spec.whatwg.org/multipage/popover.html>
+
+use blitz_traits::events::{BlitzToggleEvent, DomEventData, ToggleState};
+use blitz_traits::node_id::NodeId;
+use markup5ever::{LocalName, local_name};
+use style_dom::ElementState;
+
+use crate::BaseDocument;
+use crate::traversal::TreeTraverser;
+
+/// The state of a `popover` attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PopoverKind {
+    /// Light dismissed, and closes other `auto` popovers which aren't its ancestors
+    Auto,
+    /// Only shown and hidden explicitly
+    Manual,
+    /// Light dismissed, and closes other `hint` popovers, but leaves `auto` popovers open
+    Hint,
+}
+
+impl PopoverKind {
+    fn from_attr(value: &str) -> Self {
+        if value.is_empty() || value.eq_ignore_ascii_case("auto") {
+            Self::Auto
+        } else if value.eq_ignore_ascii_case("hint") {
+            Self::Hint
+        } else {
+            // Including invalid values
+            Self::Manual
+        }
+    }
+
+    fn is_light_dismissable(self) -> bool {
+        matches!(self, Self::Auto | Self::Hint)
+    }
+}
+
+/// The action a `popovertarget` button performs on its target (its `popovertargetaction`
+/// attribute)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PopoverTargetAction {
+    Toggle,
+    Show,
+    Hide,
+}
+
+/// The state of an open popover
+#[derive(Debug)]
+pub(crate) struct PopoverState {
+    /// The kind of popover it was opened as
+    kind: PopoverKind,
+    /// The element which was focussed when the popover was opened, and which is focussed
+    /// again when it is closed (if focus is inside it)
+    previously_focussed: Option<NodeId>,
+}
+
+impl BaseDocument {
+    /// Show a popover (`popover.showPopover()`), adding it to the top layer. `source` is the
+    /// element which caused it to be shown (if any), which it is nested under if that element
+    /// is in another open popover.
+    ///
+    /// Any light dismissable popovers which aren't its ancestors are hidden, and a `toggle`
+    /// event is queued at the popover.
+    pub fn show_popover(&mut self, popover_id: NodeId, source: Option<NodeId>) {
+        let Some(kind) = self.popover_kind(popover_id) else {
+            return;
+        };
+        if self.is_popover_open(popover_id)
+            || !self.nodes[popover_id].flags.is_in_document()
+            || self.is_modal(popover_id)
+        {
+            return;
+        }
+
+        if kind.is_light_dismissable() {
+            let ancestor = self.topmost_popover_ancestor(popover_id, source);
+            if kind == PopoverKind::Hint && ancestor.is_none() {
+                // A hint (e.g. a tooltip) leaves open `auto` popovers (e.g. menus) alone
+                for hint_id in self.light_dismissable_popovers_above(None) {
+                    if self
+                        .open_popovers
+                        .get(&hint_id)
+                        .is_some_and(|state| state.kind == PopoverKind::Hint)
+                    {
+                        self.hide_popover(hint_id);
+                    }
+                }
+            } else {
+                for id in self.light_dismissable_popovers_above(ancestor) {
+                    self.hide_popover(id);
+                }
+            }
+        }
+
+        self.snapshot_node_and(popover_id, |node| {
+            if let Some(element) = node.element_data_mut() {
+                element.element_state.insert(ElementState::POPOVER_OPEN);
+            }
+        });
+        self.add_to_top_layer(popover_id);
+        self.open_popovers.insert(
+            popover_id,
+            PopoverState {
+                kind,
+                previously_focussed: self.focus_node_id,
+            },
+        );
+        self.focus_popover(popover_id);
+
+        self.queue_event(
+            popover_id,
+            DomEventData::Toggle(BlitzToggleEvent {
+                old_state: ToggleState::Closed,
+                new_state: ToggleState::Open,
+                source,
+            }),
+        );
+        self.shell_provider.request_redraw();
+    }
+
+    /// Hide a popover (`popover.hidePopover()`), along with any popovers nested within it, and
+    /// queue `beforetoggle` and `toggle` events at it. Hiding a popover can't be cancelled, so
+    /// the `beforetoggle` event is only a notification.
+    pub fn hide_popover(&mut self, popover_id: NodeId) {
+        let Some(state) = self.open_popovers.get(&popover_id) else {
+            return;
+        };
+        if state.kind.is_light_dismissable() {
+            for id in self.light_dismissable_popovers_above(Some(popover_id)) {
+                self.hide_popover(id);
+            }
+        }
+
+        self.queue_event(
+            popover_id,
+            DomEventData::BeforeToggle(BlitzToggleEvent {
+                old_state: ToggleState::Open,
+                new_state: ToggleState::Closed,
+                source: None,
+            }),
+        );
+        let Some(state) = self.open_popovers.remove(&popover_id) else {
+            return;
+        };
+        self.snapshot_node_and(popover_id, |node| {
+            if let Some(element) = node.element_data_mut() {
+                element.element_state.remove(ElementState::POPOVER_OPEN);
+            }
+        });
+        self.remove_from_top_layer(popover_id);
+
+        // Focus is restored if it was inside the popover
+        if self
+            .focus_node_id
+            .is_some_and(|id| id == popover_id || self.is_ancestor_of(popover_id, id))
+        {
+            match state
+                .previously_focussed
+                .filter(|id| self.get_node(*id).is_some())
+            {
+                Some(id) => {
+                    self.set_focus_to(id);
+                }
+                None => self.clear_focus(),
+            }
+        }
+
+        self.queue_event(
+            popover_id,
+            DomEventData::Toggle(BlitzToggleEvent {
+                old_state: ToggleState::Open,
+                new_state: ToggleState::Closed,
+                source: None,
+            }),
+        );
+        self.shell_provider.request_redraw();
+    }
+
+    /// Whether an element is a popover which is currently open (and so matches
+    /// `:popover-open`)
+    pub fn is_popover_open(&self, node_id: NodeId) -> bool {
+        self.open_popovers.contains_key(&node_id)
+    }
+
+    /// Hide an open popover if its `popover` attribute is removed, or changed to a different
+    /// kind of popover than it was opened as
+    pub(crate) fn popover_attribute_changed(&mut self, node_id: NodeId, value: Option<&str>) {
+        let Some(state) = self.open_popovers.get(&node_id) else {
+            return;
+        };
+        if value.map(PopoverKind::from_attr) != Some(state.kind) {
+            self.hide_popover(node_id);
+        }
+    }
+
+    /// The topmost open `auto` or `hint` popover, if it is above any modal dialog. This is the
+    /// popover which pressing Escape closes.
+    pub(crate) fn topmost_light_dismissable_popover(&self) -> Option<NodeId> {
+        self.top_layer
+            .iter()
+            .rev()
+            .copied()
+            .take_while(|&id| !self.is_modal(id))
+            .find(|id| {
+                self.open_popovers
+                    .get(id)
+                    .is_some_and(|state| state.kind.is_light_dismissable())
+            })
+    }
+
+    /// The open `auto` and `hint` popovers above `endpoint` in the top layer (or all of them
+    /// if `endpoint` is `None`), from top to bottom
+    pub(crate) fn light_dismissable_popovers_above(&self, endpoint: Option<NodeId>) -> Vec<NodeId> {
+        let start = endpoint
+            .and_then(|endpoint| self.top_layer.iter().position(|&id| id == endpoint))
+            .map_or(0, |idx| idx + 1);
+        self.top_layer[start..]
+            .iter()
+            .rev()
+            .copied()
+            .filter(|id| {
+                self.open_popovers
+                    .get(id)
+                    .is_some_and(|state| state.kind.is_light_dismissable())
+            })
+            .collect()
+    }
+
+    /// The topmost open popover which pressing the pointer on `node_id` counts as pressing
+    /// inside of: the nearest open popover containing it, or the popover it is a
+    /// `popovertarget` button for. Light dismiss closes the popovers above it.
+    pub(crate) fn topmost_clicked_popover(&self, node_id: NodeId) -> Option<NodeId> {
+        let clicked = self.nearest_open_popover(node_id);
+        let invoked = self
+            .popover_target_of(node_id)
+            .map(|(target_id, _)| target_id)
+            .filter(|&target_id| self.is_popover_open(target_id));
+        self.topmost_in_top_layer(clicked, invoked)
+    }
+
+    /// The popover that a `popovertarget` button controls, and the action it performs on it.
+    /// Returns `None` if `node_id` isn't a button, is disabled, or submits a form.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/popover.html#popover-target-attribute-activation-behavior>
+    pub(crate) fn popover_target_of(
+        &self,
+        node_id: NodeId,
+    ) -> Option<(NodeId, PopoverTargetAction)> {
+        let element = self.nodes[node_id].element_data()?;
+        let is_button = match element.name.local {
+            local_name!("button") => true,
+            local_name!("input") => matches!(
+                element.attr(local_name!("type")),
+                Some("button" | "submit" | "reset" | "image")
+            ),
+            _ => false,
+        };
+        if !is_button || element.has_attr(local_name!("disabled")) {
+            return None;
+        }
+        let submits_form = (element.is_submit_button()
+            || element.attr(local_name!("type")) == Some("submit"))
+            && self.controls_to_form.contains_key(&node_id);
+        if submits_form {
+            return None;
+        }
+
+        let target_id = element
+            .attr(LocalName::from("popovertarget"))
+            .and_then(|id| self.get_element_by_id(id))
+            .filter(|&id| self.popover_kind(id).is_some())?;
+        let action = match element.attr(LocalName::from("popovertargetaction")) {
+            Some(action) if action.eq_ignore_ascii_case("show") => PopoverTargetAction::Show,
+            Some(action) if action.eq_ignore_ascii_case("hide") => PopoverTargetAction::Hide,
+            _ => PopoverTargetAction::Toggle,
+        };
+        Some((target_id, action))
+    }
+
+    /// The kind of popover an element is, or `None` if it doesn't have a `popover` attribute
+    fn popover_kind(&self, node_id: NodeId) -> Option<PopoverKind> {
+        self.get_node(node_id)?
+            .attr(LocalName::from("popover"))
+            .map(PopoverKind::from_attr)
+    }
+
+    /// The nearest inclusive ancestor of a node which is an open `auto` or `hint` popover
+    fn nearest_open_popover(&self, node_id: NodeId) -> Option<NodeId> {
+        let mut next = Some(node_id);
+        while let Some(id) = next {
+            if self
+                .open_popovers
+                .get(&id)
+                .is_some_and(|state| state.kind.is_light_dismissable())
+            {
+                return Some(id);
+            }
+            next = self.nodes[id].parent;
+        }
+        None
+    }
+
+    /// The open popover which a popover being shown is nested within: the topmost of the
+    /// nearest open popovers containing it, its source, and the source of that popover
+    ///
+    /// <https://html.spec.whatwg.org/multipage/popover.html#topmost-popover-ancestor>
+    fn topmost_popover_ancestor(
+        &self,
+        popover_id: NodeId,
+        source: Option<NodeId>,
+    ) -> Option<NodeId> {
+        let from_tree = self.nodes[popover_id]
+            .parent
+            .and_then(|parent_id| self.nearest_open_popover(parent_id));
+        let from_source = source.and_then(|source_id| self.nearest_open_popover(source_id));
+        self.topmost_in_top_layer(from_tree, from_source)
+    }
+
+    /// Whichever of two elements is higher in the top layer
+    fn topmost_in_top_layer(&self, a: Option<NodeId>, b: Option<NodeId>) -> Option<NodeId> {
+        let index =
+            |id: Option<NodeId>| id.and_then(|id| self.top_layer.iter().position(|&top| top == id));
+        match (index(a), index(b)) {
+            (Some(a_idx), Some(b_idx)) if b_idx > a_idx => b,
+            (Some(_), _) => a,
+            (None, _) => b,
+        }
+    }
+
+    /// Focus a popover which has just been shown, if it (or one of its descendants) has the
+    /// `autofocus` attribute
+    ///
+    /// <https://html.spec.whatwg.org/multipage/popover.html#popover-focusing-steps>
+    fn focus_popover(&mut self, popover_id: NodeId) {
+        let control = if self.nodes[popover_id].has_attr(local_name!("autofocus")) {
+            Some(popover_id)
+        } else {
+            TreeTraverser::new_with_root(self, popover_id).find(|&id| {
+                let node = &self.nodes[id];
+                node.is_focussable() && node.has_attr(local_name!("autofocus"))
+            })
+        };
+        if let Some(control) = control {
+            self.set_focus_to(control);
+        }
+    }
+}
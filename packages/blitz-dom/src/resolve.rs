@@ -75,10 +75,20 @@ impl BaseDocument {
         let root_node_id = self.root_element().id;
         debug_timer!(timer, feature = "log-phase-times");
 
+        // Radio buttons' states (`:indeterminate`, `:invalid`) depend on their whole group,
+        // and forms' and fieldsets' validity depends on that of their form controls
+        self.update_radio_groups();
+        self.update_form_validity();
+
         // we need to resolve stylist first since it will need to drive our layout bits
         self.resolve_stylist(current_time_for_animations);
         timer.record_time("style");
 
+        // Evaluate counters and generated content (which damages elements whose list markers
+        // or generated content change)
+        self.resolve_generated_content();
+        timer.record_time("counters");
+
         // Propagate damage flags (from mutation and restyles) up and down the tree
         if self.incremental_layout {
             self.propagate_damage_flags(root_node_id, RestyleDamage::empty());
@@ -104,6 +114,16 @@ impl BaseDocument {
         self.resolve_transforms(root_node_id);
         timer.record_time("transform");
 
+        // Position sticky and fixed boxes for the current scroll offsets
+        self.resolve_scroll_positions();
+        timer.record_time("scroll_pos");
+
+        // Advance animated images and decode images at their displayed size (which depends
+        // on whether they are scrolled into view)
+        self.resolve_image_animations(current_time_for_animations);
+        self.resolve_image_decodes();
+        timer.record_time("images");
+
         // Clear all damage and dirty flags
         if self.incremental_layout {
             for (_, node) in self.nodes.iter_mut() {
@@ -140,10 +160,17 @@ impl BaseDocument {
                     (size.height * viewport_scale) as u32,
                 );
                 drop(sub_viewport);
+                sub_doc.set_prefers_reduced_motion(self.prefers_reduced_motion);
 
                 sub_doc.resolve(current_time_for_animations);
 
                 subdoc_is_animating |= sub_doc.is_animating();
+                if let Some(time) = sub_doc.next_animation_frame_time() {
+                    self.next_image_frame_time = Some(
+                        self.next_image_frame_time
+                            .map_or(time, |next_time| next_time.min(time)),
+                    );
+                }
             }
         }
         self.subdoc_is_animating = subdoc_is_animating;
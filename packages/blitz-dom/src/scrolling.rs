@@ -3,7 +3,12 @@
 
 use blitz_traits::events::{BlitzScrollEvent, DomEvent, DomEventData};
 use blitz_traits::node_id::NodeId;
-use style::values::computed::Overflow;
+use std::collections::HashSet;
+use style::properties::ComputedValues;
+use style::properties::generated::longhands::position::computed_value::T as Position;
+use style::values::computed::{Length, LengthPercentage, Overflow, Percentage};
+use style::values::generics::length::GenericMargin;
+use style::values::generics::position::Inset;
 use web_time::{SystemTime, UNIX_EPOCH};
 
 use crate::BaseDocument;
@@ -318,6 +323,12 @@ impl BaseDocument {
                     dispatch_event(DomEvent::new(root_id, DomEventData::Scroll(event)));
                 }
 
+                self.resolve_scroll_positions();
+                // Scrolling moves nodes on screen, which the accessibility tree reflects
+                #[cfg(feature = "accessibility")]
+                {
+                    self.accessibility.layout_dirty = true;
+                }
                 self.shell_provider.request_redraw();
                 true
             }
@@ -344,6 +355,12 @@ impl BaseDocument {
                 dispatch_event(DomEvent::new(node_id, DomEventData::Scroll(event)));
 
                 self.show_scrollbars(node_id);
+                self.resolve_scroll_positions();
+                // Scrolling moves nodes on screen, which the accessibility tree reflects
+                #[cfg(feature = "accessibility")]
+                {
+                    self.accessibility.layout_dirty = true;
+                }
                 self.shell_provider.request_redraw();
                 true
             }
@@ -777,3 +794,314 @@ impl BaseDocument {
         }
     }
 }
+
+/// The scroll container which `position: sticky` descendants of a node stick within, in the
+/// (unscrolled) coordinate space of that scroll container's content.
+#[derive(Clone, Copy, Debug)]
+struct ScrollPositionContext {
+    /// The origin of the node's layout parent's border box
+    origin: Point<f64>,
+    /// The visible part of the scroll container's content
+    scrollport: kurbo::Rect,
+    /// The content box of the node's layout parent, which sticky boxes never leave
+    containing_block: kurbo::Rect,
+    /// The sum of the scroll offsets of the viewport and of every enclosing scroll container
+    total_scroll: Point<f64>,
+    /// The origin of the node's layout parent's border box relative to the viewport, were
+    /// every scroll offset zero
+    viewport_origin: Point<f64>,
+    /// The viewport, which is the containing block of fixed boxes
+    viewport: kurbo::Rect,
+}
+
+impl BaseDocument {
+    /// Recompute the [`position_offset`](crate::Node::position_offset) of every sticky and
+    /// fixed box from the current scroll offsets.
+    ///
+    /// Sticky boxes are shifted to stay within the scrollport of their nearest scroll container
+    /// (inset by their `top`/`right`/`bottom`/`left` values) without leaving their containing
+    /// block. Fixed boxes are shifted by the total scroll offset above them, which anchors them
+    /// to the viewport.
+    ///
+    /// This runs after layout and whenever a scroll offset changes. Only the layout ancestors
+    /// of sticky and fixed boxes are visited.
+    pub(crate) fn resolve_scroll_positions(&mut self) {
+        if self.sticky_and_fixed_nodes.is_empty() {
+            return;
+        }
+        let Some(root_id) = self.try_root_element().map(|root| root.id) else {
+            return;
+        };
+
+        // The sticky and fixed boxes and their layout ancestors
+        let mut visit = HashSet::new();
+        for &node_id in &self.sticky_and_fixed_nodes {
+            let mut next = Some(node_id);
+            while let Some(id) = next.filter(|id| visit.insert(*id)) {
+                next = self.nodes.get(id).and_then(|node| node.layout_parent.get());
+            }
+        }
+
+        let scale = self.viewport.scale_f64();
+        let viewport = kurbo::Rect::new(
+            0.0,
+            0.0,
+            self.viewport.window_size.0 as f64 / scale,
+            self.viewport.window_size.1 as f64 / scale,
+        );
+        let scroll = self.viewport_scroll;
+        let ctx = ScrollPositionContext {
+            origin: Point::ZERO,
+            scrollport: viewport + kurbo::Vec2::new(scroll.x, scroll.y),
+            containing_block: viewport,
+            total_scroll: scroll,
+            viewport_origin: Point::ZERO,
+            viewport,
+        };
+        self.resolve_scroll_positions_recursive(root_id, root_id, ctx, &visit);
+    }
+
+    fn resolve_scroll_positions_recursive(
+        &mut self,
+        node_id: NodeId,
+        root_id: NodeId,
+        ctx: ScrollPositionContext,
+        visit: &HashSet<NodeId>,
+    ) {
+        let node = &self.nodes[node_id];
+        if node.element_data().is_none() {
+            return;
+        }
+
+        let layout = *node.final_layout();
+        let border_box = kurbo::Rect::from_origin_size(
+            (
+                ctx.origin.x + layout.location.x as f64,
+                ctx.origin.y + layout.location.y as f64,
+            ),
+            (layout.size.width as f64, layout.size.height as f64),
+        );
+
+        let Some(styles) = node.primary_styles() else {
+            return;
+        };
+        // Elements in the top layer are positioned against the viewport like fixed boxes
+        let position = match self.top_layer.contains(&node_id) {
+            true => Position::Fixed,
+            false => styles.clone_position(),
+        };
+        let position_offset = match position {
+            Position::Sticky => {
+                sticky_offset(&styles, border_box, ctx.scrollport, ctx.containing_block)
+            }
+            Position::Fixed => {
+                let laid_out = kurbo::Point::new(
+                    ctx.viewport_origin.x + layout.location.x as f64,
+                    ctx.viewport_origin.y + layout.location.y as f64,
+                );
+                let offset = fixed_offset(&styles, &layout, laid_out, ctx.viewport);
+                Point {
+                    x: ctx.total_scroll.x + offset.x,
+                    y: ctx.total_scroll.y + offset.y,
+                }
+            }
+            _ => Point::ZERO,
+        };
+        // The root element's overflow applies to the viewport, so it is never a scroll
+        // container itself.
+        let is_scroll_container = node_id != root_id
+            && (!matches!(
+                styles.clone_overflow_x(),
+                Overflow::Visible | Overflow::Clip
+            ) || !matches!(
+                styles.clone_overflow_y(),
+                Overflow::Visible | Overflow::Clip
+            ));
+        drop(styles);
+
+        let node = &mut self.nodes[node_id];
+        *node.position_offset_mut() = position_offset;
+        let scroll = *node.scroll_offset();
+
+        let border_box = border_box + kurbo::Vec2::new(position_offset.x, position_offset.y);
+        let viewport_origin = Point {
+            x: ctx.viewport_origin.x + layout.location.x as f64 + position_offset.x,
+            y: ctx.viewport_origin.y + layout.location.y as f64 + position_offset.y,
+        };
+        let (border, padding) = (layout.border.map(f64::from), layout.padding.map(f64::from));
+        let child_ctx = if is_scroll_container {
+            // Children are positioned relative to this node's border box, and stick within
+            // its (scrolled) padding box
+            let scrollport = kurbo::Rect::new(
+                border.left,
+                border.top,
+                border_box.width() - border.right,
+                border_box.height() - border.bottom,
+            ) + kurbo::Vec2::new(scroll.x, scroll.y);
+            // Sticky children may travel through the entire scrollable overflow
+            let overflow = layout.scrollable_overflow_rect;
+            let containing_block = kurbo::Rect::new(
+                border.left + padding.left,
+                border.top + padding.top,
+                border_box.width() - border.right - padding.right,
+                border_box.height() - border.bottom - padding.bottom,
+            )
+            .union(kurbo::Rect::new(
+                overflow.left as f64,
+                overflow.top as f64,
+                overflow.right as f64,
+                overflow.bottom as f64,
+            ));
+            ScrollPositionContext {
+                origin: Point::ZERO,
+                scrollport,
+                containing_block,
+                total_scroll: Point {
+                    x: ctx.total_scroll.x + scroll.x,
+                    y: ctx.total_scroll.y + scroll.y,
+                },
+                viewport_origin,
+                viewport: ctx.viewport,
+            }
+        } else {
+            ScrollPositionContext {
+                origin: Point {
+                    x: border_box.x0 - scroll.x,
+                    y: border_box.y0 - scroll.y,
+                },
+                containing_block: kurbo::Rect::new(
+                    border_box.x0 + border.left + padding.left,
+                    border_box.y0 + border.top + padding.top,
+                    border_box.x1 - border.right - padding.right,
+                    border_box.y1 - border.bottom - padding.bottom,
+                ),
+                viewport_origin,
+                ..ctx
+            }
+        };
+
+        let children = self.nodes[node_id].layout_children.borrow().clone();
+        for child_id in children.into_iter().flatten() {
+            if visit.contains(&child_id) {
+                self.resolve_scroll_positions_recursive(child_id, root_id, child_ctx, visit);
+            }
+        }
+    }
+}
+
+/// The offset which moves a fixed box from where it was laid out (within its parent, at
+/// `laid_out` in the viewport) to where its insets place it within the viewport. An axis
+/// where both insets are `auto` keeps the box's static position.
+fn fixed_offset(
+    styles: &ComputedValues,
+    layout: &taffy::Layout,
+    laid_out: kurbo::Point,
+    viewport: kurbo::Rect,
+) -> Point<f64> {
+    let insets = styles.get_position();
+    let resolve = |inset: &Inset<Percentage, LengthPercentage>, basis: f64| match inset {
+        Inset::LengthPercentage(lp) => Some(lp.resolve(Length::new(basis as f32)).px() as f64),
+        _ => None,
+    };
+    let (margin, size) = (layout.margin.map(f64::from), layout.size.map(f64::from));
+    let margin_styles = styles.get_margin();
+    let is_auto = |margin: &GenericMargin<LengthPercentage>| matches!(margin, GenericMargin::Auto);
+
+    // The position of the box's start edge along one axis, if its insets determine it. Auto
+    // margins were resolved against the box's layout parent, so they are resolved again against
+    // the viewport.
+    let axis = |start_inset: Option<f64>,
+                end_inset: Option<f64>,
+                (margin_start, margin_end): (f64, f64),
+                (auto_start, auto_end): (bool, bool),
+                box_size: f64,
+                viewport_size: f64| match (start_inset, end_inset) {
+        (Some(start), Some(end)) if auto_start || auto_end => {
+            let free = viewport_size - start - end - box_size;
+            Some(match (auto_start, auto_end) {
+                (true, true) => start + (free / 2.0).max(0.0),
+                (true, false) => start + free - margin_end,
+                _ => start + margin_start,
+            })
+        }
+        (Some(start), _) => Some(start + margin_start),
+        (None, Some(end)) => Some(viewport_size - end - margin_end - box_size),
+        (None, None) => None,
+    };
+
+    let x = axis(
+        resolve(&insets.left, viewport.width()),
+        resolve(&insets.right, viewport.width()),
+        (margin.left, margin.right),
+        (
+            is_auto(&margin_styles.margin_left),
+            is_auto(&margin_styles.margin_right),
+        ),
+        size.width,
+        viewport.width(),
+    );
+    let y = axis(
+        resolve(&insets.top, viewport.height()),
+        resolve(&insets.bottom, viewport.height()),
+        (margin.top, margin.bottom),
+        (
+            is_auto(&margin_styles.margin_top),
+            is_auto(&margin_styles.margin_bottom),
+        ),
+        size.height,
+        viewport.height(),
+    );
+    Point {
+        x: x.map_or(0.0, |x| x - laid_out.x),
+        y: y.map_or(0.0, |y| y - laid_out.y),
+    }
+}
+
+/// The offset which keeps a sticky box with the given (unshifted) border box within the
+/// scrollport, inset by its `top`/`right`/`bottom`/`left` values, while staying inside its
+/// containing block. Where the start and end insets conflict, the start inset wins.
+fn sticky_offset(
+    styles: &ComputedValues,
+    border_box: kurbo::Rect,
+    scrollport: kurbo::Rect,
+    containing_block: kurbo::Rect,
+) -> Point<f64> {
+    let insets = styles.get_position();
+    let resolve = |inset: &Inset<Percentage, LengthPercentage>, basis: f64| match inset {
+        Inset::LengthPercentage(lp) => Some(lp.resolve(Length::new(basis as f32)).px() as f64),
+        _ => None,
+    };
+
+    // The offset along one axis, given the start and end edges of the box, the scrollport and
+    // the containing block.
+    let axis = |start_inset: Option<f64>,
+                end_inset: Option<f64>,
+                (box_start, box_end): (f64, f64),
+                (port_start, port_end): (f64, f64),
+                (cb_start, cb_end): (f64, f64)| {
+        let push = start_inset.map_or(0.0, |inset| {
+            (port_start + inset - box_start).clamp(0.0, (cb_end - box_end).max(0.0))
+        });
+        let pull = end_inset.map_or(0.0, |inset| {
+            (port_end - inset - box_end).clamp((cb_start - box_start).min(0.0), 0.0)
+        });
+        if push > 0.0 { push } else { pull }
+    };
+
+    Point {
+        x: axis(
+            resolve(&insets.left, scrollport.width()),
+            resolve(&insets.right, scrollport.width()),
+            (border_box.x0, border_box.x1),
+            (scrollport.x0, scrollport.x1),
+            (containing_block.x0, containing_block.x1),
+        ),
+        y: axis(
+            resolve(&insets.top, scrollport.height()),
+            resolve(&insets.bottom, scrollport.height()),
+            (border_box.y0, border_box.y1),
+            (scrollport.y0, scrollport.y1),
+            (containing_block.y0, containing_block.y1),
+        ),
+    }
+}
@@ -0,0 +1,703 @@
+//! The `<select>` element: its list of options, the selectedness of those options, and the
+//! drop-down listbox which is shown when a drop-down select is opened.
+//!
+//! <https://html.spec.whatwg.org/multipage/form-elements.html#the-select-element>
+
+use blitz_traits::events::{BlitzInputEvent, BlitzKeyEvent, DomEvent, DomEventData, HitResult};
+use blitz_traits::node_id::NodeId;
+use keyboard_types::{Key, Modifiers};
+use kurbo::{Point, Rect};
+use markup5ever::local_name;
+use style_dom::ElementState;
+use web_time::{Duration, Instant};
+
+use crate::util::ACTION_MOD;
+use crate::{BaseDocument, ElementData, Node};
+
+/// How long the user may pause between keypresses before a typeahead search starts over
+const TYPEAHEAD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// The width of a drop-down select's arrow (in CSS pixels), which is reserved to the right
+/// of the widest option label
+pub const SELECT_ARROW_WIDTH: f32 = 20.0;
+
+/// The horizontal padding (in CSS pixels) of each option row in a drop-down listbox
+pub const SELECT_LISTBOX_PADDING: f64 = 4.0;
+
+/// The width (in CSS pixels) of the border around a drop-down listbox
+pub const SELECT_LISTBOX_BORDER: f64 = 1.0;
+
+/// The number of rows a select displays.
+///
+/// <https://html.spec.whatwg.org/multipage/form-elements.html#concept-select-size>
+pub fn select_display_size(element: &ElementData) -> u32 {
+    match element.attr_parsed::<u32>(local_name!("size")) {
+        Some(size) if size > 0 => size,
+        _ => match element.has_attr(local_name!("multiple")) {
+            true => 4,
+            false => 1,
+        },
+    }
+}
+
+/// Whether a select is rendered as a drop-down box (as opposed to an inline listbox).
+///
+/// <https://html.spec.whatwg.org/multipage/rendering.html#the-select-element-2>
+pub fn select_is_drop_down(element: &ElementData) -> bool {
+    !element.has_attr(local_name!("multiple")) && select_display_size(element) == 1
+}
+
+impl Node {
+    /// The `<option>` elements in this select's list of options, in tree order. This is the
+    /// select's option children, and the option children of its optgroup children.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-elements.html#concept-select-option-list>
+    pub fn select_options(&self) -> Vec<NodeId> {
+        let mut options = Vec::new();
+        for child_id in self.children.iter().copied() {
+            let child = self.with(child_id);
+            if child.data.is_element_with_tag_name(&local_name!("option")) {
+                options.push(child_id);
+            } else if child
+                .data
+                .is_element_with_tag_name(&local_name!("optgroup"))
+            {
+                options.extend(child.children.iter().copied().filter(|grandchild_id| {
+                    self.with(*grandchild_id)
+                        .data
+                        .is_element_with_tag_name(&local_name!("option"))
+                }));
+            }
+        }
+        options
+    }
+
+    /// The select element whose list of options this option is in (if any)
+    pub fn option_owner_select(&self) -> Option<&Node> {
+        let parent = self.with(self.parent?);
+        if parent.data.is_element_with_tag_name(&local_name!("select")) {
+            return Some(parent);
+        }
+        if parent
+            .data
+            .is_element_with_tag_name(&local_name!("optgroup"))
+        {
+            let grandparent = self.with(parent.parent?);
+            if grandparent
+                .data
+                .is_element_with_tag_name(&local_name!("select"))
+            {
+                return Some(grandparent);
+            }
+        }
+        None
+    }
+
+    /// Whether this option is disabled, either by its own `disabled` attribute or by that of
+    /// its parent optgroup.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-elements.html#concept-option-disabled>
+    pub fn option_is_disabled(&self) -> bool {
+        self.data.has_attr(local_name!("disabled"))
+            || self.parent.is_some_and(|parent_id| {
+                let parent = self.with(parent_id);
+                parent
+                    .data
+                    .is_element_with_tag_name(&local_name!("optgroup"))
+                    && parent.data.has_attr(local_name!("disabled"))
+            })
+    }
+
+    /// The option's value: its `value` attribute, or else its text.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-elements.html#concept-option-value>
+    pub fn option_value(&self) -> String {
+        match self.attr(local_name!("value")) {
+            Some(value) => value.to_string(),
+            None => self.option_text(),
+        }
+    }
+
+    /// The option's label: its `label` attribute (if non-empty), or else its text.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-elements.html#concept-option-label>
+    pub fn option_label(&self) -> String {
+        match self.attr(local_name!("label")) {
+            Some(label) if !label.is_empty() => label.to_string(),
+            _ => self.option_text(),
+        }
+    }
+
+    /// The option's text content with ASCII whitespace stripped and collapsed.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-elements.html#dom-option-text>
+    fn option_text(&self) -> String {
+        self.text_content()
+            .split_ascii_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The options of this select which are selected, in tree order.
+    ///
+    /// Until the user changes the selection, selectedness follows the options' `selected`
+    /// attributes (or the select's `value` attribute). The result is then adjusted as per the "selectedness setting algorithm":
+    /// a select that isn't `multiple` has at most one selected option, and a drop-down
+    /// select always has one selected option (if it has any non-disabled options).
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-elements.html#selectedness-setting-algorithm>
+    pub fn select_selected_options(&self) -> Vec<NodeId> {
+        let Some(element) = self.element_data() else {
+            return Vec::new();
+        };
+        let options = self.select_options();
+        let user_selection = element
+            .select_data()
+            .and_then(|data| data.user_selection.as_ref());
+
+        // Frameworks (such as Dioxus) set a select's value through a `value` attribute, which
+        // takes precedence over the options' `selected` attributes.
+        let value_attr = element.attr(local_name!("value"));
+
+        let mut selected: Vec<NodeId> = options
+            .iter()
+            .copied()
+            .filter(|option_id| {
+                let option = self.with(*option_id);
+                match (user_selection, value_attr) {
+                    (Some(user_selection), _) => user_selection.contains(option_id),
+                    (None, Some(value)) => option.option_value() == value,
+                    (None, None) => option.data.has_attr(local_name!("selected")),
+                }
+            })
+            .collect();
+
+        if !element.has_attr(local_name!("multiple")) {
+            if selected.len() > 1 {
+                selected.drain(..selected.len() - 1);
+            }
+            if selected.is_empty() && select_display_size(element) == 1 {
+                selected.extend(
+                    options
+                        .iter()
+                        .copied()
+                        .find(|option_id| !self.with(*option_id).option_is_disabled()),
+                );
+            }
+        }
+
+        selected
+    }
+
+    /// Whether this option is selected (its "selectedness"). This is kept in the option's
+    /// `CHECKED` state by [`BaseDocument::update_select_selectedness`].
+    pub fn option_is_selected(&self) -> bool {
+        self.element_state().contains(ElementState::CHECKED) && self.option_owner_select().is_some()
+    }
+
+    /// The select's value: the value of its first selected option, or the empty string.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-elements.html#dom-select-value>
+    pub fn select_value(&self) -> String {
+        self.select_selected_options()
+            .first()
+            .map(|option_id| self.with(*option_id).option_value())
+            .unwrap_or_default()
+    }
+}
+
+impl BaseDocument {
+    /// The `<select>` whose drop-down listbox is currently open (if any)
+    pub fn open_select_node_id(&self) -> Option<NodeId> {
+        self.open_select_node_id
+    }
+
+    /// Open the drop-down listbox of a drop-down select, highlighting its selected option
+    pub fn open_select(&mut self, select_id: NodeId) {
+        let node = &self.nodes[select_id];
+        let Some(element) = node.element_data() else {
+            return;
+        };
+        if element.select_data().is_none()
+            || !select_is_drop_down(element)
+            || element.has_attr(local_name!("disabled"))
+        {
+            return;
+        }
+
+        let selected = node.select_selected_options().first().copied();
+        let select_data = self.nodes[select_id]
+            .element_data_mut()
+            .and_then(|el| el.select_data_mut())
+            .unwrap();
+        select_data.highlighted = selected;
+
+        self.open_select_node_id = Some(select_id);
+        self.mark_node_changed(select_id);
+        self.shell_provider.request_redraw();
+    }
+
+    /// Close the open drop-down listbox (if any) without changing the selection
+    pub fn close_select(&mut self) {
+        if let Some(select_id) = self.open_select_node_id.take() {
+            self.mark_node_changed(select_id);
+            self.shell_provider.request_redraw();
+        }
+    }
+
+    /// Replace the options selected in a select with `selection`, dispatching `input` and
+    /// `change` events if the selection actually changed.
+    pub(crate) fn set_select_selection(
+        &mut self,
+        select_id: NodeId,
+        selection: Vec<NodeId>,
+        dispatch_event: &mut dyn FnMut(DomEvent),
+    ) {
+        let previous = self.nodes[select_id].select_selected_options();
+
+        let Some(select_data) = self.nodes[select_id]
+            .element_data_mut()
+            .and_then(|el| el.select_data_mut())
+        else {
+            return;
+        };
+        select_data.user_selection = Some(selection);
+
+        let selected = self.nodes[select_id].select_selected_options();
+        if selected == previous {
+            return;
+        }
+        self.update_select_selectedness(select_id);
+        self.shell_provider.request_redraw();
+        // The user changed the selection, so the select's validity is now shown
+        self.set_user_validity(select_id);
+
+        let value = self.nodes[select_id].select_value();
+        dispatch_event(DomEvent::new(
+            select_id,
+            DomEventData::Input(BlitzInputEvent {
+                value: value.clone(),
+            }),
+        ));
+        dispatch_event(DomEvent::new(
+            select_id,
+            DomEventData::Change(BlitzInputEvent { value }),
+        ));
+    }
+
+    /// Update the `CHECKED` state of a select's options to match their selectedness,
+    /// restyling (and invalidating `:checked` on) only the options whose selectedness changed
+    pub(crate) fn update_select_selectedness(&mut self, select_id: NodeId) {
+        let Some(node) = self.get_node(select_id) else {
+            return;
+        };
+        if !node.data.is_element_with_tag_name(&local_name!("select")) {
+            return;
+        }
+
+        // Both lists are in tree order
+        let mut selected = node.select_selected_options().into_iter().peekable();
+        for option_id in node.select_options() {
+            let is_selected = selected.next_if_eq(&option_id).is_some();
+            if self.nodes[option_id]
+                .element_state()
+                .contains(ElementState::CHECKED)
+                == is_selected
+            {
+                continue;
+            }
+            self.snapshot_node_and(option_id, |node| {
+                if let Some(element) = node.element_data_mut() {
+                    element
+                        .element_state
+                        .set(ElementState::CHECKED, is_selected);
+                }
+            });
+            self.mark_node_changed(option_id);
+        }
+    }
+
+    /// The bounds of the open drop-down listbox of `select_id` in page coordinates (CSS
+    /// pixels). The listbox is placed below the select, or above it if there is not enough
+    /// room in the viewport below it.
+    pub fn select_listbox_rect(&self, select_id: NodeId) -> Option<Rect> {
+        if self.open_select_node_id != Some(select_id) {
+            return None;
+        }
+        let node = &self.nodes[select_id];
+        let select_data = node.element_data()?.select_data()?;
+
+        let pos = node.absolute_position(0.0, 0.0);
+        let size = node.final_layout().size;
+        let width = (size.width as f64).max(
+            select_data.label_width as f64 + 2.0 * (SELECT_LISTBOX_PADDING + SELECT_LISTBOX_BORDER),
+        );
+        let height = select_data.option_labels.len() as f64 * select_data.row_height as f64
+            + 2.0 * SELECT_LISTBOX_BORDER;
+
+        let below = pos.y as f64 + size.height as f64;
+        let viewport_bottom =
+            self.viewport_scroll.y + self.viewport.window_size.1 as f64 / self.viewport.scale_f64();
+        let top = if below + height > viewport_bottom
+            && pos.y as f64 - height >= self.viewport_scroll.y
+        {
+            pos.y as f64 - height
+        } else {
+            below
+        };
+
+        Some(Rect::from_origin_size((pos.x as f64, top), (width, height)))
+    }
+
+    /// The rows of the open drop-down listbox of `select_id` in page coordinates (CSS pixels)
+    pub fn select_listbox_rows(&self, select_id: NodeId) -> Vec<(NodeId, Rect)> {
+        let Some(bounds) = self.select_listbox_rect(select_id) else {
+            return Vec::new();
+        };
+        let select_data = self.nodes[select_id]
+            .element_data()
+            .and_then(|el| el.select_data())
+            .unwrap();
+        let row_height = select_data.row_height as f64;
+        let inner = bounds.inset(-SELECT_LISTBOX_BORDER);
+
+        select_data
+            .option_labels
+            .iter()
+            .enumerate()
+            .map(|(idx, label)| {
+                let y0 = inner.y0 + idx as f64 * row_height;
+                (
+                    label.option_id,
+                    Rect::new(inner.x0, y0, inner.x1, y0 + row_height),
+                )
+            })
+            .collect()
+    }
+
+    /// Hit test the open drop-down listbox (if any), which is painted above all other content.
+    /// Hits are attributed to the select itself.
+    pub(crate) fn hit_open_select_listbox(&self, x: f32, y: f32) -> Option<HitResult> {
+        let select_id = self.open_select_node_id?;
+        let bounds = self.select_listbox_rect(select_id)?;
+        if !bounds.contains(Point::new(x as f64, y as f64)) {
+            return None;
+        }
+        let pos = self.nodes[select_id].absolute_position(0.0, 0.0);
+        Some(HitResult {
+            node_id: select_id,
+            is_text: false,
+            x: x - pos.x,
+            y: y - pos.y,
+        })
+    }
+
+    /// The option at page coordinates (x, y) within a select's open drop-down listbox or
+    /// within an inline listbox select.
+    fn select_option_at_point(&self, select_id: NodeId, x: f32, y: f32) -> Option<NodeId> {
+        if self.open_select_node_id == Some(select_id) {
+            return self
+                .select_listbox_rows(select_id)
+                .into_iter()
+                .find(|(_, row)| row.contains(Point::new(x as f64, y as f64)))
+                .map(|(option_id, _)| option_id);
+        }
+
+        let node = &self.nodes[select_id];
+        let element = node.element_data()?;
+        if select_is_drop_down(element) {
+            return None;
+        }
+        let select_data = element.select_data()?;
+        let layout = node.final_layout();
+        let pos = node.absolute_position(0.0, 0.0);
+        let content_y = y - pos.y - layout.border.top - layout.padding.top;
+        if content_y < 0.0
+            || content_y >= layout.content_box_height()
+            || select_data.row_height <= 0.0
+        {
+            return None;
+        }
+        let idx = (content_y / select_data.row_height) as usize;
+        select_data
+            .option_labels
+            .get(idx)
+            .map(|label| label.option_id)
+    }
+
+    /// The default action for a click on a select: open or close a drop-down select's
+    /// listbox, or pick the clicked option.
+    pub(crate) fn handle_select_click(
+        &mut self,
+        select_id: NodeId,
+        x: f32,
+        y: f32,
+        mods: Modifiers,
+        dispatch_event: &mut dyn FnMut(DomEvent),
+    ) {
+        let node = &self.nodes[select_id];
+        let Some(element) = node.element_data() else {
+            return;
+        };
+        let is_drop_down = select_is_drop_down(element);
+        let is_multiple = element.has_attr(local_name!("multiple"));
+
+        let option_id = self
+            .select_option_at_point(select_id, x, y)
+            .filter(|option_id| !self.nodes[*option_id].option_is_disabled());
+
+        if is_drop_down {
+            if self.open_select_node_id == Some(select_id) {
+                if let Some(option_id) = option_id {
+                    self.set_select_selection(select_id, vec![option_id], dispatch_event);
+                }
+                self.close_select();
+            } else {
+                self.open_select(select_id);
+            }
+            return;
+        }
+
+        let Some(option_id) = option_id else {
+            return;
+        };
+        let mut selection = self.nodes[select_id].select_selected_options();
+        if is_multiple && mods.contains(ACTION_MOD) {
+            if let Some(idx) = selection.iter().position(|id| *id == option_id) {
+                selection.remove(idx);
+            } else {
+                selection.push(option_id);
+            }
+        } else {
+            selection = vec![option_id];
+        }
+        self.set_select_highlighted(select_id, Some(option_id));
+        self.set_select_selection(select_id, selection, dispatch_event);
+    }
+
+    /// Highlight the option under the pointer at page coordinates (x, y) in the open
+    /// drop-down listbox (if any). Returns whether the highlighted option changed.
+    pub(crate) fn hover_open_select_listbox(&mut self, x: f32, y: f32) -> bool {
+        let Some(select_id) = self.open_select_node_id else {
+            return false;
+        };
+        let Some(option_id) = self
+            .select_option_at_point(select_id, x, y)
+            .filter(|option_id| !self.nodes[*option_id].option_is_disabled())
+        else {
+            return false;
+        };
+        let highlighted = self.nodes[select_id]
+            .element_data()
+            .and_then(|el| el.select_data())
+            .and_then(|data| data.highlighted);
+        if highlighted == Some(option_id) {
+            return false;
+        }
+        self.set_select_highlighted(select_id, Some(option_id));
+        true
+    }
+
+    fn set_select_highlighted(&mut self, select_id: NodeId, option_id: Option<NodeId>) {
+        if let Some(select_data) = self.nodes[select_id]
+            .element_data_mut()
+            .and_then(|el| el.select_data_mut())
+        {
+            select_data.highlighted = option_id;
+        }
+        self.shell_provider.request_redraw();
+    }
+
+    /// The default action for a keypress on a focused select: move the selection (or the
+    /// highlighted option of an open listbox) with the arrow/Home/End keys or a typeahead
+    /// search, and open, commit or dismiss a drop-down select's listbox.
+    pub(crate) fn handle_select_keypress(
+        &mut self,
+        select_id: NodeId,
+        event: &BlitzKeyEvent,
+        dispatch_event: &mut dyn FnMut(DomEvent),
+    ) {
+        let node = &self.nodes[select_id];
+        let Some(element) = node.element_data() else {
+            return;
+        };
+        if element.select_data().is_none() || element.has_attr(local_name!("disabled")) {
+            return;
+        }
+        let is_drop_down = select_is_drop_down(element);
+        let is_open = self.open_select_node_id == Some(select_id);
+
+        // The option navigation starts from: the highlighted option of an open or inline
+        // listbox, else the selected option.
+        let current = element
+            .select_data()
+            .and_then(|data| data.highlighted)
+            .filter(|_| is_open || !is_drop_down)
+            .or_else(|| node.select_selected_options().last().copied());
+
+        // Only enabled options can be navigated to
+        let options: Vec<NodeId> = node
+            .select_options()
+            .into_iter()
+            .filter(|option_id| !node.with(*option_id).option_is_disabled())
+            .collect();
+        let current_idx = current.and_then(|id| options.iter().position(|o| *o == id));
+
+        if is_drop_down {
+            match &event.key {
+                Key::ArrowDown | Key::ArrowUp
+                    if !is_open && event.modifiers.contains(Modifiers::ALT) =>
+                {
+                    self.open_select(select_id);
+                    return;
+                }
+                Key::F4 if !is_open => {
+                    self.open_select(select_id);
+                    return;
+                }
+                Key::Character(c) if c == " " && !is_open && self.typeahead_is_empty(select_id) => {
+                    self.open_select(select_id);
+                    return;
+                }
+                Key::Enter if is_open => {
+                    if let Some(option_id) = current {
+                        self.set_select_selection(select_id, vec![option_id], dispatch_event);
+                    }
+                    self.close_select();
+                    return;
+                }
+                Key::Character(c) if c == " " && is_open && self.typeahead_is_empty(select_id) => {
+                    if let Some(option_id) = current {
+                        self.set_select_selection(select_id, vec![option_id], dispatch_event);
+                    }
+                    self.close_select();
+                    return;
+                }
+                Key::Escape if is_open => {
+                    self.close_select();
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        let next = match current_idx {
+            Some(idx) => options.get(idx + 1).or(options.get(idx)),
+            None => options.first(),
+        };
+        let prev = match current_idx {
+            Some(idx) => options.get(idx.saturating_sub(1)),
+            None => options.first(),
+        };
+        let target = match &event.key {
+            Key::ArrowDown => next,
+            Key::ArrowRight if is_drop_down => next,
+            Key::ArrowUp => prev,
+            Key::ArrowLeft if is_drop_down => prev,
+            Key::PageDown | Key::End => options.last(),
+            Key::PageUp | Key::Home => options.first(),
+            Key::Character(c)
+                if !event
+                    .modifiers
+                    .intersects(Modifiers::CONTROL | Modifiers::META | Modifiers::ALT) =>
+            {
+                return self.select_typeahead(select_id, c, &options, current_idx, dispatch_event);
+            }
+            _ => None,
+        };
+
+        if let Some(option_id) = target.copied() {
+            self.move_select_to_option(select_id, option_id, event.modifiers, dispatch_event);
+        }
+    }
+
+    fn typeahead_is_empty(&self, select_id: NodeId) -> bool {
+        self.nodes[select_id]
+            .element_data()
+            .and_then(|el| el.select_data())
+            .is_none_or(|data| {
+                data.typeahead.is_empty()
+                    || data
+                        .typeahead_time
+                        .is_none_or(|time| time.elapsed() > TYPEAHEAD_TIMEOUT)
+            })
+    }
+
+    /// Extend the typeahead search with the typed characters and move to the first option
+    /// whose label starts with the search string. Typing the same character repeatedly
+    /// cycles through the options starting with that character.
+    fn select_typeahead(
+        &mut self,
+        select_id: NodeId,
+        typed: &str,
+        options: &[NodeId],
+        current_idx: Option<usize>,
+        dispatch_event: &mut dyn FnMut(DomEvent),
+    ) {
+        let Some(select_data) = self.nodes[select_id]
+            .element_data_mut()
+            .and_then(|el| el.select_data_mut())
+        else {
+            return;
+        };
+
+        let now = Instant::now();
+        if select_data
+            .typeahead_time
+            .is_none_or(|time| now.duration_since(time) > TYPEAHEAD_TIMEOUT)
+        {
+            select_data.typeahead.clear();
+        }
+        select_data.typeahead_time = Some(now);
+        select_data.typeahead.push_str(&typed.to_lowercase());
+        let search = select_data.typeahead.clone();
+
+        // A search consisting of a single repeated character cycles through the options
+        // starting with that character, starting after the current option.
+        let mut chars = search.chars();
+        let first_char = chars.next();
+        let is_repeated_char = chars.all(|c| Some(c) == first_char);
+        let (search, start) = if is_repeated_char {
+            let search = first_char.map(String::from).unwrap_or_default();
+            (search, current_idx.map(|idx| idx + 1).unwrap_or(0))
+        } else {
+            (search, current_idx.unwrap_or(0))
+        };
+
+        let node = &self.nodes[select_id];
+        let matched = (0..options.len())
+            .map(|offset| options[(start + offset) % options.len()])
+            .find(|option_id| {
+                node.with(*option_id)
+                    .option_label()
+                    .to_lowercase()
+                    .starts_with(&search)
+            });
+
+        if let Some(option_id) = matched {
+            self.move_select_to_option(select_id, option_id, Modifiers::empty(), dispatch_event);
+        }
+    }
+
+    /// Keyboard navigation to an option. In an open drop-down listbox this only moves the
+    /// highlight (the selection is committed when the listbox is closed). Otherwise the
+    /// option becomes the selected option (or, with Ctrl/Cmd held in a `multiple` select,
+    /// just the highlighted option).
+    fn move_select_to_option(
+        &mut self,
+        select_id: NodeId,
+        option_id: NodeId,
+        mods: Modifiers,
+        dispatch_event: &mut dyn FnMut(DomEvent),
+    ) {
+        self.set_select_highlighted(select_id, Some(option_id));
+        if self.open_select_node_id == Some(select_id) {
+            return;
+        }
+        let is_multiple = self.nodes[select_id].data.has_attr(local_name!("multiple"));
+        if is_multiple && mods.contains(ACTION_MOD) {
+            return;
+        }
+        self.set_select_selection(select_id, vec![option_id], dispatch_event);
+    }
+}
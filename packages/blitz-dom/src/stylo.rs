@@ -6,7 +6,7 @@ use std::ptr::NonNull;
 use std::sync::atomic::Ordering;
 
 use crate::StyleThreading;
-use crate::layout::damage::compute_layout_damage;
+use crate::layout::damage::{RESOLVE_COUNTERS, compute_layout_damage};
 use crate::node::Node;
 use crate::node::NodeData;
 use markup5ever::{LocalName, LocalNameStaticSet, Namespace, NamespaceStaticSet, local_name};
@@ -148,6 +148,15 @@ impl crate::document::BaseDocument {
                 .then(|| STYLE_THREAD_POOL.pool());
             let rayon_pool = pool_guard.as_ref().and_then(|g| g.as_ref());
             style::driver::traverse_dom(&traverser, token, rayon_pool);
+
+            // Counters depend on the styles of the whole tree, so they are resolved again if
+            // the counter properties of any element changed
+            for (_, node) in self.nodes.iter_mut() {
+                if node.damage().is_some_and(|d| d.contains(RESOLVE_COUNTERS)) {
+                    node.remove_damage(RESOLVE_COUNTERS);
+                    self.counters_dirty = true;
+                }
+            }
         }
 
         for opaque in self.snapshots.keys() {
@@ -414,22 +423,26 @@ impl selectors::Element for BlitzNode<'_> {
                         && elem.attr(local_name!("href")).is_some()
                 })
                 .unwrap_or(false),
-            NonTSPseudoClass::Checked => self
-                .data
-                .downcast_element()
-                .and_then(|elem| elem.checkbox_input_checked())
-                .unwrap_or(false),
-            NonTSPseudoClass::Valid => false,
-            NonTSPseudoClass::Invalid => false,
+            NonTSPseudoClass::Checked => match self.data.downcast_element() {
+                Some(elem) if elem.name.local == local_name!("option") => self.option_is_selected(),
+                Some(elem) => elem.checkbox_input_checked().unwrap_or(false),
+                None => false,
+            },
+            NonTSPseudoClass::Valid => self.is_valid() == Some(true),
+            NonTSPseudoClass::Invalid => self.is_valid() == Some(false),
             NonTSPseudoClass::Defined => false,
             NonTSPseudoClass::Disabled => self.element_state().contains(ElementState::DISABLED),
             NonTSPseudoClass::Enabled => self.element_state().contains(ElementState::ENABLED),
             NonTSPseudoClass::Focus => self.element_state().contains(ElementState::FOCUS),
-            NonTSPseudoClass::FocusWithin => false,
-            NonTSPseudoClass::FocusVisible => false,
+            NonTSPseudoClass::FocusWithin => {
+                self.element_state().contains(ElementState::FOCUS_WITHIN)
+            }
+            NonTSPseudoClass::FocusVisible => {
+                self.element_state().contains(ElementState::FOCUSRING)
+            }
             NonTSPseudoClass::Fullscreen => false,
             NonTSPseudoClass::Hover => self.element_state().contains(ElementState::HOVER),
-            NonTSPseudoClass::Indeterminate => false,
+            NonTSPseudoClass::Indeterminate => self.is_indeterminate(),
             NonTSPseudoClass::Lang(_) => false,
             NonTSPseudoClass::CustomState(_) => false,
             NonTSPseudoClass::Link => self
@@ -440,24 +453,36 @@ impl selectors::Element for BlitzNode<'_> {
                         && elem.attr(local_name!("href")).is_some()
                 })
                 .unwrap_or(false),
-            NonTSPseudoClass::PlaceholderShown => false,
-            NonTSPseudoClass::ReadWrite => false,
-            NonTSPseudoClass::ReadOnly => false,
+            NonTSPseudoClass::PlaceholderShown => self.is_placeholder_shown(),
+            NonTSPseudoClass::ReadWrite => self.is_read_write(),
+            NonTSPseudoClass::ReadOnly => !self.is_read_write(),
             NonTSPseudoClass::ServoNonZeroBorder => false,
             NonTSPseudoClass::Target => false,
             NonTSPseudoClass::Visited => false,
             NonTSPseudoClass::Autofill => false,
-            NonTSPseudoClass::Default => false,
+            NonTSPseudoClass::Default => self.is_default(),
 
-            NonTSPseudoClass::InRange => false,
-            NonTSPseudoClass::Modal => false,
+            NonTSPseudoClass::InRange => self.is_in_range() == Some(true),
+            NonTSPseudoClass::Modal => self.element_state().contains(ElementState::MODAL),
             NonTSPseudoClass::Open => false,
-            NonTSPseudoClass::Optional => false,
-            NonTSPseudoClass::OutOfRange => false,
-            NonTSPseudoClass::PopoverOpen => false,
-            NonTSPseudoClass::Required => false,
-            NonTSPseudoClass::UserInvalid => false,
-            NonTSPseudoClass::UserValid => false,
+            NonTSPseudoClass::Optional => {
+                self.supports_required() && !self.data.has_attr(local_name!("required"))
+            }
+            NonTSPseudoClass::OutOfRange => self.is_in_range() == Some(false),
+            NonTSPseudoClass::PopoverOpen => {
+                self.element_state().contains(ElementState::POPOVER_OPEN)
+            }
+            NonTSPseudoClass::Required => {
+                self.supports_required() && self.data.has_attr(local_name!("required"))
+            }
+            NonTSPseudoClass::UserInvalid => {
+                self.element_data().is_some_and(|elem| elem.user_validity)
+                    && self.validity().is_some_and(|v| !v.valid())
+            }
+            NonTSPseudoClass::UserValid => {
+                self.element_data().is_some_and(|elem| elem.user_validity)
+                    && self.validity().is_some_and(|v| v.valid())
+            }
             NonTSPseudoClass::MozMeterOptimum => false,
             NonTSPseudoClass::MozMeterSubOptimum => false,
             NonTSPseudoClass::MozMeterSubSubOptimum => false,
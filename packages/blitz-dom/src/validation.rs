@@ -0,0 +1,425 @@
+//! Client-side form validation: the validity states of form controls, which are checked
+//! against the constraints set by their `required`, `pattern`, `min`/`max`, `step`,
+//! `minlength`/`maxlength` and `type` attributes.
+//!
+//! <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#constraints>
+
+use std::sync::LazyLock;
+
+use blitz_traits::node_id::NodeId;
+use markup5ever::local_name;
+use regex::Regex;
+
+use crate::traversal::TreeTraverser;
+use crate::{BaseDocument, ElementData, Node, select_display_size};
+
+/// <https://html.spec.whatwg.org/multipage/input.html#valid-e-mail-address>
+static EMAIL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^[a-zA-Z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)*$",
+    )
+    .unwrap()
+});
+
+/// The ways in which a form control's value fails to satisfy its constraints
+///
+/// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#the-constraint-validation-api>
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValidityState {
+    /// The control is `required` but has no value
+    pub value_missing: bool,
+    /// The value isn't a valid email address or url (`type=email`/`type=url`)
+    pub type_mismatch: bool,
+    /// The value doesn't match the `pattern` attribute
+    pub pattern_mismatch: bool,
+    /// The value (as edited by the user) is longer than the `maxlength` attribute
+    pub too_long: bool,
+    /// The value (as edited by the user) is shorter than the `minlength` attribute
+    pub too_short: bool,
+    /// The value is less than the `min` attribute
+    pub range_underflow: bool,
+    /// The value is greater than the `max` attribute
+    pub range_overflow: bool,
+    /// The value doesn't fit the `step` attribute
+    pub step_mismatch: bool,
+    /// The user entered something that can't be converted to a value (`type=number`)
+    pub bad_input: bool,
+}
+
+impl ValidityState {
+    /// Whether the control satisfies all of its constraints
+    pub fn valid(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Node {
+    /// Whether this element is a form control which is subject to constraint validation. Controls
+    /// which are disabled (including by a disabled `<fieldset>`), readonly or in a `<datalist>`
+    /// are barred from constraint validation.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#candidate-for-constraint-validation>
+    pub fn is_candidate_for_constraint_validation(&self) -> bool {
+        let Some(element) = self.element_data() else {
+            return false;
+        };
+        let barred = match element.name.local {
+            local_name!("input") => {
+                element.has_attr(local_name!("readonly"))
+                    || matches!(
+                        element.attr(local_name!("type")),
+                        Some("hidden" | "reset" | "button" | "submit" | "image")
+                    )
+            }
+            local_name!("textarea") => element.has_attr(local_name!("readonly")),
+            local_name!("select") => false,
+            _ => return false,
+        };
+
+        !barred
+            && !element.has_attr(local_name!("disabled"))
+            && !self.is_in_disabled_fieldset()
+            && !self.has_datalist_ancestor()
+    }
+
+    /// Whether this element is disabled by a disabled `<fieldset>` ancestor. The contents of
+    /// a fieldset's first `<legend>` aren't disabled by it.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#concept-fe-disabled>
+    fn is_in_disabled_fieldset(&self) -> bool {
+        let mut child_id = self.id;
+        let mut ancestor_id = self.parent;
+        while let Some(id) = ancestor_id {
+            let ancestor = self.with(id);
+            if ancestor
+                .data
+                .is_element_with_tag_name(&local_name!("fieldset"))
+                && ancestor.has_attr(local_name!("disabled"))
+            {
+                let first_legend = ancestor.children.iter().copied().find(|&legend_id| {
+                    self.with(legend_id)
+                        .data
+                        .is_element_with_tag_name(&local_name!("legend"))
+                });
+                if first_legend != Some(child_id) {
+                    return true;
+                }
+            }
+            child_id = id;
+            ancestor_id = ancestor.parent;
+        }
+        false
+    }
+
+    fn has_datalist_ancestor(&self) -> bool {
+        let mut ancestor_id = self.parent;
+        while let Some(id) = ancestor_id {
+            let ancestor = self.with(id);
+            if ancestor
+                .data
+                .is_element_with_tag_name(&local_name!("datalist"))
+            {
+                return true;
+            }
+            ancestor_id = ancestor.parent;
+        }
+        false
+    }
+
+    /// The validity state of this form control, or `None` if it isn't a candidate for
+    /// constraint validation
+    pub fn validity(&self) -> Option<ValidityState> {
+        if !self.is_candidate_for_constraint_validation() {
+            return None;
+        }
+        let element = self.element_data()?;
+        let required = element.has_attr(local_name!("required"));
+
+        let mut validity = ValidityState::default();
+        match element.name.local {
+            local_name!("select") => {
+                validity.value_missing = required && self.select_value_missing(element);
+            }
+            local_name!("textarea") => {
+                let value = control_value(element);
+                validity.value_missing = required && value.is_empty();
+                check_length(element, value, &mut validity);
+            }
+            _ => self.input_validity(element, required, &mut validity),
+        }
+        Some(validity)
+    }
+
+    /// Whether this element matches `:valid` (`Some(true)`) or `:invalid` (`Some(false)`). Form
+    /// controls match them according to their validity, and forms and fieldsets match
+    /// `:invalid` if any of the form controls that they contain are invalid.
+    pub fn is_valid(&self) -> Option<bool> {
+        let element = self.element_data()?;
+        match element.name.local {
+            local_name!("form") | local_name!("fieldset") => Some(!element.has_invalid_control),
+            _ => self.validity().map(|validity| validity.valid()),
+        }
+    }
+
+    fn input_validity(&self, element: &ElementData, required: bool, validity: &mut ValidityState) {
+        let input_type = element.attr(local_name!("type")).unwrap_or("text");
+        match input_type {
+            "checkbox" => {
+                validity.value_missing =
+                    required && !element.checkbox_input_checked().unwrap_or(false);
+            }
+            // A radio button's group is `required` if any of its radio buttons are
+            "radio" => {
+                validity.value_missing =
+                    element.radio_group.required && !element.radio_group.has_checked;
+            }
+            "file" => {
+                validity.value_missing = required
+                    && element
+                        .attr(local_name!("value"))
+                        .is_none_or(|value| value.is_empty());
+            }
+            _ => {
+                let value = control_value(element);
+                validity.value_missing = required && value.is_empty();
+                if value.is_empty() {
+                    return;
+                }
+
+                // With `multiple`, an email input's value is a comma-separated list
+                let values: Vec<&str> =
+                    if input_type == "email" && element.has_attr(local_name!("multiple")) {
+                        value
+                            .split(',')
+                            .map(|value| value.trim_matches(|c: char| c.is_ascii_whitespace()))
+                            .collect()
+                    } else {
+                        vec![value]
+                    };
+
+                match input_type {
+                    "email" => {
+                        validity.type_mismatch =
+                            !values.iter().all(|value| EMAIL_REGEX.is_match(value));
+                    }
+                    "url" => validity.type_mismatch = url::Url::parse(value).is_err(),
+                    "number" => check_number(element, value, validity),
+                    _ => {}
+                }
+
+                // Number inputs also have a text editor, but don't support these attributes
+                if let Some(input) = element.text_input_data()
+                    && input_type != "number"
+                {
+                    if let Some(pattern) = &input.pattern {
+                        validity.pattern_mismatch =
+                            !values.iter().all(|value| pattern.is_match(value));
+                    }
+                    check_length(element, value, validity);
+                }
+            }
+        }
+    }
+
+    /// Whether this form control's value is within its `min`/`max` range (`:in-range`) or not
+    /// (`:out-of-range`), or `None` if it has no range limitations or isn't a candidate for
+    /// constraint validation
+    pub fn is_in_range(&self) -> Option<bool> {
+        let element = self.element_data()?;
+        if element.name.local != local_name!("input") {
+            return None;
+        }
+        // Range inputs always have a range (which defaults to 0-100), but their value is
+        // clamped to it
+        let has_range = match element.attr(local_name!("type")) {
+            Some("number") => {
+                element.has_attr(local_name!("min")) || element.has_attr(local_name!("max"))
+            }
+            Some("range") => true,
+            _ => false,
+        };
+        if !has_range {
+            return None;
+        }
+        let validity = self.validity()?;
+        Some(!validity.range_underflow && !validity.range_overflow)
+    }
+
+    /// Whether a select has no selected options other than its placeholder label option
+    fn select_value_missing(&self, element: &ElementData) -> bool {
+        let placeholder = self.select_placeholder_label_option(element);
+        self.select_selected_options()
+            .iter()
+            .all(|option_id| Some(*option_id) == placeholder)
+    }
+
+    /// The first option of a drop-down select, if it has an empty value and is used as a
+    /// placeholder ("Choose one...") rather than a real choice
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-elements.html#placeholder-label-option>
+    fn select_placeholder_label_option(&self, element: &ElementData) -> Option<NodeId> {
+        if element.has_attr(local_name!("multiple")) || select_display_size(element) != 1 {
+            return None;
+        }
+        let option_id = *self.select_options().first()?;
+        let option = self.with(option_id);
+        (option.parent == Some(self.id) && option.option_value().is_empty()).then_some(option_id)
+    }
+}
+
+impl ElementData {
+    /// Compile a text input's `pattern` attribute when it is set or removed, so that it isn't
+    /// recompiled each time `:valid`/`:invalid` are matched
+    pub(crate) fn update_input_pattern(&mut self) {
+        let pattern = self.attr(local_name!("pattern")).and_then(compile_pattern);
+        if let Some(input_data) = self.text_input_data_mut() {
+            input_data.pattern = pattern;
+        }
+    }
+}
+
+impl BaseDocument {
+    /// The form controls owned by a form which don't satisfy their constraints, in tree order
+    ///
+    /// <https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#statically-validate-the-constraints>
+    pub fn invalid_form_controls(&mut self, form_id: NodeId) -> Vec<NodeId> {
+        self.update_radio_groups();
+        TreeTraverser::new(self)
+            .filter(|node_id| self.controls_to_form.get(node_id) == Some(&form_id))
+            .filter(|node_id| {
+                self.nodes[*node_id]
+                    .validity()
+                    .is_some_and(|validity| !validity.valid())
+            })
+            .collect()
+    }
+
+    /// Start reflecting a form control's validity in `:user-valid`/`:user-invalid` (because
+    /// the user has interacted with it, or tried to submit its form)
+    pub(crate) fn set_user_validity(&mut self, node_id: NodeId) {
+        if let Some(element) = self.nodes[node_id].element_data_mut() {
+            element.user_validity = true;
+        }
+        self.invalidate_form_state(node_id);
+    }
+}
+
+/// The value of a form control: the contents of its text editor, or else its `value` attribute
+fn control_value(element: &ElementData) -> &str {
+    match element.text_input_data() {
+        Some(input) => input.editor.raw_text(),
+        None => element.attr(local_name!("value")).unwrap_or_default(),
+    }
+}
+
+/// Check a value against the `minlength` and `maxlength` attributes, which (so that pages can
+/// provide overly long default values) only apply once the user has edited the value
+fn check_length(element: &ElementData, value: &str, validity: &mut ValidityState) {
+    let user_edited = element
+        .text_input_data()
+        .is_some_and(|input| input.user_edited);
+    if !user_edited || value.is_empty() {
+        return;
+    }
+
+    // Lengths are measured in UTF-16 code units
+    let length = value.encode_utf16().count();
+    validity.too_long = element
+        .attr_parsed::<usize>(local_name!("maxlength"))
+        .is_some_and(|max| length > max);
+    validity.too_short = element
+        .attr_parsed::<usize>(local_name!("minlength"))
+        .is_some_and(|min| length < min);
+}
+
+/// Check the value of a `type=number` input against its `min`, `max` and `step` attributes
+fn check_number(element: &ElementData, value: &str, validity: &mut ValidityState) {
+    let Some(number) = parse_floating_point_number(value) else {
+        validity.bad_input = true;
+        return;
+    };
+
+    let min = element
+        .attr(local_name!("min"))
+        .and_then(parse_floating_point_number);
+    let max = element
+        .attr(local_name!("max"))
+        .and_then(parse_floating_point_number);
+    validity.range_underflow = min.is_some_and(|min| number < min);
+    validity.range_overflow = max.is_some_and(|max| number > max);
+
+    // <https://html.spec.whatwg.org/multipage/input.html#concept-input-step>
+    let step = match element.attr(local_name!("step")) {
+        Some(step) if step.eq_ignore_ascii_case("any") => None,
+        Some(step) => parse_floating_point_number(step)
+            .filter(|step| *step > 0.0)
+            .or(Some(1.0)),
+        None => Some(1.0),
+    };
+    if let Some(step) = step {
+        let steps = (number - min.unwrap_or(0.0)) / step;
+        // Allow for floating point error (e.g. with `step=0.1`)
+        validity.step_mismatch = (steps - steps.round()).abs() > 1e-7;
+    }
+}
+
+/// <https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-floating-point-number-values>
+fn parse_floating_point_number(value: &str) -> Option<f64> {
+    // Rust also parses a leading `+`, `inf`, `NaN`, etc, none of which are valid here
+    let valid_chars = value
+        .bytes()
+        .all(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E'));
+    if !valid_chars || value.starts_with('+') {
+        return None;
+    }
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+/// Compile a `pattern` attribute, which must match the whole of a value. Invalid patterns are
+/// ignored (`None`).
+fn compile_pattern(pattern: &str) -> Option<Regex> {
+    Regex::new(&format!("^(?:{pattern})$")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_addresses() {
+        assert!(EMAIL_REGEX.is_match("someone@example.com"));
+        assert!(EMAIL_REGEX.is_match("first.last+tag@sub.example-domain.org"));
+        assert!(EMAIL_REGEX.is_match("local@localhost"));
+        assert!(!EMAIL_REGEX.is_match("someone"));
+        assert!(!EMAIL_REGEX.is_match("someone@"));
+        assert!(!EMAIL_REGEX.is_match("some one@example.com"));
+        assert!(!EMAIL_REGEX.is_match("someone@-example.com"));
+    }
+
+    #[test]
+    fn floating_point_numbers() {
+        assert_eq!(parse_floating_point_number("12"), Some(12.0));
+        assert_eq!(parse_floating_point_number("-1.5e2"), Some(-150.0));
+        assert_eq!(parse_floating_point_number(".5"), Some(0.5));
+        assert_eq!(parse_floating_point_number("+1"), None);
+        assert_eq!(parse_floating_point_number("inf"), None);
+        assert_eq!(parse_floating_point_number("1e999"), None);
+        assert_eq!(parse_floating_point_number("12px"), None);
+        assert_eq!(parse_floating_point_number(""), None);
+    }
+
+    #[test]
+    fn patterns_match_the_whole_value() {
+        let letters = compile_pattern("[a-z]+").unwrap();
+        assert!(letters.is_match("abc"));
+        assert!(!letters.is_match("abc1"));
+        let alternatives = compile_pattern("a|b").unwrap();
+        assert!(alternatives.is_match("b"));
+        assert!(!alternatives.is_match("ab"));
+        // Invalid patterns are ignored
+        assert!(compile_pattern("(").is_none());
+    }
+}
@@ -51,17 +51,23 @@ impl HtmlDocument {
         Self::parse_with(xml, config, DocumentHtmlParser::parse_xml_into_mutator)
     }
 
-    fn parse_with(
-        content: &str,
-        mut config: DocumentConfig,
-        parse: impl for<'a, 'd> Fn(&'a mut blitz_dom::DocumentMutator<'d>, &str),
-    ) -> Self {
+    /// Create an empty [`BaseDocument`], ensuring that the default UA stylesheet is included
+    /// if custom UA stylesheets were specified.
+    pub(crate) fn create_base_document(mut config: DocumentConfig) -> BaseDocument {
         if let Some(ss) = &mut config.ua_stylesheets {
             if !ss.iter().any(|s| s == DEFAULT_CSS) {
                 ss.push(String::from(DEFAULT_CSS));
             }
         }
-        let mut doc = BaseDocument::new(config);
+        BaseDocument::new(config)
+    }
+
+    fn parse_with(
+        content: &str,
+        config: DocumentConfig,
+        parse: impl for<'a, 'd> Fn(&'a mut blitz_dom::DocumentMutator<'d>, &str),
+    ) -> Self {
+        let mut doc = Self::create_base_document(config);
         let mut mutr = doc.mutate();
         parse(&mut mutr, content);
         drop(mutr);
@@ -23,6 +23,20 @@ fn html5ever_to_blitz_attr(attr: html5ever::Attribute) -> Attribute {
     }
 }
 
+/// The options used to parse HTML documents and fragments
+pub(crate) fn html_parse_opts() -> ParseOpts {
+    ParseOpts {
+        tokenizer: TokenizerOpts::default(),
+        tree_builder: TreeBuilderOpts {
+            exact_errors: false,
+            scripting_enabled: false, // Enables parsing of <noscript> tags
+            iframe_srcdoc: false,
+            drop_doctype: true,
+            quirks_mode: QuirksMode::NoQuirks,
+        },
+    }
+}
+
 #[derive(Copy, Clone, Default, Debug)]
 pub struct HtmlProvider;
 
@@ -102,17 +116,7 @@ impl<'m, 'doc> DocumentHtmlParser<'m, 'doc> {
             // Parse as HTML
             let mut sink = DocumentHtmlParser::new(mutr);
             sink.is_xml = false;
-            let opts = ParseOpts {
-                tokenizer: TokenizerOpts::default(),
-                tree_builder: TreeBuilderOpts {
-                    exact_errors: false,
-                    scripting_enabled: false, // Enables parsing of <noscript> tags
-                    iframe_srcdoc: false,
-                    drop_doctype: true,
-                    quirks_mode: QuirksMode::NoQuirks,
-                },
-            };
-            html5ever::parse_document(sink, opts)
+            html5ever::parse_document(sink, html_parse_opts())
                 .from_utf8()
                 .read_from(&mut html.as_bytes())
                 .unwrap();
@@ -141,17 +145,7 @@ impl<'m, 'doc> DocumentHtmlParser<'m, 'doc> {
         html: &str,
     ) {
         let sink = DocumentHtmlParser::new(mutr);
-
-        let opts = ParseOpts {
-            tokenizer: TokenizerOpts::default(),
-            tree_builder: TreeBuilderOpts {
-                exact_errors: false,
-                scripting_enabled: false, // Enables parsing of <noscript> tags
-                iframe_srcdoc: false,
-                drop_doctype: true,
-                quirks_mode: QuirksMode::NoQuirks,
-            },
-        };
+        let opts = html_parse_opts();
         html5ever::driver::parse_fragment_for_element(sink, opts, element_id, false, None)
             .from_utf8()
             .read_from(&mut html.as_bytes())
@@ -0,0 +1,486 @@
+//! Incremental parsing of HTML into a live document as its bytes arrive over the network.
+
+use std::borrow::Cow;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::sync::{Arc, Mutex};
+use std::task::{Context as TaskContext, Waker};
+
+use blitz_dom::{
+    BaseDocument, DocGuard, DocGuardMut, Document, DocumentConfig, DocumentMutator, EventDriver,
+    NodeId, NoopEventHandler, PendingMutations,
+};
+use blitz_traits::net::Bytes;
+use encoding_rs::{Decoder, Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252, X_USER_DEFINED};
+use html5ever::driver::Parser;
+use html5ever::{
+    QualName,
+    tendril::{StrTendril, TendrilSink},
+    tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink},
+};
+
+use crate::html_sink::html_parse_opts;
+use crate::{DocumentHtmlParser, HtmlDocument};
+
+/// The number of bytes which are buffered (if the encoding is not otherwise known) and then
+/// scanned for a `<meta charset>` before parsing begins.
+const PRESCAN_LENGTH: usize = 1024;
+
+/// An HTML document which is parsed incrementally as its bytes arrive.
+///
+/// Bytes may either be written directly (using [`write`](Self::write) and
+/// [`finish`](Self::finish)) or sent from another thread using a [`HtmlStreamSender`], in which
+/// case they are parsed the next time the document is [`poll`](Document::poll)ed. In either
+/// case the partially parsed document can be resolved and painted between chunks. Linked
+/// stylesheets in the `<head>` block rendering (see
+/// [`has_pending_critical_resources`](BaseDocument::has_pending_critical_resources)) as they
+/// would for a fully-parsed document.
+///
+/// Unlike [`HtmlDocument::from_html`], the content is always parsed as HTML (never as XHTML).
+pub struct StreamingHtmlDocument {
+    doc: Rc<RefCell<BaseDocument>>,
+    /// The mutations made while parsing the current chunk, which are flushed once the whole
+    /// chunk has been parsed
+    pending: Rc<RefCell<PendingMutations>>,
+    /// The HTML parser. `None` once the document has finished parsing.
+    parser: Option<Parser<StreamingSink>>,
+    decoding: DecoderState,
+    sender: Sender<StreamMessage>,
+    receiver: Receiver<StreamMessage>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl StreamingHtmlDocument {
+    /// Create an empty document whose character encoding is determined from the content
+    /// (a byte order mark or `<meta charset>`), defaulting to UTF-8.
+    pub fn new(config: DocumentConfig) -> Self {
+        Self::with_content_type(config, None)
+    }
+
+    /// Create an empty document, using the `charset` parameter of the `Content-Type` the
+    /// document was served with (if any) as its character encoding.
+    pub fn with_content_type(config: DocumentConfig, content_type: Option<&str>) -> Self {
+        let doc = Rc::new(RefCell::new(HtmlDocument::create_base_document(config)));
+        let pending = Rc::new(RefCell::new(PendingMutations::default()));
+        let sink = StreamingSink {
+            doc: doc.clone(),
+            pending: pending.clone(),
+            errors: RefCell::new(Vec::new()),
+        };
+        let parser = html5ever::parse_document(sink, html_parse_opts());
+
+        let transport_encoding = content_type.and_then(charset_from_content_type);
+        let decoding = match transport_encoding {
+            // A byte order mark still takes precedence over the transport encoding. This is
+            // handled by the decoder.
+            Some(encoding) => DecoderState::Decoding(encoding.new_decoder()),
+            None => DecoderState::Sniffing(Vec::new()),
+        };
+
+        let (sender, receiver) = channel();
+        Self {
+            doc,
+            pending,
+            parser: Some(parser),
+            decoding,
+            sender,
+            receiver,
+            waker: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Create a [`HtmlStreamSender`] which can be used to send bytes to this document from
+    /// another thread (e.g. as they are received from the network)
+    pub fn sender(&self) -> HtmlStreamSender {
+        HtmlStreamSender {
+            sender: self.sender.clone(),
+            waker: self.waker.clone(),
+        }
+    }
+
+    /// Whether the whole document has been parsed
+    pub fn is_finished(&self) -> bool {
+        self.parser.is_none()
+    }
+
+    /// Parse the next chunk of the document
+    pub fn write(&mut self, bytes: &[u8]) {
+        if self.is_finished() {
+            return;
+        }
+
+        let text = match &mut self.decoding {
+            DecoderState::Decoding(decoder) => decode(decoder, bytes, false),
+            DecoderState::Sniffing(buffer) => {
+                buffer.extend_from_slice(bytes);
+                if buffer.len() < PRESCAN_LENGTH {
+                    return;
+                }
+                let buffer = std::mem::take(buffer);
+                let mut decoder = sniff_encoding(&buffer).new_decoder();
+                let text = decode(&mut decoder, &buffer, false);
+                self.decoding = DecoderState::Decoding(decoder);
+                text
+            }
+        };
+
+        self.process(text);
+    }
+
+    /// Signal that the whole document has been received, parsing any remaining content
+    pub fn finish(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+
+        let text = match &mut self.decoding {
+            DecoderState::Decoding(decoder) => decode(decoder, &[], true),
+            DecoderState::Sniffing(buffer) => {
+                let mut decoder = sniff_encoding(buffer).new_decoder();
+                decode(&mut decoder, buffer, true)
+            }
+        };
+        self.process(text);
+
+        if let Some(parser) = self.parser.take() {
+            parser.finish();
+        }
+        self.flush_mutations();
+    }
+
+    /// Finish parsing the document and convert it into its inner [`BaseDocument`]
+    pub fn into_inner(mut self) -> BaseDocument {
+        self.finish();
+        match Rc::try_unwrap(self.doc) {
+            Ok(doc) => doc.into_inner(),
+            Err(_) => unreachable!("the document is only shared with the parser"),
+        }
+    }
+
+    fn process(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(parser) = &mut self.parser {
+            parser.process(StrTendril::from(text));
+        }
+        self.flush_mutations();
+    }
+
+    /// Flush the mutations made while parsing a chunk (e.g. applying `<style>` elements)
+    fn flush_mutations(&mut self) {
+        let pending = self.pending.take();
+        let mut doc = self.doc.borrow_mut();
+        drop(DocumentMutator::resume(&mut doc, pending));
+    }
+}
+
+impl Document for StreamingHtmlDocument {
+    fn inner(&self) -> DocGuard<'_> {
+        DocGuard::RefCell(self.doc.borrow())
+    }
+
+    fn inner_mut(&mut self) -> DocGuardMut<'_> {
+        DocGuardMut::RefCell(self.doc.borrow_mut())
+    }
+
+    fn poll(&mut self, task_context: Option<TaskContext>) -> bool {
+        if let Some(cx) = task_context {
+            *self.waker.lock().unwrap() = Some(cx.waker().clone());
+        }
+
+        let mut changed = false;
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                StreamMessage::Chunk(bytes) => self.write(&bytes),
+                StreamMessage::End => self.finish(),
+            }
+            changed = true;
+        }
+
+        let mut doc = self.inner_mut();
+        let mut driver = EventDriver::new(&mut *doc, NoopEventHandler);
+        driver.dispatch_pending_events();
+
+        changed
+    }
+}
+
+enum StreamMessage {
+    Chunk(Bytes),
+    End,
+}
+
+/// Sends the bytes of a document to a [`StreamingHtmlDocument`], waking the document's event
+/// loop so that they are parsed promptly.
+#[derive(Clone)]
+pub struct HtmlStreamSender {
+    sender: Sender<StreamMessage>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+impl HtmlStreamSender {
+    /// Send the next chunk of the document
+    pub fn send(&self, bytes: impl Into<Bytes>) {
+        self.send_message(StreamMessage::Chunk(bytes.into()));
+    }
+
+    /// Signal that the whole document has been sent
+    pub fn finish(self) {
+        self.send_message(StreamMessage::End);
+    }
+
+    fn send_message(&self, message: StreamMessage) {
+        // The document may have been dropped, in which case there is nothing to do
+        if self.sender.send(message).is_ok() {
+            if let Some(waker) = self.waker.lock().unwrap().as_ref() {
+                waker.wake_by_ref();
+            }
+        }
+    }
+}
+
+/// The state of the conversion of the document's bytes into UTF-8
+enum DecoderState {
+    /// Buffering the start of the document until its encoding can be determined
+    Sniffing(Vec<u8>),
+    Decoding(Decoder),
+}
+
+fn decode(decoder: &mut Decoder, bytes: &[u8], last: bool) -> String {
+    let capacity = decoder
+        .max_utf8_buffer_length(bytes.len())
+        .unwrap_or(bytes.len());
+    let mut text = String::with_capacity(capacity);
+    let _ = decoder.decode_to_string(bytes, &mut text, last);
+    text
+}
+
+/// Determine the encoding of a document from its first bytes.
+///
+/// <https://html.spec.whatwg.org/multipage/parsing.html#determining-the-character-encoding>
+fn sniff_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _bom_length)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    // The spec allows a locale-dependent default. We always use UTF-8.
+    prescan(bytes).unwrap_or(UTF_8)
+}
+
+/// A simplified version of the "prescan a byte stream to determine its encoding" algorithm.
+///
+/// <https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding>
+fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+    let bytes = &bytes[..bytes.len().min(PRESCAN_LENGTH)];
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let rest = &bytes[pos..];
+        if rest.starts_with(b"<!--") {
+            pos += find(rest, b"-->").map_or(rest.len(), |end| end + 3);
+        } else if rest.len() > 5
+            && rest[..5].eq_ignore_ascii_case(b"<meta")
+            && (rest[5].is_ascii_whitespace() || rest[5] == b'/')
+        {
+            let end = find(rest, b">").unwrap_or(rest.len());
+            let attrs = String::from_utf8_lossy(&rest[5..end]).to_ascii_lowercase();
+            if let Some(encoding) = extract_charset(&attrs).and_then(encoding_for_label) {
+                // Documents can't declare themselves as UTF-16 as it isn't ASCII-compatible
+                return Some(match encoding {
+                    e if e == UTF_16BE || e == UTF_16LE => UTF_8,
+                    e if e == X_USER_DEFINED => WINDOWS_1252,
+                    e => e,
+                });
+            }
+            pos += end;
+        } else {
+            pos += 1;
+        }
+    }
+    None
+}
+
+fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    extract_charset(&content_type.to_ascii_lowercase()).and_then(encoding_for_label)
+}
+
+fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    Encoding::for_label(label.as_bytes())
+}
+
+/// Extract the value of a `charset=` parameter (from a lowercased `Content-Type` value or
+/// the attributes of a `<meta>` tag)
+fn extract_charset(s: &str) -> Option<&str> {
+    let rest = &s[s.find("charset")? + "charset".len()..];
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let value = match rest.strip_prefix(['"', '\'']) {
+        Some(quoted) => {
+            let quote = rest.as_bytes()[0] as char;
+            &quoted[..quoted.find(quote).unwrap_or(quoted.len())]
+        }
+        None => {
+            let end = rest
+                .find(|c: char| c.is_ascii_whitespace() || matches!(c, ';' | '"' | '\'' | '/'))
+                .unwrap_or(rest.len());
+            &rest[..end]
+        }
+    };
+    (!value.is_empty()).then_some(value)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// A [`TreeSink`] which writes into a document that is shared with its owning
+/// [`StreamingHtmlDocument`] (so that the document can be accessed between chunks). Each
+/// operation is applied using a fresh [`DocumentHtmlParser`], resuming the mutations of the
+/// current chunk so that they are only flushed once the chunk has been parsed.
+struct StreamingSink {
+    doc: Rc<RefCell<BaseDocument>>,
+    pending: Rc<RefCell<PendingMutations>>,
+    errors: RefCell<Vec<Cow<'static, str>>>,
+}
+
+impl StreamingSink {
+    fn with_parser<R>(&self, f: impl FnOnce(&DocumentHtmlParser) -> R) -> R {
+        let mut doc = self.doc.borrow_mut();
+        let mut mutr = DocumentMutator::resume(&mut doc, self.pending.take());
+        let result = f(&DocumentHtmlParser::new(&mut mutr));
+        *self.pending.borrow_mut() = mutr.suspend();
+        result
+    }
+}
+
+impl TreeSink for StreamingSink {
+    type Output = ();
+
+    type Handle = NodeId;
+
+    type ElemName<'a>
+        = Ref<'a, QualName>
+    where
+        Self: 'a;
+
+    fn finish(self) -> Self::Output {
+        #[cfg(feature = "tracing")]
+        for error in self.errors.borrow().iter() {
+            tracing::error!("{error}");
+        }
+    }
+
+    fn parse_error(&self, msg: Cow<'static, str>) {
+        self.errors.borrow_mut().push(msg);
+    }
+
+    fn get_document(&self) -> Self::Handle {
+        self.doc.borrow().root_node().id
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a Self::Handle) -> Self::ElemName<'a> {
+        Ref::map(self.doc.borrow(), |doc| {
+            doc.get_node(*target)
+                .and_then(|node| node.element_data())
+                .map(|element| &element.name)
+                .expect("TreeSink::elem_name called on a node which is not an element!")
+        })
+    }
+
+    fn create_element(
+        &self,
+        name: QualName,
+        attrs: Vec<html5ever::Attribute>,
+        flags: ElementFlags,
+    ) -> Self::Handle {
+        self.with_parser(|parser| parser.create_element(name, attrs, flags))
+    }
+
+    fn create_comment(&self, text: StrTendril) -> Self::Handle {
+        self.with_parser(|parser| parser.create_comment(text))
+    }
+
+    fn create_pi(&self, target: StrTendril, data: StrTendril) -> Self::Handle {
+        self.with_parser(|parser| parser.create_pi(target, data))
+    }
+
+    fn append(&self, parent: &Self::Handle, child: NodeOrText<Self::Handle>) {
+        self.with_parser(|parser| parser.append(parent, child))
+    }
+
+    fn append_before_sibling(&self, sibling: &Self::Handle, new_node: NodeOrText<Self::Handle>) {
+        self.with_parser(|parser| parser.append_before_sibling(sibling, new_node))
+    }
+
+    fn append_based_on_parent_node(
+        &self,
+        element: &Self::Handle,
+        prev_element: &Self::Handle,
+        child: NodeOrText<Self::Handle>,
+    ) {
+        self.with_parser(|parser| parser.append_based_on_parent_node(element, prev_element, child))
+    }
+
+    fn append_doctype_to_document(
+        &self,
+        _name: StrTendril,
+        _public_id: StrTendril,
+        _system_id: StrTendril,
+    ) {
+        // Ignore. We don't care about the DOCTYPE for now.
+    }
+
+    fn get_template_contents(&self, target: &Self::Handle) -> Self::Handle {
+        *target
+    }
+
+    fn same_node(&self, x: &Self::Handle, y: &Self::Handle) -> bool {
+        x == y
+    }
+
+    fn set_quirks_mode(&self, _mode: QuirksMode) {}
+
+    fn add_attrs_if_missing(&self, target: &Self::Handle, attrs: Vec<html5ever::Attribute>) {
+        self.with_parser(|parser| parser.add_attrs_if_missing(target, attrs))
+    }
+
+    fn remove_from_parent(&self, target: &Self::Handle) {
+        self.with_parser(|parser| parser.remove_from_parent(target))
+    }
+
+    fn reparent_children(&self, node: &Self::Handle, new_parent: &Self::Handle) {
+        self.with_parser(|parser| parser.reparent_children(node, new_parent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_charset_parameter() {
+        assert_eq!(extract_charset("text/html; charset=utf-8"), Some("utf-8"));
+        assert_eq!(
+            extract_charset(r#" charset="windows-1252""#),
+            Some("windows-1252")
+        );
+        assert_eq!(
+            extract_charset(r#" http-equiv="content-type" content="text/html; charset=koi8-r""#),
+            Some("koi8-r")
+        );
+        assert_eq!(extract_charset("text/html"), None);
+    }
+
+    #[test]
+    fn prescan_finds_meta_charset() {
+        assert_eq!(
+            prescan(b"<!doctype html><meta charset=\"iso-8859-1\">"),
+            Some(WINDOWS_1252)
+        );
+        // `<meta>` inside comments is ignored
+        assert_eq!(prescan(b"<!-- <meta charset=\"koi8-r\"> --><p>"), None);
+        // UTF-16 declarations are treated as UTF-8
+        assert_eq!(prescan(b"<META CHARSET=utf-16le>"), Some(UTF_8));
+    }
+}
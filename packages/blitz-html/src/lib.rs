@@ -2,7 +2,9 @@
 
 mod html_document;
 mod html_sink;
+mod html_stream;
 
 pub use html_document::HtmlDocument;
 pub use html_sink::DocumentHtmlParser;
 pub use html_sink::HtmlProvider;
+pub use html_stream::{HtmlStreamSender, StreamingHtmlDocument};
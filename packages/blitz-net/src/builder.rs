@@ -0,0 +1,275 @@
+//! Configuration of a [`Provider`](crate::Provider)'s HTTP behaviour
+
+use blitz_traits::net::{HeaderMap, Method, NetWaker, StatusCode};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{DummyNetWaker, Provider, ProviderError};
+
+/// The user agent sent with requests unless one is configured
+pub(crate) const DEFAULT_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64; rv:60.0) Gecko/20100101 Firefox/81.0";
+
+/// Matches real browsers' per-origin cap of 6.
+pub(crate) const DEFAULT_PER_HOST_MAX_CONCURRENT: usize = 6;
+
+/// How failed HTTP requests are retried.
+///
+/// Only idempotent requests (`GET`, `HEAD`, `OPTIONS`, `TRACE`, `PUT` and `DELETE`) are
+/// retried, and only when they fail with a connection error, a timeout, or a status which
+/// indicates a transient failure (`408`, `429`, `500`, `502`, `503` or `504`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of times a request is retried after the first attempt
+    pub max_retries: u32,
+    /// The delay before the first retry. The delay doubles after every retry.
+    pub initial_backoff: Duration,
+    /// The maximum delay between retries (also the maximum `Retry-After` that is honoured)
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry requests
+    pub const fn none() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+
+    /// Retry requests up to `max_retries` times, starting with a 200ms delay
+    pub const fn retries(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::none()
+        }
+    }
+
+    /// The delay before retry number `retry` (starting from 0)
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+
+    /// Whether a request with the given method may be retried
+    pub(crate) fn is_idempotent(method: &Method) -> bool {
+        matches!(
+            *method,
+            Method::GET
+                | Method::HEAD
+                | Method::OPTIONS
+                | Method::TRACE
+                | Method::PUT
+                | Method::DELETE
+        )
+    }
+
+    /// Whether a response with the given status may succeed if retried
+    pub(crate) fn is_transient_status(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::REQUEST_TIMEOUT
+                | StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+}
+
+/// How requests made by a [`Provider`] behave, shared by its in-flight requests
+#[derive(Debug)]
+pub(crate) struct RequestPolicy {
+    pub(crate) user_agent: String,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) per_host_max_concurrent: usize,
+}
+
+/// A builder for a [`Provider`] with a custom user agent, default headers, timeouts,
+/// redirect and retry policies, proxies and per-host concurrency.
+///
+/// ```no_run
+/// use blitz_net::{Provider, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let provider = Provider::builder()
+///     .user_agent("MyApp/1.0")
+///     .timeout(Duration::from_secs(30))
+///     .max_redirects(5)
+///     .retry(RetryPolicy::retries(3))
+///     .build()
+///     .unwrap();
+/// ```
+pub struct ProviderBuilder {
+    waker: Option<Arc<dyn NetWaker>>,
+    user_agent: String,
+    default_headers: HeaderMap,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    max_redirects: usize,
+    retry: RetryPolicy,
+    proxies: Vec<reqwest::Proxy>,
+    no_proxy: bool,
+    per_host_max_concurrent: usize,
+}
+
+impl Default for ProviderBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProviderBuilder {
+    pub fn new() -> Self {
+        Self {
+            waker: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            default_headers: HeaderMap::new(),
+            timeout: None,
+            connect_timeout: None,
+            max_redirects: 10,
+            retry: RetryPolicy::none(),
+            proxies: Vec::new(),
+            no_proxy: false,
+            per_host_max_concurrent: DEFAULT_PER_HOST_MAX_CONCURRENT,
+        }
+    }
+
+    /// The waker which is notified when a document's request completes
+    pub fn waker(mut self, waker: Option<Arc<dyn NetWaker>>) -> Self {
+        self.waker = waker;
+        self
+    }
+
+    /// The `User-Agent` header sent with every HTTP request
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Headers sent with every HTTP request (unless the request sets them itself)
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = headers;
+        self
+    }
+
+    /// The maximum time a request may take, from sending it until its whole body has been
+    /// received. Applies to each attempt separately when requests are retried.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The maximum time taken to connect to a server
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// The maximum number of redirects followed for a request (`0` to not follow
+    /// redirects). Defaults to 10.
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    /// How failed requests are retried. Defaults to [`RetryPolicy::none`].
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Send requests through a proxy. May be called multiple times to add proxies for
+    /// different schemes or hosts. Without any proxies the system proxy settings are used.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Don't use any proxies (including the system proxy settings)
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// The maximum number of concurrent requests to a single host. Defaults to 6.
+    pub fn per_host_max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.per_host_max_concurrent = max_concurrent.max(1);
+        self
+    }
+
+    /// Build the [`Provider`]. Fails if the HTTP client can't be initialised (e.g. if the
+    /// TLS backend can't be loaded).
+    pub fn build(self) -> Result<Provider, ProviderError> {
+        let builder = reqwest::Client::builder().default_headers(self.default_headers);
+        #[cfg(feature = "cookies")]
+        let builder = builder.cookie_store(true);
+
+        // Redirects, proxies and connections are handled by the browser on wasm
+        #[cfg(not(target_arch = "wasm32"))]
+        let builder = {
+            let mut builder = builder.redirect(match self.max_redirects {
+                0 => reqwest::redirect::Policy::none(),
+                max => reqwest::redirect::Policy::limited(max),
+            });
+            if let Some(timeout) = self.connect_timeout {
+                builder = builder.connect_timeout(timeout);
+            }
+            if self.no_proxy {
+                builder = builder.no_proxy();
+            }
+            for proxy in self.proxies {
+                builder = builder.proxy(proxy);
+            }
+            builder
+        };
+
+        let client = builder.build()?;
+        let policy = RequestPolicy {
+            user_agent: self.user_agent,
+            timeout: self.timeout,
+            retry: self.retry,
+            per_host_max_concurrent: self.per_host_max_concurrent,
+        };
+        let waker = self.waker.unwrap_or(Arc::new(DummyNetWaker));
+
+        Ok(Provider::from_client(client, waker, policy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn only_idempotent_requests_are_retried() {
+        assert!(RetryPolicy::is_idempotent(&Method::GET));
+        assert!(RetryPolicy::is_idempotent(&Method::PUT));
+        assert!(!RetryPolicy::is_idempotent(&Method::POST));
+        assert!(!RetryPolicy::is_idempotent(&Method::PATCH));
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! Provides an implementation of the [`blitz_traits::net::NetProvider`] trait.
 
-use blitz_traits::net::{AbortSignal, Body, Bytes, NetHandler, NetProvider, NetWaker, Request};
+use blitz_traits::net::{
+    AbortSignal, Body, Bytes, NetError, NetHandler, NetProvider, NetWaker, Request, Response,
+};
 use data_url::DataUrl;
 use std::{
     collections::HashMap,
@@ -10,19 +12,20 @@ use std::{
     pin::Pin,
     sync::{Arc, Mutex},
     task::Poll,
+    time::Duration,
 };
-use tokio::sync::Semaphore;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+mod builder;
+
+use builder::RequestPolicy;
+pub use builder::{ProviderBuilder, RetryPolicy};
 
 #[cfg(feature = "cache")]
 use http_cache_reqwest::{
     CACacheManager, Cache, CacheMode, CacheOptions, HttpCache, HttpCacheOptions,
 };
 
-const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:60.0) Gecko/20100101 Firefox/81.0";
-
-/// Matches real browsers' per-origin cap of 6.
-const PER_HOST_MAX_CONCURRENT: usize = 6;
-
 type HostLimits = Arc<Mutex<HashMap<String, Arc<Semaphore>>>>;
 
 #[cfg(feature = "cache")]
@@ -75,20 +78,58 @@ where
     tokio::spawn(fut);
 }
 
-pub struct Provider {
+#[cfg(not(target_arch = "wasm32"))]
+async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Wait for a `setTimeout` (as there is no tokio timer on wasm)
+#[cfg(target_arch = "wasm32")]
+async fn sleep(duration: Duration) {
+    let millis = duration.as_millis().min(i32::MAX as u128) as i32;
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let scheduled = web_sys::window().is_some_and(|window| {
+            window
+                .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+                .is_ok()
+        });
+        // Outside of a window (e.g. in a worker) retry without waiting
+        if !scheduled {
+            let _ = resolve.call0(&wasm_bindgen::JsValue::UNDEFINED);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// The state shared by a [`Provider`]'s in-flight requests
+#[derive(Clone)]
+struct FetchContext {
     client: Client,
-    waker: Arc<dyn NetWaker>,
     per_host_limits: HostLimits,
+    policy: Arc<RequestPolicy>,
+}
+
+pub struct Provider {
+    context: FetchContext,
+    waker: Arc<dyn NetWaker>,
     #[cfg(feature = "cache")]
     cache_manager: CACacheManager,
 }
 impl Provider {
     pub fn new(waker: Option<Arc<dyn NetWaker>>) -> Self {
-        let builder = reqwest::Client::builder();
-        #[cfg(feature = "cookies")]
-        let builder = builder.cookie_store(true);
-        let client = builder.build().unwrap();
+        ProviderBuilder::new().waker(waker).build().unwrap()
+    }
 
+    /// A builder for a provider with a custom user agent, timeouts, retries, etc
+    pub fn builder() -> ProviderBuilder {
+        ProviderBuilder::new()
+    }
+
+    fn from_client(
+        client: reqwest::Client,
+        waker: Arc<dyn NetWaker>,
+        policy: RequestPolicy,
+    ) -> Self {
         #[cfg(feature = "cache")]
         let cache_manager = CACacheManager::new(get_cache_path(), true);
 
@@ -115,11 +156,13 @@ impl Provider {
             }))
             .build();
 
-        let waker = waker.unwrap_or(Arc::new(DummyNetWaker));
         Self {
-            client,
+            context: FetchContext {
+                client,
+                per_host_limits: Arc::new(Mutex::new(HashMap::new())),
+                policy: Arc::new(policy),
+            },
             waker,
-            per_host_limits: Arc::new(Mutex::new(HashMap::new())),
             #[cfg(feature = "cache")]
             cache_manager,
         }
@@ -146,30 +189,99 @@ impl Provider {
 }
 impl Provider {
     async fn fetch_inner(
-        client: Client,
+        context: FetchContext,
         request: Request,
-        per_host_limits: HostLimits,
-    ) -> Result<(String, Bytes), ProviderError> {
+    ) -> Result<(Response, Bytes), ProviderError> {
+        let (response, body) = Self::open_inner(context, request, true).await?;
+        Ok((response, body.bytes().await?))
+    }
+
+    /// Start a request, resolving once the response headers have been received, or once
+    /// the whole body has been received if `read_body` is set (so that failures while
+    /// reading it are retried too)
+    async fn open_inner(
+        context: FetchContext,
+        request: Request,
+        read_body: bool,
+    ) -> Result<(Response, ResponseBody), ProviderError> {
         match request.url.scheme() {
             "data" => {
                 let data_url = DataUrl::process(request.url.as_str())?;
                 let decoded = data_url.decode_to_vec()?;
-                Ok((request.url.to_string(), Bytes::from(decoded.0)))
+                Ok((
+                    Response::new(request.url.to_string()),
+                    ResponseBody::buffered(Bytes::from(decoded.0)),
+                ))
             }
             "file" => {
                 let file_content = std::fs::read(request.url.path())?;
-                Ok((request.url.to_string(), Bytes::from(file_content)))
+                Ok((
+                    Response::new(request.url.to_string()),
+                    ResponseBody::buffered(Bytes::from(file_content)),
+                ))
             }
-            _ => Self::fetch_http(client, request, per_host_limits).await,
+            _ => Self::open_http(context, request, read_body).await,
         }
     }
 
-    async fn fetch_http(
-        client: Client,
+    /// Send an HTTP request, retrying idempotent requests which fail transiently according
+    /// to the provider's [`RetryPolicy`]. If `read_body` is set, the body is read as part of
+    /// each attempt.
+    async fn open_http(
+        context: FetchContext,
         request: Request,
-        per_host_limits: HostLimits,
-    ) -> Result<(String, Bytes), ProviderError> {
-        // Acquire a per-host permit, held for the duration of the request, to
+        read_body: bool,
+    ) -> Result<(Response, ResponseBody), ProviderError> {
+        let retry = context.policy.retry;
+        let max_retries = match RetryPolicy::is_idempotent(&request.method) {
+            true => retry.max_retries,
+            false => 0,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let result = match Self::send_http(&context, request.clone()).await {
+                Ok((response, body)) if read_body => body
+                    .bytes()
+                    .await
+                    .map(|bytes| (response, ResponseBody::buffered(bytes))),
+                result => result,
+            };
+            if attempt >= max_retries {
+                return result;
+            }
+
+            let delay = match &result {
+                Ok((response, _)) if RetryPolicy::is_transient_status(response.status) => {
+                    Some(retry_after(&response.headers).unwrap_or_else(|| retry.backoff(attempt)))
+                }
+                Err(err) if err.is_transient() => Some(retry.backoff(attempt)),
+                _ => None,
+            };
+            let Some(delay) = delay else {
+                return result;
+            };
+
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                url = request.url.as_str(),
+                attempt = attempt + 1,
+                delay = ?delay,
+                "Retrying request"
+            );
+
+            // Release the per-host permit while waiting
+            drop(result);
+            sleep(delay.min(retry.max_backoff)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send_http(
+        context: &FetchContext,
+        request: Request,
+    ) -> Result<(Response, ResponseBody), ProviderError> {
+        // Acquire a per-host permit, held until the body has been read, to
         // keep total in-flight requests per origin bounded.
         let host_key = request
             .url
@@ -177,24 +289,28 @@ impl Provider {
             .map(str::to_owned)
             .unwrap_or_default();
         let semaphore = {
-            let mut map = per_host_limits.lock().unwrap();
+            let mut map = context.per_host_limits.lock().unwrap();
             map.entry(host_key)
-                .or_insert_with(|| Arc::new(Semaphore::new(PER_HOST_MAX_CONCURRENT)))
+                .or_insert_with(|| Arc::new(Semaphore::new(context.policy.per_host_max_concurrent)))
                 .clone()
         };
-        let _permit = semaphore
-            .acquire()
+        let permit = semaphore
+            .acquire_owned()
             .await
             .expect("per-host semaphore was closed");
 
-        let mut req = client
+        let mut req = context
+            .client
             .request(request.method, request.url)
             .headers(request.headers)
-            .header("User-Agent", USER_AGENT);
+            .header("User-Agent", context.policy.user_agent.as_str());
 
         if let Some(content_type) = request.content_type.as_ref() {
             req = req.header("Content-Type", content_type);
         }
+        if let Some(timeout) = context.policy.timeout {
+            req = req.timeout(timeout);
+        }
 
         let req = req
             .apply_body(request.body, request.content_type.as_deref())
@@ -203,20 +319,40 @@ impl Provider {
         let status = response.status();
         let final_url = response.url().to_string();
 
-        if status.is_success() {
-            return Ok((final_url, response.bytes().await?));
+        #[cfg(feature = "tracing")]
+        if !status.is_success() {
+            tracing::warn!(
+                url = final_url.as_str(),
+                status = status.as_u16(),
+                "HTTP error status"
+            );
         }
 
-        #[cfg(feature = "tracing")]
-        tracing::warn!(
-            url = final_url.as_str(),
-            status = status.as_u16(),
-            "HTTP error status"
-        );
-        Err(ProviderError::HttpStatus {
-            status,
-            url: final_url,
-        })
+        let metadata = Response::new(final_url)
+            .status(status)
+            .headers(response.headers().clone());
+        let body = ResponseBody {
+            inner: ResponseBodyInner::Http {
+                response,
+                _permit: permit,
+            },
+            signal: None,
+        };
+        Ok((metadata, body))
+    }
+
+    /// Treat a response with a non-success status as an error
+    fn success_body(
+        result: Result<(Response, Bytes), ProviderError>,
+    ) -> Result<(String, Bytes), ProviderError> {
+        let (response, bytes) = result?;
+        if !response.is_success() {
+            return Err(ProviderError::HttpStatus {
+                status: response.status,
+                url: response.url,
+            });
+        }
+        Ok((response.url, bytes))
     }
 
     #[allow(clippy::type_complexity)]
@@ -228,10 +364,10 @@ impl Provider {
         #[cfg(feature = "tracing")]
         let url = request.url.to_string();
 
-        let client = self.client.clone();
-        let per_host_limits = self.per_host_limits.clone();
+        let context = self.context.clone();
         spawn(async move {
-            let result = Self::fetch_inner(client, request, per_host_limits).await;
+            let result = Self::fetch_inner(context, request).await;
+            let result = Self::success_body(result);
 
             #[cfg(feature = "tracing")]
             if let Err(e) = &result {
@@ -246,13 +382,23 @@ impl Provider {
         });
     }
 
+    /// Fetch a resource, returning the final URL and body of a successful response.
+    /// Responses with a non-success status are returned as [`ProviderError::HttpStatus`].
     pub async fn fetch_async(&self, request: Request) -> Result<(String, Bytes), ProviderError> {
+        Self::success_body(self.fetch_response(request).await)
+    }
+
+    /// Fetch a resource, returning the response (whatever its status) and its body. This
+    /// allows the body of an error response (e.g. a server's 404 page) to be displayed.
+    pub async fn fetch_response(
+        &self,
+        request: Request,
+    ) -> Result<(Response, Bytes), ProviderError> {
         #[cfg(feature = "tracing")]
         let url = request.url.to_string();
 
-        let client = self.client.clone();
-        let per_host_limits = self.per_host_limits.clone();
-        let result = Self::fetch_inner(client, request, per_host_limits).await;
+        let context = self.context.clone();
+        let result = Self::fetch_inner(context, request).await;
 
         #[cfg(feature = "tracing")]
         if let Err(e) = &result {
@@ -265,47 +411,113 @@ impl Provider {
 
         result
     }
+
+    /// Fetch a resource, resolving as soon as the response headers have been received.
+    /// The body can then be read incrementally using [`ResponseBody::chunk`] (e.g. to
+    /// parse a large HTML document while it is still downloading).
+    pub async fn fetch_streaming(
+        &self,
+        mut request: Request,
+    ) -> Result<(Response, ResponseBody), ProviderError> {
+        #[cfg(feature = "tracing")]
+        tracing::info!(url = request.url.as_str(), "Fetching (streaming)");
+
+        let context = self.context.clone();
+        let signal = request.signal.take();
+        let result = match signal.clone() {
+            Some(signal) => {
+                AbortFetch::new(
+                    signal,
+                    Box::pin(async move { Self::open_inner(context, request, false).await }),
+                )
+                .await
+            }
+            None => Self::open_inner(context, request, false).await,
+        };
+
+        let (response, mut body) = result?;
+        body.signal = signal;
+        Ok((response, body))
+    }
+}
+
+/// The body of a response which is read incrementally. See [`Provider::fetch_streaming`].
+pub struct ResponseBody {
+    inner: ResponseBodyInner,
+    signal: Option<AbortSignal>,
+}
+
+enum ResponseBodyInner {
+    /// The whole body is already available (e.g. `data:` and `file:` urls)
+    Buffered(Option<Bytes>),
+    Http {
+        response: reqwest::Response,
+        _permit: OwnedSemaphorePermit,
+    },
+}
+
+impl ResponseBody {
+    fn buffered(bytes: Bytes) -> Self {
+        Self {
+            inner: ResponseBodyInner::Buffered(Some(bytes)),
+            signal: None,
+        }
+    }
+
+    /// Read the next chunk of the body. Returns `None` once the body is complete.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, ProviderError> {
+        if self.signal.as_ref().is_some_and(|signal| signal.aborted()) {
+            return Err(ProviderError::Abort);
+        }
+        match &mut self.inner {
+            ResponseBodyInner::Buffered(bytes) => Ok(bytes.take()),
+            ResponseBodyInner::Http { response, .. } => Ok(response.chunk().await?),
+        }
+    }
+
+    /// Read the rest of the body
+    pub async fn bytes(self) -> Result<Bytes, ProviderError> {
+        match self.inner {
+            ResponseBodyInner::Buffered(bytes) => Ok(bytes.unwrap_or_default()),
+            ResponseBodyInner::Http { response, .. } => Ok(response.bytes().await?),
+        }
+    }
 }
 
 impl NetProvider for Provider {
     fn fetch(&self, doc_id: usize, mut request: Request, handler: Box<dyn NetHandler>) {
-        let client = self.client.clone();
-        let per_host_limits = self.per_host_limits.clone();
+        let context = self.context.clone();
 
         #[cfg(feature = "tracing")]
         tracing::info!(url = request.url.as_str(), "Fetching");
 
         let waker = self.waker.clone();
         spawn(async move {
-            #[cfg(feature = "tracing")]
             let url = request.url.to_string();
 
             let signal = request.signal.take();
             let result = if let Some(signal) = signal {
                 AbortFetch::new(
                     signal,
-                    Box::pin(
-                        async move { Self::fetch_inner(client, request, per_host_limits).await },
-                    ),
+                    Box::pin(async move { Self::fetch_inner(context, request).await }),
                 )
                 .await
             } else {
-                Self::fetch_inner(client, request, per_host_limits).await
+                Self::fetch_inner(context, request).await
             };
 
             waker.wake(doc_id);
 
             match result {
-                Ok((response_url, bytes)) => {
-                    handler.bytes(response_url, bytes);
+                Ok((response, bytes)) => {
                     #[cfg(feature = "tracing")]
                     tracing::info!(url = url.as_str(), "Success fetching");
+                    handler.response(response, bytes);
                 }
                 Err(e) => {
                     #[cfg(feature = "tracing")]
                     tracing::error!(url = url.as_str(), error = ?e, "Error fetching");
-                    #[cfg(not(feature = "tracing"))]
-                    let _ = e;
+                    handler.failed(url, e.into());
                 }
             };
         });
@@ -366,6 +578,40 @@ pub enum ProviderError {
     },
 }
 
+impl ProviderError {
+    /// Whether the request may succeed if it is retried (connection errors, timeouts and
+    /// connections which are lost while reading the body)
+    fn is_transient(&self) -> bool {
+        // A connection which is lost while reading the body is reported as a body or a
+        // decode error
+        let is_transient =
+            |e: &reqwest::Error| e.is_connect() || e.is_timeout() || e.is_body() || e.is_decode();
+        match self {
+            Self::ReqwestError(e) => is_transient(e),
+            #[cfg(feature = "cache")]
+            Self::ReqwestMiddlewareError(reqwest_middleware::Error::Reqwest(e)) => is_transient(e),
+            _ => false,
+        }
+    }
+}
+
+/// The delay requested by a response's `Retry-After` header, which is either a number of
+/// seconds or an HTTP date
+fn retry_after(headers: &blitz_traits::net::HeaderMap) -> Option<Duration> {
+    let value = headers.get("Retry-After")?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    // `SystemTime::now` isn't available on wasm, so compare times since the epoch
+    let date = httpdate::parse_http_date(value).ok()?;
+    let date = date.duration_since(std::time::UNIX_EPOCH).ok()?;
+    let now = web_time::SystemTime::now()
+        .duration_since(web_time::UNIX_EPOCH)
+        .ok()?;
+    Some(date.saturating_sub(now))
+}
+
 impl std::fmt::Display for ProviderError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -381,6 +627,16 @@ impl std::fmt::Display for ProviderError {
     }
 }
 
+impl From<ProviderError> for NetError {
+    fn from(value: ProviderError) -> Self {
+        match value {
+            ProviderError::Abort => NetError::Aborted,
+            ProviderError::HttpStatus { status, .. } => NetError::HttpStatus(status),
+            other => NetError::Fetch(other.to_string()),
+        }
+    }
+}
+
 impl From<std::io::Error> for ProviderError {
     fn from(value: std::io::Error) -> Self {
         Self::Io(value)
@@ -455,3 +711,31 @@ struct DummyNetWaker;
 impl NetWaker for DummyNetWaker {
     fn wake(&self, _client_id: usize) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blitz_traits::net::HeaderMap;
+
+    fn retry_after_header(value: &str) -> Option<Duration> {
+        let mut headers = HeaderMap::new();
+        headers.insert("Retry-After", value.parse().unwrap());
+        retry_after(&headers)
+    }
+
+    #[test]
+    fn retry_after_accepts_seconds_and_dates() {
+        assert_eq!(retry_after_header("120"), Some(Duration::from_secs(120)));
+        // Dates in the past don't delay the retry
+        assert_eq!(
+            retry_after_header("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+
+        let in_a_minute = std::time::SystemTime::now() + Duration::from_secs(60);
+        let delay = retry_after_header(&httpdate::fmt_http_date(in_a_minute)).unwrap();
+        assert!(delay > Duration::from_secs(55) && delay <= Duration::from_secs(60));
+
+        assert_eq!(retry_after_header("soon"), None);
+    }
+}
@@ -4,6 +4,7 @@ mod box_shadow;
 mod clip_path;
 mod form_controls;
 mod mask;
+mod top_layer;
 
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -16,12 +17,12 @@ use crate::filters::convert_filters;
 use crate::kurbo_css::NonUniformRoundedRectRadii;
 use crate::layers::LayerManager;
 use crate::sizing::compute_object_fit;
-use crate::text::DrawTextContext;
+use crate::text::{DrawTextContext, StrokeTextArgs, stroke_text};
 use crate::{CustomWidgetSceneMap, SELECTION_COLOR};
 use anyrender::{PaintScene, Scene};
 use blitz_dom::node::{
     ListItemLayout, ListItemLayoutPosition, Marker, NodeData, RasterImageData, TextInputData,
-    TextNodeData,
+    TextInputPlaceholder, TextNodeData,
 };
 use blitz_dom::{BaseDocument, ElementData, Node, NodeId, local_name};
 use blitz_traits::devtools::DevtoolSettings;
@@ -242,6 +243,12 @@ impl<'dom, 'a> BlitzDomPainter<'dom, 'a> {
             viewport_clip_rect,
         );
 
+        // Top layer elements (e.g. modal dialogs) are drawn above the rest of the document
+        self.draw_top_layer(scene, viewport_clip_rect);
+
+        // The open select's drop-down listbox is drawn above all other content
+        self.draw_open_select_listbox(scene);
+
         // Render debug overlay
         if self.dom.devtools().highlight_hover {
             if let Some(node_id) = self.dom.as_ref().get_hover_node_id() {
@@ -362,9 +369,9 @@ impl<'dom, 'a> BlitzDomPainter<'dom, 'a> {
             size,
             border,
             padding,
-            location,
             ..
         } = *node.final_layout();
+        let location = node.painted_location();
         let box_position = Vec2::new(location.x as f64, location.y as f64) * self.scale;
         let box_size = Size::new(size.width as f64, size.height as f64);
         let border_box = Rect::from_origin_size(box_position.to_point(), box_size);
@@ -534,6 +541,7 @@ impl<'dom, 'a> BlitzDomPainter<'dom, 'a> {
                                 cx.draw_custom_widget(scene);
                                 cx.draw_sub_document(scene);
                                 cx.draw_input(scene);
+                                cx.draw_select(scene, content_position);
                                 cx.draw_text_input_text(scene, content_position);
                                 cx.draw_inline_layout(scene, content_position);
                                 cx.draw_marker(scene, content_position);
@@ -813,11 +821,15 @@ impl ElementCx<'_, '_> {
             let transform =
                 self.transform * Affine::translate((pos.x * self.scale, pos.y * self.scale));
 
+            // Lines hidden by `-webkit-line-clamp` aren't painted at all
+            let truncation = &text_layout.truncation;
+            let visible_lines = truncation.visible_lines.unwrap_or(usize::MAX);
+
             // Render inline element backgrounds (e.g. `<span style="background: ...">`)
             // behind the text and selection highlight.
             crate::text::draw_inline_backgrounds(
                 scene,
-                text_layout.layout.lines(),
+                text_layout.layout.lines().take(visible_lines),
                 self.context.dom,
                 transform,
                 self.node.id,
@@ -828,6 +840,7 @@ impl ElementCx<'_, '_> {
                 crate::text::draw_text_selection(
                     scene,
                     &text_layout.layout,
+                    visible_lines,
                     transform,
                     sel_start,
                     sel_end,
@@ -836,21 +849,110 @@ impl ElementCx<'_, '_> {
 
             // Render text
             let mut draw_text_context = self.context.draw_text_context.borrow_mut();
-            crate::text::stroke_text(
-                scene,
-                text_layout.layout.lines(),
-                self.context.dom,
-                transform,
-                self.scale,
-                self.node.id,
-                &mut draw_text_context,
-            );
+            let Some(ellipsis) = truncation.ellipsis.as_deref() else {
+                stroke_text(
+                    scene,
+                    text_layout.layout.lines().take(visible_lines),
+                    &StrokeTextArgs {
+                        doc: self.context.dom,
+                        layers: &self.context.layer_manager,
+                        inline_root_id: self.node.id,
+                        transform,
+                        scale: self.scale,
+                    },
+                    &mut draw_text_context,
+                );
+                return;
+            };
+
+            // Truncated lines are clipped at the end of the last cluster that fits and
+            // followed by the ellipsis. The text itself is left untouched so that selection
+            // and hit-testing still operate on the original content.
+            for (line_index, line) in text_layout.layout.lines().take(visible_lines).enumerate() {
+                let metrics = *line.metrics();
+                let Some(truncated) = truncation.line(line_index) else {
+                    stroke_text(
+                        scene,
+                        std::iter::once(line),
+                        &StrokeTextArgs {
+                            doc: self.context.dom,
+                            layers: &self.context.layer_manager,
+                            inline_root_id: self.node.id,
+                            transform,
+                            scale: self.scale,
+                        },
+                        &mut draw_text_context,
+                    );
+                    continue;
+                };
+
+                // Clipped to the content box horizontally, and to the line vertically
+                let clip = Rect::new(
+                    0.0,
+                    metrics.block_min_coord as f64,
+                    truncated.clip_x as f64,
+                    metrics.block_max_coord as f64,
+                );
+                self.context.layer_manager.maybe_with_layer(
+                    scene,
+                    true,
+                    1.0,
+                    transform,
+                    &clip,
+                    None,
+                    None,
+                    |scene| {
+                        stroke_text(
+                            scene,
+                            std::iter::once(line),
+                            &StrokeTextArgs {
+                                doc: self.context.dom,
+                                layers: &self.context.layer_manager,
+                                inline_root_id: self.node.id,
+                                transform,
+                                scale: self.scale,
+                            },
+                            &mut draw_text_context,
+                        );
+                    },
+                );
+
+                let ellipsis_baseline = ellipsis
+                    .lines()
+                    .next()
+                    .map(|line| line.metrics().baseline)
+                    .unwrap_or(0.0);
+                let ellipsis_transform = transform
+                    * Affine::translate((
+                        truncated.ellipsis_x as f64,
+                        (metrics.baseline - ellipsis_baseline) as f64,
+                    ));
+                stroke_text(
+                    scene,
+                    ellipsis.lines(),
+                    &StrokeTextArgs {
+                        doc: self.context.dom,
+                        layers: &self.context.layer_manager,
+                        inline_root_id: self.node.id,
+                        transform: ellipsis_transform,
+                        scale: self.scale,
+                    },
+                    &mut draw_text_context,
+                );
+            }
         }
     }
 
     fn draw_text_input_text(&self, scene: &mut impl PaintScene, pos: Point) {
         // Render the text in text inputs
         if let Some(input_data) = self.text_input {
+            // Render the placeholder (beneath the caret) while the input is empty
+            if let Some(placeholder) = &input_data.placeholder
+                && input_data.is_empty()
+            {
+                self.draw_text_input_placeholder(scene, placeholder, input_data, pos);
+            }
+
             // For single-line inputs, add an offset to vertically center the text input layout
             // within the content box of it's node.
             let y_offset = self.node.text_input_v_centering_offset(self.scale);
@@ -902,18 +1004,56 @@ impl ElementCx<'_, '_> {
 
             // Render text
             let mut draw_text_context = self.context.draw_text_context.borrow_mut();
-            crate::text::stroke_text(
+            stroke_text(
                 scene,
                 input_data.editor.try_layout().unwrap().lines(),
-                self.context.dom,
-                transform,
-                self.scale,
-                self.node.id,
+                &StrokeTextArgs {
+                    doc: self.context.dom,
+                    layers: &self.context.layer_manager,
+                    inline_root_id: self.node.id,
+                    transform,
+                    scale: self.scale,
+                },
                 &mut draw_text_context,
             );
         }
     }
 
+    fn draw_text_input_placeholder(
+        &self,
+        scene: &mut impl PaintScene,
+        placeholder: &TextInputPlaceholder,
+        input_data: &TextInputData,
+        pos: Point,
+    ) {
+        // Like the input's value, the placeholder is vertically centered in single-line inputs
+        let layout = &placeholder.layout;
+        let y_offset = if input_data.is_multiline {
+            0.0
+        } else {
+            let content_box_height = self.frame.content_box.height() / self.scale;
+            ((content_box_height - layout.height() as f64 / self.scale) / 2.0).max(0.0)
+        };
+        let scroll_offset = input_data.scroll_offset as f64 * self.scale;
+        let (scroll_x, scroll_y) = if input_data.is_multiline {
+            (0.0, scroll_offset)
+        } else {
+            (scroll_offset, 0.0)
+        };
+        let transform = self.transform
+            * Affine::translate((
+                pos.x * self.scale - scroll_x,
+                (pos.y + y_offset) * self.scale - scroll_y,
+            ));
+
+        let styles = &placeholder.styles;
+        let color = styles
+            .clone_color()
+            .as_srgb_color()
+            .multiply_alpha(styles.get_effects().opacity);
+        crate::text::fill_text(scene, layout.lines(), color, transform, self.scale);
+    }
+
     fn draw_marker(&self, scene: &mut impl PaintScene, pos: Point) {
         if let Some(ListItemLayout {
             marker,
@@ -950,13 +1090,16 @@ impl ElementCx<'_, '_> {
                 self.transform * Affine::translate((pos.x * self.scale, pos.y * self.scale));
 
             let mut draw_text_context = self.context.draw_text_context.borrow_mut();
-            crate::text::stroke_text(
+            stroke_text(
                 scene,
                 layout.lines(),
-                self.context.dom,
-                transform,
-                self.scale,
-                self.node.id,
+                &StrokeTextArgs {
+                    doc: self.context.dom,
+                    layers: &self.context.layer_manager,
+                    inline_root_id: self.node.id,
+                    transform,
+                    scale: self.scale,
+                },
                 &mut draw_text_context,
             );
         }
@@ -1060,7 +1203,11 @@ impl ElementCx<'_, '_> {
     }
 
     fn draw_image(&self, scene: &mut impl PaintScene) {
-        if let Some(image) = self.element.raster_image_data() {
+        if let Some(image) = self
+            .element
+            .raster_image_data()
+            .filter(|image| !image.is_evicted())
+        {
             let width = self.frame.content_box.width() as u32;
             let height = self.frame.content_box.height() as u32;
             let x = self.frame.content_box.origin().x;
@@ -1077,8 +1224,8 @@ impl ElementCx<'_, '_> {
                 height: height as f32,
             };
             let object_size = taffy::Size {
-                width: image.width as f32,
-                height: image.height as f32,
+                width: image.natural_width as f32,
+                height: image.natural_height as f32,
             };
             let paint_size = compute_object_fit(container_size, Some(object_size), object_fit);
 
@@ -1092,8 +1239,9 @@ impl ElementCx<'_, '_> {
             let x = x + x_offset.px() as f64;
             let y = y + y_offset.px() as f64;
 
-            let x_scale = paint_size.width as f64 / object_size.width as f64;
-            let y_scale = paint_size.height as f64 / object_size.height as f64;
+            // The image may have been decoded at less than its natural size
+            let x_scale = paint_size.width as f64 / image.width as f64;
+            let y_scale = paint_size.height as f64 / image.height as f64;
             let transform = self
                 .transform
                 .pre_translate(Vec2 { x, y })
@@ -1,8 +1,13 @@
-use super::ElementCx;
+use super::{BlitzDomPainter, ElementCx};
+use crate::SELECTION_COLOR;
 use crate::color::{Color, ToColorColor as _};
+use crate::text::{StrokeTextArgs, stroke_text};
 use anyrender::PaintScene;
-use blitz_dom::local_name;
-use kurbo::{Affine, BezPath, Cap, Circle, Join, Point, RoundedRect, Stroke, Vec2};
+use blitz_dom::{
+    SELECT_ARROW_WIDTH, SELECT_LISTBOX_BORDER, SELECT_LISTBOX_PADDING, local_name,
+    select_is_drop_down,
+};
+use kurbo::{Affine, BezPath, Cap, Circle, Join, Point, Rect, RoundedRect, Stroke, Vec2};
 use peniko::Fill;
 use style::dom::TElement as _;
 
@@ -43,6 +48,157 @@ impl ElementCx<'_, '_> {
             _ => {}
         }
     }
+
+    /// Draw a select: the selected option's label and an arrow for a drop-down select, or
+    /// the rows of an inline listbox with the selected options highlighted.
+    pub(super) fn draw_select(&self, scene: &mut impl PaintScene, pos: Point) {
+        let Some(select_data) = self.element.select_data() else {
+            return;
+        };
+        let selected = self.node.select_selected_options();
+        let mut draw_text_context = self.context.draw_text_context.borrow_mut();
+
+        if select_is_drop_down(self.element) {
+            // Vertically center the label of the selected option within the content box
+            if let Some(layout) = selected
+                .first()
+                .and_then(|option_id| select_data.option_label(*option_id))
+            {
+                let content_height = self.frame.content_box.height() / self.scale;
+                let y_offset = (content_height - layout.height() as f64) / 2.0;
+                let transform = self.transform
+                    * Affine::translate((pos.x * self.scale, (pos.y + y_offset) * self.scale));
+                stroke_text(
+                    scene,
+                    layout.lines(),
+                    &StrokeTextArgs {
+                        doc: self.context.dom,
+                        layers: &self.context.layer_manager,
+                        inline_root_id: self.node.id,
+                        transform,
+                        scale: self.scale,
+                    },
+                    &mut draw_text_context,
+                );
+            }
+
+            // Draw a chevron in the space reserved for the arrow
+            let arrow_width = SELECT_ARROW_WIDTH as f64 * self.scale;
+            let center = Point::new(
+                self.frame.content_box.x1 - arrow_width / 2.0,
+                self.frame.content_box.center().y,
+            );
+            let size = 3.5 * self.scale;
+            let mut path = BezPath::new();
+            path.move_to((center.x - size, center.y - size / 2.0));
+            path.line_to((center.x, center.y + size / 2.0));
+            path.line_to((center.x + size, center.y - size / 2.0));
+
+            let stroke = Stroke::new(1.5 * self.scale)
+                .with_caps(Cap::Round)
+                .with_join(Join::Round);
+            let color = self.style.clone_color().as_srgb_color();
+            scene.stroke(&stroke, self.transform, color, None, &path);
+        } else {
+            let row_height = select_data.row_height as f64;
+            for (idx, label) in select_data.option_labels.iter().enumerate() {
+                let y = pos.y + idx as f64 * row_height;
+                if selected.contains(&label.option_id) {
+                    let row = Rect::new(
+                        self.frame.content_box.x0,
+                        y * self.scale,
+                        self.frame.content_box.x1,
+                        (y + row_height) * self.scale,
+                    );
+                    scene.fill(Fill::NonZero, self.transform, SELECTION_COLOR, None, &row);
+                }
+
+                let transform =
+                    self.transform * Affine::translate((pos.x * self.scale, y * self.scale));
+                stroke_text(
+                    scene,
+                    label.layout.lines(),
+                    &StrokeTextArgs {
+                        doc: self.context.dom,
+                        layers: &self.context.layer_manager,
+                        inline_root_id: self.node.id,
+                        transform,
+                        scale: self.scale,
+                    },
+                    &mut draw_text_context,
+                );
+            }
+        }
+    }
+}
+
+impl BlitzDomPainter<'_, '_> {
+    /// Draw the drop-down listbox of the open select (if any). This is drawn after (and so
+    /// above) all other content in the document.
+    pub(super) fn draw_open_select_listbox(&self, scene: &mut impl PaintScene) {
+        let dom = self.dom.as_ref();
+        let Some(select_id) = dom.open_select_node_id() else {
+            return;
+        };
+        let Some(bounds) = dom.select_listbox_rect(select_id) else {
+            return;
+        };
+        let Some(node) = dom.get_node(select_id) else {
+            return;
+        };
+        let Some(select_data) = node.element_data().and_then(|el| el.select_data()) else {
+            return;
+        };
+
+        // The listbox geometry is in page coordinates (CSS pixels)
+        let viewport_scroll = dom.viewport_scroll();
+        let transform = Affine::translate(Vec2 {
+            x: self.initial_x - (viewport_scroll.x * self.scale),
+            y: self.initial_y - (viewport_scroll.y * self.scale),
+        });
+        let scale = self.scale;
+
+        let frame = bounds.scale_from_origin(scale);
+        let border_width = SELECT_LISTBOX_BORDER * scale;
+        scene.fill(Fill::NonZero, transform, Color::WHITE, None, &frame);
+        scene.stroke(
+            &Stroke::new(border_width),
+            transform,
+            Color::from_rgb8(153, 153, 153),
+            None,
+            &frame.inset(-border_width / 2.0),
+        );
+
+        let mut draw_text_context = self.draw_text_context.borrow_mut();
+        for (option_id, row) in dom.select_listbox_rows(select_id) {
+            if select_data.highlighted == Some(option_id) {
+                let row = row.scale_from_origin(scale);
+                scene.fill(Fill::NonZero, transform, SELECTION_COLOR, None, &row);
+            }
+
+            let Some(layout) = select_data.option_label(option_id) else {
+                continue;
+            };
+            let y_offset = (row.height() - layout.height() as f64) / 2.0;
+            let text_transform = transform
+                * Affine::translate((
+                    (row.x0 + SELECT_LISTBOX_PADDING) * scale,
+                    (row.y0 + y_offset) * scale,
+                ));
+            stroke_text(
+                scene,
+                layout.lines(),
+                &StrokeTextArgs {
+                    doc: dom,
+                    layers: &self.layer_manager,
+                    inline_root_id: select_id,
+                    transform: text_transform,
+                    scale,
+                },
+                &mut draw_text_context,
+            );
+        }
+    }
 }
 
 fn draw_checkbox(
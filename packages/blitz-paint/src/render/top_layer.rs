@@ -0,0 +1,52 @@
+use super::BlitzDomPainter;
+use crate::color::ToColorColor as _;
+use anyrender::PaintScene;
+use kurbo::{Affine, Rect, Vec2};
+use peniko::Fill;
+
+impl BlitzDomPainter<'_, '_> {
+    /// Draw the elements in the top layer (from bottom to top), each above a `::backdrop`
+    /// which covers the viewport. These are drawn after (and so above) the rest of the
+    /// document.
+    pub(super) fn draw_top_layer(&self, scene: &mut impl PaintScene, viewport_clip_rect: Rect) {
+        let dom = self.dom.as_ref();
+        let viewport_scroll = dom.viewport_scroll();
+        let page_transform = Affine::translate(Vec2 {
+            x: self.initial_x - (viewport_scroll.x * self.scale),
+            y: self.initial_y - (viewport_scroll.y * self.scale),
+        });
+        let viewport = Rect::from_origin_size(
+            (self.initial_x, self.initial_y),
+            (self.width as f64, self.height as f64),
+        );
+
+        for &node_id in dom.top_layer() {
+            let node = &dom.tree()[node_id];
+            // Elements which aren't laid out (e.g. because an ancestor is `display: none`)
+            // aren't rendered
+            let Some(parent_id) = node.layout_parent.get() else {
+                continue;
+            };
+            if matches!(node.style().display, taffy::Display::None) {
+                continue;
+            }
+
+            if let Some(backdrop) = dom.backdrop_styles(node_id) {
+                let current_color = backdrop.clone_color();
+                let color = backdrop
+                    .clone_background_color()
+                    .resolve_to_absolute(&current_color)
+                    .as_srgb_color()
+                    .multiply_alpha(backdrop.get_effects().opacity);
+                scene.fill(Fill::NonZero, Affine::IDENTITY, color, None, &viewport);
+            }
+
+            // Top layer elements are laid out in place, so they are drawn relative to their
+            // layout parent
+            let origin = dom.tree()[parent_id].absolute_position(0.0, 0.0);
+            let transform = page_transform
+                * Affine::translate(Vec2::new(origin.x as f64, origin.y as f64) * self.scale);
+            self.render_element(scene, node_id, transform, viewport_clip_rect);
+        }
+    }
+}
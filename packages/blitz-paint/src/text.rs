@@ -1,9 +1,11 @@
 use anyrender::PaintScene;
+use anyrender::filters::{Filter, FilterEffect};
 use blitz_dom::{BaseDocument, NodeId, node::TextBrush, util::ToColorColor};
-use kurbo::{Affine, BezPath, Cap, Circle, Rect, Stroke};
-use parley::{Affinity, Cursor, Layout, Line, PositionedLayoutItem, Selection};
+use kurbo::{Affine, BezPath, Cap, Circle, Rect, Stroke, Vec2};
+use parley::{Affinity, Cursor, GlyphRun, Layout, Line, PositionedLayoutItem, Selection};
 use peniko::Fill;
 use std::collections::HashMap;
+use std::sync::Arc;
 use style::properties::generated::longhands::text_decoration_style::computed_value::T as TextDecorationStyle;
 use style::values::computed::{
     Length, LengthPercentage, TextDecorationLength, TextDecorationLine, TextUnderlinePosition,
@@ -11,6 +13,7 @@ use style::values::computed::{
 use style::values::generics::text::{GenericTextDecorationInset, GenericTextDecorationLength};
 
 use crate::color::{Color, ToColorColor as _};
+use crate::layers::LayerManager;
 use crate::{FONT_EMBOLDEN_ENABLED, SELECTION_COLOR};
 
 /// Draw the backgrounds of inline elements (e.g. `<span style="background: ...">`).
@@ -148,15 +151,39 @@ struct DecorationStackEntry {
     text_color: Color,
     /// The decoration this node introduces as a decorating box, if any.
     decoration: Option<ResolvedDecoration>,
+    /// This node's (inherited) `text-shadow`, used for the shadows of runs whose
+    /// innermost node is this one and of the decoration it introduces.
+    shadows: Vec<ResolvedTextShadow>,
+}
+
+/// A single `text-shadow` entry, in device pixels.
+#[derive(Clone)]
+struct ResolvedTextShadow {
+    offset: Vec2,
+    /// The standard deviation of the Gaussian blur (half the CSS blur radius).
+    blur_std_dev: f64,
+    color: Color,
+}
+
+impl ResolvedTextShadow {
+    /// How far the shadow's blur extends beyond the painted shape.
+    fn blur_extent(&self) -> f64 {
+        self.blur_std_dev * 3.0
+    }
 }
 
 /// Resolve the cached style values for a single node into a [`DecorationStackEntry`].
-fn resolve_decoration_entry(doc: &BaseDocument, node_id: NodeId) -> DecorationStackEntry {
+fn resolve_decoration_entry(
+    doc: &BaseDocument,
+    node_id: NodeId,
+    scale: f64,
+) -> DecorationStackEntry {
     let Some(styles) = doc.get_node(node_id).and_then(|node| node.primary_styles()) else {
         return DecorationStackEntry {
             node_id,
             text_color: Color::BLACK,
             decoration: None,
+            shadows: Vec::new(),
         };
     };
 
@@ -193,10 +220,33 @@ fn resolve_decoration_entry(doc: &BaseDocument, node_id: NodeId) -> DecorationSt
         }
     });
 
+    // Shadows are listed front to back, but painted back to front. A shadow colour of
+    // `currentColor` resolves against the node's own colour.
+    let current_color = styles.clone_color();
+    let shadows = itext
+        .text_shadow
+        .0
+        .iter()
+        .rev()
+        .map(|shadow| ResolvedTextShadow {
+            offset: Vec2::new(
+                shadow.horizontal.px() as f64 * scale,
+                shadow.vertical.px() as f64 * scale,
+            ),
+            blur_std_dev: shadow.blur.px() as f64 * scale / 2.0,
+            color: shadow
+                .color
+                .resolve_to_absolute(&current_color)
+                .as_srgb_color(),
+        })
+        .filter(|shadow| shadow.color.components[3] != 0.0)
+        .collect();
+
     DecorationStackEntry {
         node_id,
         text_color,
         decoration,
+        shadows,
     }
 }
 
@@ -226,6 +276,8 @@ struct DecorationRunGeometry {
 struct LineDecoration {
     node_id: NodeId,
     deco: ResolvedDecoration,
+    /// The decorating box's `text-shadow`, which also applies to its decoration.
+    shadows: Vec<ResolvedTextShadow>,
     min_x: f64,
     max_x: f64,
     /// Geometry from a run whose innermost node is the decorating box itself.
@@ -241,7 +293,12 @@ struct LineDecoration {
 pub(crate) struct DrawTextContext {
     stack: Vec<DecorationStackEntry>,
     path_scratch: Vec<NodeId>,
+    /// The decorating boxes of every line, in line order.
     deco_boxes: Vec<LineDecoration>,
+    /// The end of each line's decorating boxes in `deco_boxes`.
+    line_deco_ends: Vec<usize>,
+    /// The glyph colour of each run, in order.
+    run_colors: Vec<Color>,
     win_ascent_ratios: WinAscentCache,
 }
 
@@ -395,167 +452,288 @@ fn flush_line_decorations(
     // Draw innermost boxes first so ancestors' decorations paint on top, matching the
     // per-run drawing order this replaced (`stack.iter().rev()`).
     for acc in deco_boxes.iter().rev() {
-        let deco = &acc.deco;
-        // Prefer the decorating box's own font; fall back to the first run it covers.
+        draw_line_decoration(
+            scene,
+            transform,
+            scale,
+            acc,
+            acc.deco.color,
+            win_ascent_ratios,
+        );
+    }
+}
+
+/// Paint the `text-shadow`s of the decorations accumulated for one line. Each decoration
+/// casts the shadows of its decorating box.
+fn flush_line_decoration_shadows(
+    scene: &mut impl PaintScene,
+    transform: Affine,
+    scale: f64,
+    deco_boxes: &[LineDecoration],
+    win_ascent_ratios: &mut WinAscentCache,
+    layers: &LayerManager,
+) {
+    for acc in deco_boxes.iter().rev() {
         let Some(geom) = acc.own.as_ref().or(acc.first.as_ref()) else {
             continue;
         };
-        let width = acc.max_x - acc.min_x;
-        if width <= 0.0 {
-            continue;
-        }
-        let brush = anyrender::Paint::from(deco.color);
-
-        // `text-decoration-inset` shortens (or, when negative, extends) the line from the
-        // inline-start/end edges. Percentages resolve against the decoration line length
-        // (the box's total advance on this line); `auto` is no inset.
-        let (inset_start, inset_end) = match &deco.inset {
-            GenericTextDecorationInset::LengthPercentage { start, end } => {
-                // The extent is in device pixels; convert to CSS pixels so the resolved
-                // result can be scaled back.
-                let line_length = Length::new((width / scale) as f32);
-                (
-                    start.resolve(line_length).px() as f64 * scale,
-                    end.resolve(line_length).px() as f64 * scale,
-                )
-            }
-            GenericTextDecorationInset::Auto => (0.0, 0.0),
-        };
-
-        if deco.line.contains(TextDecorationLine::UNDERLINE) {
-            let size = decoration_size(
-                &deco.thickness,
-                geom.underline_size,
-                geom.css_font_size,
-                scale,
-            );
-
-            // `text-underline-offset` moves the underline away from the text. `auto` keeps
-            // the font's suggested position; otherwise it resolves against the font size
-            // (percentages relative to 1em).
-            let extra_underline_offset = deco
-                .underline_offset
-                .as_ref()
-                .map(|lp| lp.resolve(Length::new(geom.css_font_size as f32)).px() as f64 * scale)
-                .unwrap_or(0.0);
-
-            // `text-underline-position: under` places the underline below the glyph box
-            // (below descenders) rather than at the font's suggested position near the
-            // alphabetic baseline. We anchor the top of the line at the descent so it clears
-            // descending glyphs like "gqy".
-            let base_offset = if deco.underline_under {
-                -geom.descent
-            } else {
-                geom.underline_offset
-            };
-            let offset = base_offset - extra_underline_offset as f32;
-
-            // A `double` underline extends downward, away from the text.
-            draw_decoration_line(
-                scene,
-                transform,
-                scale,
-                acc.min_x,
-                width,
-                geom.baseline,
-                offset,
-                size,
-                &brush,
-                1.0,
-                deco.style,
-                inset_start,
-                inset_end,
-            );
-        }
-        if deco.line.contains(TextDecorationLine::OVERLINE) {
-            // Fonts don't provide a dedicated overline metric, so reuse the underline
-            // thickness. The line sits at the top of the "em box": its lower edge rests on
-            // the ascent so it clears the glyphs, and it extends upward from there.
-            //
-            // Browsers use the OS/2 `usWinAscent` for this edge, which is taller than the
-            // hhea-based ascent Parley reports; using the smaller value would draw the
-            // overline too low. Fall back to Parley's ascent for fonts without a usable
-            // OS/2 table.
-            let size = decoration_size(
-                &deco.thickness,
-                geom.underline_size,
-                geom.css_font_size,
-                scale,
-            );
-            let ascent =
-                win_ascent(win_ascent_ratios, &geom.font, geom.font_size).unwrap_or(geom.ascent);
-            let offset = ascent + size;
-
-            // A `double` overline extends upward, away from the text.
-            draw_decoration_line(
+        // Decorations stay within (roughly) an em of the baseline
+        let font_size = geom.font_size as f64;
+        let baseline = geom.baseline as f64;
+        let bounds = Rect::new(
+            acc.min_x,
+            baseline - 1.5 * font_size,
+            acc.max_x,
+            baseline + 1.5 * font_size,
+        );
+        for shadow in &acc.shadows {
+            with_text_shadow_layer(
                 scene,
+                layers,
                 transform,
-                scale,
-                acc.min_x,
-                width,
-                geom.baseline,
-                offset,
-                size,
-                &brush,
-                -1.0,
-                deco.style,
-                inset_start,
-                inset_end,
+                shadow,
+                bounds,
+                |scene, transform| {
+                    draw_line_decoration(
+                        scene,
+                        transform,
+                        scale,
+                        acc,
+                        shadow.color,
+                        win_ascent_ratios,
+                    );
+                },
             );
         }
-        if deco.line.contains(TextDecorationLine::LINE_THROUGH) {
-            let size = decoration_size(
-                &deco.thickness,
-                geom.strikethrough_size,
-                geom.css_font_size,
-                scale,
-            );
+    }
+}
 
-            // Centre the line-through a third of the ascent above the baseline, matching
-            // Chrome (which places it `2/3 * ascent` below the text-top). Parley's
-            // `strikethrough_offset` (the font's `yStrikeoutPosition`) sits lower and would
-            // draw the line too close to the baseline.
-            let ascent =
-                win_ascent(win_ascent_ratios, &geom.font, geom.font_size).unwrap_or(geom.ascent);
-            let offset = ascent / 3.0 + size / 2.0;
+/// Paint with a `text-shadow`: `paint` receives `transform` offset by the shadow's offset
+/// and draws into a layer which blurs it (if the shadow has a blur radius). `bounds` is the
+/// extent of what will be painted, before the offset is applied.
+fn with_text_shadow_layer<S: PaintScene>(
+    scene: &mut S,
+    layers: &LayerManager,
+    transform: Affine,
+    shadow: &ResolvedTextShadow,
+    bounds: Rect,
+    paint: impl FnOnce(&mut S, Affine),
+) {
+    let transform = transform.then_translate(shadow.offset);
+    let filter = (shadow.blur_std_dev > 0.0).then(|| {
+        Arc::new(Filter::linear_list([FilterEffect::blur(
+            shadow.blur_std_dev as f32,
+        )]))
+    });
+    let extent = shadow.blur_extent();
+    layers.maybe_with_layer(
+        scene,
+        filter.is_some(),
+        1.0,
+        transform,
+        &bounds.inflate(extent, extent),
+        filter,
+        None,
+        |scene| paint(scene, transform),
+    );
+}
 
-            draw_decoration_line(
-                scene,
-                transform,
-                scale,
-                acc.min_x,
-                width,
-                geom.baseline,
-                offset,
-                size,
-                &brush,
-                1.0,
-                deco.style,
-                inset_start,
-                inset_end,
-            );
+/// Paint the decoration accumulated for one decorating box on one line, in `color`.
+fn draw_line_decoration(
+    scene: &mut impl PaintScene,
+    transform: Affine,
+    scale: f64,
+    acc: &LineDecoration,
+    color: Color,
+    win_ascent_ratios: &mut WinAscentCache,
+) {
+    let deco = &acc.deco;
+    // Prefer the decorating box's own font; fall back to the first run it covers.
+    let Some(geom) = acc.own.as_ref().or(acc.first.as_ref()) else {
+        return;
+    };
+    let width = acc.max_x - acc.min_x;
+    if width <= 0.0 {
+        return;
+    }
+    let brush = anyrender::Paint::from(color);
+
+    // `text-decoration-inset` shortens (or, when negative, extends) the line from the
+    // inline-start/end edges. Percentages resolve against the decoration line length
+    // (the box's total advance on this line); `auto` is no inset.
+    let (inset_start, inset_end) = match &deco.inset {
+        GenericTextDecorationInset::LengthPercentage { start, end } => {
+            // The extent is in device pixels; convert to CSS pixels so the resolved
+            // result can be scaled back.
+            let line_length = Length::new((width / scale) as f32);
+            (
+                start.resolve(line_length).px() as f64 * scale,
+                end.resolve(line_length).px() as f64 * scale,
+            )
         }
+        GenericTextDecorationInset::Auto => (0.0, 0.0),
+    };
+
+    if deco.line.contains(TextDecorationLine::UNDERLINE) {
+        let size = decoration_size(
+            &deco.thickness,
+            geom.underline_size,
+            geom.css_font_size,
+            scale,
+        );
+
+        // `text-underline-offset` moves the underline away from the text. `auto` keeps
+        // the font's suggested position; otherwise it resolves against the font size
+        // (percentages relative to 1em).
+        let extra_underline_offset = deco
+            .underline_offset
+            .as_ref()
+            .map(|lp| lp.resolve(Length::new(geom.css_font_size as f32)).px() as f64 * scale)
+            .unwrap_or(0.0);
+
+        // `text-underline-position: under` places the underline below the glyph box
+        // (below descenders) rather than at the font's suggested position near the
+        // alphabetic baseline. We anchor the top of the line at the descent so it clears
+        // descending glyphs like "gqy".
+        let base_offset = if deco.underline_under {
+            -geom.descent
+        } else {
+            geom.underline_offset
+        };
+        let offset = base_offset - extra_underline_offset as f32;
+
+        // A `double` underline extends downward, away from the text.
+        draw_decoration_line(
+            scene,
+            transform,
+            scale,
+            acc.min_x,
+            width,
+            geom.baseline,
+            offset,
+            size,
+            &brush,
+            1.0,
+            deco.style,
+            inset_start,
+            inset_end,
+        );
+    }
+    if deco.line.contains(TextDecorationLine::OVERLINE) {
+        // Fonts don't provide a dedicated overline metric, so reuse the underline
+        // thickness. The line sits at the top of the "em box": its lower edge rests on
+        // the ascent so it clears the glyphs, and it extends upward from there.
+        //
+        // Browsers use the OS/2 `usWinAscent` for this edge, which is taller than the
+        // hhea-based ascent Parley reports; using the smaller value would draw the
+        // overline too low. Fall back to Parley's ascent for fonts without a usable
+        // OS/2 table.
+        let size = decoration_size(
+            &deco.thickness,
+            geom.underline_size,
+            geom.css_font_size,
+            scale,
+        );
+        let ascent =
+            win_ascent(win_ascent_ratios, &geom.font, geom.font_size).unwrap_or(geom.ascent);
+        let offset = ascent + size;
+
+        // A `double` overline extends upward, away from the text.
+        draw_decoration_line(
+            scene,
+            transform,
+            scale,
+            acc.min_x,
+            width,
+            geom.baseline,
+            offset,
+            size,
+            &brush,
+            -1.0,
+            deco.style,
+            inset_start,
+            inset_end,
+        );
     }
+    if deco.line.contains(TextDecorationLine::LINE_THROUGH) {
+        let size = decoration_size(
+            &deco.thickness,
+            geom.strikethrough_size,
+            geom.css_font_size,
+            scale,
+        );
+
+        // Centre the line-through a third of the ascent above the baseline, matching
+        // Chrome (which places it `2/3 * ascent` below the text-top). Parley's
+        // `strikethrough_offset` (the font's `yStrikeoutPosition`) sits lower and would
+        // draw the line too close to the baseline.
+        let ascent =
+            win_ascent(win_ascent_ratios, &geom.font, geom.font_size).unwrap_or(geom.ascent);
+        let offset = ascent / 3.0 + size / 2.0;
+
+        draw_decoration_line(
+            scene,
+            transform,
+            scale,
+            acc.min_x,
+            width,
+            geom.baseline,
+            offset,
+            size,
+            &brush,
+            1.0,
+            deco.style,
+            inset_start,
+            inset_end,
+        );
+    }
+}
+
+/// What [`stroke_text`] paints with: the document that the text's brushes refer to, and
+/// where the text is placed.
+pub(crate) struct StrokeTextArgs<'a> {
+    pub(crate) doc: &'a BaseDocument,
+    pub(crate) layers: &'a LayerManager,
+    /// The node whose inline layout is being painted. Decorations are propagated from it
+    /// and its descendants, but not from its ancestors.
+    pub(crate) inline_root_id: NodeId,
+    pub(crate) transform: Affine,
+    pub(crate) scale: f64,
 }
 
+/// Draw the glyph runs of an inline layout, along with the decorations (underlines, etc)
+/// propagated to them and the `text-shadow`s of both. The shadows of every line are painted
+/// beneath the glyphs and decorations of all lines.
 pub(crate) fn stroke_text<'a>(
     scene: &mut impl PaintScene,
     lines: impl Iterator<Item = Line<'a, TextBrush>>,
-    doc: &BaseDocument,
-    transform: Affine,
-    scale: f64,
-    inline_root_id: NodeId,
+    args: &StrokeTextArgs<'_>,
     context: &mut DrawTextContext,
 ) {
+    let &StrokeTextArgs {
+        doc,
+        layers,
+        inline_root_id,
+        transform,
+        scale,
+    } = args;
     let DrawTextContext {
         stack,
         path_scratch,
         deco_boxes,
+        line_deco_ends,
+        run_colors,
         win_ascent_ratios,
     } = context;
     stack.clear();
     path_scratch.clear();
     deco_boxes.clear();
+    line_deco_ends.clear();
+    run_colors.clear();
+
+    // The lines are walked twice: first to resolve styles and paint shadows, then to paint
+    // the glyphs and decorations on top.
+    let lines: Vec<Line<'a, TextBrush>> = lines.collect();
 
     // Persistent stack mirroring the ancestor path (inline root -> current run's
     // node) as we walk the runs. The `text-decoration-*` properties are *not*
@@ -564,12 +742,12 @@ pub(crate) fn stroke_text<'a>(
     // ancestor chain on every run, we cache each node's resolved values here and,
     // for each run, only resolve styles for the nodes newly descended into (popping
     // as we ascend). `path_scratch` is a reusable buffer for the run's node path.
-    for line in lines {
+    for line in &lines {
         // Decorations accumulated for this line, keyed by decorating box, so each box is
         // painted once (spanning all its runs) using its own font — matching Firefox, which
         // draws one decoration per box rather than one stepped segment per differently-sized
-        // run. Clearing preserves the allocation for the next line and inline context.
-        deco_boxes.clear();
+        // run.
+        let line_start = deco_boxes.len();
 
         for item in line.items() {
             if let PositionedLayoutItem::GlyphRun(glyph_run) = item {
@@ -578,10 +756,6 @@ pub(crate) fn stroke_text<'a>(
                 let font_size = run.font_size();
                 let metrics = run.metrics();
                 let style = glyph_run.style();
-                let synthesis = run.synthesis();
-                let glyph_xform = synthesis
-                    .skew()
-                    .map(|angle| Affine::skew(angle.to_radians().tan() as f64, 0.0));
 
                 let css_font_size = font_size as f64 / scale;
 
@@ -606,37 +780,37 @@ pub(crate) fn stroke_text<'a>(
                     .count();
                 stack.truncate(shared);
                 for &node_id in &path_scratch[shared..] {
-                    stack.push(resolve_decoration_entry(doc, node_id));
+                    stack.push(resolve_decoration_entry(doc, node_id, scale));
                 }
 
-                // The glyph colour comes from the run's own node (the stack top): `color`
-                // inherits, so the innermost inline element already carries the right value.
-                let text_color = stack.last().map(|e| e.text_color).unwrap_or(Color::BLACK);
-
-                let embolden = if FONT_EMBOLDEN_ENABLED {
-                    let fs = font_size as f64 / scale;
-                    kurbo::Vec2::new((0.015125 * fs).min(0.3), (0.0121 * fs).min(0.3))
-                } else {
-                    kurbo::Vec2::default()
-                };
-
-                scene.draw_glyphs(
-                    font,
-                    font_size,
-                    !FONT_EMBOLDEN_ENABLED, // hint
-                    run.normalized_coords(),
-                    embolden,
-                    Fill::NonZero,
-                    &anyrender::Paint::from(text_color),
-                    1.0, // alpha
-                    transform,
-                    glyph_xform,
-                    glyph_run.positioned_glyphs().map(|glyph| anyrender::Glyph {
-                        id: glyph.id as _,
-                        x: glyph.x,
-                        y: glyph.y,
-                    }),
-                );
+                // The glyph colour and shadows come from the run's own node (the stack
+                // top): `color` and `text-shadow` inherit, so the innermost inline element
+                // already carries the right values.
+                let run_node = stack.last();
+                run_colors.push(run_node.map(|e| e.text_color).unwrap_or(Color::BLACK));
+
+                if let Some(entry) = run_node.filter(|e| !e.shadows.is_empty()) {
+                    let x = glyph_run.offset() as f64;
+                    let baseline = glyph_run.baseline() as f64;
+                    let bounds = Rect::new(
+                        x,
+                        baseline - metrics.ascent as f64,
+                        x + glyph_run.advance() as f64,
+                        baseline + metrics.descent as f64,
+                    );
+                    for shadow in &entry.shadows {
+                        with_text_shadow_layer(
+                            scene,
+                            layers,
+                            transform,
+                            shadow,
+                            bounds,
+                            |scene, transform| {
+                                draw_glyph_run(scene, &glyph_run, shadow.color, transform, scale);
+                            },
+                        );
+                    }
+                }
 
                 // Accumulate this run's contribution to each decorating box on its ancestor
                 // path. The decoration is drawn once per box after the whole line has been
@@ -661,12 +835,16 @@ pub(crate) fn stroke_text<'a>(
                     if entry.decoration.is_none() {
                         continue;
                     }
-                    let idx = match deco_boxes.iter().position(|d| d.node_id == entry.node_id) {
-                        Some(idx) => idx,
+                    let idx = match deco_boxes[line_start..]
+                        .iter()
+                        .position(|d| d.node_id == entry.node_id)
+                    {
+                        Some(idx) => line_start + idx,
                         None => {
                             deco_boxes.push(LineDecoration {
                                 node_id: entry.node_id,
                                 deco: entry.decoration.clone().unwrap(),
+                                shadows: entry.shadows.clone(),
                                 min_x: f64::INFINITY,
                                 max_x: f64::NEG_INFINITY,
                                 own: None,
@@ -690,15 +868,103 @@ pub(crate) fn stroke_text<'a>(
             }
         }
 
-        flush_line_decorations(scene, transform, scale, deco_boxes, win_ascent_ratios);
+        flush_line_decoration_shadows(
+            scene,
+            transform,
+            scale,
+            &deco_boxes[line_start..],
+            win_ascent_ratios,
+            layers,
+        );
+        line_deco_ends.push(deco_boxes.len());
+    }
+
+    let mut run_colors = run_colors.iter();
+    let mut line_start = 0;
+    for (line, &line_end) in lines.iter().zip(line_deco_ends.iter()) {
+        for item in line.items() {
+            if let PositionedLayoutItem::GlyphRun(glyph_run) = item {
+                let text_color = run_colors.next().copied().unwrap_or(Color::BLACK);
+                draw_glyph_run(scene, &glyph_run, text_color, transform, scale);
+            }
+        }
+
+        flush_line_decorations(
+            scene,
+            transform,
+            scale,
+            &deco_boxes[line_start..line_end],
+            win_ascent_ratios,
+        );
+        line_start = line_end;
     }
 }
 
-/// Draw selection highlight rectangles for the given byte range in a layout.
-/// Uses Parley's Selection type for accurate geometry calculation.
+/// Draw all of the glyphs of a layout in a single `color`, for text which isn't part of the
+/// DOM (and so can't be styled by the nodes in its brushes) such as input placeholders.
+pub(crate) fn fill_text<'a>(
+    scene: &mut impl PaintScene,
+    lines: impl Iterator<Item = Line<'a, TextBrush>>,
+    color: Color,
+    transform: Affine,
+    scale: f64,
+) {
+    for line in lines {
+        for item in line.items() {
+            if let PositionedLayoutItem::GlyphRun(glyph_run) = item {
+                draw_glyph_run(scene, &glyph_run, color, transform, scale);
+            }
+        }
+    }
+}
+
+/// Draw the glyphs of a single run in `color`.
+fn draw_glyph_run(
+    scene: &mut impl PaintScene,
+    glyph_run: &GlyphRun<'_, TextBrush>,
+    color: Color,
+    transform: Affine,
+    scale: f64,
+) {
+    let run = glyph_run.run();
+    let font_size = run.font_size();
+    let glyph_xform = run
+        .synthesis()
+        .skew()
+        .map(|angle| Affine::skew(angle.to_radians().tan() as f64, 0.0));
+
+    let embolden = if FONT_EMBOLDEN_ENABLED {
+        let fs = font_size as f64 / scale;
+        kurbo::Vec2::new((0.015125 * fs).min(0.3), (0.0121 * fs).min(0.3))
+    } else {
+        kurbo::Vec2::default()
+    };
+
+    scene.draw_glyphs(
+        run.font(),
+        font_size,
+        !FONT_EMBOLDEN_ENABLED, // hint
+        run.normalized_coords(),
+        embolden,
+        Fill::NonZero,
+        &anyrender::Paint::from(color),
+        1.0, // alpha
+        transform,
+        glyph_xform,
+        glyph_run.positioned_glyphs().map(|glyph| anyrender::Glyph {
+            id: glyph.id as _,
+            x: glyph.x,
+            y: glyph.y,
+        }),
+    );
+}
+
+/// Draw selection highlight rectangles for the given byte range in a layout, on the lines
+/// before `visible_lines`. Uses Parley's Selection type for accurate geometry calculation.
 pub(crate) fn draw_text_selection(
     scene: &mut impl PaintScene,
     layout: &Layout<TextBrush>,
+    visible_lines: usize,
     transform: Affine,
     selection_start: usize,
     selection_end: usize,
@@ -707,7 +973,10 @@ pub(crate) fn draw_text_selection(
     let focus = Cursor::from_byte_index(layout, selection_end, Affinity::Downstream);
     let selection = Selection::new(anchor, focus);
 
-    selection.geometry_with(layout, |rect, _line_idx| {
+    selection.geometry_with(layout, |rect, line_idx| {
+        if line_idx >= visible_lines {
+            return;
+        }
         let rect = kurbo::Rect::new(rect.x0, rect.y0, rect.x1, rect.y1);
         scene.fill(Fill::NonZero, transform, SELECTION_COLOR, None, &rect);
     });
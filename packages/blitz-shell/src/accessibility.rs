@@ -41,10 +41,19 @@ impl AccessibilityState {
             ),
         }
     }
-    pub fn update_tree(&mut self, doc: &BaseDocument) {
-        let _ = doc;
+    /// Send the whole of the document's accessibility tree (when assistive technology
+    /// first requests it)
+    pub fn initial_tree(&mut self, doc: &mut BaseDocument) {
+        self.adapter.update_if_active(|| {
+            doc.reset_accessibility_tree();
+            doc.accessibility_tree_update()
+        });
+    }
+
+    /// Send the changes to the document's accessibility tree since it was last sent
+    pub fn update_tree(&mut self, doc: &mut BaseDocument) {
         self.adapter
-            .update_if_active(|| doc.build_accessibility_tree());
+            .update_if_active(|| doc.accessibility_tree_update());
     }
 
     /// Allows reacting to window events.
@@ -3,9 +3,10 @@ use crate::event::{BlitzShellEvent, BlitzShellProxy};
 use anyrender::WindowRenderer;
 use std::collections::HashMap;
 use std::sync::mpsc::Receiver;
+use web_time::Instant;
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
-use winit::event_loop::ActiveEventLoop;
+use winit::event_loop::{ActiveEventLoop, ControlFlow};
 use winit::window::WindowId;
 
 #[cfg(target_os = "macos")]
@@ -18,6 +19,8 @@ pub struct BlitzApplication<Rend: WindowRenderer> {
     pub pending_windows: Vec<WindowConfig<Rend>>,
     pub proxy: BlitzShellProxy,
     pub event_queue: Receiver<BlitzShellEvent>,
+    /// The control flow to go back to once no animated images are waiting for a frame
+    control_flow_before_frames: Option<ControlFlow>,
 }
 
 impl<Rend: WindowRenderer> BlitzApplication<Rend> {
@@ -27,6 +30,7 @@ impl<Rend: WindowRenderer> BlitzApplication<Rend> {
             pending_windows: Vec::new(),
             proxy,
             event_queue,
+            control_flow_before_frames: None,
         }
     }
 
@@ -84,8 +88,8 @@ impl<Rend: WindowRenderer> BlitzApplication<Rend> {
                         accesskit_xplat::WindowEvent::AccessibilityDeactivated => {
                             // TODO
                         }
-                        accesskit_xplat::WindowEvent::ActionRequested(_req) => {
-                            // TODO
+                        accesskit_xplat::WindowEvent::ActionRequested(req) => {
+                            window.handle_accessibility_action(req);
                         }
                     }
                 }
@@ -176,13 +180,35 @@ impl<Rend: WindowRenderer> ApplicationHandler for BlitzApplication<Rend> {
         Some(self)
     }
 
-    #[cfg(target_os = "ios")]
-    fn about_to_wait(&mut self, _event_loop: &dyn ActiveEventLoop) {
+    fn about_to_wait(&mut self, event_loop: &dyn ActiveEventLoop) {
         for view in self.windows.values_mut() {
             if view.ios_request_redraw.get() {
                 view.window.request_redraw();
             }
         }
+
+        // Redraw windows whose animated images have a frame due, and wake up in time for the
+        // next one
+        let now = Instant::now();
+        let mut next_deadline: Option<Instant> = None;
+        for view in self.windows.values_mut() {
+            let Some(deadline) = view.next_frame_deadline else {
+                continue;
+            };
+            if deadline <= now {
+                view.next_frame_deadline = None;
+                view.request_redraw();
+            } else {
+                next_deadline = Some(next_deadline.map_or(deadline, |next| next.min(deadline)));
+            }
+        }
+        if let Some(deadline) = next_deadline {
+            self.control_flow_before_frames
+                .get_or_insert_with(|| event_loop.control_flow());
+            event_loop.set_control_flow(ControlFlow::WaitUntil(deadline));
+        } else if let Some(control_flow) = self.control_flow_before_frames.take() {
+            event_loop.set_control_flow(control_flow);
+        }
     }
 }
 
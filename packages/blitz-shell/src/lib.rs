@@ -12,6 +12,7 @@ mod application;
 mod convert_events;
 mod event;
 mod net;
+mod preferences;
 mod window;
 
 #[cfg(feature = "accessibility")]
@@ -188,6 +189,97 @@ impl ShellProvider for BlitzShellProvider {
             .map_err(|_| blitz_traits::shell::ClipboardError)
     }
 
+    #[cfg(all(
+        feature = "clipboard",
+        any(
+            target_os = "windows",
+            target_os = "macos",
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )
+    ))]
+    fn get_clipboard_contents(
+        &self,
+    ) -> Result<blitz_traits::shell::ClipboardContents, blitz_traits::shell::ClipboardError> {
+        let mut cb = arboard::Clipboard::new().map_err(|_| blitz_traits::shell::ClipboardError)?;
+        let contents = blitz_traits::shell::ClipboardContents {
+            text: cb.get_text().ok(),
+            html: cb.get().html().ok(),
+            image: None,
+        };
+        if contents == blitz_traits::shell::ClipboardContents::default() {
+            return Err(blitz_traits::shell::ClipboardError);
+        }
+        Ok(contents)
+    }
+
+    #[cfg(all(
+        feature = "clipboard",
+        any(
+            target_os = "windows",
+            target_os = "macos",
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )
+    ))]
+    fn get_clipboard_image(
+        &self,
+    ) -> Result<blitz_traits::shell::ClipboardImage, blitz_traits::shell::ClipboardError> {
+        let mut cb = arboard::Clipboard::new().map_err(|_| blitz_traits::shell::ClipboardError)?;
+        let image = cb
+            .get_image()
+            .map_err(|_| blitz_traits::shell::ClipboardError)?;
+        Ok(blitz_traits::shell::ClipboardImage {
+            width: image.width,
+            height: image.height,
+            rgba: image.bytes.into_owned(),
+        })
+    }
+
+    #[cfg(all(
+        feature = "clipboard",
+        any(
+            target_os = "windows",
+            target_os = "macos",
+            target_os = "linux",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "netbsd",
+            target_os = "openbsd"
+        )
+    ))]
+    fn set_clipboard_contents(
+        &self,
+        contents: blitz_traits::shell::ClipboardContents,
+    ) -> Result<(), blitz_traits::shell::ClipboardError> {
+        let mut cb = arboard::Clipboard::new().map_err(|_| blitz_traits::shell::ClipboardError)?;
+        let result = match contents {
+            blitz_traits::shell::ClipboardContents {
+                html: Some(html),
+                text,
+                ..
+            } => cb.set_html(html, text),
+            blitz_traits::shell::ClipboardContents {
+                text: Some(text), ..
+            } => cb.set_text(text),
+            blitz_traits::shell::ClipboardContents {
+                image: Some(image), ..
+            } => cb.set_image(arboard::ImageData {
+                width: image.width,
+                height: image.height,
+                bytes: image.rgba.into(),
+            }),
+            _ => return Err(blitz_traits::shell::ClipboardError),
+        };
+        result.map_err(|_| blitz_traits::shell::ClipboardError)
+    }
+
     #[cfg(all(
         feature = "file-dialog",
         any(
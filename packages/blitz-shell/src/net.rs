@@ -29,7 +29,7 @@ mod data_uri_net_provider {
     //!
     //! Provides an implementation of the [`blitz_traits::net::NetProvider`] trait.
 
-    use blitz_traits::net::{Bytes, NetHandler, NetProvider, NetWaker, Request};
+    use blitz_traits::net::{Bytes, NetError, NetHandler, NetProvider, NetWaker, Request};
     use data_url::DataUrl;
     use std::sync::Arc;
 
@@ -48,22 +48,23 @@ mod data_uri_net_provider {
 
     impl NetProvider for DataUriNetProvider {
         fn fetch(&self, _doc_id: usize, request: Request, handler: Box<dyn NetHandler>) {
-            // let callback = &self.resource_callback;
+            let url = request.url.to_string();
             match request.url.scheme() {
                 "data" => {
                     let Ok(data_url) = DataUrl::process(request.url.as_str()) else {
-                        // callback.call(doc_id, Err(Some(String::from("Failed to parse data uri"))));
-                        return;
+                        let error = NetError::Fetch(String::from("Failed to parse data uri"));
+                        return handler.failed(url, error);
                     };
                     let Ok(decoded) = data_url.decode_to_vec() else {
-                        // callback.call(doc_id, Err(Some(String::from("Failed to decode data uri"))));
-                        return;
+                        let error = NetError::Fetch(String::from("Failed to decode data uri"));
+                        return handler.failed(url, error);
                     };
                     let bytes = Bytes::from(decoded.0);
-                    handler.bytes(request.url.to_string(), bytes);
+                    handler.bytes(url, bytes);
                 }
-                _ => {
-                    // callback.call(doc_id, Err(Some(String::from("UnsupportedScheme"))));
+                scheme => {
+                    let error = NetError::Fetch(format!("Unsupported scheme: {scheme}"));
+                    handler.failed(url, error);
                 }
             };
         }
@@ -0,0 +1,56 @@
+//! User preferences which winit doesn't expose, read from the operating system.
+
+/// Whether the user has asked the operating system to minimise non-essential motion (the
+/// `prefers-reduced-motion` media feature). Defaults to `false` where this can't be
+/// determined.
+#[cfg(target_os = "linux")]
+pub(crate) fn prefers_reduced_motion() -> bool {
+    read_setting(
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "enable-animations"],
+    )
+    .is_some_and(|value| value == "false")
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn prefers_reduced_motion() -> bool {
+    read_setting(
+        "defaults",
+        &["read", "com.apple.universalaccess", "reduceMotion"],
+    )
+    .is_some_and(|value| value == "1")
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn prefers_reduced_motion() -> bool {
+    // "Animate controls and elements inside windows" (`SPI_GETCLIENTAREAANIMATION`) is
+    // stored as `MinAnimate` alongside the other animation settings
+    read_setting(
+        "reg",
+        &[
+            "query",
+            r"HKCU\Control Panel\Desktop\WindowMetrics",
+            "/v",
+            "MinAnimate",
+        ],
+    )
+    .is_some_and(|value| value.split_whitespace().last() == Some("0"))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+pub(crate) fn prefers_reduced_motion() -> bool {
+    false
+}
+
+/// Run a command which prints a setting, returning its trimmed output if it succeeds
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn read_setting(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
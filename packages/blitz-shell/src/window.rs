@@ -5,12 +5,13 @@ use crate::convert_events::{
     winit_key_event_to_blitz, winit_modifiers_to_kbt_modifiers,
 };
 use crate::event::{BlitzShellEvent, BlitzShellProxy, create_waker};
+use crate::preferences;
 use anyrender::WindowRenderer;
 use blitz_dom::Document;
 use blitz_paint::paint_scene;
 use blitz_traits::events::{
-    BlitzPointerEvent, BlitzPointerId, BlitzWheelDelta, BlitzWheelEvent, MouseEventButton,
-    MouseEventButtons, PointerCoords, PointerDetails, UiEvent,
+    BlitzFileDragEvent, BlitzPointerEvent, BlitzPointerId, BlitzWheelDelta, BlitzWheelEvent,
+    MouseEventButton, MouseEventButtons, PointerCoords, PointerDetails, UiEvent,
 };
 use blitz_traits::shell::Viewport;
 use winit::dpi::{LogicalPosition, PhysicalInsets, PhysicalPosition};
@@ -18,8 +19,10 @@ use winit::keyboard::PhysicalKey;
 
 use atomic_refcell::AtomicRefCell;
 use std::any::Any;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::task::Waker;
+use std::time::Duration;
 use web_time::Instant;
 use winit::event::{ButtonSource, ElementState, MouseButton};
 use winit::event_loop::ActiveEventLoop;
@@ -95,6 +98,9 @@ pub struct View<Rend: WindowRenderer> {
     /// avoid a reference cycle.
     pub active_events: Arc<AtomicRefCell<Vec<BlitzPointerEvent>>>,
     pub animation_timer: Option<Instant>,
+    /// When the next frame of an animated image is due, if the window needs redrawing then
+    /// (checked by `BlitzApplication::about_to_wait`)
+    pub next_frame_deadline: Option<Instant>,
     pub is_visible: bool,
     pub safe_area_insets: PhysicalInsets<u32>,
 
@@ -195,6 +201,7 @@ impl<Rend: WindowRenderer> View<Rend> {
         let mut doc = config.doc;
         let mut inner = doc.inner_mut();
         inner.set_viewport(viewport);
+        inner.set_prefers_reduced_motion(preferences::prefers_reduced_motion());
         inner.set_shell_provider(Arc::new(shell_provider));
 
         // If the document title is set prior to the window being created then it will
@@ -211,6 +218,7 @@ impl<Rend: WindowRenderer> View<Rend> {
             renderer: config.renderer,
             waker: None,
             animation_timer: None,
+            next_frame_deadline: None,
             keyboard_modifiers: Default::default(),
             proxy: proxy.clone(),
             window: winit_window.clone(),
@@ -373,19 +381,18 @@ impl<Rend: WindowRenderer> View<Rend> {
         if let Some(waker) = &self.waker {
             let cx = std::task::Context::from_waker(waker);
             if self.doc.poll(Some(cx)) {
-                #[cfg(feature = "accessibility")]
-                {
-                    let inner = self.doc.inner();
-                    if inner.has_changes() {
-                        self.accessibility.update_tree(&inner);
-                    }
-                }
-
                 self.request_redraw();
                 return true;
             }
         }
 
+        // The accessibility tree is updated when the window is redrawn, so changes which
+        // don't need repainting (e.g. focus moving) still need a redraw to be sent
+        #[cfg(feature = "accessibility")]
+        if self.doc.inner().has_accessibility_changes() {
+            self.request_redraw();
+        }
+
         false
     }
 
@@ -415,6 +422,7 @@ impl<Rend: WindowRenderer> View<Rend> {
         let (width, height) = inner.viewport().window_size;
         let scale = inner.viewport().scale_f64();
         let is_animating = inner.is_animating();
+        let next_frame_time = inner.next_animation_frame_time();
         let is_blocked = inner.has_pending_critical_resources();
         let insets = self.safe_area_insets;
 
@@ -432,11 +440,22 @@ impl<Rend: WindowRenderer> View<Rend> {
             });
         }
 
+        #[cfg(feature = "accessibility")]
+        if inner.has_accessibility_changes() {
+            self.accessibility.update_tree(&mut inner);
+        }
+
         drop(inner);
 
         if !is_blocked && is_visible && is_animating {
             self.request_redraw();
         }
+
+        // Animated images only need redrawing when their next frame is due
+        self.next_frame_deadline = next_frame_time
+            .filter(|_| !is_blocked && is_visible)
+            .zip(self.animation_timer)
+            .map(|(time, start)| start + Duration::from_secs_f64(time));
     }
 
     pub fn pointer_coords(&self, position: PhysicalPosition<f64>) -> PointerCoords {
@@ -462,6 +481,18 @@ impl<Rend: WindowRenderer> View<Rend> {
         }
     }
 
+    fn file_drag_event(
+        &self,
+        position: PhysicalPosition<f64>,
+        paths: Vec<PathBuf>,
+    ) -> BlitzFileDragEvent {
+        BlitzFileDragEvent {
+            coords: self.pointer_coords(position),
+            mods: winit_modifiers_to_kbt_modifiers(self.keyboard_modifiers.state()),
+            paths,
+        }
+    }
+
     pub fn window_id(&self) -> WindowId {
         self.window.id()
     }
@@ -520,8 +551,24 @@ impl<Rend: WindowRenderer> View<Rend> {
 
     #[cfg(feature = "accessibility")]
     pub fn build_accessibility_tree(&mut self) {
-        let inner = self.doc.inner();
-        self.accessibility.update_tree(&inner);
+        let mut inner = self.doc.inner_mut();
+        self.accessibility.initial_tree(&mut inner);
+    }
+
+    /// Perform an action requested by assistive technology (e.g. a screen reader)
+    #[cfg(feature = "accessibility")]
+    pub fn handle_accessibility_action(&mut self, request: &accesskit::ActionRequest) {
+        let event = self.doc.inner().accessibility_action_event(request);
+        match event {
+            Some(event) => self.doc.handle_ui_event(event),
+            None => {
+                if !self.doc.inner_mut().forward_accessibility_action(request) {
+                    return;
+                }
+            }
+        }
+        // The accessibility tree is updated when the window is redrawn
+        self.request_redraw();
     }
 
     #[cfg(target_arch = "wasm32")]
@@ -849,10 +896,21 @@ impl<Rend: WindowRenderer> View<Rend> {
             WindowEvent::PanGesture { .. } => {},
             WindowEvent::DoubleTapGesture { .. } => {},
             WindowEvent::RotationGesture { .. } => {},
-            WindowEvent::DragEntered { .. } => {},
-            WindowEvent::DragMoved { .. } => {},
-            WindowEvent::DragDropped { .. } => {},
-            WindowEvent::DragLeft { .. } => {},
+            WindowEvent::DragEntered { paths, position } => {
+                let event = self.file_drag_event(position, paths);
+                self.doc.handle_ui_event(UiEvent::FileDragEnter(event));
+            },
+            WindowEvent::DragMoved { position } => {
+                let event = self.file_drag_event(position, Vec::new());
+                self.doc.handle_ui_event(UiEvent::FileDragMove(event));
+            },
+            WindowEvent::DragDropped { paths, position } => {
+                let event = self.file_drag_event(position, paths);
+                self.doc.handle_ui_event(UiEvent::FileDrop(event));
+            },
+            WindowEvent::DragLeft { .. } => {
+                self.doc.handle_ui_event(UiEvent::FileDragLeave);
+            },
         }
     }
 }
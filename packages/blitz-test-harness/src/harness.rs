@@ -1,6 +1,8 @@
 use std::sync::Arc;
 
-use blitz_dom::{DocGuard, DocGuardMut, Document, DocumentConfig};
+use blitz_dom::{
+    DocGuard, DocGuardMut, Document, DocumentConfig, ImageDecoding, LocalName, QualName, ns,
+};
 use blitz_html::{HtmlDocument, HtmlProvider};
 use blitz_traits::events::UiEvent;
 use blitz_traits::net::NetProvider;
@@ -45,6 +47,8 @@ impl HarnessOptions {
             base_url: self.base_url,
             net_provider: self.net_provider,
             html_parser_provider: Some(Arc::new(HtmlProvider) as _),
+            // Decode images as soon as they load so that tests are deterministic
+            image_decoding: ImageDecoding::Inline,
             ..Default::default()
         }
     }
@@ -182,4 +186,22 @@ impl<D: Document> Harness<D> {
         drop(doc);
         self.pump();
     }
+
+    /// Set attribute `name` of the first element matching `selector` and [`pump`](Self::pump)
+    pub fn set_attribute(&mut self, selector: &str, name: &str, value: &str) {
+        let node_id = self.node(selector);
+        self.base_mut()
+            .mutate()
+            .set_attribute(node_id, qname(name), value);
+        self.pump();
+    }
+}
+
+/// A [`QualName`] in the HTML namespace, for creating elements and setting attributes
+pub fn qname(local: &str) -> QualName {
+    QualName {
+        prefix: None,
+        ns: ns!(html),
+        local: LocalName::from(local),
+    }
 }
@@ -6,12 +6,13 @@
 
 use blitz_dom::Document;
 use blitz_traits::events::{
-    BlitzImeEvent, BlitzKeyEvent, BlitzPointerEvent, BlitzPointerId, BlitzWheelDelta,
-    BlitzWheelEvent, KeyState, MouseEventButton, MouseEventButtons, Point, PointerCoords,
-    PointerDetails, UiEvent,
+    BlitzFileDragEvent, BlitzImeEvent, BlitzKeyEvent, BlitzPointerEvent, BlitzPointerId,
+    BlitzWheelDelta, BlitzWheelEvent, KeyState, MouseEventButton, MouseEventButtons, Point,
+    PointerCoords, PointerDetails, UiEvent,
 };
 use keyboard_types::{Code, Key, Location, Modifiers};
 use smol_str::SmolStr;
+use std::path::PathBuf;
 
 use crate::Harness;
 
@@ -72,6 +73,15 @@ pub fn touch_pointer_event(finger: u64, x: f32, y: f32) -> BlitzPointerEvent {
     )
 }
 
+/// Construct a [`BlitzFileDragEvent`] for files at `paths` dragged to page coordinates `(x, y)`
+pub fn file_drag_event(x: f32, y: f32, paths: Vec<PathBuf>) -> BlitzFileDragEvent {
+    BlitzFileDragEvent {
+        coords: coords(x, y),
+        mods: Modifiers::default(),
+        paths,
+    }
+}
+
 /// Construct a [`BlitzKeyEvent`] for `key` in the given `state`.
 ///
 /// For [`Key::Character`] keys, `text` is populated with the character(s) on press.
@@ -92,6 +102,19 @@ pub fn key_event(key: Key, state: KeyState, modifiers: Modifiers) -> BlitzKeyEve
     }
 }
 
+/// The [`UiEvent`]s for pressing and releasing `key`, for recording with
+/// [`Harness::dispatch_recorded`]
+pub fn key_press(key: Key) -> [UiEvent; 2] {
+    [
+        UiEvent::KeyDown(key_event(
+            key.clone(),
+            KeyState::Pressed,
+            Modifiers::empty(),
+        )),
+        UiEvent::KeyUp(key_event(key, KeyState::Released, Modifiers::empty())),
+    ]
+}
+
 impl<D: Document> Harness<D> {
     /// Click (pointer down + up) the center of the first element matching `selector`
     pub fn click(&mut self, selector: &str) {
@@ -148,6 +171,15 @@ impl<D: Document> Harness<D> {
         self.pump();
     }
 
+    /// Drag the files at `paths` in from outside of the application and drop them at page
+    /// coordinates `(x, y)`
+    pub fn drop_files_at(&mut self, x: f32, y: f32, paths: Vec<PathBuf>) {
+        self.dispatch(UiEvent::FileDragEnter(file_drag_event(x, y, paths.clone())));
+        self.dispatch(UiEvent::FileDragMove(file_drag_event(x, y, Vec::new())));
+        self.dispatch(UiEvent::FileDrop(file_drag_event(x, y, paths)));
+        self.pump();
+    }
+
     /// Tap (touch down + up) the center of the first element matching `selector`
     pub fn tap(&mut self, selector: &str) {
         let (x, y) = self.center_of(selector);
@@ -2,7 +2,7 @@
 
 use std::fmt::Write as _;
 
-use blitz_dom::{Document, Node, NodeData};
+use blitz_dom::{Document, Node, NodeData, local_name};
 use blitz_traits::events::HitResult;
 use blitz_traits::node_id::NodeId;
 
@@ -47,6 +47,18 @@ impl<D: Document> Harness<D> {
             .to_vec()
     }
 
+    /// The `id`s of the elements matching `selector` (skipping those without one)
+    pub fn ids(&self, selector: &str) -> Vec<String> {
+        let node_ids = self.query_all(selector);
+        let doc = self.base();
+        node_ids
+            .into_iter()
+            .filter_map(|node_id| doc.get_node(node_id)?.attr(local_name!("id")))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+
     /// The border-box of the first element matching `selector`, in page coordinates
     pub fn layout_rect(&self, selector: &str) -> Rect {
         let node_id = self.node(selector);
@@ -10,13 +10,22 @@
 //! - DOM inspection helpers (selectors, layout rects, hit-testing, tree dumps)
 //! - Programmatic input synthesis (clicks, taps, drags, wheel, keyboard, IME) that routes
 //!   through the real event-dispatch pipeline, without requiring a window
+//! - [`ManualNetProvider`] and [`StaticNetProvider`] for controlling when and how resources
+//!   load
+//! - Pixel counting for asserting on rendered output ([`count_pixels`])
 //!
 //! No window, GPU, or compositor is required, so tests run headless.
 
 mod harness;
 mod input;
 mod inspect;
+mod net;
+mod pixels;
 
-pub use harness::{Harness, HarnessOptions};
-pub use input::{key_event, mouse_pointer_event, pointer_event, touch_pointer_event};
+pub use harness::{Harness, HarnessOptions, qname};
+pub use input::{
+    file_drag_event, key_event, key_press, mouse_pointer_event, pointer_event, touch_pointer_event,
+};
 pub use inspect::Rect;
+pub use net::{ManualNetProvider, StaticNetProvider};
+pub use pixels::count_pixels;
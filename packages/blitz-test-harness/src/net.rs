@@ -0,0 +1,74 @@
+//! [`NetProvider`]s for tests which control when (and whether) resources load.
+
+use std::sync::Mutex;
+
+use blitz_traits::net::{Bytes, NetHandler, NetProvider, Request};
+
+/// A [`NetProvider`] which records requests so the test can check which resources were
+/// requested and deliver the responses at a time of its choosing
+#[derive(Default)]
+pub struct ManualNetProvider {
+    requests: Mutex<Vec<(String, Box<dyn NetHandler>)>>,
+}
+
+impl NetProvider for ManualNetProvider {
+    fn fetch(&self, _doc_id: usize, request: Request, handler: Box<dyn NetHandler>) {
+        self.requests
+            .lock()
+            .unwrap()
+            .push((request.url.to_string(), handler));
+    }
+}
+
+impl ManualNetProvider {
+    /// The number of requests which haven't been taken yet
+    pub fn pending_requests(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// Take the first request whose url ends with `suffix`, returning its url and handler.
+    /// Panics if there is no such request.
+    pub fn take_request(&self, suffix: &str) -> (String, Box<dyn NetHandler>) {
+        let mut requests = self.requests.lock().unwrap();
+        let idx = requests
+            .iter()
+            .position(|(url, _)| url.ends_with(suffix))
+            .unwrap_or_else(|| panic!("{suffix} was not requested"));
+        requests.remove(idx)
+    }
+
+    /// Take the most recent request, returning its url and handler
+    pub fn take_last_request(&self) -> Option<(String, Box<dyn NetHandler>)> {
+        self.requests.lock().unwrap().pop()
+    }
+
+    /// Take all requests, returning the file names of their urls in order
+    pub fn take_requested_files(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|(url, _)| url.rsplit('/').next().unwrap().to_string())
+            .collect()
+    }
+}
+
+/// A [`NetProvider`] which immediately responds to every request with the same bytes (e.g.
+/// an image)
+pub struct StaticNetProvider {
+    bytes: Bytes,
+}
+
+impl StaticNetProvider {
+    pub fn new(bytes: &'static [u8]) -> Self {
+        Self {
+            bytes: Bytes::from_static(bytes),
+        }
+    }
+}
+
+impl NetProvider for StaticNetProvider {
+    fn fetch(&self, _doc_id: usize, request: Request, handler: Box<dyn NetHandler>) {
+        handler.bytes(request.url.to_string(), self.bytes.clone());
+    }
+}
@@ -0,0 +1,17 @@
+//! Helpers for inspecting rendered RGBA8 pixel buffers.
+
+use std::ops::Range;
+
+/// The number of pixels in the columns `columns` of an RGBA8 `buffer` that is `width` pixels
+/// wide which are mostly `rgb` (each channel within 60 of it)
+pub fn count_pixels(buffer: &[u8], width: u32, columns: Range<u32>, rgb: [u8; 3]) -> usize {
+    let height = buffer.len() / (width as usize * 4);
+    (0..height)
+        .flat_map(|y| {
+            columns
+                .clone()
+                .map(move |x| (y * width as usize + x as usize) * 4)
+        })
+        .filter(|&idx| (0..3).all(|c| (buffer[idx + c] as i32 - rgb[c] as i32).abs() < 60))
+        .count()
+}
@@ -1,5 +1,6 @@
 //! Types to represent UI and DOM events
 
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -9,6 +10,7 @@ use keyboard_types::{Code, Key, Location, Modifiers};
 use smol_str::SmolStr;
 
 use crate::NodeId;
+use crate::shell::{ClipboardContents, ClipboardImage};
 
 #[derive(Default)]
 pub struct EventState {
@@ -69,6 +71,16 @@ pub enum UiEvent {
     KeyDown(BlitzKeyEvent),
     Ime(BlitzImeEvent),
     AppleStandardKeybinding(SmolStr),
+    /// Files dragged in from outside of the application entered the window
+    FileDragEnter(BlitzFileDragEvent),
+    /// Files dragged in from outside of the application moved within the window
+    FileDragMove(BlitzFileDragEvent),
+    /// Files dragged in from outside of the application were dropped on the window
+    FileDrop(BlitzFileDragEvent),
+    /// Files dragged in from outside of the application left the window (or the drag was cancelled)
+    FileDragLeave,
+    /// Assistive technology (e.g. a screen reader) requested an action on behalf of the user
+    AccessibilityAction(BlitzAccessibilityActionEvent),
 }
 impl UiEvent {
     pub fn discriminant(&self) -> u8 {
@@ -144,14 +156,40 @@ pub enum DomEventKind {
     KeyPress,
     KeyDown,
     KeyUp,
+    BeforeInput,
     Input,
+    Change,
     Ime,
 
+    Invalid,
+    Reset,
+
+    Cancel,
+    Close,
+
+    BeforeToggle,
+    Toggle,
+
+    Copy,
+    Cut,
+    Paste,
+
+    DragStart,
+    Drag,
+    DragEnd,
+    DragEnter,
+    DragOver,
+    DragLeave,
+    Drop,
+
     Focus,
     Blur,
     FocusIn,
     FocusOut,
 
+    Load,
+    Error,
+
     AppleStandardKeybinding,
 }
 impl DomEventKind {
@@ -195,13 +233,39 @@ impl FromStr for DomEventKind {
             "keypress" => Ok(Self::KeyPress),
             "keydown" => Ok(Self::KeyDown),
             "keyup" => Ok(Self::KeyUp),
+            "beforeinput" => Ok(Self::BeforeInput),
             "input" => Ok(Self::Input),
+            "change" => Ok(Self::Change),
             "composition" => Ok(Self::Ime),
 
+            "invalid" => Ok(Self::Invalid),
+            "reset" => Ok(Self::Reset),
+
+            "cancel" => Ok(Self::Cancel),
+            "close" => Ok(Self::Close),
+
+            "beforetoggle" => Ok(Self::BeforeToggle),
+            "toggle" => Ok(Self::Toggle),
+
+            "copy" => Ok(Self::Copy),
+            "cut" => Ok(Self::Cut),
+            "paste" => Ok(Self::Paste),
+
+            "dragstart" => Ok(Self::DragStart),
+            "drag" => Ok(Self::Drag),
+            "dragend" => Ok(Self::DragEnd),
+            "dragenter" => Ok(Self::DragEnter),
+            "dragover" => Ok(Self::DragOver),
+            "dragleave" => Ok(Self::DragLeave),
+            "drop" => Ok(Self::Drop),
+
             "focus" => Ok(Self::Focus),
             "blur" => Ok(Self::Blur),
             "focusin" => Ok(Self::FocusIn),
             "focusout" => Ok(Self::FocusOut),
+
+            "load" => Ok(Self::Load),
+            "error" => Ok(Self::Error),
             _ => Err(()),
         }
     }
@@ -242,14 +306,40 @@ pub enum DomEventData {
     KeyPress(BlitzKeyEvent),
     KeyDown(BlitzKeyEvent),
     KeyUp(BlitzKeyEvent),
+    BeforeInput(BlitzBeforeInputEvent),
     Input(BlitzInputEvent),
+    Change(BlitzInputEvent),
     Ime(BlitzImeEvent),
 
+    Invalid(BlitzFormEvent),
+    Reset(BlitzFormEvent),
+
+    Cancel(BlitzDialogEvent),
+    Close(BlitzDialogEvent),
+
+    BeforeToggle(BlitzToggleEvent),
+    Toggle(BlitzToggleEvent),
+
+    Copy(BlitzClipboardEvent),
+    Cut(BlitzClipboardEvent),
+    Paste(BlitzClipboardEvent),
+
+    DragStart(BlitzDragEvent),
+    Drag(BlitzDragEvent),
+    DragEnd(BlitzDragEvent),
+    DragEnter(BlitzDragEvent),
+    DragOver(BlitzDragEvent),
+    DragLeave(BlitzDragEvent),
+    Drop(BlitzDragEvent),
+
     Focus(BlitzFocusEvent),
     Blur(BlitzFocusEvent),
     FocusIn(BlitzFocusEvent),
     FocusOut(BlitzFocusEvent),
 
+    Load(BlitzLoadEvent),
+    Error(BlitzLoadEvent),
+
     AppleStandardKeybinding(SmolStr),
 }
 impl DomEventData {
@@ -297,14 +387,40 @@ impl DomEventData {
             Self::KeyPress { .. } => "keypress",
             Self::KeyDown { .. } => "keydown",
             Self::KeyUp { .. } => "keyup",
+            Self::BeforeInput { .. } => "beforeinput",
             Self::Input { .. } => "input",
+            Self::Change { .. } => "change",
             Self::Ime { .. } => "composition",
 
+            Self::Invalid { .. } => "invalid",
+            Self::Reset { .. } => "reset",
+
+            Self::Cancel { .. } => "cancel",
+            Self::Close { .. } => "close",
+
+            Self::BeforeToggle { .. } => "beforetoggle",
+            Self::Toggle { .. } => "toggle",
+
+            Self::Copy { .. } => "copy",
+            Self::Cut { .. } => "cut",
+            Self::Paste { .. } => "paste",
+
+            Self::DragStart { .. } => "dragstart",
+            Self::Drag { .. } => "drag",
+            Self::DragEnd { .. } => "dragend",
+            Self::DragEnter { .. } => "dragenter",
+            Self::DragOver { .. } => "dragover",
+            Self::DragLeave { .. } => "dragleave",
+            Self::Drop { .. } => "drop",
+
             Self::Focus { .. } => "focus",
             Self::Blur { .. } => "blur",
             Self::FocusIn { .. } => "focusin",
             Self::FocusOut { .. } => "focusout",
 
+            Self::Load { .. } => "load",
+            Self::Error { .. } => "error",
+
             Self::AppleStandardKeybinding { .. } => "applekeybinding",
         }
     }
@@ -343,14 +459,40 @@ impl DomEventData {
             Self::KeyPress { .. } => DomEventKind::KeyPress,
             Self::KeyDown { .. } => DomEventKind::KeyDown,
             Self::KeyUp { .. } => DomEventKind::KeyUp,
+            Self::BeforeInput { .. } => DomEventKind::BeforeInput,
             Self::Input { .. } => DomEventKind::Input,
+            Self::Change { .. } => DomEventKind::Change,
             Self::Ime { .. } => DomEventKind::Ime,
 
+            Self::Invalid { .. } => DomEventKind::Invalid,
+            Self::Reset { .. } => DomEventKind::Reset,
+
+            Self::Cancel { .. } => DomEventKind::Cancel,
+            Self::Close { .. } => DomEventKind::Close,
+
+            Self::BeforeToggle { .. } => DomEventKind::BeforeToggle,
+            Self::Toggle { .. } => DomEventKind::Toggle,
+
+            Self::Copy { .. } => DomEventKind::Copy,
+            Self::Cut { .. } => DomEventKind::Cut,
+            Self::Paste { .. } => DomEventKind::Paste,
+
+            Self::DragStart { .. } => DomEventKind::DragStart,
+            Self::Drag { .. } => DomEventKind::Drag,
+            Self::DragEnd { .. } => DomEventKind::DragEnd,
+            Self::DragEnter { .. } => DomEventKind::DragEnter,
+            Self::DragOver { .. } => DomEventKind::DragOver,
+            Self::DragLeave { .. } => DomEventKind::DragLeave,
+            Self::Drop { .. } => DomEventKind::Drop,
+
             Self::Focus { .. } => DomEventKind::Focus,
             Self::Blur { .. } => DomEventKind::Blur,
             Self::FocusIn { .. } => DomEventKind::FocusIn,
             Self::FocusOut { .. } => DomEventKind::FocusOut,
 
+            Self::Load { .. } => DomEventKind::Load,
+            Self::Error { .. } => DomEventKind::Error,
+
             Self::AppleStandardKeybinding { .. } => DomEventKind::AppleStandardKeybinding,
         }
     }
@@ -390,13 +532,40 @@ impl DomEventData {
             Self::KeyUp { .. } => true,
             Self::KeyPress { .. } => true,
             Self::Ime { .. } => true,
+            Self::BeforeInput { .. } => true,
             Self::Input { .. } => false,
+            Self::Change { .. } => false,
+
+            Self::Invalid { .. } => true,
+            Self::Reset { .. } => true,
+
+            Self::Cancel { .. } => true,
+            Self::Close { .. } => false,
+
+            // Only cancelable when a popover is being shown (see `BlitzToggleEvent`)
+            Self::BeforeToggle(event) => event.new_state == ToggleState::Open,
+            Self::Toggle { .. } => false,
+
+            Self::Copy { .. } => true,
+            Self::Cut { .. } => true,
+            Self::Paste { .. } => true,
+
+            Self::DragStart { .. } => true,
+            Self::Drag { .. } => true,
+            Self::DragEnd { .. } => false,
+            Self::DragEnter { .. } => true,
+            Self::DragOver { .. } => true,
+            Self::DragLeave { .. } => false,
+            Self::Drop { .. } => true,
 
             Self::Focus { .. } => false,
             Self::Blur { .. } => false,
             Self::FocusIn { .. } => false,
             Self::FocusOut { .. } => false,
 
+            Self::Load { .. } => false,
+            Self::Error { .. } => false,
+
             Self::AppleStandardKeybinding { .. } => true,
         }
     }
@@ -436,13 +605,39 @@ impl DomEventData {
             Self::KeyUp { .. } => true,
             Self::KeyPress { .. } => true,
             Self::Ime { .. } => true,
+            Self::BeforeInput { .. } => true,
             Self::Input { .. } => true,
+            Self::Change { .. } => true,
+
+            Self::Invalid { .. } => false,
+            Self::Reset { .. } => true,
+
+            Self::Cancel { .. } => false,
+            Self::Close { .. } => false,
+
+            Self::BeforeToggle { .. } => false,
+            Self::Toggle { .. } => false,
+
+            Self::Copy { .. } => true,
+            Self::Cut { .. } => true,
+            Self::Paste { .. } => true,
+
+            Self::DragStart { .. } => true,
+            Self::Drag { .. } => true,
+            Self::DragEnd { .. } => true,
+            Self::DragEnter { .. } => true,
+            Self::DragOver { .. } => true,
+            Self::DragLeave { .. } => true,
+            Self::Drop { .. } => true,
 
             Self::Focus { .. } => false,
             Self::Blur { .. } => false,
             Self::FocusIn { .. } => true,
             Self::FocusOut { .. } => true,
 
+            Self::Load { .. } => false,
+            Self::Error { .. } => false,
+
             Self::AppleStandardKeybinding { .. } => false,
         }
     }
@@ -467,7 +662,7 @@ pub enum BlitzPointerId {
     Finger(u64),
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct PointerCoords {
     pub page_x: f32,
     pub page_y: f32,
@@ -728,9 +923,340 @@ pub struct BlitzInputEvent {
     pub value: String,
 }
 
+/// An edit which is about to be made to a text input. The edit is made by the event's default
+/// action, so cancelling the event prevents it.
+#[derive(Clone, Debug)]
+pub struct BlitzBeforeInputEvent {
+    /// The input's value before the edit
+    pub value: String,
+    /// The kind of edit
+    pub input_type: InputType,
+    /// The text being inserted (`None` for deletions and undo/redo)
+    pub data: Option<String>,
+}
+
+/// The kind of edit described by a `beforeinput` event
+///
+/// See: https://w3c.github.io/input-events/#interface-InputEvent-Attributes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InputType {
+    InsertText,
+    InsertLineBreak,
+    InsertFromPaste,
+    InsertCompositionText,
+    DeleteContentBackward,
+    DeleteContentForward,
+    DeleteWordBackward,
+    DeleteWordForward,
+    DeleteSoftLineBackward,
+    DeleteSoftLineForward,
+    DeleteHardLineBackward,
+    DeleteHardLineForward,
+    DeleteByCut,
+    InsertReplacementText,
+    HistoryUndo,
+    HistoryRedo,
+}
+
+impl InputType {
+    /// The value of the event's `inputType` attribute ("insertText", "historyUndo", etc)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InsertText => "insertText",
+            Self::InsertLineBreak => "insertLineBreak",
+            Self::InsertFromPaste => "insertFromPaste",
+            Self::InsertCompositionText => "insertCompositionText",
+            Self::DeleteContentBackward => "deleteContentBackward",
+            Self::DeleteContentForward => "deleteContentForward",
+            Self::DeleteWordBackward => "deleteWordBackward",
+            Self::DeleteWordForward => "deleteWordForward",
+            Self::DeleteSoftLineBackward => "deleteSoftLineBackward",
+            Self::DeleteSoftLineForward => "deleteSoftLineForward",
+            Self::DeleteHardLineBackward => "deleteHardLineBackward",
+            Self::DeleteHardLineForward => "deleteHardLineForward",
+            Self::DeleteByCut => "deleteByCut",
+            Self::InsertReplacementText => "insertReplacementText",
+            Self::HistoryUndo => "historyUndo",
+            Self::HistoryRedo => "historyRedo",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BlitzFocusEvent;
 
+/// An event fired at a form or form control which carries no data (`invalid`, `reset`)
+#[derive(Clone, Debug)]
+pub struct BlitzFormEvent;
+
+/// An event fired at a dialog which carries no data (`cancel`, `close`)
+#[derive(Clone, Debug)]
+pub struct BlitzDialogEvent;
+
+/// Whether a popover is open or closed, before or after a `beforetoggle` or `toggle` event
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToggleState {
+    Open,
+    Closed,
+}
+
+impl ToggleState {
+    /// The value of the event's `oldState` or `newState` attribute ("open" or "closed")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Closed => "closed",
+        }
+    }
+}
+
+/// A `beforetoggle` or `toggle` event, fired at a popover when it is shown or hidden.
+///
+/// Cancelling a `beforetoggle` event prevents the popover from being shown, but it can't
+/// prevent a popover from being hidden.
+#[derive(Clone, Debug)]
+pub struct BlitzToggleEvent {
+    pub old_state: ToggleState,
+    pub new_state: ToggleState,
+    /// The element which caused the popover to be toggled (e.g. a `popovertarget` button)
+    pub source: Option<NodeId>,
+}
+
+/// A drag-and-drop event (`dragstart`, `drag`, `dragend`, `dragenter`, `dragover`, `dragleave`
+/// and `drop`)
+#[derive(Clone, Debug)]
+pub struct BlitzDragEvent {
+    pub coords: PointerCoords,
+    pub mods: Modifiers,
+    /// The data being dragged. This is shared by all of the events of a drag-and-drop operation.
+    pub data_transfer: DataTransfer,
+}
+
+/// An action requested by assistive technology
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccessibilityAction {
+    /// Activate the element, as if it were clicked
+    Click,
+    Focus,
+    ScrollIntoView,
+    /// Replace the value of a text input
+    SetValue(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct BlitzAccessibilityActionEvent {
+    /// The node that the action is performed on
+    pub target: NodeId,
+    pub action: AccessibilityAction,
+}
+
+/// A clipboard event (`copy`, `cut` and `paste`).
+///
+/// For `copy` and `cut` events, `data_transfer` starts out empty: cancelling the event writes
+/// the data added to it by the event's handlers to the clipboard (instead of the selection).
+/// For `paste` events it holds the contents of the clipboard, and the text inserted into a
+/// text input is its `text/plain` data, so handlers can change what is pasted by replacing it.
+#[derive(Clone, Debug)]
+pub struct BlitzClipboardEvent {
+    pub data_transfer: DataTransfer,
+}
+
+/// Files being dragged over (or dropped on) the window from outside of the application
+#[derive(Clone, Debug)]
+pub struct BlitzFileDragEvent {
+    pub coords: PointerCoords,
+    pub mods: Modifiers,
+    /// The paths of the dragged files. Only set for [`UiEvent::FileDragEnter`] and
+    /// [`UiEvent::FileDrop`] (platforms don't resend them as the drag moves).
+    pub paths: Vec<PathBuf>,
+}
+
+/// The operation a drop will perform, as chosen by the drop target
+/// (the [`dropEffect`](https://html.spec.whatwg.org/multipage/dnd.html#dom-datatransfer-dropeffect)
+/// of a `DataTransfer`)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DropEffect {
+    /// The drop is not allowed
+    #[default]
+    None,
+    Copy,
+    Move,
+    Link,
+}
+
+/// The data being dragged in a drag-and-drop operation, or copied to or pasted from the
+/// clipboard (equivalent to the web's
+/// [`DataTransfer`](https://html.spec.whatwg.org/multipage/dnd.html#the-datatransfer-interface)).
+///
+/// Cloning a `DataTransfer` creates a new handle to the same data, so data added by a
+/// `dragstart` handler can be read by a `drop` handler, and the drop effect chosen by a
+/// `dragover` handler is seen by the document.
+#[derive(Clone, Debug, Default)]
+pub struct DataTransfer {
+    inner: Arc<AtomicRefCell<DataTransferData>>,
+}
+
+#[derive(Debug, Default)]
+struct DataTransferData {
+    /// (format, data) pairs in the order that they were added
+    items: Vec<(String, String)>,
+    files: Vec<PathBuf>,
+    image: Option<Arc<ClipboardImage>>,
+    /// Reads `image` the first time that it is asked for
+    load_image: Option<ImageLoader>,
+    drop_effect: DropEffect,
+}
+
+struct ImageLoader(Box<dyn FnOnce() -> Option<ClipboardImage> + Send + Sync>);
+
+impl std::fmt::Debug for ImageLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ImageLoader")
+    }
+}
+
+impl PartialEq for DataTransfer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl DataTransfer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A `DataTransfer` holding the files at `paths`
+    pub fn from_files(paths: Vec<PathBuf>) -> Self {
+        let data_transfer = Self::default();
+        data_transfer.inner.borrow_mut().files = paths;
+        data_transfer
+    }
+
+    /// A `DataTransfer` holding the contents of the clipboard
+    pub fn from_clipboard_contents(contents: ClipboardContents) -> Self {
+        let data_transfer = Self::default();
+        if let Some(text) = &contents.text {
+            data_transfer.set_data("text/plain", text);
+        }
+        if let Some(html) = &contents.html {
+            data_transfer.set_data("text/html", html);
+        }
+        data_transfer.set_image(contents.image);
+        data_transfer
+    }
+
+    /// The data in the formats that can be written to the clipboard
+    pub fn to_clipboard_contents(&self) -> ClipboardContents {
+        ClipboardContents {
+            text: self.get_data("text/plain"),
+            html: self.get_data("text/html"),
+            image: self.image().map(|image| (*image).clone()),
+        }
+    }
+
+    /// Set the data for the given format (e.g. `text/plain`), replacing any existing data for it
+    pub fn set_data(&self, format: &str, data: &str) {
+        let format = normalize_format(format);
+        let mut inner = self.inner.borrow_mut();
+        match inner.items.iter_mut().find(|(f, _)| *f == format) {
+            Some((_, existing)) => *existing = data.to_string(),
+            None => inner.items.push((format, data.to_string())),
+        }
+    }
+
+    /// Get the data for the given format (e.g. `text/plain`)
+    pub fn get_data(&self, format: &str) -> Option<String> {
+        let format = normalize_format(format);
+        let inner = self.inner.borrow();
+        inner
+            .items
+            .iter()
+            .find(|(f, _)| *f == format)
+            .map(|(_, data)| data.clone())
+    }
+
+    /// Remove the data for the given format, or all (non-file) data if `format` is `None`
+    pub fn clear_data(&self, format: Option<&str>) {
+        let mut inner = self.inner.borrow_mut();
+        match format.map(normalize_format) {
+            Some(format) => inner.items.retain(|(f, _)| *f != format),
+            None => inner.items.clear(),
+        }
+    }
+
+    /// The formats for which there is data, followed by `Files` if any files are being dragged
+    pub fn types(&self) -> Vec<String> {
+        let inner = self.inner.borrow();
+        let mut types: Vec<String> = inner.items.iter().map(|(f, _)| f.clone()).collect();
+        if !inner.files.is_empty() {
+            types.push(String::from("Files"));
+        }
+        types
+    }
+
+    /// The files being dragged
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.inner.borrow().files.clone()
+    }
+
+    pub fn has_files(&self) -> bool {
+        !self.inner.borrow().files.is_empty()
+    }
+
+    /// The image being transferred (pasted images)
+    pub fn image(&self) -> Option<Arc<ClipboardImage>> {
+        let mut inner = self.inner.borrow_mut();
+        if let Some(ImageLoader(load)) = inner.load_image.take() {
+            inner.image = load().map(Arc::new);
+        }
+        inner.image.clone()
+    }
+
+    pub fn set_image(&self, image: Option<ClipboardImage>) {
+        let mut inner = self.inner.borrow_mut();
+        inner.image = image.map(Arc::new);
+        inner.load_image = None;
+    }
+
+    /// Set the image to one which is read by `load` the first time that it is asked for (as
+    /// reading an image from the clipboard is slow, and most pastes only use the text)
+    pub fn set_image_with(
+        &self,
+        load: impl FnOnce() -> Option<ClipboardImage> + Send + Sync + 'static,
+    ) {
+        let mut inner = self.inner.borrow_mut();
+        inner.image = None;
+        inner.load_image = Some(ImageLoader(Box::new(load)));
+    }
+
+    pub fn drop_effect(&self) -> DropEffect {
+        self.inner.borrow().drop_effect
+    }
+
+    pub fn set_drop_effect(&self, drop_effect: DropEffect) {
+        self.inner.borrow_mut().drop_effect = drop_effect;
+    }
+}
+
+/// Formats are ASCII lowercased, and `text` and `url` are aliases for `text/plain` and
+/// `text/uri-list`
+fn normalize_format(format: &str) -> String {
+    match format.to_ascii_lowercase().as_str() {
+        "text" => String::from("text/plain"),
+        "url" => String::from("text/uri-list"),
+        format => format.to_string(),
+    }
+}
+
+/// A resource (image, stylesheet, etc) associated with the target element finished loading
+/// (`load`) or failed to load (`error`)
+#[derive(Clone, Debug)]
+pub struct BlitzLoadEvent {
+    /// The url of the resource
+    pub url: String,
+}
+
 /// Copy of Winit IME event to avoid lower-level Blitz crates depending on winit
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum BlitzImeEvent {
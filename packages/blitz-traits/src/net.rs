@@ -1,7 +1,7 @@
 //! Abstractions of networking so that custom networking implementations can be provided
 
 pub use bytes::Bytes;
-pub use http::{self, HeaderMap, Method};
+pub use http::{self, HeaderMap, Method, StatusCode};
 use serde::{
     Serialize,
     ser::{SerializeSeq, SerializeTuple},
@@ -31,8 +31,32 @@ pub trait NetProvider: Send + Sync + 'static {
 
 /// A type that parses raw bytes from a network request into a Data and then calls
 /// the NetCallack with the result.
+///
+/// Exactly one of [`bytes`](Self::bytes), [`response`](Self::response) or
+/// [`failed`](Self::failed) is called for each request.
 pub trait NetHandler: Send + Sync + 'static {
+    /// Called with the body of a successful response
     fn bytes(self: Box<Self>, resolved_url: String, bytes: Bytes);
+
+    /// Called with the status, headers and body of a response.
+    ///
+    /// The default implementation forwards successful responses to [`bytes`](Self::bytes)
+    /// and reports any other status to [`failed`](Self::failed). Handlers which need the
+    /// response's headers (or the body of an error response) should override it.
+    fn response(self: Box<Self>, response: Response, bytes: Bytes) {
+        if response.is_success() {
+            self.bytes(response.url, bytes);
+        } else {
+            let error = NetError::HttpStatus(response.status);
+            self.failed(response.url, error);
+        }
+    }
+
+    /// Called if the request failed. `url` is the final URL of the request if a response
+    /// was received, and the requested URL otherwise.
+    fn failed(self: Box<Self>, url: String, error: NetError) {
+        let _ = (url, error);
+    }
 }
 
 /// A callback which gets called every time a network request completes
@@ -77,6 +101,79 @@ impl Request {
     }
 }
 
+/// The metadata of a response to a [`Request`], loosely representing
+/// <https://fetch.spec.whatwg.org/#responses>
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// The final URL of the response (after following any redirects)
+    pub url: String,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+}
+impl Response {
+    /// A `200 OK` response from `url` with no headers
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Whether the response has a 2xx status
+    pub fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    /// The (lowercased) MIME type essence of the response's `Content-Type` header,
+    /// without any parameters. For example `text/css` for `text/css; charset=utf-8`.
+    pub fn mime_type(&self) -> Option<String> {
+        let content_type = self
+            .headers
+            .get(http::header::CONTENT_TYPE)?
+            .to_str()
+            .ok()?;
+        let essence = content_type.split(';').next()?.trim();
+        (!essence.is_empty()).then(|| essence.to_ascii_lowercase())
+    }
+}
+
+/// The reason a request failed
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub enum NetError {
+    /// The request was aborted through its [`AbortSignal`]
+    Aborted,
+    /// The server responded with a non-success status
+    HttpStatus(StatusCode),
+    /// The request could not be completed: e.g. a DNS failure, a refused connection, a
+    /// missing file or an unsupported URL scheme
+    Fetch(String),
+}
+
+impl std::fmt::Display for NetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Aborted => write!(f, "request aborted"),
+            Self::HttpStatus(status) => write!(f, "HTTP {status}"),
+            Self::Fetch(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
 #[derive(Debug, Clone)]
 pub enum Body {
     Bytes(Bytes),
@@ -6,6 +6,25 @@ use cursor_icon::CursorIcon;
 // TODO: fill out with meaningful errors
 pub struct ClipboardError;
 
+/// The contents of the clipboard in each of the formats that Blitz supports
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClipboardContents {
+    /// Plain text (`text/plain`)
+    pub text: Option<String>,
+    /// An HTML fragment (`text/html`)
+    pub html: Option<String>,
+    pub image: Option<ClipboardImage>,
+}
+
+/// An image on the clipboard
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipboardImage {
+    pub width: usize,
+    pub height: usize,
+    /// The image's pixels as 8-bit RGBA, row by row
+    pub rgba: Vec<u8>,
+}
+
 /// Abstraction over windowing / operating system ("shell") functionality that allows a Blitz document
 /// to access that functionality without depending on a specific shell environment.
 pub trait ShellProvider: Send + Sync + 'static {
@@ -32,6 +51,25 @@ pub trait ShellProvider: Send + Sync + 'static {
         let _ = text;
         Err(ClipboardError)
     }
+    /// Read the clipboard's text and HTML. Defaults to reading it as text. Images are read
+    /// separately, by [`ShellProvider::get_clipboard_image`], as they are slow to read.
+    fn get_clipboard_contents(&self) -> Result<ClipboardContents, ClipboardError> {
+        Ok(ClipboardContents {
+            text: Some(self.get_clipboard_text()?),
+            ..Default::default()
+        })
+    }
+    /// Read an image from the clipboard
+    fn get_clipboard_image(&self) -> Result<ClipboardImage, ClipboardError> {
+        Err(ClipboardError)
+    }
+    /// Replace the contents of the clipboard. Defaults to writing only the text.
+    fn set_clipboard_contents(&self, contents: ClipboardContents) -> Result<(), ClipboardError> {
+        match contents.text {
+            Some(text) => self.set_clipboard_text(text),
+            None => Err(ClipboardError),
+        }
+    }
     fn open_file_dialog(
         &self,
         multiple: bool,
@@ -14,8 +14,11 @@
 use std::sync::Arc;
 
 use anyrender_vello::VelloWindowRenderer as WindowRenderer;
-use blitz_dom::DocumentConfig;
+use blitz_dom::{Document, DocumentConfig};
 use blitz_html::HtmlDocument;
+#[cfg(feature = "net")]
+#[cfg(not(target_arch = "wasm32"))]
+use blitz_html::StreamingHtmlDocument;
 use blitz_shell::{
     BlitzApplication, BlitzShellProxy, Config, EventLoop, WindowConfig, create_default_event_loop,
 };
@@ -62,21 +65,42 @@ pub fn launch_url(url: &str) {
     let net_provider = create_net_provider(proxy.clone());
     let application = BlitzApplication::new(proxy, reciever);
 
-    let (url, bytes) = rt
-        .block_on(net_provider.fetch_async(blitz_traits::net::Request::get(url)))
+    // Parse the document as it downloads, rather than waiting for the whole body
+    let (response, mut body) = rt
+        .block_on(net_provider.fetch_streaming(blitz_traits::net::Request::get(url)))
         .unwrap();
-    let html = std::str::from_utf8(bytes.as_ref()).unwrap();
-
-    launch_internal(
-        html,
-        Config {
-            stylesheets: Vec::new(),
-            base_url: Some(url),
-        },
-        event_loop,
-        application,
-        net_provider,
-    )
+    let content_type = response
+        .headers
+        .get("content-type")
+        .and_then(|value| value.to_str().ok());
+    let doc = StreamingHtmlDocument::with_content_type(
+        document_config(
+            Config {
+                stylesheets: Vec::new(),
+                base_url: Some(response.url.clone()),
+            },
+            net_provider.clone(),
+        ),
+        content_type,
+    );
+
+    let sender = doc.sender();
+    rt.spawn(async move {
+        loop {
+            match body.chunk().await {
+                Ok(Some(chunk)) => sender.send(chunk),
+                Ok(None) => break,
+                Err(_err) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::error!(error = ?_err, "Reading document body");
+                    break;
+                }
+            }
+        }
+        sender.finish();
+    });
+
+    launch_document(Box::new(doc), event_loop, application)
 }
 
 pub fn launch_static_html(html: &str) {
@@ -107,20 +131,29 @@ fn launch_internal(
     html: &str,
     cfg: Config,
     event_loop: EventLoop,
-    mut application: BlitzApplication<WindowRenderer>,
+    application: BlitzApplication<WindowRenderer>,
     net_provider: Arc<dyn NetProvider>,
 ) {
-    let doc = HtmlDocument::from_html(
-        html,
-        DocumentConfig {
-            base_url: cfg.base_url,
-            ua_stylesheets: Some(cfg.stylesheets),
-            net_provider: Some(net_provider),
-            ..Default::default()
-        },
-    );
+    let doc = HtmlDocument::from_html(html, document_config(cfg, net_provider));
+    launch_document(Box::new(doc), event_loop, application)
+}
+
+fn document_config(cfg: Config, net_provider: Arc<dyn NetProvider>) -> DocumentConfig {
+    DocumentConfig {
+        base_url: cfg.base_url,
+        ua_stylesheets: Some(cfg.stylesheets),
+        net_provider: Some(net_provider),
+        ..Default::default()
+    }
+}
+
+fn launch_document(
+    doc: Box<dyn Document>,
+    event_loop: EventLoop,
+    mut application: BlitzApplication<WindowRenderer>,
+) {
     let renderer = WindowRenderer::new();
-    let window = WindowConfig::new(Box::new(doc) as _, renderer);
+    let window = WindowConfig::new(doc, renderer);
 
     // Create application
 
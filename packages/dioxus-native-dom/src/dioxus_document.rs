@@ -1,8 +1,9 @@
 //! Integration between Dioxus and Blitz
 use crate::NodeId;
 use crate::events::{
-    BlitzKeyboardData, NativeConverter, NativeFocusData, NativeFormData, NativePointerData,
-    NativeScrollData, NativeTouchData, NativeWheelData, NodeHandle,
+    BlitzKeyboardData, NativeCancelData, NativeClipboardData, NativeConverter, NativeFocusData,
+    NativeFormData, NativeImageData, NativePointerData, NativeScrollData, NativeTouchData,
+    NativeWheelData, NodeHandle,
 };
 use crate::mutation_writer::{DioxusState, MutationWriter};
 use crate::qual_name;
@@ -201,6 +202,16 @@ impl DioxusDocument {
 
         self.vdom_state.queued_mounted_events = queued_mounted_events;
     }
+
+    /// Dispatch events generated by the document (e.g. resource `load` and `error` events)
+    fn dispatch_pending_events(&mut self) {
+        let handler = DioxusEventHandler {
+            vdom: &mut self.vdom,
+            vdom_state: &mut self.vdom_state,
+        };
+        let mut driver = EventDriver::new(&mut self.inner, handler);
+        driver.dispatch_pending_events();
+    }
 }
 
 // Implement DocumentLike and required traits for DioxusDocument
@@ -229,6 +240,8 @@ impl Document for DioxusDocument {
         // `ScriptDocument` from blitz-script)
         let subdoc_changes = self.inner.borrow_mut().poll_subdocuments(Some(&waker));
 
+        self.dispatch_pending_events();
+
         {
             let fut = self.vdom.wait_for_work();
             let mut pinned_fut = pin!(fut);
@@ -318,17 +331,59 @@ impl EventHandler for DioxusEventHandler<'_> {
             DomEventData::FocusIn(_) => Some(wrap_event_data(NativeFocusData)),
             DomEventData::FocusOut(_) => Some(wrap_event_data(NativeFocusData)),
 
+            DomEventData::Load(_) => Some(wrap_event_data(NativeImageData { load_error: false })),
+            DomEventData::Error(_) => Some(wrap_event_data(NativeImageData { load_error: true })),
+
             DomEventData::KeyDown(kevent)
             | DomEventData::KeyUp(kevent)
             | DomEventData::KeyPress(kevent) => {
                 Some(wrap_event_data(BlitzKeyboardData(kevent.clone())))
             }
 
-            DomEventData::Input(data) => Some(wrap_event_data(NativeFormData {
+            DomEventData::Input(data) | DomEventData::Change(data) => {
+                Some(wrap_event_data(NativeFormData {
+                    value: data.value.clone(),
+                    values: vec![],
+                    input_type: None,
+                }))
+            }
+
+            DomEventData::BeforeInput(data) => Some(wrap_event_data(NativeFormData {
                 value: data.value.clone(),
                 values: vec![],
+                input_type: Some(data.input_type),
             })),
 
+            DomEventData::Invalid(_) | DomEventData::Reset(_) => {
+                Some(wrap_event_data(NativeFormData {
+                    value: String::new(),
+                    values: vec![],
+                    input_type: None,
+                }))
+            }
+
+            DomEventData::Copy(cevent)
+            | DomEventData::Cut(cevent)
+            | DomEventData::Paste(cevent) => Some(wrap_event_data(NativeClipboardData(
+                cevent.data_transfer.clone(),
+            ))),
+
+            // TODO: Expose drag events to script
+            DomEventData::DragStart(_)
+            | DomEventData::Drag(_)
+            | DomEventData::DragEnd(_)
+            | DomEventData::DragEnter(_)
+            | DomEventData::DragOver(_)
+            | DomEventData::DragLeave(_)
+            | DomEventData::Drop(_) => None,
+
+            DomEventData::Cancel(_) | DomEventData::Close(_) => {
+                Some(wrap_event_data(NativeCancelData))
+            }
+
+            // TODO: Expose popover toggle events to script
+            DomEventData::BeforeToggle(_) | DomEventData::Toggle(_) => None,
+
             // TODO: Implement IME handling
             DomEventData::Ime(_) => None,
 
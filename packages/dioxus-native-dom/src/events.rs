@@ -4,16 +4,17 @@ use blitz_dom::{
 };
 use blitz_traits::events::{
     BlitzKeyEvent, BlitzPointerEvent, BlitzPointerId, BlitzScrollEvent, BlitzWheelDelta,
-    BlitzWheelEvent, MouseEventButton,
+    BlitzWheelEvent, DataTransfer, InputType, MouseEventButton,
 };
 use dioxus_html::{
     AnimationData, CancelData, ClipboardData, CompositionData, DragData, FocusData, FormData,
-    FormValue, HasFileData, HasFocusData, HasFormData, HasKeyboardData, HasMouseData,
-    HasPointerData, HasScrollData, HasTouchData, HasTouchPointData, HasWheelData,
-    HtmlEventConverter, ImageData, KeyboardData, MediaData, MountedData, MountedError,
-    MountedResult, MouseData, PlatformEventData, PointerData, RenderedElementBacking, ResizeData,
-    ScrollBehavior, ScrollData, ScrollLogicalPosition, ScrollToOptions, SelectionData, ToggleData,
-    TouchData, TouchPoint, TransitionData, VisibleData, WheelData,
+    FormValue, HasCancelData, HasClipboardData, HasFileData, HasFocusData, HasFormData,
+    HasImageData, HasKeyboardData, HasMouseData, HasPointerData, HasScrollData, HasTouchData,
+    HasTouchPointData, HasWheelData, HtmlEventConverter, ImageData, KeyboardData, MediaData,
+    MountedData, MountedError, MountedResult, MouseData, PlatformEventData, PointerData,
+    RenderedElementBacking, ResizeData, ScrollBehavior, ScrollData, ScrollLogicalPosition,
+    ScrollToOptions, SelectionData, ToggleData, TouchData, TouchPoint, TransitionData, VisibleData,
+    WheelData,
     geometry::{
         ClientPoint, ElementPoint, PagePoint, PixelsRect, PixelsSize, PixelsVector2D, ScreenPoint,
         WheelDelta,
@@ -40,7 +41,7 @@ pub struct NativeConverter {}
 
 impl HtmlEventConverter for NativeConverter {
     fn convert_cancel_data(&self, _event: &PlatformEventData) -> CancelData {
-        unimplemented!("todo: convert_cancel_data in dioxus-native. requires support in blitz")
+        NativeCancelData.into()
     }
 
     fn convert_form_data(&self, event: &PlatformEventData) -> FormData {
@@ -71,8 +72,12 @@ impl HtmlEventConverter for NativeConverter {
         unimplemented!("todo: convert_animation_data in dioxus-native. requires support in blitz")
     }
 
-    fn convert_clipboard_data(&self, _event: &PlatformEventData) -> ClipboardData {
-        unimplemented!("todo: convert_clipboard_data in dioxus-native. requires support in blitz")
+    fn convert_clipboard_data(&self, event: &PlatformEventData) -> ClipboardData {
+        event
+            .downcast::<NativeClipboardData>()
+            .unwrap()
+            .clone()
+            .into()
     }
 
     fn convert_composition_data(&self, _event: &PlatformEventData) -> CompositionData {
@@ -83,8 +88,8 @@ impl HtmlEventConverter for NativeConverter {
         unimplemented!("todo: convert_drag_data in dioxus-native. requires support in blitz")
     }
 
-    fn convert_image_data(&self, _event: &PlatformEventData) -> ImageData {
-        unimplemented!("todo: convert_image_data in dioxus-native. requires support in blitz")
+    fn convert_image_data(&self, event: &PlatformEventData) -> ImageData {
+        event.downcast::<NativeImageData>().unwrap().clone().into()
     }
 
     fn convert_media_data(&self, _event: &PlatformEventData) -> MediaData {
@@ -299,6 +304,8 @@ impl RenderedElementBacking for NodeHandle {
 pub struct NativeFormData {
     pub value: String,
     pub values: Vec<(String, FormValue)>,
+    /// The kind of edit announced by a `beforeinput` event
+    pub input_type: Option<InputType>,
 }
 
 impl HasFormData for NativeFormData {
@@ -554,6 +561,49 @@ impl HasFocusData for NativeFocusData {
     }
 }
 
+/// The data of a dialog's `cancel` or `close` event, which carry no data
+#[derive(Clone)]
+pub struct NativeCancelData;
+impl HasCancelData for NativeCancelData {
+    fn as_any(&self) -> &dyn Any {
+        self as &dyn Any
+    }
+}
+
+/// The data of a `copy`, `cut` or `paste` event. Handlers can access it by downcasting the
+/// event's data to this type.
+#[derive(Clone)]
+pub struct NativeClipboardData(pub(crate) DataTransfer);
+
+impl NativeClipboardData {
+    /// The data being copied or pasted. Handlers of `copy` and `cut` events that cancel the
+    /// event can set the data that is written to the clipboard, and handlers of `paste` events
+    /// can replace the `text/plain` data to change the text that is pasted.
+    pub fn data_transfer(&self) -> &DataTransfer {
+        &self.0
+    }
+}
+
+impl HasClipboardData for NativeClipboardData {
+    fn as_any(&self) -> &dyn Any {
+        self as &dyn Any
+    }
+}
+
+#[derive(Clone)]
+pub struct NativeImageData {
+    pub(crate) load_error: bool,
+}
+impl HasImageData for NativeImageData {
+    fn load_error(&self) -> bool {
+        self.load_error
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self as &dyn Any
+    }
+}
+
 #[derive(Clone)]
 pub struct NativeScrollData(pub(crate) BlitzScrollEvent);
 impl HasScrollData for NativeScrollData {
@@ -1,7 +1,7 @@
 use blitz_shell::BlitzShellProxy;
 use std::sync::Arc;
 
-use blitz_traits::net::{NetHandler, NetProvider, Request};
+use blitz_traits::net::{NetError, NetHandler, NetProvider, Request};
 
 pub struct DioxusNativeNetProvider {
     inner_net_provider: Option<Arc<dyn NetProvider + 'static>>,
@@ -51,6 +51,8 @@ impl NetProvider for DioxusNativeNetProvider {
                 Err(_) => {
                     #[cfg(feature = "tracing")]
                     tracing::warn!("fetching asset from file system error {request:#?}");
+                    let error = NetError::Fetch(String::from("Asset not found"));
+                    handler.failed(request.url.to_string(), error);
                 }
             }
         } else if let Some(inner) = &self.inner_net_provider {
@@ -58,6 +60,8 @@ impl NetProvider for DioxusNativeNetProvider {
         } else {
             #[cfg(feature = "tracing")]
             tracing::warn!("net feature not enabled, cannot fetch {request:#?}");
+            let error = NetError::Fetch(String::from("Networking is disabled"));
+            handler.failed(request.url.to_string(), error);
         }
     }
 }
@@ -8,6 +8,7 @@ pub(crate) mod stylo {
     pub(crate) use style::properties::generated::longhands::direction::computed_value::T as Direction;
     pub(crate) use style::properties::longhands::aspect_ratio::computed_value::T as AspectRatio;
     pub(crate) use style::properties::longhands::position::computed_value::T as Position;
+    pub(crate) use style::properties::style_structs::Position as PositionStyles;
     pub(crate) use style::values::computed::length_percentage::CalcLengthPercentage;
     pub(crate) use style::values::computed::length_percentage::Unpacked as UnpackedLengthPercentage;
     pub(crate) use style::values::computed::{
@@ -208,6 +209,10 @@ pub fn display(input: stylo::Display) -> taffy::Display {
         stylo::DisplayInside::FlowRoot => taffy::Display::FlowRoot,
         #[cfg(feature = "block")]
         stylo::DisplayInside::TableCell => taffy::Display::Block,
+        // The legacy `-webkit-box` is only laid out as a block container: the form it takes
+        // when used with `-webkit-line-clamp`
+        #[cfg(feature = "block")]
+        stylo::DisplayInside::WebkitBox => taffy::Display::Block,
         // TODO: Support display:contents in Taffy
         // TODO: Support table layout in Taffy
         #[cfg(feature = "grid")]
@@ -257,13 +262,40 @@ pub fn position(input: stylo::Position) -> taffy::Position {
         stylo::Position::Relative => taffy::Position::Relative,
         stylo::Position::Static => taffy::Position::Relative,
 
-        // TODO: support position:fixed and sticky
         stylo::Position::Absolute => taffy::Position::Absolute,
+        // Fixed boxes are laid out like absolutely positioned boxes and sticky boxes like
+        // relatively positioned ones. The scroll-dependent part of their position is applied
+        // after layout (see `BaseDocument::resolve_scroll_positions` in blitz-dom).
         stylo::Position::Fixed => taffy::Position::Absolute,
         stylo::Position::Sticky => taffy::Position::Relative,
     }
 }
 
+/// Convert the `top`/`right`/`bottom`/`left` properties of a box to Taffy insets.
+///
+/// The insets of a sticky box don't shift it during layout: they constrain it to the
+/// scrollport of its nearest scroll container instead, which depends on scroll offsets.
+#[inline]
+pub fn insets(
+    position: stylo::Position,
+    styles: &stylo::PositionStyles,
+) -> taffy::Rect<taffy::LengthPercentageAuto> {
+    if position == stylo::Position::Sticky {
+        return taffy::Rect {
+            left: taffy::LengthPercentageAuto::AUTO,
+            right: taffy::LengthPercentageAuto::AUTO,
+            top: taffy::LengthPercentageAuto::AUTO,
+            bottom: taffy::LengthPercentageAuto::AUTO,
+        };
+    }
+    taffy::Rect {
+        left: self::inset(&styles.left),
+        right: self::inset(&styles.right),
+        top: self::inset(&styles.top),
+        bottom: self::inset(&styles.bottom),
+    }
+}
+
 #[inline]
 pub fn overflow(input: stylo::Overflow) -> taffy::Overflow {
     match input {
@@ -754,12 +786,7 @@ pub fn to_taffy_style(style: &stylo::ComputedValues) -> taffy::Style<Atom> {
         },
         aspect_ratio: self::aspect_ratio(pos.aspect_ratio),
 
-        inset: taffy::Rect {
-            left: self::inset(&pos.left),
-            right: self::inset(&pos.right),
-            top: self::inset(&pos.top),
-            bottom: self::inset(&pos.bottom),
-        },
+        inset: self::insets(style.clone_position(), pos),
         margin: taffy::Rect {
             left: self::margin(&margin.margin_left),
             right: self::margin(&margin.margin_right),
@@ -0,0 +1,158 @@
+//! The accessibility tree exposes each element's accessible name and
+//! description, its layout bounds, its states (checked, disabled, expanded,
+//! etc) and the actions that assistive technology can perform on it. Actions
+//! are performed by dispatching [`UiEvent::AccessibilityAction`]s, which go
+//! through the same event handling as the equivalent user input.
+
+use accesskit::{Action, Node, NodeId, Rect, Toggled};
+use blitz_test_harness::Harness;
+use blitz_traits::events::{AccessibilityAction, BlitzAccessibilityActionEvent, UiEvent};
+use std::collections::HashMap;
+
+/// Build the accessibility tree, keyed by AccessKit node id
+fn accessibility_nodes(harness: &Harness) -> HashMap<NodeId, Node> {
+    harness
+        .base()
+        .build_accessibility_tree()
+        .nodes
+        .into_iter()
+        .collect()
+}
+
+#[track_caller]
+fn node_for<'a>(harness: &Harness, nodes: &'a HashMap<NodeId, Node>, selector: &str) -> &'a Node {
+    let node_id = harness.node(selector);
+    nodes
+        .get(&NodeId(node_id.as_u64()))
+        .unwrap_or_else(|| panic!("no accessibility node for {selector}"))
+}
+
+fn perform(harness: &mut Harness, selector: &str, action: AccessibilityAction) -> Vec<String> {
+    let target = harness.node(selector);
+    let events = harness.dispatch_recorded([UiEvent::AccessibilityAction(
+        BlitzAccessibilityActionEvent { target, action },
+    )]);
+    harness.pump();
+    events
+}
+
+#[test]
+fn accessible_names() {
+    let harness = Harness::from_html(
+        r#"<html><body>
+        <button id="aria-label" aria-label="Close">X</button>
+        <span id="heading-text">Settings</span>
+        <div id="labelledby" role="dialog" aria-labelledby="heading-text"></div>
+        <label for="for-input">Email address</label>
+        <input id="for-input" type="email">
+        <label>Name <input id="wrapped-input"></label>
+        <img id="img" alt="A red  panda" src="">
+        <button id="content">Save <b>draft</b></button>
+        <input id="submit" type="submit">
+        <input id="titled" title="Search terms">
+        <a id="described" href="/help" title="Opens the help page">Help</a>
+        </body></html>"#,
+    );
+    let nodes = accessibility_nodes(&harness);
+    let label = |selector| node_for(&harness, &nodes, selector).label();
+
+    assert_eq!(label("#aria-label"), Some("Close"));
+    assert_eq!(label("#labelledby"), Some("Settings"));
+    assert_eq!(label("#for-input"), Some("Email address"));
+    assert_eq!(label("#wrapped-input"), Some("Name"));
+    assert_eq!(label("#img"), Some("A red panda"));
+    assert_eq!(label("#content"), Some("Save draft"));
+    assert_eq!(label("#submit"), Some("Submit"));
+    assert_eq!(label("#titled"), Some("Search terms"));
+    assert_eq!(label("#described"), Some("Help"));
+    assert_eq!(
+        node_for(&harness, &nodes, "#described").description(),
+        Some("Opens the help page")
+    );
+    // The title isn't repeated as the description when it is the name
+    assert_eq!(node_for(&harness, &nodes, "#titled").description(), None);
+}
+
+#[test]
+fn states() {
+    let harness = Harness::from_html(
+        r#"<html><body>
+        <input id="checked" type="checkbox" checked>
+        <input id="unchecked" type="checkbox">
+        <div id="mixed" role="checkbox" aria-checked="mixed"></div>
+        <button id="disabled" disabled>Disabled</button>
+        <input id="required" required readonly>
+        <details open><summary id="open">Open</summary>Content</details>
+        <details><summary id="closed">Closed</summary>Content</details>
+        <h3 id="heading">Heading</h3>
+        <div id="hidden" style="display:none"></div>
+        </body></html>"#,
+    );
+    let nodes = accessibility_nodes(&harness);
+    let node = |selector| node_for(&harness, &nodes, selector);
+
+    assert_eq!(node("#checked").toggled(), Some(Toggled::True));
+    assert_eq!(node("#unchecked").toggled(), Some(Toggled::False));
+    assert_eq!(node("#mixed").toggled(), Some(Toggled::Mixed));
+    assert!(node("#disabled").is_disabled());
+    assert!(!node("#disabled").supports_action(Action::Click));
+    assert!(node("#required").is_required());
+    assert!(node("#required").is_read_only());
+    assert_eq!(node("#open").is_expanded(), Some(true));
+    assert_eq!(node("#closed").is_expanded(), Some(false));
+    assert_eq!(node("#heading").level(), Some(3));
+    assert!(node("#hidden").is_hidden());
+}
+
+#[test]
+fn bounds_and_actions() {
+    let harness = Harness::from_html(
+        r#"<html><body style="margin:0">
+        <div style="height:50px"></div>
+        <button id="button" style="display:block; width:100px; height:20px; margin-left:10px;">Go</button>
+        <input id="input" style="display:block; width:200px; height:20px;">
+        </body></html>"#,
+    );
+    let nodes = accessibility_nodes(&harness);
+    let button = node_for(&harness, &nodes, "#button");
+    assert_eq!(button.bounds(), Some(Rect::new(10.0, 50.0, 110.0, 70.0)));
+    assert!(button.supports_action(Action::Click));
+    assert!(button.supports_action(Action::Focus));
+    assert!(button.supports_action(Action::ScrollIntoView));
+    assert!(!button.supports_action(Action::SetValue));
+
+    let input = node_for(&harness, &nodes, "#input");
+    assert!(input.supports_action(Action::SetValue));
+    assert!(!input.supports_action(Action::Click));
+}
+
+#[test]
+fn actions_are_performed_through_events() {
+    let mut harness = Harness::from_html(
+        r#"<html><body>
+        <input id="checkbox" type="checkbox">
+        <input id="input" value="old">
+        </body></html>"#,
+    );
+
+    let events = perform(&mut harness, "#checkbox", AccessibilityAction::Click);
+    assert!(events.contains(&String::from("click")), "{events:?}");
+    let nodes = accessibility_nodes(&harness);
+    assert_eq!(
+        node_for(&harness, &nodes, "#checkbox").toggled(),
+        Some(Toggled::True)
+    );
+
+    let events = perform(&mut harness, "#input", AccessibilityAction::Focus);
+    assert_eq!(events, ["focus", "focusin"]);
+    assert_eq!(harness.focused(), Some(harness.node("#input")));
+
+    let events = perform(
+        &mut harness,
+        "#input",
+        AccessibilityAction::SetValue(String::from("new")),
+    );
+    assert_eq!(events, ["input"]);
+    let nodes = accessibility_nodes(&harness);
+    assert_eq!(node_for(&harness, &nodes, "#input").value(), Some("new"));
+}
@@ -0,0 +1,224 @@
+//! The accessibility tree is sent to assistive technology incrementally. After the first
+//! (whole) tree, each update contains only the nodes that have changed: because of
+//! mutations, attribute and state changes, text edits or layout. Sub-documents are
+//! stitched into the tree under the element that contains them.
+
+use accesskit::{Affine, Node, NodeId, Role, TreeUpdate};
+use blitz_dom::DocumentConfig;
+use blitz_html::{HtmlDocument, HtmlProvider};
+use blitz_test_harness::{Harness, qname};
+use std::sync::Arc;
+
+/// Resolve the document and take the changes to its accessibility tree
+fn take_update(harness: &mut Harness) -> TreeUpdate {
+    harness.pump();
+    harness.base_mut().accessibility_tree_update()
+}
+
+/// The elements in the update (by `id`), sorted. Text nodes are listed as `#text`.
+fn updated(harness: &Harness, update: &TreeUpdate) -> Vec<String> {
+    let doc = harness.base();
+    let mut updated: Vec<String> = update
+        .nodes
+        .iter()
+        .map(|(id, _)| {
+            let node = doc
+                .get_node(blitz_dom::NodeId::from_u64(id.0))
+                .unwrap_or_else(|| panic!("update contains unknown node {id:?}"));
+            match node.is_text_node() {
+                true => String::from("#text"),
+                false => node.attr(blitz_dom::local_name!("id")).unwrap().to_string(),
+            }
+        })
+        .collect();
+    updated.sort();
+    updated
+}
+
+fn load(html: &str) -> Harness {
+    let mut harness = Harness::from_html(html);
+    let initial = take_update(&mut harness);
+    assert!(initial.tree.is_some());
+    harness
+}
+
+const PAGE: &str = r#"<html style="height:600px"><body style="margin:0; height:500px">
+    <div id="spacer" style="height:10px"></div>
+    <button id="button" style="display:block; width:100px; height:20px;">Save</button>
+    <input id="input" style="display:block; width:100px; height:20px;">
+    <ul id="list" style="height:100px"></ul>
+</body></html>"#;
+
+#[test]
+fn unchanged_document_sends_no_nodes() {
+    let mut harness = load(PAGE);
+    let update = take_update(&mut harness);
+    assert!(update.tree.is_none());
+    assert!(update.nodes.is_empty(), "{:?}", update.nodes);
+}
+
+#[test]
+fn hovering_and_resolving_without_changes_send_nothing() {
+    let mut harness = load(PAGE);
+    let (x, y) = harness.center_of("#button");
+    harness.move_mouse_to(x, y);
+    harness.pump();
+    assert!(!harness.base().has_accessibility_changes());
+
+    // Unless layout changes, resolving again leaves the tree as it is
+    harness.pump();
+    assert!(!harness.base().has_accessibility_changes());
+}
+
+#[test]
+fn attribute_changes_send_the_changed_node() {
+    let mut harness = load(PAGE);
+    harness.set_attribute("#button", "aria-label", "Save draft");
+
+    let update = take_update(&mut harness);
+    assert_eq!(updated(&harness, &update), ["button"]);
+    assert_eq!(update.nodes[0].1.label(), Some("Save draft"));
+}
+
+#[test]
+fn text_changes_update_names_computed_from_content() {
+    let mut harness = load(PAGE);
+    let button = harness.node("#button");
+    let text = harness.base().get_node(button).unwrap().children[0];
+    harness.base_mut().mutate().set_node_text(text, "Submit");
+
+    let update = take_update(&mut harness);
+    assert_eq!(updated(&harness, &update), ["#text", "button"]);
+    let button = harness.node("#button");
+    let (_, node) = update
+        .nodes
+        .iter()
+        .find(|(id, _)| id.0 == button.as_u64())
+        .unwrap();
+    assert_eq!(node.label(), Some("Submit"));
+}
+
+#[test]
+fn inserted_nodes_are_sent_with_their_parent() {
+    let mut harness = load(PAGE);
+    let list = harness.node("#list");
+    {
+        let mut doc = harness.base_mut();
+        let mut mutator = doc.mutate();
+        let item = mutator.create_element(qname("li"), Vec::new());
+        mutator.set_attribute(item, qname("id"), "item");
+        let text = mutator.create_text_node("Item");
+        mutator.append_children(item, &[text]);
+        mutator.append_children(list, &[item]);
+    }
+
+    let update = take_update(&mut harness);
+    assert_eq!(updated(&harness, &update), ["#text", "item", "list"]);
+}
+
+#[test]
+fn nodes_moved_by_layout_are_sent() {
+    let mut harness = load(PAGE);
+    harness.set_attribute("#spacer", "style", "height:50px");
+
+    let update = take_update(&mut harness);
+    assert_eq!(
+        updated(&harness, &update),
+        ["button", "input", "list", "spacer"]
+    );
+}
+
+#[test]
+fn focus_and_text_edits_are_sent() {
+    let mut harness = load(PAGE);
+    harness.click("#input");
+    let input = harness.node("#input");
+    let update = take_update(&mut harness);
+    assert_eq!(update.focus, NodeId(input.as_u64()));
+
+    harness.type_text("hi");
+    let update = take_update(&mut harness);
+    assert_eq!(updated(&harness, &update), ["input"]);
+    assert_eq!(update.nodes[0].1.value(), Some("hi"));
+    assert_eq!(update.focus, NodeId(input.as_u64()));
+}
+
+/// The node in the tree (of the whole document) with the given label
+fn labelled<'a>(update: &'a TreeUpdate, label: &str) -> &'a (NodeId, Node) {
+    update
+        .nodes
+        .iter()
+        .find(|(_, node)| node.label() == Some(label))
+        .unwrap_or_else(|| panic!("no node labelled {label}"))
+}
+
+#[test]
+fn sub_documents_are_stitched_into_the_tree() {
+    let mut harness = Harness::from_html(
+        r#"<html><body style="margin:0">
+        <div id="spacer" style="height:30px"></div>
+        <iframe id="frame" style="display:block; width:200px; height:100px; border:0; padding:0"></iframe>
+        </body></html>"#,
+    );
+    let sub_document = HtmlDocument::from_html(
+        r#"<html><body style="margin:0"><button id="inner">Inner</button></body></html>"#,
+        DocumentConfig {
+            html_parser_provider: Some(Arc::new(HtmlProvider) as _),
+            ..Default::default()
+        },
+    );
+    let frame = harness.node("#frame");
+    harness
+        .base_mut()
+        .set_sub_document(frame, Box::new(sub_document));
+    harness.pump();
+
+    // The sub-document's nodes are given ids that don't clash with the document's, and its
+    // root is placed under the iframe, positioned over it
+    let tree = harness.base_mut().accessibility_tree_update();
+    let (inner_id, _) = *labelled(&tree, "Inner");
+    assert!(
+        harness
+            .base()
+            .get_node(blitz_dom::NodeId::from_u64(inner_id.0))
+            .is_none()
+    );
+    let (_, iframe) = tree
+        .nodes
+        .iter()
+        .find(|(id, _)| id.0 == frame.as_u64())
+        .unwrap();
+    let container_id = *iframe.children().last().unwrap();
+    let (_, container) = tree
+        .nodes
+        .iter()
+        .find(|(id, _)| *id == container_id)
+        .unwrap();
+    assert_eq!(container.role(), Role::GenericContainer);
+    assert_eq!(container.transform(), Some(&Affine::translate((0.0, 30.0))));
+
+    // Changes to the sub-document are sent incrementally
+    {
+        let mut doc = harness.base_mut();
+        let sub_doc = doc.get_node_mut(frame).unwrap().subdoc_mut().unwrap();
+        let mut sub_doc = sub_doc.inner_mut();
+        let inner = sub_doc.get_element_by_id("inner").unwrap();
+        sub_doc
+            .mutate()
+            .set_attribute(inner, qname("aria-label"), "Changed");
+    }
+    let update = take_update(&mut harness);
+    assert_eq!(update.nodes.len(), 1, "{:?}", update.nodes);
+    assert_eq!(update.nodes[0].0, inner_id);
+    assert_eq!(update.nodes[0].1.label(), Some("Changed"));
+
+    // Moving the iframe moves the sub-document
+    harness.set_attribute("#spacer", "style", "height:50px");
+    let update = take_update(&mut harness);
+    let (_, container) = update
+        .nodes
+        .iter()
+        .find(|(id, _)| *id == container_id)
+        .unwrap();
+    assert_eq!(container.transform(), Some(&Affine::translate((0.0, 50.0))));
+}
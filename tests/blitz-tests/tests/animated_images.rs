@@ -0,0 +1,131 @@
+//! Animated images. The frame shown by an `<img>` advances with the document's
+//! animation clock, with each frame decoded as playback reaches it, and the
+//! document reports when the next frame is due rather than animating every
+//! frame. Images scrolled out of view are paused, as are all images when the
+//! user prefers reduced motion (which also matches `prefers-reduced-motion`).
+
+use blitz_dom::node::{ImageData, SpecialElementData};
+use blitz_dom::{DocumentConfig, ImageDecoding};
+use blitz_html::HtmlDocument;
+use blitz_test_harness::StaticNetProvider;
+use blitz_traits::shell::Viewport;
+use std::sync::Arc;
+
+/// A 1x1 GIF which loops forever between a red and a blue frame, showing each for 100ms
+const RED_BLUE_GIF: &[u8] = &[
+    b'G', b'I', b'F', b'8', b'9', b'a', // Header
+    0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, // Logical screen descriptor
+    0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, // Global color table (red, blue)
+    0x21, 0xFF, 0x0B, b'N', b'E', b'T', b'S', b'C', b'A', b'P', b'E', b'2', b'.', b'0', 0x03, 0x01,
+    0x00, 0x00, 0x00, // Loop forever
+    0x21, 0xF9, 0x04, 0x00, 0x0A, 0x00, 0x00, 0x00, // 100ms delay
+    0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // Image descriptor
+    0x02, 0x02, 0x44, 0x01, 0x00, // Red pixel
+    0x21, 0xF9, 0x04, 0x00, 0x0A, 0x00, 0x00, 0x00, // 100ms delay
+    0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // Image descriptor
+    0x02, 0x02, 0x4C, 0x01, 0x00, // Blue pixel
+    0x3B, // Trailer
+];
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+fn load(html: &str, prefers_reduced_motion: bool) -> HtmlDocument {
+    let mut doc = HtmlDocument::from_html(
+        html,
+        DocumentConfig {
+            base_url: Some("http://example.com/".to_string()),
+            net_provider: Some(Arc::new(StaticNetProvider::new(RED_BLUE_GIF)) as _),
+            viewport: Some(Viewport::new(800, 600, 1.0, Default::default())),
+            image_decoding: ImageDecoding::Inline,
+            prefers_reduced_motion,
+            ..Default::default()
+        },
+    );
+    doc.resolve(0.0);
+    doc
+}
+
+/// The pixel currently shown by the `<img id="img">` element
+fn current_pixel(doc: &HtmlDocument) -> [u8; 4] {
+    let img = doc.get_element_by_id("img").unwrap();
+    let element = doc.get_node(img).unwrap().element_data().unwrap();
+    let SpecialElementData::Image(image) = &element.special_data else {
+        panic!("image not loaded");
+    };
+    let ImageData::Raster(raster) = &**image else {
+        panic!("expected a raster image");
+    };
+    raster.data.data().try_into().unwrap()
+}
+
+const IMG: &str = r#"<html><body><img id="img" src="anim.gif"></body></html>"#;
+
+#[test]
+fn frames_advance_with_animation_clock() {
+    let mut doc = load(IMG, false);
+    assert_eq!(current_pixel(&doc), RED);
+    // Playing images don't need a redraw every frame, only when the next frame is due
+    assert!(!doc.is_animating());
+    assert_eq!(doc.next_animation_frame_time(), Some(0.1));
+
+    doc.resolve(0.05);
+    assert_eq!(current_pixel(&doc), RED);
+    assert_eq!(doc.next_animation_frame_time(), Some(0.1));
+
+    doc.resolve(0.15);
+    assert_eq!(current_pixel(&doc), BLUE);
+
+    // The animation loops
+    doc.resolve(0.25);
+    assert_eq!(current_pixel(&doc), RED);
+    doc.resolve(0.35);
+    assert_eq!(current_pixel(&doc), BLUE);
+}
+
+#[test]
+fn reduced_motion_pauses_animation() {
+    let mut doc = load(IMG, true);
+    doc.resolve(0.15);
+    assert_eq!(current_pixel(&doc), RED);
+    assert_eq!(doc.next_animation_frame_time(), None);
+
+    // Playback resumes when the preference changes
+    doc.set_prefers_reduced_motion(false);
+    doc.resolve(0.25);
+    assert_eq!(doc.next_animation_frame_time(), Some(0.35));
+    doc.resolve(0.4);
+    assert_eq!(current_pixel(&doc), BLUE);
+}
+
+#[test]
+fn reduced_motion_matches_media_query() {
+    let html = r#"<html><head><style>
+        #box { width: 10px; height: 10px; }
+        @media (prefers-reduced-motion: reduce) { #box { width: 20px; } }
+    </style></head><body><div id="box"></div></body></html>"#;
+    let box_width = |doc: &HtmlDocument| {
+        let node_id = doc.get_element_by_id("box").unwrap();
+        doc.get_node(node_id).unwrap().final_layout().size.width
+    };
+
+    let mut doc = load(html, false);
+    assert_eq!(box_width(&doc), 10.0);
+    doc.set_prefers_reduced_motion(true);
+    doc.resolve(0.0);
+    assert_eq!(box_width(&doc), 20.0);
+}
+
+#[test]
+fn images_out_of_view_are_paused() {
+    let mut doc = load(
+        r#"<html><body>
+            <div style="height:2000px"></div>
+            <img id="img" src="anim.gif">
+        </body></html>"#,
+        false,
+    );
+    doc.resolve(0.15);
+    assert_eq!(current_pixel(&doc), RED);
+    assert_eq!(doc.next_animation_frame_time(), None);
+}
@@ -0,0 +1,297 @@
+//! Clipboard events. Ctrl/Cmd + C, X and V fire cancelable `copy`, `cut` and
+//! `paste` events. Their default actions copy the selection (of a text input,
+//! or of the document as both text and HTML) to the clipboard and paste the
+//! event's `text/plain` data into text inputs. Cancelling `copy` or `cut`
+//! writes the data added by the event's handlers to the clipboard instead.
+//! Cutting from and pasting into text inputs are edits, which fire `beforeinput`.
+
+use blitz_dom::{Document, EventDriver, EventHandler, local_name};
+use blitz_test_harness::{Harness, key_event};
+use blitz_traits::events::{DomEvent, DomEventData, EventState, UiEvent};
+use blitz_traits::node_id::NodeId;
+use blitz_traits::shell::{ClipboardContents, ClipboardError, ClipboardImage, ShellProvider};
+use keyboard_types::{Key, KeyState, Modifiers};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[cfg(target_os = "macos")]
+const ACTION: Modifiers = Modifiers::SUPER;
+#[cfg(not(target_os = "macos"))]
+const ACTION: Modifiers = Modifiers::CONTROL;
+
+const INPUTS: &str = r#"<html><body style="margin:0">
+    <input id="source" value="hello world" style="width:200px; height:20px;">
+    <input id="target" style="width:200px; height:20px;">
+    <input id="password" type="password" value="secret" style="width:200px; height:20px;">
+</body></html>"#;
+
+/// An in-memory clipboard
+#[derive(Default)]
+struct MockClipboard {
+    contents: Mutex<ClipboardContents>,
+    /// The number of times that the clipboard's image has been read
+    image_reads: AtomicUsize,
+}
+
+impl MockClipboard {
+    fn contents(&self) -> ClipboardContents {
+        self.contents.lock().unwrap().clone()
+    }
+}
+
+impl ShellProvider for MockClipboard {
+    fn get_clipboard_contents(&self) -> Result<ClipboardContents, ClipboardError> {
+        Ok(ClipboardContents {
+            image: None,
+            ..self.contents()
+        })
+    }
+    fn get_clipboard_image(&self) -> Result<ClipboardImage, ClipboardError> {
+        self.image_reads.fetch_add(1, Ordering::Relaxed);
+        self.contents().image.ok_or(ClipboardError)
+    }
+    fn set_clipboard_contents(&self, contents: ClipboardContents) -> Result<(), ClipboardError> {
+        *self.contents.lock().unwrap() = contents;
+        Ok(())
+    }
+}
+
+fn load(html: &str) -> (Harness, Arc<MockClipboard>) {
+    let mut harness = Harness::from_html(html);
+    let clipboard = Arc::new(MockClipboard::default());
+    harness.base_mut().shell_provider = clipboard.clone();
+    (harness, clipboard)
+}
+
+fn value(harness: &Harness, selector: &str) -> String {
+    let node_id = harness.node(selector);
+    let doc = harness.base();
+    let element = doc.get_node(node_id).unwrap().element_data().unwrap();
+    element
+        .text_input_data()
+        .unwrap()
+        .editor
+        .raw_text()
+        .to_string()
+}
+
+fn shortcut(harness: &mut Harness, key: &str) {
+    harness.press_with(Key::Character(key.into()), ACTION);
+}
+
+/// Records clipboard events as "name #id" (and `beforeinput` events as "beforeinput
+/// inputType"), and optionally replaces their data
+/// (cancelling `copy` and `cut` events to have it written to the clipboard)
+#[derive(Clone, Default)]
+struct ClipboardRecorder {
+    events: Rc<RefCell<Vec<String>>>,
+    images: Rc<RefCell<Vec<(usize, usize)>>>,
+    replace_with: Option<&'static str>,
+}
+
+impl EventHandler for ClipboardRecorder {
+    fn handle_event(
+        &mut self,
+        _chain: &[NodeId],
+        event: &mut DomEvent,
+        doc: &mut dyn Document,
+        event_state: &mut EventState,
+    ) {
+        let data = match &event.data {
+            DomEventData::Copy(data) | DomEventData::Cut(data) | DomEventData::Paste(data) => data,
+            DomEventData::BeforeInput(data) => {
+                let input_type = data.input_type.as_str();
+                self.events
+                    .borrow_mut()
+                    .push(format!("beforeinput {input_type}"));
+                return;
+            }
+            _ => return,
+        };
+        let id = doc
+            .inner()
+            .get_node(event.target)
+            .and_then(|node| node.attr(local_name!("id")))
+            .unwrap_or_default()
+            .to_string();
+        self.events
+            .borrow_mut()
+            .push(format!("{} #{id}", event.name()));
+        if let Some(image) = data.data_transfer.image() {
+            self.images.borrow_mut().push((image.width, image.height));
+        }
+
+        if let Some(text) = self.replace_with {
+            data.data_transfer.set_data("text/plain", text);
+            if !matches!(event.data, DomEventData::Paste(_)) {
+                event_state.prevent_default();
+            }
+        }
+    }
+}
+
+/// Press the clipboard shortcut for `key`, with `recorder` handling the events
+fn recorded_shortcut(harness: &mut Harness, key: &str, recorder: ClipboardRecorder) {
+    let key = Key::Character(key.into());
+    let mut doc = harness.base_mut();
+    let mut driver = EventDriver::new(&mut *doc, recorder);
+    driver.handle_ui_event(UiEvent::KeyDown(key_event(
+        key.clone(),
+        KeyState::Pressed,
+        ACTION,
+    )));
+    driver.handle_ui_event(UiEvent::KeyUp(key_event(key, KeyState::Released, ACTION)));
+    drop(doc);
+    harness.pump();
+}
+
+#[test]
+fn copy_and_paste_between_inputs() {
+    let (mut harness, clipboard) = load(INPUTS);
+    harness.click("#source");
+    shortcut(&mut harness, "a");
+
+    let recorder = ClipboardRecorder::default();
+    recorded_shortcut(&mut harness, "c", recorder.clone());
+    assert_eq!(*recorder.events.borrow(), ["copy #source"]);
+    assert_eq!(clipboard.contents().text.as_deref(), Some("hello world"));
+
+    harness.click("#target");
+    let recorder = ClipboardRecorder::default();
+    recorded_shortcut(&mut harness, "v", recorder.clone());
+    assert_eq!(
+        *recorder.events.borrow(),
+        ["paste #target", "beforeinput insertFromPaste"]
+    );
+    assert_eq!(value(&harness, "#target"), "hello world");
+}
+
+#[test]
+fn cut_removes_selection() {
+    let (mut harness, clipboard) = load(INPUTS);
+    harness.click("#source");
+    shortcut(&mut harness, "a");
+    let recorder = ClipboardRecorder::default();
+    recorded_shortcut(&mut harness, "x", recorder.clone());
+    assert_eq!(
+        *recorder.events.borrow(),
+        ["cut #source", "beforeinput deleteByCut"]
+    );
+
+    assert_eq!(value(&harness, "#source"), "");
+    assert_eq!(clipboard.contents().text.as_deref(), Some("hello world"));
+
+    // The cut can be undone
+    shortcut(&mut harness, "z");
+    assert_eq!(value(&harness, "#source"), "hello world");
+}
+
+#[test]
+fn passwords_are_not_copied() {
+    let (mut harness, clipboard) = load(INPUTS);
+    harness.click("#password");
+    shortcut(&mut harness, "a");
+    shortcut(&mut harness, "x");
+
+    assert_eq!(value(&harness, "#password"), "secret");
+    assert_eq!(clipboard.contents(), ClipboardContents::default());
+}
+
+#[test]
+fn paste_handler_overrides_inserted_text() {
+    let (mut harness, clipboard) = load(INPUTS);
+    clipboard
+        .set_clipboard_contents(ClipboardContents {
+            text: Some(String::from("from clipboard")),
+            ..Default::default()
+        })
+        .unwrap();
+
+    harness.click("#target");
+    let recorder = ClipboardRecorder {
+        replace_with: Some("replaced"),
+        ..Default::default()
+    };
+    recorded_shortcut(&mut harness, "v", recorder);
+    assert_eq!(value(&harness, "#target"), "replaced");
+}
+
+#[test]
+fn cancelled_copy_writes_handler_data() {
+    let (mut harness, clipboard) = load(INPUTS);
+    harness.click("#source");
+    shortcut(&mut harness, "a");
+
+    let recorder = ClipboardRecorder {
+        replace_with: Some("custom"),
+        ..Default::default()
+    };
+    recorded_shortcut(&mut harness, "x", recorder);
+    assert_eq!(clipboard.contents().text.as_deref(), Some("custom"));
+    // Cancelling the cut also keeps the selected text
+    assert_eq!(value(&harness, "#source"), "hello world");
+}
+
+#[test]
+fn document_selection_is_copied_as_text_and_html() {
+    let (mut harness, clipboard) = load(
+        r#"<html><body style="margin:0"><p id="first">Hello <b>bold</b></p><p id="second">Second paragraph</p></body></html>"#,
+    );
+    let first = harness.node("#first");
+    let second = harness.node("#second");
+    harness.base_mut().set_text_selection(first, 0, second, 6);
+    shortcut(&mut harness, "c");
+
+    let contents = clipboard.contents();
+    assert_eq!(contents.text.as_deref(), Some("Hello bold Second"));
+    assert_eq!(
+        contents.html.as_deref(),
+        Some("<p id=\"first\">Hello <b>bold</b></p>\n<p id=\"second\">Second</p>")
+    );
+}
+
+#[test]
+fn pasted_images_are_exposed_to_handlers() {
+    let (mut harness, clipboard) = load(INPUTS);
+    clipboard
+        .set_clipboard_contents(ClipboardContents {
+            image: Some(ClipboardImage {
+                width: 2,
+                height: 1,
+                rgba: vec![255; 8],
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+
+    harness.click("#target");
+    let recorder = ClipboardRecorder::default();
+    recorded_shortcut(&mut harness, "v", recorder.clone());
+    assert_eq!(*recorder.images.borrow(), [(2, 1)]);
+    assert_eq!(clipboard.image_reads.load(Ordering::Relaxed), 1);
+    // Images can't be pasted into text inputs
+    assert_eq!(value(&harness, "#target"), "");
+}
+
+#[test]
+fn pasted_images_are_only_read_when_used() {
+    let (mut harness, clipboard) = load(INPUTS);
+    clipboard
+        .set_clipboard_contents(ClipboardContents {
+            text: Some(String::from("text")),
+            image: Some(ClipboardImage {
+                width: 2,
+                height: 1,
+                rgba: vec![255; 8],
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+
+    harness.click("#target");
+    shortcut(&mut harness, "v");
+    assert_eq!(value(&harness, "#target"), "text");
+    assert_eq!(clipboard.image_reads.load(Ordering::Relaxed), 0);
+}
@@ -0,0 +1,230 @@
+//! CSS counters. `counter-reset`, `counter-increment` and `counter-set` are
+//! evaluated across the tree, list markers are numbered from the `list-item`
+//! counter (respecting `<ol start reversed>` and `<li value>`), `counter()` and
+//! `counters()` can be used in `content`, and list markers can use any of the
+//! predefined counter styles or styles defined with `@counter-style`.
+
+use blitz_dom::node::Marker;
+use blitz_dom::{DocumentConfig, LocalName, QualName, ns};
+use blitz_html::HtmlDocument;
+use blitz_traits::shell::{ColorScheme, Viewport};
+
+fn load(html: &str) -> HtmlDocument {
+    let mut doc = HtmlDocument::from_html(
+        html,
+        DocumentConfig {
+            viewport: Some(Viewport::new(800, 600, 1.0, ColorScheme::Light)),
+            ..Default::default()
+        },
+    );
+    doc.resolve(0.0);
+    doc
+}
+
+/// The list markers of the elements matching a selector
+fn markers(doc: &HtmlDocument, selector: &str) -> Vec<String> {
+    doc.query_selector_all(selector)
+        .unwrap()
+        .into_iter()
+        .map(|id| {
+            let element = doc.get_node(id).unwrap().element_data().unwrap();
+            match &element.list_item_data.as_ref().expect("no marker").marker {
+                Marker::Char(char) => char.to_string(),
+                Marker::String(string) => string.clone(),
+            }
+        })
+        .collect()
+}
+
+/// The text of the `::before` pseudo-elements of the elements matching a selector
+fn before_text(doc: &HtmlDocument, selector: &str) -> Vec<String> {
+    doc.query_selector_all(selector)
+        .unwrap()
+        .into_iter()
+        .map(|id| {
+            let before_id = doc.get_node(id).unwrap().before().expect("no ::before");
+            doc.get_node(before_id).unwrap().text_content()
+        })
+        .collect()
+}
+
+#[test]
+fn ordered_list_attributes() {
+    let doc = load(
+        r#"<html><body>
+        <ol id="plain"><li>a<li>b<li>c</ol>
+        <ol id="start" start="5"><li>a<li>b</ol>
+        <ol id="reversed" reversed><li>a<li>b<li>c</ol>
+        <ol id="both" reversed start="10"><li>a<li>b</ol>
+        <ol id="value"><li>a<li value="7">b<li>c</ol>
+        </body></html>"#,
+    );
+
+    assert_eq!(markers(&doc, "#plain li"), ["1. ", "2. ", "3. "]);
+    assert_eq!(markers(&doc, "#start li"), ["5. ", "6. "]);
+    assert_eq!(markers(&doc, "#reversed li"), ["3. ", "2. ", "1. "]);
+    assert_eq!(markers(&doc, "#both li"), ["10. ", "9. "]);
+    assert_eq!(markers(&doc, "#value li"), ["1. ", "7. ", "8. "]);
+}
+
+#[test]
+fn predefined_counter_styles() {
+    let doc = load(
+        r#"<html><body>
+        <ol style="list-style-type: lower-roman"><li>a<li>b<li>c<li>d</ol>
+        <ol style="list-style-type: lower-greek"><li>a<li>b</ol>
+        <ol style="list-style-type: decimal-leading-zero"><li>a<li>b</ol>
+        <ol style="list-style-type: cjk-decimal" start="10"><li>a</ol>
+        <ol style="list-style-type: simp-chinese-informal" start="12"><li>a</ol>
+        <ul><li>a</ul>
+        </body></html>"#,
+    );
+
+    assert_eq!(
+        markers(&doc, "ol:nth-of-type(1) li"),
+        ["i. ", "ii. ", "iii. ", "iv. "]
+    );
+    assert_eq!(markers(&doc, "ol:nth-of-type(2) li"), ["α. ", "β. "]);
+    assert_eq!(markers(&doc, "ol:nth-of-type(3) li"), ["01. ", "02. "]);
+    assert_eq!(markers(&doc, "ol:nth-of-type(4) li"), ["一〇、"]);
+    assert_eq!(markers(&doc, "ol:nth-of-type(5) li"), ["十二、"]);
+    assert_eq!(markers(&doc, "ul li"), ["•"]);
+}
+
+#[test]
+fn counter_style_rules() {
+    let doc = load(
+        r#"<html><head><style>
+        @counter-style thumbs { system: cyclic; symbols: "👍" "👎"; suffix: " "; }
+        @counter-style paren-roman { system: extends upper-roman; prefix: "("; suffix: ") "; }
+        @counter-style small-binary { system: numeric; symbols: "0" "1"; range: 1 3; }
+        #thumbs { list-style-type: thumbs; }
+        #roman { list-style-type: paren-roman; }
+        #binary { list-style-type: small-binary; }
+        </style></head><body>
+        <ol id="thumbs"><li>a<li>b<li>c</ol>
+        <ol id="roman"><li>a<li>b</ol>
+        <ol id="binary"><li>a<li>b<li>c<li>d</ol>
+        </body></html>"#,
+    );
+
+    assert_eq!(markers(&doc, "#thumbs li"), ["👍 ", "👎 ", "👍 "]);
+    assert_eq!(markers(&doc, "#roman li"), ["(I) ", "(II) "]);
+    // Values outside the range fall back to decimal
+    assert_eq!(markers(&doc, "#binary li"), ["1. ", "10. ", "11. ", "4. "]);
+}
+
+#[test]
+fn counters_in_generated_content() {
+    let doc = load(
+        r#"<html><head><style>
+        body { counter-reset: section; }
+        h2 { counter-increment: section; counter-reset: sub; }
+        h2::before { content: "Section " counter(section, upper-alpha) ": "; }
+        h3 { counter-increment: sub; }
+        h3::before { content: counter(section) "." counter(sub) " "; }
+        .skip { counter-set: section 9; }
+        ol.nested { list-style-type: none; }
+        ol.nested li::before { content: counters(list-item, ".") " "; }
+        </style></head><body>
+        <h2>One</h2><h3>a</h3><h3>b</h3>
+        <h2>Two</h2><h3>a</h3>
+        <h2 class="skip">Ten</h2>
+        <ol class="nested"><li>a<ol class="nested"><li>b<li>c</ol><li>d</ol>
+        </body></html>"#,
+    );
+
+    assert_eq!(
+        before_text(&doc, "h2"),
+        ["Section A: ", "Section B: ", "Section I: "]
+    );
+    assert_eq!(before_text(&doc, "h3"), ["1.1 ", "1.2 ", "2.1 "]);
+    assert_eq!(
+        before_text(&doc, "ol.nested li"),
+        ["1 ", "1.1 ", "1.2 ", "2 "]
+    );
+}
+
+#[test]
+fn markers_update_when_list_items_are_removed() {
+    let mut doc = load(r#"<html><body><ol><li id="first">a<li>b<li>c</ol></body></html>"#);
+    assert_eq!(markers(&doc, "li"), ["1. ", "2. ", "3. "]);
+
+    let first = doc.get_element_by_id("first").unwrap();
+    doc.mutate().remove_and_drop_node(first);
+    doc.resolve(0.0);
+    assert_eq!(markers(&doc, "li"), ["1. ", "2. "]);
+}
+
+#[test]
+fn huge_counter_values_fall_back_to_decimal() {
+    let doc = load(
+        r#"<html><head><style>
+        body { counter-reset: x 2147483647; }
+        p::before { content: counter(x, symbols(symbolic "*")) " " counter(x, upper-roman); }
+        </style></head><body><p>a</p></body></html>"#,
+    );
+    assert_eq!(before_text(&doc, "p"), ["2147483647 2147483647"]);
+}
+
+#[test]
+fn counters_update_when_counter_properties_change() {
+    let mut doc = load(
+        r#"<html><head><style>
+        p::before { content: counter(x); }
+        </style></head><body style="counter-reset: x 1"><p>a</p></body></html>"#,
+    );
+    assert_eq!(before_text(&doc, "p"), ["1"]);
+
+    let body = doc.query_selector("body").unwrap().unwrap();
+    doc.mutate()
+        .set_style_property(body, "counter-reset", "x 5");
+    doc.resolve(0.0);
+    assert_eq!(before_text(&doc, "p"), ["5"]);
+
+    let p = doc.query_selector("p").unwrap().unwrap();
+    doc.mutate()
+        .set_style_property(p, "counter-increment", "x 2");
+    doc.resolve(0.0);
+    assert_eq!(before_text(&doc, "p"), ["7"]);
+}
+
+#[test]
+fn counters_update_when_counter_attributes_change() {
+    let mut doc = load(
+        r#"<html><head><style>
+        .hidden { display: none; }
+        p::before { content: attr(data-label); }
+        </style></head><body>
+        <ol id="list"><li id="item">a<li>b</ol>
+        <p id="label" data-label="one">a</p>
+        </body></html>"#,
+    );
+    let attr = |name: &str| QualName::new(None, ns!(), LocalName::from(name));
+    assert_eq!(markers(&doc, "li"), ["1. ", "2. "]);
+
+    let list = doc.get_element_by_id("list").unwrap();
+    doc.mutate().set_attribute(list, attr("start"), "3");
+    doc.resolve(0.0);
+    assert_eq!(markers(&doc, "li"), ["3. ", "4. "]);
+
+    doc.mutate().set_attribute(list, attr("reversed"), "");
+    doc.resolve(0.0);
+    assert_eq!(markers(&doc, "li"), ["3. ", "2. "]);
+
+    doc.mutate().clear_attribute(list, attr("reversed"));
+    let item = doc.get_element_by_id("item").unwrap();
+    doc.mutate().set_attribute(item, attr("value"), "7");
+    doc.resolve(0.0);
+    assert_eq!(markers(&doc, "li"), ["7. ", "8. "]);
+
+    // Attributes which affect counters through styles are picked up by restyling
+    doc.mutate().set_attribute(item, attr("class"), "hidden");
+    doc.resolve(0.0);
+    assert_eq!(markers(&doc, "li:not(.hidden)"), ["3. "]);
+
+    let label = doc.get_element_by_id("label").unwrap();
+    doc.mutate().set_attribute(label, attr("data-label"), "two");
+    doc.resolve(0.0);
+    assert_eq!(before_text(&doc, "p"), ["two"]);
+}
@@ -0,0 +1,189 @@
+//! Custom widgets can expose their own accessibility subtree, which takes the
+//! place of their element's children in the document's tree, and perform the
+//! actions that assistive technology requests on it. They are also told when
+//! their node gains or loses focus, and receive keyboard events while focussed.
+
+use accesskit::{Action, ActionRequest, Node, NodeId, Rect, Role, TreeId, TreeUpdate};
+use blitz_dom::{Widget, WidgetAccessibilityTree};
+use blitz_test_harness::Harness;
+use blitz_traits::events::UiEvent;
+use keyboard_types::Key;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const PAGE: &str = r#"<html><body style="margin:0">
+    <div style="height:30px"></div>
+    <canvas id="map" width="100" height="50" style="display:block; padding:5px">Fallback</canvas>
+    <input id="input">
+</body></html>"#;
+
+const ROOT: NodeId = NodeId(0);
+const ZOOM_IN: NodeId = NodeId(1);
+const PIN: NodeId = NodeId(2);
+
+/// A map with a zoom button and a pin, which records what it receives
+#[derive(Clone, Default)]
+struct Map {
+    log: Rc<RefCell<Vec<String>>>,
+    zoom: Rc<Cell<u32>>,
+}
+
+impl Widget for Map {
+    fn focus(&mut self) {
+        self.log.borrow_mut().push(String::from("focus"));
+    }
+
+    fn blur(&mut self) {
+        self.log.borrow_mut().push(String::from("blur"));
+    }
+
+    fn handle_event(&mut self, event: &UiEvent) {
+        if let UiEvent::KeyDown(event) = event {
+            self.log.borrow_mut().push(format!("keydown {}", event.key));
+        }
+    }
+
+    fn accessibility_tree(&self) -> Option<WidgetAccessibilityTree> {
+        let mut root = Node::new(Role::Group);
+        root.set_label("Map");
+        root.set_children(vec![ZOOM_IN, PIN]);
+
+        let mut zoom_in = Node::new(Role::Button);
+        zoom_in.set_label(format!("Zoom in (zoom {})", self.zoom.get()));
+        zoom_in.set_bounds(Rect::new(0.0, 0.0, 20.0, 20.0));
+        zoom_in.add_action(Action::Click);
+
+        let mut pin = Node::new(Role::Image);
+        pin.set_label("Pin");
+
+        Some(WidgetAccessibilityTree {
+            root: ROOT,
+            nodes: vec![(ROOT, root), (ZOOM_IN, zoom_in), (PIN, pin)],
+        })
+    }
+
+    fn accessibility_revision(&self) -> u64 {
+        u64::from(self.zoom.get())
+    }
+
+    fn accessibility_action(&mut self, request: &ActionRequest) {
+        self.log
+            .borrow_mut()
+            .push(format!("{:?} {}", request.action, request.target_node.0));
+        if request.target_node == ZOOM_IN && request.action == Action::Click {
+            self.zoom.set(self.zoom.get() + 1);
+        }
+    }
+}
+
+fn load() -> (Harness, Map) {
+    let mut harness = Harness::from_html(PAGE);
+    let map = Map::default();
+    let node_id = harness.node("#map");
+    harness
+        .base_mut()
+        .mutate()
+        .set_custom_widget(node_id, Box::new(map.clone()));
+    harness.pump();
+    (harness, map)
+}
+
+fn nodes(update: TreeUpdate) -> HashMap<NodeId, Node> {
+    update.nodes.into_iter().collect()
+}
+
+fn labelled<'a>(nodes: &'a HashMap<NodeId, Node>, label: &str) -> (NodeId, &'a Node) {
+    nodes
+        .iter()
+        .find(|(_, node)| node.label() == Some(label))
+        .map(|(id, node)| (*id, node))
+        .unwrap_or_else(|| panic!("no node labelled {label}"))
+}
+
+fn request(action: Action, target_node: NodeId) -> ActionRequest {
+    ActionRequest {
+        action,
+        target_tree: TreeId::ROOT,
+        target_node,
+        data: None,
+    }
+}
+
+#[test]
+fn subtree_replaces_the_elements_children() {
+    let (harness, _) = load();
+    let map = NodeId(harness.node("#map").as_u64());
+    let nodes = nodes(harness.base().build_accessibility_tree());
+
+    // The subtree's root is the widget's element
+    let root = &nodes[&map];
+    assert_eq!(root.role(), Role::Group);
+    assert_eq!(root.label(), Some("Map"));
+    assert_eq!(root.bounds(), Some(Rect::new(0.0, 30.0, 110.0, 90.0)));
+
+    // Its nodes are given ids that don't clash with the document's, and are positioned
+    // relative to the widget's content box
+    let (zoom_in_id, zoom_in) = labelled(&nodes, "Zoom in (zoom 0)");
+    let (pin_id, _) = labelled(&nodes, "Pin");
+    assert_eq!(root.children(), [zoom_in_id, pin_id]);
+    assert!(
+        harness
+            .base()
+            .get_node(blitz_dom::NodeId::from_u64(zoom_in_id.0))
+            .is_none()
+    );
+    assert_eq!(zoom_in.bounds(), Some(Rect::new(5.0, 35.0, 25.0, 55.0)));
+
+    // The element's fallback content isn't exposed
+    assert!(!nodes.values().any(|node| node.value() == Some("Fallback")));
+}
+
+#[test]
+fn actions_are_performed_by_the_widget() {
+    let (mut harness, map) = load();
+    let tree = nodes(harness.base_mut().accessibility_tree_update());
+    let (zoom_in_id, _) = labelled(&tree, "Zoom in (zoom 0)");
+    let map_id = NodeId(harness.node("#map").as_u64());
+    // The subtree is only queried again once the widget's revision changes
+    harness.pump();
+    assert!(!harness.base().has_accessibility_changes());
+
+    // Actions on the root are performed by the widget too (other than focussing)
+    for target in [zoom_in_id, map_id] {
+        let request = request(Action::Click, target);
+        assert!(
+            harness
+                .base()
+                .accessibility_action_event(&request)
+                .is_none()
+        );
+        assert!(harness.base_mut().forward_accessibility_action(&request));
+    }
+    assert_eq!(*map.log.borrow(), ["Click 1", "Click 0"]);
+    assert!(
+        harness
+            .base()
+            .accessibility_action_event(&request(Action::Focus, map_id))
+            .is_some()
+    );
+
+    // Only the nodes that have changed are sent
+    harness.pump();
+    assert!(harness.base().has_accessibility_changes());
+    let update = harness.base_mut().accessibility_tree_update();
+    assert_eq!(update.nodes.len(), 1, "{:?}", update.nodes);
+    assert_eq!(update.nodes[0].0, zoom_in_id);
+    assert_eq!(update.nodes[0].1.label(), Some("Zoom in (zoom 1)"));
+}
+
+#[test]
+fn focus_and_keyboard_events() {
+    let (mut harness, map) = load();
+    harness.click("#map");
+    assert_eq!(harness.focused(), Some(harness.node("#map")));
+    harness.press(Key::Character(String::from("+")));
+    harness.click("#input");
+
+    assert_eq!(*map.log.borrow(), ["focus", "keydown +", "blur"]);
+}
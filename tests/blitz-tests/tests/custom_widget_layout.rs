@@ -3,7 +3,10 @@
 //! Regression test for #706: `set_custom_widget` writes into the same
 //! `SpecialElementData` slot the replaced-layout match reads, so any replaced
 //! element carrying a widget used to reach `unreachable!()` on the next layout.
+//!
+//! Widgets can also lay themselves out within the constraints given by Taffy.
 
+use blitz_dom::node::{ComputedStyles, LayoutInput, LayoutOutput};
 use blitz_dom::{DocumentConfig, IntrinsicSizes, Widget};
 use blitz_html::{HtmlDocument, HtmlProvider};
 use blitz_traits::shell::{ColorScheme, Viewport};
@@ -23,7 +26,22 @@ impl Widget for SizedProbe {
     }
 }
 
-fn widget_size(html: &str, widget: Box<dyn Widget>) -> (f32, f32) {
+/// Fills the width it is given, with a fixed height and baseline
+struct Chart;
+impl Widget for Chart {
+    fn layout(&mut self, inputs: &LayoutInput, _styles: &ComputedStyles) -> Option<LayoutOutput> {
+        let size = taffy::Size {
+            width: inputs.known_dimensions.width.unwrap_or(120.0),
+            height: 40.0,
+        };
+        Some(LayoutOutput {
+            baselines: taffy::Baselines::from_first(Some(30.0)),
+            ..LayoutOutput::from_outer_size(size)
+        })
+    }
+}
+
+fn load(html: &str, widget: Box<dyn Widget>) -> HtmlDocument {
     let mut doc = HtmlDocument::from_html(
         html,
         DocumentConfig {
@@ -39,11 +57,19 @@ fn widget_size(html: &str, widget: Box<dyn Widget>) -> (f32, f32) {
         .expect("#widget not found");
     doc.mutate().set_custom_widget(node_id, widget);
     doc.resolve(0.0);
+    doc
+}
 
+fn size_of(doc: &HtmlDocument, selector: &str) -> (f32, f32) {
+    let node_id = doc.query_selector(selector).unwrap().unwrap();
     let layout = doc.get_node(node_id).unwrap().final_layout();
     (layout.size.width, layout.size.height)
 }
 
+fn widget_size(html: &str, widget: Box<dyn Widget>) -> (f32, f32) {
+    size_of(&load(html, widget), "#widget")
+}
+
 #[test]
 fn canvas_with_widget_keeps_its_attribute_size() {
     let size = widget_size(
@@ -109,3 +135,36 @@ fn content_attributes_override_the_widget_reported_size() {
     );
     assert_eq!(size, (120.0, 60.0));
 }
+
+#[test]
+fn widget_layout_receives_the_constraints() {
+    let size = widget_size(
+        r#"<html><body style="margin:0;">
+            <div style="width: 300px"><div id="widget"></div></div>
+        </body></html>"#,
+        Box::new(Chart),
+    );
+    assert_eq!(size, (300.0, 40.0));
+}
+
+#[test]
+fn widget_layout_baseline_is_used_for_alignment() {
+    let doc = load(
+        r#"<html><body style="margin:0;">
+            <div style="display: flex; align-items: baseline; font-size: 16px">
+                <div id="text">Label</div>
+                <div id="widget"></div>
+            </div>
+        </body></html>"#,
+        Box::new(Chart),
+    );
+    assert_eq!(size_of(&doc, "#widget"), (120.0, 40.0));
+
+    // The text is moved down to line up with the widget's baseline
+    let location = |selector| {
+        let node_id = doc.query_selector(selector).unwrap().unwrap();
+        doc.get_node(node_id).unwrap().final_layout().location.y
+    };
+    assert_eq!(location("#widget"), 0.0);
+    assert!(location("#text") > 10.0, "{}", location("#text"));
+}
@@ -0,0 +1,253 @@
+//! `<dialog>`: showing a dialog non-modally or modally, closing it with a return
+//! value, and `<form method=dialog>`. A modal dialog matches `:modal`, is
+//! positioned against the viewport and painted in the top layer above its
+//! `::backdrop`, and makes the rest of the document inert (to hit testing, focus
+//! and assistive technologies) until it is closed. Escape fires a `cancel` event
+//! at it, which closes it unless cancelled.
+
+use accesskit::NodeId as AccessKitNodeId;
+use anyrender::render_to_buffer;
+use anyrender_vello_cpu::VelloCpuImageRenderer;
+use blitz_paint::paint_scene;
+use blitz_test_harness::{Harness, key_press};
+use keyboard_types::Key;
+
+const WIDTH: u32 = 300;
+const HEIGHT: u32 = 200;
+
+const PAGE: &str = r#"<html><head><style>
+    body { margin: 0; height: 200px; }
+    button { width: 60px; height: 20px; }
+    dialog { width: 100px; height: 60px; padding: 0; border: none; }
+</style></head><body>
+    <button id="open">Open</button>
+    <button id="other">Other</button>
+    <dialog id="dialog">
+        <form method="dialog">
+            <button id="confirm" value="confirmed">OK</button>
+            <button id="cancel" type="button">Cancel</button>
+        </form>
+    </dialog>
+</body></html>"#;
+
+fn show_modal(harness: &mut Harness) {
+    let dialog = harness.node("#dialog");
+    harness.base_mut().mutate().show_modal_dialog(dialog);
+    harness.pump();
+}
+
+/// The names of the events which the document has queued but not yet dispatched (e.g.
+/// `close`)
+fn pending_events(harness: &mut Harness) -> Vec<String> {
+    harness
+        .base_mut()
+        .take_pending_events()
+        .iter()
+        .map(|event| event.name().to_string())
+        .collect()
+}
+
+#[test]
+fn show_modal_opens_the_dialog_and_focuses_it() {
+    let mut harness = Harness::from_html(PAGE);
+    assert!(harness.ids("dialog[open]").is_empty());
+    assert_eq!(harness.layout_rect("#dialog").height, 0.0);
+
+    harness.click("#open");
+    show_modal(&mut harness);
+
+    let dialog = harness.node("#dialog");
+    assert_eq!(harness.ids("dialog[open]"), ["dialog"]);
+    assert_eq!(harness.ids(":modal"), ["dialog"]);
+    assert_eq!(harness.layout_rect("#dialog").height, 60.0);
+    assert_eq!(harness.base().top_layer(), [dialog]);
+    // The first focusable element in the dialog is focused
+    assert_eq!(harness.focused(), Some(harness.node("#confirm")));
+}
+
+#[test]
+fn close_restores_focus_and_sets_the_return_value() {
+    let mut harness = Harness::from_html(PAGE);
+    harness.click("#open");
+    show_modal(&mut harness);
+
+    let dialog = harness.node("#dialog");
+    harness
+        .base_mut()
+        .mutate()
+        .close_dialog(dialog, Some("done"));
+    assert_eq!(pending_events(&mut harness), ["close"]);
+    harness.pump();
+
+    assert!(harness.ids("dialog[open]").is_empty());
+    assert!(harness.ids(":modal").is_empty());
+    assert!(harness.base().top_layer().is_empty());
+    assert_eq!(harness.base().dialog_return_value(dialog), "done");
+    assert_eq!(harness.focused(), Some(harness.node("#open")));
+
+    // Closing without a return value keeps the previous one
+    harness.base_mut().mutate().show_modal_dialog(dialog);
+    harness.base_mut().mutate().close_dialog(dialog, None);
+    assert_eq!(harness.base().dialog_return_value(dialog), "done");
+}
+
+#[test]
+fn show_is_not_modal() {
+    let mut harness = Harness::from_html(PAGE);
+    let dialog = harness.node("#dialog");
+    harness.base_mut().mutate().show_dialog(dialog);
+    harness.pump();
+
+    assert_eq!(harness.ids("dialog[open]"), ["dialog"]);
+    assert!(harness.ids(":modal").is_empty());
+    assert!(harness.base().top_layer().is_empty());
+
+    // The rest of the document is still interactive
+    harness.click("#other");
+    assert_eq!(harness.focused(), Some(harness.node("#other")));
+}
+
+#[test]
+fn modal_dialog_makes_the_document_inert() {
+    let mut harness = Harness::from_html(PAGE);
+    show_modal(&mut harness);
+    let dialog = harness.node("#dialog");
+    let other = harness.node("#other");
+    assert!(harness.base().is_inert(other));
+    assert!(!harness.base().is_inert(harness.node("#cancel")));
+
+    // Tab navigation cycles within the dialog
+    harness.press(Key::Tab);
+    assert_eq!(harness.focused(), Some(harness.node("#cancel")));
+    harness.press(Key::Tab);
+    assert_eq!(harness.focused(), Some(harness.node("#confirm")));
+
+    // Content inside the dialog can be hit, but points outside of it hit the dialog (its
+    // backdrop) rather than the content beneath
+    let (x, y) = harness.center_of("#cancel");
+    assert_eq!(harness.hit_node(x, y), harness.node("#cancel"));
+    let (x, y) = harness.center_of("#other");
+    assert_eq!(harness.hit_node(x, y), dialog);
+    harness.click_at(x, y);
+    assert_ne!(harness.focused(), Some(other));
+    assert!(!harness.base_mut().set_focus_to(other));
+
+    // Inert content is hidden from assistive technologies, but the dialog's ancestors aren't
+    let tree = harness.base().build_accessibility_tree();
+    let is_hidden = |selector: &str| {
+        let id = AccessKitNodeId(harness.node(selector).as_u64());
+        let (_, node) = tree
+            .nodes
+            .iter()
+            .find(|(node_id, _)| *node_id == id)
+            .unwrap();
+        node.is_hidden()
+    };
+    assert!(is_hidden("#other"));
+    assert!(!is_hidden("body"));
+    assert!(!is_hidden("#cancel"));
+
+    harness.base_mut().mutate().close_dialog(dialog, None);
+    harness.pump();
+    assert!(!harness.base().is_inert(other));
+    assert_eq!(harness.hit_node(x, y), other);
+}
+
+#[test]
+fn modal_dialog_is_positioned_against_the_viewport() {
+    let mut harness = Harness::from_html(
+        r#"<html><head><style>
+            body { margin: 0; }
+            #container {
+                position: relative; overflow: hidden;
+                margin: 50px 0 0 20px; width: 40px; height: 40px;
+            }
+            dialog { width: 100px; height: 60px; padding: 0; border: none; }
+            button { width: 60px; height: 20px; }
+        </style></head><body>
+            <div id="container">
+                <dialog id="dialog"><button id="inside">OK</button></dialog>
+            </div>
+        </body></html>"#,
+    );
+    harness.set_viewport_size(WIDTH, HEIGHT);
+    show_modal(&mut harness);
+
+    // The dialog is centered in the viewport rather than in its container, and isn't
+    // clipped by it
+    let rect = harness.layout_rect("#dialog");
+    assert_eq!((rect.x, rect.y), (100.0, 70.0));
+    let (x, y) = harness.center_of("#inside");
+    assert_eq!(harness.hit_node(x, y), harness.node("#inside"));
+}
+
+#[test]
+fn escape_cancels_the_modal_dialog() {
+    let mut harness = Harness::from_html(PAGE);
+    show_modal(&mut harness);
+
+    let names = harness.dispatch_recorded(key_press(Key::Escape));
+    assert!(names.contains(&"cancel".to_string()), "{names:?}");
+    assert_eq!(pending_events(&mut harness), ["close"]);
+    harness.pump();
+
+    assert!(harness.ids("dialog[open]").is_empty());
+    assert_eq!(
+        harness.base().dialog_return_value(harness.node("#dialog")),
+        ""
+    );
+}
+
+#[test]
+fn form_method_dialog_closes_the_dialog() {
+    let mut harness = Harness::from_html(PAGE);
+    show_modal(&mut harness);
+
+    harness.click("#confirm");
+
+    assert!(harness.ids("dialog[open]").is_empty());
+    assert_eq!(
+        harness.base().dialog_return_value(harness.node("#dialog")),
+        "confirmed"
+    );
+}
+
+#[test]
+fn top_layer_is_painted_above_the_backdrop() {
+    let mut harness = Harness::from_html(
+        r#"<html><head><style>
+            body { margin: 0; height: 200px; background: white; }
+            #cover {
+                position: relative; z-index: 10;
+                width: 300px; height: 200px; background: #ff0000;
+            }
+            dialog { width: 100px; height: 60px; padding: 0; border: none; background: #00ff00; }
+            dialog::backdrop { background: #0000ff; }
+        </style></head><body>
+            <div id="cover"></div>
+            <dialog id="dialog"></dialog>
+        </body></html>"#,
+    );
+    harness.set_viewport_size(WIDTH, HEIGHT);
+    show_modal(&mut harness);
+
+    let buffer = {
+        let mut doc = harness.base_mut();
+        render_to_buffer::<VelloCpuImageRenderer, _>(
+            |scene| paint_scene(scene, &mut doc, 1.0, WIDTH, HEIGHT, 0, 0),
+            WIDTH,
+            HEIGHT,
+        )
+    };
+    let pixel = |x: f32, y: f32| {
+        let idx = ((y as u32 * WIDTH + x as u32) * 4) as usize;
+        [buffer[idx], buffer[idx + 1], buffer[idx + 2]]
+    };
+
+    // The backdrop covers the (positioned) content, and the dialog is drawn above it
+    let (x, y) = harness.center_of("#dialog");
+    assert_eq!(pixel(x, y), [0, 255, 0]);
+    assert_eq!(pixel(2.0, 2.0), [0, 0, 255]);
+    // None of the content beneath the backdrop shows through
+    assert!(!buffer.chunks_exact(4).any(|px| px[..3] == [255, 0, 0]));
+}
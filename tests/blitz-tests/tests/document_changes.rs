@@ -0,0 +1,10 @@
+//! `BaseDocument::has_changes` reports whether the document has been mutated
+//! (including by parsing it).
+
+use blitz_test_harness::Harness;
+
+#[test]
+fn parsed_documents_have_changes() {
+    let harness = Harness::from_html(r#"<html><body><p id="text">Hello</p></body></html>"#);
+    assert!(harness.base().has_changes());
+}
@@ -0,0 +1,249 @@
+//! Drag and drop. Dragging a `draggable="true"` element fires `dragstart` at it,
+//! `drag` as it moves, `dragenter`/`dragover`/`dragleave` at the elements under
+//! the pointer, and `drop` (if the drop target cancelled `dragover`) followed by
+//! `dragend` when it is released. Files dragged in from outside of the
+//! application are dropped on the element under the cursor, and are selected by
+//! `<input type=file>` elements.
+
+use blitz_dom::{Document, EventDriver, EventHandler, local_name};
+use blitz_test_harness::{Harness, file_drag_event, mouse_pointer_event};
+use blitz_traits::events::{DomEvent, DomEventData, DropEffect, EventState, UiEvent};
+use blitz_traits::node_id::NodeId;
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+const DRAG: &str = r#"<html><body style="margin:0">
+    <div id="source" draggable="true" style="width:50px; height:50px;">Drag me</div>
+    <div id="target" style="width:50px; height:50px; margin-top:50px;"></div>
+</body></html>"#;
+
+/// Records drag events (and click events) as "name #id", fills the drag data in
+/// `dragstart` and optionally cancels `dragstart` and `dragover` events
+#[derive(Clone, Default)]
+struct DragRecorder {
+    events: Rc<RefCell<Vec<String>>>,
+    dropped_data: Rc<RefCell<Option<String>>>,
+    drop_effect: Rc<RefCell<Option<DropEffect>>>,
+    cancel_dragstart: bool,
+    accept_drop: bool,
+}
+
+impl EventHandler for DragRecorder {
+    fn handle_event(
+        &mut self,
+        _chain: &[NodeId],
+        event: &mut DomEvent,
+        doc: &mut dyn Document,
+        event_state: &mut EventState,
+    ) {
+        let data = match &event.data {
+            DomEventData::DragStart(data)
+            | DomEventData::Drag(data)
+            | DomEventData::DragEnd(data)
+            | DomEventData::DragEnter(data)
+            | DomEventData::DragOver(data)
+            | DomEventData::DragLeave(data)
+            | DomEventData::Drop(data) => Some(data),
+            DomEventData::Click(_) => None,
+            _ => return,
+        };
+        let id = doc
+            .inner()
+            .get_node(event.target)
+            .and_then(|node| node.attr(local_name!("id")))
+            .unwrap_or_default()
+            .to_string();
+        self.events
+            .borrow_mut()
+            .push(format!("{} #{id}", event.name()));
+
+        let Some(data) = data else {
+            return;
+        };
+        match &event.data {
+            DomEventData::DragStart(_) => {
+                data.data_transfer.set_data("text", "dragged");
+                if self.cancel_dragstart {
+                    event_state.prevent_default();
+                }
+            }
+            DomEventData::DragOver(_) if self.accept_drop && id == "target" => {
+                event_state.prevent_default();
+            }
+            DomEventData::Drop(_) => {
+                *self.dropped_data.borrow_mut() = data.data_transfer.get_data("text/plain");
+            }
+            DomEventData::DragEnd(_) => {
+                *self.drop_effect.borrow_mut() = Some(data.data_transfer.drop_effect());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Drag `#source` onto `#target` with the mouse
+fn drag_source_to_target(harness: &mut Harness, recorder: DragRecorder) {
+    let (source_x, source_y) = harness.center_of("#source");
+    let (target_x, target_y) = harness.center_of("#target");
+    let mut doc = harness.base_mut();
+    let mut driver = EventDriver::new(&mut *doc, recorder);
+    driver.handle_ui_event(UiEvent::PointerDown(mouse_pointer_event(
+        source_x, source_y,
+    )));
+    driver.handle_ui_event(UiEvent::PointerMove(mouse_pointer_event(
+        source_x,
+        source_y + 10.0,
+    )));
+    driver.handle_ui_event(UiEvent::PointerMove(mouse_pointer_event(
+        target_x, target_y,
+    )));
+    driver.handle_ui_event(UiEvent::PointerUp(mouse_pointer_event(target_x, target_y)));
+    drop(doc);
+    harness.pump();
+}
+
+#[test]
+fn dragging_an_element_fires_drag_events() {
+    let mut harness = Harness::from_html(DRAG);
+    let recorder = DragRecorder {
+        accept_drop: true,
+        ..Default::default()
+    };
+    drag_source_to_target(&mut harness, recorder.clone());
+
+    assert_eq!(
+        *recorder.events.borrow(),
+        [
+            "dragstart #source",
+            "drag #source",
+            "dragenter #target",
+            "dragover #target",
+            "drop #target",
+            "dragend #source",
+        ]
+    );
+    assert_eq!(recorder.dropped_data.take().as_deref(), Some("dragged"));
+    assert_eq!(recorder.drop_effect.take(), Some(DropEffect::Copy));
+}
+
+#[test]
+fn drop_requires_cancelled_dragover() {
+    let mut harness = Harness::from_html(DRAG);
+    let recorder = DragRecorder::default();
+    drag_source_to_target(&mut harness, recorder.clone());
+
+    assert_eq!(
+        *recorder.events.borrow(),
+        [
+            "dragstart #source",
+            "drag #source",
+            "dragenter #target",
+            "dragover #target",
+            "dragleave #target",
+            "dragend #source",
+        ]
+    );
+    assert_eq!(recorder.dropped_data.take(), None);
+    assert_eq!(recorder.drop_effect.take(), Some(DropEffect::None));
+}
+
+#[test]
+fn cancelling_dragstart_prevents_drag() {
+    let mut harness = Harness::from_html(DRAG);
+    let recorder = DragRecorder {
+        cancel_dragstart: true,
+        accept_drop: true,
+        ..Default::default()
+    };
+    drag_source_to_target(&mut harness, recorder.clone());
+
+    assert_eq!(*recorder.events.borrow(), ["dragstart #source"]);
+}
+
+#[test]
+fn files_are_dropped_on_element_under_cursor() {
+    let mut harness = Harness::from_html(
+        r#"<html><body style="margin:0">
+        <div id="zone" style="width:100px; height:100px;"></div>
+        </body></html>"#,
+    );
+
+    #[derive(Clone, Default)]
+    struct FileDropRecorder {
+        dropped: Rc<RefCell<Vec<(NodeId, Vec<PathBuf>, Vec<String>)>>>,
+    }
+
+    impl EventHandler for FileDropRecorder {
+        fn handle_event(
+            &mut self,
+            _chain: &[NodeId],
+            event: &mut DomEvent,
+            _doc: &mut dyn Document,
+            event_state: &mut EventState,
+        ) {
+            match &event.data {
+                DomEventData::DragOver(_) => event_state.prevent_default(),
+                DomEventData::Drop(data) => self.dropped.borrow_mut().push((
+                    event.target,
+                    data.data_transfer.files(),
+                    data.data_transfer.types(),
+                )),
+                _ => {}
+            }
+        }
+    }
+
+    let recorder = FileDropRecorder::default();
+    let (x, y) = harness.center_of("#zone");
+    let paths = vec![PathBuf::from("/tmp/notes.txt")];
+    let mut doc = harness.base_mut();
+    let mut driver = EventDriver::new(&mut *doc, recorder.clone());
+    let event = |paths| file_drag_event(x, y, paths);
+    driver.handle_ui_event(UiEvent::FileDragEnter(event(paths.clone())));
+    driver.handle_ui_event(UiEvent::FileDragMove(event(Vec::new())));
+    driver.handle_ui_event(UiEvent::FileDrop(event(paths.clone())));
+    drop(doc);
+
+    assert_eq!(
+        *recorder.dropped.borrow(),
+        [(harness.node("#zone"), paths, vec![String::from("Files")])]
+    );
+}
+
+#[test]
+fn files_dropped_on_file_input_are_selected() {
+    let mut harness = Harness::from_html(
+        r#"<html><body style="margin:0">
+        <input id="single" type="file" style="display:block; width:200px; height:30px;">
+        <input id="multiple" type="file" multiple style="display:block; width:200px; height:30px;">
+        <div id="elsewhere" style="width:200px; height:30px;"></div>
+        </body></html>"#,
+    );
+    let paths = vec![PathBuf::from("/tmp/a.txt"), PathBuf::from("/tmp/b.txt")];
+    let files = |harness: &Harness, selector: &str| {
+        let node_id = harness.node(selector);
+        let doc = harness.base();
+        let element = doc.get_node(node_id).unwrap().element_data().unwrap();
+        element.file_data().map(|files| files.to_vec())
+    };
+
+    let (x, y) = harness.center_of("#single");
+    harness.drop_files_at(x, y, paths.clone());
+    assert_eq!(files(&harness, "#single").unwrap(), &paths[..1]);
+    assert!(harness.text_content("#single").contains("a.txt"));
+
+    let (x, y) = harness.center_of("#multiple");
+    harness.drop_files_at(x, y, paths.clone());
+    assert_eq!(files(&harness, "#multiple").unwrap(), paths);
+    assert!(
+        harness
+            .text_content("#multiple")
+            .contains("2 Files Selected")
+    );
+
+    // Other elements don't accept dropped files by default
+    let (x, y) = harness.center_of("#elsewhere");
+    harness.drop_files_at(x, y, vec![PathBuf::from("/tmp/c.txt")]);
+    assert_eq!(files(&harness, "#single").unwrap(), &paths[..1]);
+}
@@ -0,0 +1,84 @@
+//! `:focus-visible` and `:focus-within`. Focus is visible when it was moved
+//! using the keyboard (and always for text inputs), but not when an element was
+//! clicked. `:focus-within` matches the focussed element and its ancestors, and
+//! follows focus as it moves.
+
+use blitz_test_harness::Harness;
+use keyboard_types::{Key, Modifiers};
+
+const PAGE: &str = r#"<html><head><style>
+    #toolbar { width: 100px; }
+    #toolbar:focus-within { width: 200px; }
+    button { width: 50px; height: 20px; }
+    button:focus-visible { width: 80px; }
+</style></head><body style="margin:0">
+    <div id="toolbar">
+        <button id="bold">B</button>
+        <button id="italic">I</button>
+    </div>
+    <form id="form"><input id="input" style="width:100px; height:20px"></form>
+</body></html>"#;
+
+#[test]
+fn clicking_doesnt_show_focus() {
+    let mut harness = Harness::from_html(PAGE);
+    harness.click("#bold");
+
+    assert_eq!(harness.ids(":focus"), ["bold"]);
+    assert!(harness.ids(":focus-visible").is_empty());
+    assert_eq!(harness.layout_rect("#bold").width, 50.0);
+}
+
+#[test]
+fn keyboard_navigation_shows_focus() {
+    let mut harness = Harness::from_html(PAGE);
+    harness.click("#bold");
+    harness.press(Key::Tab);
+
+    assert_eq!(harness.ids(":focus-visible"), ["italic"]);
+    assert_eq!(harness.layout_rect("#italic").width, 80.0);
+
+    // Clicking hides it again
+    harness.click("#bold");
+    assert!(harness.ids(":focus-visible").is_empty());
+    assert_eq!(harness.layout_rect("#italic").width, 50.0);
+}
+
+#[test]
+fn keyboard_shortcuts_dont_show_focus() {
+    let mut harness = Harness::from_html(PAGE);
+    harness.click("#bold");
+    harness.press_with(Key::Character(String::from("a")), Modifiers::CONTROL);
+    let italic = harness.node("#italic");
+    harness.base_mut().set_focus_to(italic);
+    harness.pump();
+
+    assert!(harness.ids(":focus-visible").is_empty());
+}
+
+#[test]
+fn text_inputs_always_show_focus() {
+    let mut harness = Harness::from_html(PAGE);
+    harness.click("#input");
+    assert_eq!(harness.ids(":focus-visible"), ["input"]);
+}
+
+#[test]
+fn focus_within_follows_focus() {
+    let mut harness = Harness::from_html(PAGE);
+    assert!(harness.ids(":focus-within").is_empty());
+
+    harness.click("#italic");
+    assert_eq!(harness.ids(":focus-within"), ["toolbar", "italic"]);
+    assert_eq!(harness.layout_rect("#toolbar").width, 200.0);
+
+    harness.click("#input");
+    assert_eq!(harness.ids(":focus-within"), ["form", "input"]);
+    assert_eq!(harness.layout_rect("#toolbar").width, 100.0);
+
+    // Removing the focussed element removes the state from its ancestors
+    let input = harness.node("#input");
+    harness.base_mut().mutate().remove_node(input);
+    harness.pump();
+    assert!(harness.ids(":focus-within").is_empty());
+}
@@ -0,0 +1,228 @@
+//! Form state pseudo-classes: `:placeholder-shown`, `:read-write`/`:read-only`,
+//! `:required`/`:optional`, `:indeterminate`, `:default` and
+//! `:in-range`/`:out-of-range`. They follow the controls' attributes and their
+//! live state (typing, checking and the checkbox indeterminate flag), and
+//! elements are restyled when that state changes.
+
+use blitz_test_harness::{Harness, qname};
+use keyboard_types::Key;
+
+#[test]
+fn placeholder_shown_follows_typing() {
+    let mut harness = Harness::from_html(
+        r#"<html><head><style>
+            label { display: block; width: 100px; height: 10px; }
+            input:placeholder-shown + label { width: 200px; }
+        </style></head><body>
+            <input id="name" placeholder="Name"><label id="label"></label>
+            <input id="filled" placeholder="Name" value="Ada">
+            <input id="plain">
+            <textarea id="notes" placeholder="Notes"></textarea>
+        </body></html>"#,
+    );
+    assert_eq!(harness.ids(":placeholder-shown"), ["name", "notes"]);
+    assert_eq!(harness.layout_rect("#label").width, 200.0);
+
+    harness.click("#name");
+    harness.type_text("A");
+    assert_eq!(harness.ids(":placeholder-shown"), ["notes"]);
+    assert_eq!(harness.layout_rect("#label").width, 100.0);
+
+    harness.press(Key::Backspace);
+    assert_eq!(harness.ids(":placeholder-shown"), ["name", "notes"]);
+    assert_eq!(harness.layout_rect("#label").width, 200.0);
+}
+
+#[test]
+fn read_write_and_read_only() {
+    let mut harness = Harness::from_html(
+        r#"<html><body>
+            <input id="text">
+            <input id="readonly" readonly>
+            <textarea id="disabled" disabled></textarea>
+            <input id="checkbox" type="checkbox">
+            <div id="editor" contenteditable><p id="paragraph">Text</p></div>
+            <div id="static" contenteditable="false"></div>
+        </body></html>"#,
+    );
+    assert_eq!(harness.ids(":read-write"), ["text", "editor", "paragraph"]);
+    assert_eq!(
+        harness.ids(":read-only"),
+        ["readonly", "disabled", "checkbox", "static"]
+    );
+
+    harness.set_attribute("#text", "readonly", "");
+    assert_eq!(harness.ids(":read-write"), ["editor", "paragraph"]);
+}
+
+#[test]
+fn required_and_optional() {
+    let mut harness = Harness::from_html(
+        r#"<html><head><style>
+            input { width: 100px; }
+            input:required { width: 150px; }
+        </style></head><body>
+            <input id="email" required>
+            <input id="phone">
+            <select id="country" required></select>
+            <input id="hidden" type="hidden" required>
+            <input id="submit" type="submit">
+        </body></html>"#,
+    );
+    assert_eq!(harness.ids(":required"), ["email", "country"]);
+    assert_eq!(harness.ids(":optional"), ["phone"]);
+
+    harness.set_attribute("#phone", "required", "");
+    assert_eq!(harness.ids(":required"), ["email", "phone", "country"]);
+    assert_eq!(harness.layout_rect("#phone").width, 150.0);
+}
+
+#[test]
+fn indeterminate_checkboxes_radios_and_progress() {
+    let mut harness = Harness::from_html(
+        r#"<html><head><style>
+            input { width: 20px; height: 20px; }
+            input:indeterminate { width: 30px; }
+        </style></head><body>
+            <input id="all" type="checkbox">
+            <input id="small" type="radio" name="size">
+            <input id="large" type="radio" name="size">
+            <progress id="loading"></progress>
+            <progress id="loaded" value="1"></progress>
+        </body></html>"#,
+    );
+    assert_eq!(harness.ids(":indeterminate"), ["small", "large", "loading"]);
+
+    // The checkbox flag is set by script
+    let all = harness.node("#all");
+    harness.base_mut().mutate().set_indeterminate(all, true);
+    harness.pump();
+    assert_eq!(
+        harness.ids("input:indeterminate"),
+        ["all", "small", "large"]
+    );
+    assert_eq!(harness.layout_rect("#all").width, 30.0);
+
+    // Toggling the checkbox clears the flag, and checking a radio button settles its group
+    harness.click("#all");
+    harness.click("#large");
+    assert!(harness.ids("input:indeterminate").is_empty());
+    assert_eq!(harness.layout_rect("#all").width, 20.0);
+    assert_eq!(harness.layout_rect("#small").width, 20.0);
+}
+
+#[test]
+fn radio_groups_follow_attribute_changes() {
+    let mut harness = Harness::from_html(
+        r#"<html><head><style>
+            input { width: 20px; height: 20px; }
+            input:indeterminate { width: 30px; }
+            input:checked { height: 30px; }
+        </style></head><body>
+            <input id="small" type="radio" name="size">
+            <input id="large" type="radio" name="size">
+            <input id="red" type="radio" name="colour" checked>
+            <input id="blue" type="radio" name="colour">
+        </body></html>"#,
+    );
+    assert_eq!(harness.layout_rect("#large").width, 30.0);
+
+    // Checking a radio button settles (and restyles) the rest of its group
+    harness.set_attribute("#small", "checked", "true");
+    assert_eq!(harness.layout_rect("#small").height, 30.0);
+    assert_eq!(harness.layout_rect("#large").width, 20.0);
+
+    // Moving the checked radio button to another group leaves its old group unsettled
+    harness.set_attribute("#small", "name", "shape");
+    assert_eq!(harness.layout_rect("#large").width, 30.0);
+
+    // Checking a radio button unchecks the rest of its group
+    harness.set_attribute("#blue", "checked", "true");
+    assert_eq!(harness.ids("input:checked"), ["small", "blue"]);
+    assert_eq!(harness.layout_rect("#red").height, 20.0);
+}
+
+#[test]
+fn default_options_and_buttons() {
+    let mut harness = Harness::from_html(
+        r#"<html><body>
+            <form>
+                <input id="news" type="checkbox" checked>
+                <input id="offers" type="checkbox">
+                <select><option id="first">1</option><option id="second" selected>2</option></select>
+                <button id="cancel" type="button">Cancel</button>
+                <button id="save">Save</button>
+                <input id="send" type="submit">
+            </form>
+            <button id="outside">Outside</button>
+        </body></html>"#,
+    );
+    assert_eq!(harness.ids(":default"), ["news", "second", "save"]);
+
+    // `:default` reflects the default state, not the current one
+    harness.click("#news");
+    harness.click("#offers");
+    assert_eq!(harness.ids(":default"), ["news", "second", "save"]);
+
+    harness.set_attribute("#save", "type", "button");
+    assert_eq!(harness.ids(":default"), ["news", "second", "send"]);
+}
+
+#[test]
+fn inserting_a_submit_button_moves_default() {
+    let mut harness = Harness::from_html(
+        r#"<html><head><style>
+            button { display: block; width: 50px; height: 20px; }
+            button:default { width: 80px; }
+        </style></head><body>
+            <form><input id="name"><button id="save">Save</button></form>
+        </body></html>"#,
+    );
+    assert_eq!(harness.layout_rect("#save").width, 80.0);
+
+    // A submit button inserted before the default button takes its place
+    let save = harness.node("#save");
+    let send = {
+        let mut doc = harness.base_mut();
+        let mut mutator = doc.mutate();
+        let send = mutator.create_element(qname("button"), Vec::new());
+        mutator.set_attribute(send, qname("id"), "send");
+        mutator.insert_nodes_before(save, &[send]);
+        send
+    };
+    harness.pump();
+    assert_eq!(harness.ids(":default"), ["send"]);
+    assert_eq!(harness.layout_rect("#send").width, 80.0);
+    assert_eq!(harness.layout_rect("#save").width, 50.0);
+
+    // Removing it makes the old button the default again
+    harness.base_mut().mutate().remove_node(send);
+    harness.pump();
+    assert_eq!(harness.ids(":default"), ["save"]);
+    assert_eq!(harness.layout_rect("#save").width, 80.0);
+}
+
+#[test]
+fn in_range_follows_the_value() {
+    let mut harness = Harness::from_html(
+        r#"<html><head><style>
+            input { width: 100px; }
+            input:out-of-range { width: 150px; }
+        </style></head><body>
+            <input id="quantity" type="number" min="1" max="10">
+            <input id="unbounded" type="number">
+            <input id="volume" type="range">
+        </body></html>"#,
+    );
+    assert_eq!(harness.ids(":in-range"), ["quantity", "volume"]);
+    assert!(harness.ids(":out-of-range").is_empty());
+
+    harness.click("#quantity");
+    harness.type_text("50");
+    assert_eq!(harness.ids(":out-of-range"), ["quantity"]);
+    assert_eq!(harness.layout_rect("#quantity").width, 150.0);
+
+    harness.set_attribute("#quantity", "max", "100");
+    assert_eq!(harness.ids(":in-range"), ["quantity", "volume"]);
+    assert_eq!(harness.layout_rect("#quantity").width, 100.0);
+}
@@ -0,0 +1,170 @@
+//! Form reset. Clicking a `type=reset` button fires a cancelable `reset` event
+//! at its form, whose default action restores text inputs to their `value`
+//! attribute, checkboxes and radio buttons to their `checked` attribute and
+//! selects to their default options. `BaseDocument::reset_form` resets a form
+//! without firing an event.
+
+use blitz_dom::{Document, EventDriver, EventHandler};
+use blitz_test_harness::{Harness, mouse_pointer_event};
+use blitz_traits::events::{DomEvent, DomEventData, EventState, UiEvent};
+use blitz_traits::node_id::NodeId;
+use keyboard_types::Key;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const FORM: &str = r#"<html><body style="margin:0">
+    <form id="form">
+        <input id="text" value="default" style="width:200px; height:20px;">
+        <input id="checkbox" type="checkbox" checked style="width:20px; height:20px;">
+        <input id="radio-a" type="radio" name="group" checked style="width:20px; height:20px;">
+        <input id="radio-b" type="radio" name="group" style="width:20px; height:20px;">
+        <select id="select" style="width:100px;">
+            <option value="a">A</option>
+            <option value="b" selected>B</option>
+            <option value="c">C</option>
+        </select>
+        <button id="reset" type="reset" style="width:50px; height:20px;">Reset</button>
+    </form>
+    <input id="outside" value="outside" style="width:200px; height:20px;">
+</body></html>"#;
+
+fn text_value(harness: &Harness, selector: &str) -> String {
+    let node_id = harness.node(selector);
+    let doc = harness.base();
+    let element = doc.get_node(node_id).unwrap().element_data().unwrap();
+    element
+        .text_input_data()
+        .unwrap()
+        .editor
+        .raw_text()
+        .to_string()
+}
+
+fn checked(harness: &Harness, selector: &str) -> bool {
+    let node_id = harness.node(selector);
+    let doc = harness.base();
+    let element = doc.get_node(node_id).unwrap().element_data().unwrap();
+    element.checkbox_input_checked().unwrap()
+}
+
+fn select_value(harness: &Harness) -> String {
+    let node_id = harness.node("#select");
+    harness.base().get_node(node_id).unwrap().select_value()
+}
+
+/// Change every control in the form away from its default value
+fn edit_form(harness: &mut Harness) {
+    harness.click("#text");
+    harness.press(Key::End);
+    harness.type_text("!");
+    harness.click("#checkbox");
+    harness.click("#radio-b");
+    harness.click("#outside");
+    harness.type_text("?");
+
+    harness.click("#select");
+    harness.press(Key::ArrowDown);
+    harness.press(Key::Enter);
+
+    assert_eq!(text_value(harness, "#text"), "default!");
+    assert!(!checked(harness, "#checkbox"));
+    assert!(checked(harness, "#radio-b"));
+    assert_eq!(select_value(harness), "c");
+}
+
+fn assert_reset(harness: &Harness) {
+    assert_eq!(text_value(harness, "#text"), "default");
+    assert!(checked(harness, "#checkbox"));
+    assert!(checked(harness, "#radio-a"));
+    assert!(!checked(harness, "#radio-b"));
+    assert_eq!(select_value(harness), "b");
+    // Controls outside of the form are left alone
+    assert_eq!(text_value(harness, "#outside"), "outside?");
+}
+
+/// Records `reset` events (and their targets), optionally cancelling them
+#[derive(Clone, Default)]
+struct ResetRecorder {
+    targets: Rc<RefCell<Vec<NodeId>>>,
+    cancel: bool,
+}
+
+impl EventHandler for ResetRecorder {
+    fn handle_event(
+        &mut self,
+        _chain: &[NodeId],
+        event: &mut DomEvent,
+        _doc: &mut dyn Document,
+        event_state: &mut EventState,
+    ) {
+        if matches!(event.data, DomEventData::Reset(_)) {
+            self.targets.borrow_mut().push(event.target);
+            if self.cancel {
+                event_state.prevent_default();
+            }
+        }
+    }
+}
+
+/// Click the reset button, returning the targets of the `reset` events
+fn click_reset(harness: &mut Harness, cancel: bool) -> Vec<NodeId> {
+    let recorder = ResetRecorder {
+        cancel,
+        ..Default::default()
+    };
+    let targets = recorder.targets.clone();
+    let (x, y) = harness.center_of("#reset");
+    let event = mouse_pointer_event(x, y);
+    let mut doc = harness.base_mut();
+    let mut driver = EventDriver::new(&mut *doc, recorder);
+    driver.handle_ui_event(UiEvent::PointerDown(event.clone()));
+    driver.handle_ui_event(UiEvent::PointerUp(event));
+    drop(doc);
+    harness.pump();
+    targets.take()
+}
+
+#[test]
+fn reset_button_resets_form() {
+    let mut harness = Harness::from_html(FORM);
+    edit_form(&mut harness);
+
+    let targets = click_reset(&mut harness, false);
+    assert_eq!(targets, [harness.node("#form")]);
+    assert_reset(&harness);
+}
+
+#[test]
+fn cancelling_reset_event_keeps_values() {
+    let mut harness = Harness::from_html(FORM);
+    edit_form(&mut harness);
+
+    let targets = click_reset(&mut harness, true);
+    assert_eq!(targets.len(), 1);
+    assert_eq!(text_value(&harness, "#text"), "default!");
+    assert!(!checked(&harness, "#checkbox"));
+}
+
+#[test]
+fn reset_form_method() {
+    let mut harness = Harness::from_html(FORM);
+    edit_form(&mut harness);
+
+    let form_id = harness.node("#form");
+    harness.base_mut().reset_form(form_id);
+    harness.pump();
+    assert_reset(&harness);
+
+    // Edits after a reset can't be undone past it
+    assert!(
+        !harness
+            .base()
+            .get_node(harness.node("#text"))
+            .unwrap()
+            .element_data()
+            .unwrap()
+            .text_input_data()
+            .unwrap()
+            .can_undo()
+    );
+}
@@ -0,0 +1,310 @@
+//! Client-side form validation. Form controls are checked against their
+//! `required`, `pattern`, `min`/`max`/`step`, `minlength`/`maxlength` and
+//! `type` constraints, which is reflected in `:valid`/`:invalid` (and, once the
+//! user has interacted with a control, `:user-valid`/`:user-invalid`). Forms
+//! and fieldsets containing invalid controls match `:invalid`, and controls in
+//! a disabled fieldset aren't validated. Forms with invalid controls aren't
+//! submitted: an `invalid` event is fired at each
+//! invalid control and the first one is focused, unless the form is
+//! `novalidate` or the submitter is `formnovalidate`.
+
+use blitz_dom::{LocalName, QualName, ValidityState, ns};
+use blitz_test_harness::{Harness, mouse_pointer_event};
+use blitz_traits::events::UiEvent;
+use blitz_traits::navigation::{NavigationOptions, NavigationProvider};
+use keyboard_types::Key;
+use std::sync::{Arc, Mutex};
+
+/// Records the urls navigated to (by form submissions)
+#[derive(Default)]
+struct NavigationRecorder {
+    urls: Mutex<Vec<String>>,
+}
+
+impl NavigationProvider for NavigationRecorder {
+    fn navigate_to(&self, options: NavigationOptions) {
+        self.urls.lock().unwrap().push(options.url.to_string());
+    }
+}
+
+fn load_form(html: &str) -> (Harness, Arc<NavigationRecorder>) {
+    let mut harness = Harness::from_html(html);
+    let recorder = Arc::new(NavigationRecorder::default());
+    harness.base_mut().navigation_provider = recorder.clone();
+    (harness, recorder)
+}
+
+fn validity(harness: &Harness, selector: &str) -> Option<ValidityState> {
+    let node_id = harness.node(selector);
+    harness.base().get_node(node_id).unwrap().validity()
+}
+
+/// Click the element matching `selector`, returning the names of the dispatched events
+fn click_recorded(harness: &mut Harness, selector: &str) -> Vec<String> {
+    let (x, y) = harness.center_of(selector);
+    let event = mouse_pointer_event(x, y);
+    let events = harness.dispatch_recorded([
+        UiEvent::PointerDown(event.clone()),
+        UiEvent::PointerUp(event),
+    ]);
+    harness.pump();
+    events
+}
+
+#[test]
+fn constraints() {
+    let (harness, _) = load_form(
+        r#"<html><body><form>
+        <input id="required" required>
+        <input id="filled" required value="x">
+        <input id="email" type="email" value="not an email">
+        <input id="emails" type="email" multiple value="a@example.com, b@example.com">
+        <input id="url" type="url" value="example.com">
+        <input id="pattern" pattern="[0-9]+" value="12a">
+        <input id="number" type="number" min="1" max="10" step="2" value="4">
+        <input id="big" type="number" max="10" value="11">
+        <input id="bad" type="number" value="twelve">
+        <input id="long" maxlength="2" value="too long">
+        <input id="checkbox" type="checkbox" required>
+        <input id="radio-a" type="radio" name="group" required>
+        <input id="radio-b" type="radio" name="group" checked>
+        <select id="select" required><option value="">Choose</option><option>A</option></select>
+        <input id="disabled" required disabled>
+        <input id="readonly" required readonly>
+        </form></body></html>"#,
+    );
+
+    assert_eq!(
+        harness.ids("form :invalid"),
+        [
+            "required", "email", "url", "pattern", "number", "big", "bad", "checkbox", "select"
+        ]
+    );
+    // Controls barred from constraint validation are neither valid nor invalid
+    assert!(
+        harness
+            .ids("form :valid")
+            .iter()
+            .all(|id| id != "disabled" && id != "readonly")
+    );
+    assert!(validity(&harness, "#disabled").is_none());
+
+    let number = validity(&harness, "#number").unwrap();
+    assert!(number.step_mismatch && !number.range_underflow && !number.range_overflow);
+    assert!(validity(&harness, "#big").unwrap().range_overflow);
+    assert!(validity(&harness, "#bad").unwrap().bad_input);
+    assert!(validity(&harness, "#select").unwrap().value_missing);
+    // `maxlength` only applies to values edited by the user
+    assert!(validity(&harness, "#long").unwrap().valid());
+    // A checked radio button satisfies `required` for its whole group
+    assert!(validity(&harness, "#radio-a").unwrap().valid());
+}
+
+#[test]
+fn user_validity_follows_interaction() {
+    let (mut harness, _) = load_form(
+        r#"<html><body style="margin:0"><form>
+        <input id="name" required minlength="3" style="width:200px; height:20px;">
+        <input id="other" style="width:200px; height:20px;">
+        </form></body></html>"#,
+    );
+    assert_eq!(harness.ids("form :invalid"), ["name"]);
+    assert!(harness.ids(":user-invalid").is_empty());
+
+    // Editing doesn't show the control's validity until the user moves on
+    harness.click("#name");
+    harness.type_text("ab");
+    assert!(validity(&harness, "#name").unwrap().too_short);
+    assert!(harness.ids(":user-invalid").is_empty());
+
+    harness.press(Key::Tab);
+    assert_eq!(harness.ids(":user-invalid"), ["name"]);
+
+    harness.click("#name");
+    harness.press(Key::End);
+    harness.type_text("c");
+    assert_eq!(harness.ids(":user-valid"), ["name"]);
+    assert!(harness.ids(":invalid").is_empty());
+}
+
+#[test]
+fn invalid_forms_are_not_submitted() {
+    let (mut harness, navigations) = load_form(
+        r#"<html><body style="margin:0"><form action="http://example.com/submit">
+        <input id="first" name="first" style="width:200px; height:20px;">
+        <input id="name" name="name" required style="width:200px; height:20px;">
+        <input id="age" name="age" type="number" min="0" value="-1" style="width:200px; height:20px;">
+        <button id="submit" style="width:50px; height:20px;">Submit</button>
+        </form></body></html>"#,
+    );
+
+    let events = click_recorded(&mut harness, "#submit");
+    assert_eq!(
+        events.iter().filter(|name| *name == "invalid").count(),
+        2,
+        "{events:?}"
+    );
+    assert!(navigations.urls.lock().unwrap().is_empty());
+    // The first invalid control is focused, and every control now shows its validity
+    assert_eq!(harness.focused(), Some(harness.node("#name")));
+    assert_eq!(harness.ids(":user-invalid"), ["name", "age"]);
+
+    harness.type_text("Jo");
+    harness.click("#age");
+    harness.press(Key::Backspace);
+    harness.press(Key::Backspace);
+    harness.type_text("3");
+    harness.click("#submit");
+    assert_eq!(
+        *navigations.urls.lock().unwrap(),
+        ["http://example.com/submit?first=&name=Jo&age=3"]
+    );
+}
+
+#[test]
+fn novalidate_skips_validation() {
+    let (mut harness, navigations) = load_form(
+        r#"<html><body style="margin:0">
+        <form id="form" action="http://example.com/submit">
+        <input name="name" required style="width:200px; height:20px;">
+        <button id="submit" style="width:50px; height:20px;">Submit</button>
+        <button id="skip" formnovalidate style="width:50px; height:20px;">Skip</button>
+        </form></body></html>"#,
+    );
+
+    harness.click("#submit");
+    assert!(navigations.urls.lock().unwrap().is_empty());
+
+    harness.click("#skip");
+    assert_eq!(navigations.urls.lock().unwrap().len(), 1);
+
+    let form_id = harness.node("#form");
+    let name = QualName {
+        prefix: None,
+        ns: ns!(),
+        local: LocalName::from("novalidate"),
+    };
+    harness.base_mut().mutate().set_attribute(form_id, name, "");
+    harness.pump();
+    harness.click("#submit");
+    assert_eq!(navigations.urls.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn forms_and_fieldsets_reflect_their_controls() {
+    let (mut harness, _) = load_form(
+        r#"<html><body style="margin:0"><form id="form">
+        <fieldset id="required-fields">
+            <input id="name" required style="width:200px; height:20px;">
+        </fieldset>
+        <fieldset id="optional-fields"><input id="note"></fieldset>
+        </form></body></html>"#,
+    );
+    assert_eq!(harness.ids(":invalid"), ["form", "required-fields", "name"]);
+    assert_eq!(harness.ids("fieldset:valid"), ["optional-fields"]);
+
+    harness.click("#name");
+    harness.type_text("Jo");
+    assert!(harness.ids(":invalid").is_empty());
+    assert_eq!(
+        harness.ids("form:valid, fieldset:valid"),
+        ["form", "required-fields", "optional-fields"]
+    );
+}
+
+#[test]
+fn patterns_follow_mutations() {
+    let (mut harness, _) = load_form(
+        r#"<html><body><form>
+        <input id="code" value="abc">
+        </form></body></html>"#,
+    );
+    assert!(harness.ids("form :invalid").is_empty());
+
+    let code = harness.node("#code");
+    harness.base_mut().mutate().set_attribute(
+        code,
+        QualName::new(None, ns!(), LocalName::from("pattern")),
+        "[0-9]+",
+    );
+    harness.pump();
+    assert!(validity(&harness, "#code").unwrap().pattern_mismatch);
+    assert_eq!(harness.ids("form :invalid"), ["code"]);
+
+    harness
+        .base_mut()
+        .mutate()
+        .clear_attribute(code, QualName::new(None, ns!(), LocalName::from("pattern")));
+    harness.pump();
+    assert!(harness.ids("form :invalid").is_empty());
+}
+
+#[test]
+fn disabled_fieldsets_bar_their_controls() {
+    let (mut harness, _) = load_form(
+        r#"<html><body><form>
+        <fieldset id="fieldset" disabled>
+            <legend><input id="in-legend" required></legend>
+            <input id="disabled" required>
+        </fieldset>
+        </form></body></html>"#,
+    );
+    // The contents of the fieldset's first legend aren't disabled
+    assert!(validity(&harness, "#disabled").is_none());
+    assert!(validity(&harness, "#in-legend").unwrap().value_missing);
+    assert_eq!(harness.ids("form :invalid"), ["fieldset", "in-legend"]);
+
+    let fieldset = harness.node("#fieldset");
+    harness.base_mut().mutate().clear_attribute(
+        fieldset,
+        QualName::new(None, ns!(), LocalName::from("disabled")),
+    );
+    harness.pump();
+    assert_eq!(
+        harness.ids("form :invalid"),
+        ["fieldset", "in-legend", "disabled"]
+    );
+}
+
+#[test]
+fn radio_groups_follow_mutations() {
+    let (mut harness, _) = load_form(
+        r#"<html><body><form>
+        <input id="a" type="radio" name="group" required>
+        <input id="b" type="radio" name="group">
+        </form></body></html>"#,
+    );
+    // Every radio button in a required group is missing a value
+    assert_eq!(harness.ids("form :invalid"), ["a", "b"]);
+
+    let b = harness.node("#b");
+    harness.base_mut().mutate().set_attribute(
+        b,
+        QualName::new(None, ns!(), LocalName::from("checked")),
+        "",
+    );
+    harness.pump();
+    assert!(harness.ids("form :invalid").is_empty());
+
+    // Renaming the checked radio button moves it to another group
+    harness.base_mut().mutate().set_attribute(
+        b,
+        QualName::new(None, ns!(), LocalName::from("name")),
+        "other",
+    );
+    harness.pump();
+    assert_eq!(harness.ids("form :invalid"), ["a"]);
+
+    harness.base_mut().mutate().set_attribute(
+        b,
+        QualName::new(None, ns!(), LocalName::from("name")),
+        "group",
+    );
+    harness.pump();
+    assert!(harness.ids("form :invalid").is_empty());
+
+    // Removing the checked radio button leaves its group without a value
+    harness.base_mut().mutate().remove_and_drop_node(b);
+    harness.pump();
+    assert_eq!(harness.ids("form :invalid"), ["a"]);
+}
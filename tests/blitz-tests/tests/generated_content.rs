@@ -0,0 +1,174 @@
+//! Generated content. Every kind of `content` item (strings, counters,
+//! `attr()`, quotes and `url()` images) is materialised into the
+//! `::before`/`::after` pseudo-element's subtree, and kept up to date when the
+//! attributes it depends on change.
+
+use blitz_dom::node::{ImageData, NodeData, SpecialElementData};
+use blitz_dom::{DocumentConfig, ImageDecoding, LocalName, QualName, ns};
+use blitz_html::HtmlDocument;
+use blitz_test_harness::StaticNetProvider;
+use blitz_traits::shell::{ColorScheme, Viewport};
+use std::sync::Arc;
+
+/// A 1x1 GIF
+const GIF_1X1: &[u8] = &[
+    b'G', b'I', b'F', b'8', b'9', b'a', // Header
+    0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, // Logical screen descriptor
+    0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, // Global color table (red, blue)
+    0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // Image descriptor
+    0x02, 0x02, 0x44, 0x01, 0x00, // Red pixel
+    0x3B, // Trailer
+];
+
+fn load(html: &str) -> HtmlDocument {
+    let mut doc = HtmlDocument::from_html(
+        html,
+        DocumentConfig {
+            base_url: Some("http://example.com/".to_string()),
+            net_provider: Some(Arc::new(StaticNetProvider::new(GIF_1X1)) as _),
+            viewport: Some(Viewport::new(800, 600, 1.0, ColorScheme::Light)),
+            image_decoding: ImageDecoding::Inline,
+            ..Default::default()
+        },
+    );
+    doc.resolve(0.0);
+    doc
+}
+
+/// The children of the `::before` pseudo-element of the element with the given id, as
+/// strings (text nodes) or `<img src>` (images)
+fn before_content(doc: &HtmlDocument, id: &str) -> Vec<String> {
+    let node_id = doc.get_element_by_id(id).unwrap();
+    let before_id = doc
+        .get_node(node_id)
+        .unwrap()
+        .before()
+        .expect("no ::before");
+    doc.get_node(before_id)
+        .unwrap()
+        .children
+        .iter()
+        .map(|&child_id| {
+            let child = doc.get_node(child_id).unwrap();
+            match &child.data {
+                NodeData::Text(text) => text.content.clone(),
+                NodeData::Element(el) => {
+                    format!("<img {}>", el.attr(LocalName::from("src")).unwrap())
+                }
+                _ => panic!("unexpected pseudo-element child"),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn attr_and_strings() {
+    let doc = load(
+        r#"<html><head><style>
+        #attr::before { content: "(" attr(title) ")"; }
+        #missing::before { content: "[" attr(data-missing) "]"; }
+        </style></head><body>
+        <div id="attr" title="a title"></div>
+        <div id="missing"></div>
+        </body></html>"#,
+    );
+
+    assert_eq!(before_content(&doc, "attr"), ["(a title)"]);
+    assert_eq!(before_content(&doc, "missing"), ["[]"]);
+}
+
+#[test]
+fn attr_fallback_and_namespace() {
+    let doc = load(
+        r#"<html><head><style>
+        @namespace xlink url(http://www.w3.org/1999/xlink);
+        #fallback::before { content: attr(data-missing, "none"); }
+        #present::before { content: attr(title, "none"); }
+        #namespaced::before { content: attr(xlink|title, "none"); }
+        </style></head><body>
+        <div id="fallback"></div>
+        <div id="present" title="a title"></div>
+        <div id="namespaced" title="a title"></div>
+        </body></html>"#,
+    );
+
+    assert_eq!(before_content(&doc, "fallback"), ["none"]);
+    assert_eq!(before_content(&doc, "present"), ["a title"]);
+    // The `title` attribute isn't in the XLink namespace
+    assert_eq!(before_content(&doc, "namespaced"), ["none"]);
+}
+
+#[test]
+fn quotes_nest_across_the_tree() {
+    let doc = load(
+        r#"<html><head><style>
+        #quotes { quotes: "«" "»" "‹" "›"; }
+        #quotes::before, #inner::before { content: open-quote; }
+        #inner::after, #quotes::after { content: close-quote; }
+        #extra::before { content: close-quote "x"; }
+        #auto::before { content: open-quote "hi" close-quote; }
+        </style></head><body>
+        <div id="quotes"><span id="inner"></span></div>
+        <div id="extra"></div>
+        <div id="auto"></div>
+        </body></html>"#,
+    );
+
+    // Nested quotes use the next pair of quotes
+    assert_eq!(before_content(&doc, "quotes"), ["«"]);
+    assert_eq!(before_content(&doc, "inner"), ["‹"]);
+    // A close quote without a matching open quote generates nothing
+    assert_eq!(before_content(&doc, "extra"), ["x"]);
+    // `quotes: auto` uses curly quotes
+    assert_eq!(before_content(&doc, "auto"), ["\u{201c}hi\u{201d}"]);
+}
+
+#[test]
+fn url_images_are_replaced_elements() {
+    let doc = load(
+        r#"<html><head><style>
+        #icon::before { content: "a" url(icon.gif) "b"; }
+        </style></head><body><div id="icon"></div></body></html>"#,
+    );
+
+    assert_eq!(
+        before_content(&doc, "icon"),
+        ["a", "<img http://example.com/icon.gif>", "b"]
+    );
+
+    let node_id = doc.get_element_by_id("icon").unwrap();
+    let before_id = doc.get_node(node_id).unwrap().before().unwrap();
+    let image_id = doc.get_node(before_id).unwrap().children[1];
+    let image = doc.get_node(image_id).unwrap();
+    let element = image.element_data().unwrap();
+    assert!(matches!(
+        &element.special_data,
+        SpecialElementData::Image(image) if matches!(**image, ImageData::Raster(_))
+    ));
+    let size = image.final_layout().size;
+    assert_eq!((size.width, size.height), (1.0, 1.0));
+}
+
+#[test]
+fn content_updates_when_attributes_change() {
+    let mut doc = load(
+        r#"<html><head><style>
+        #attr::after { content: attr(data-count) " items"; }
+        </style></head><body><div id="attr" data-count="1"></div></body></html>"#,
+    );
+    let node_id = doc.get_element_by_id("attr").unwrap();
+    let after_text = |doc: &HtmlDocument| {
+        let after_id = doc.get_node(node_id).unwrap().after().unwrap();
+        doc.get_node(after_id).unwrap().text_content()
+    };
+    assert_eq!(after_text(&doc), "1 items");
+
+    let name = QualName {
+        prefix: None,
+        ns: ns!(),
+        local: LocalName::from("data-count"),
+    };
+    doc.mutate().set_attribute(node_id, name, "2");
+    doc.resolve(0.0);
+    assert_eq!(after_text(&doc), "2 items");
+}
@@ -0,0 +1,172 @@
+//! Image decoding. Raster images displayed by `<img>` elements are only decoded
+//! once they have been laid out, at the size they are painted at (accounting for
+//! `object-fit` and the viewport's scale), share their decoded pixels with other elements displaying the same
+//! image, and have their pixels evicted when they are out of view and the
+//! decoded pixels exceed the document's memory budget.
+
+use blitz_dom::node::{ImageData, RasterImageData, SpecialElementData};
+use blitz_dom::{DocumentConfig, ImageDecoding};
+use blitz_html::HtmlDocument;
+use blitz_test_harness::StaticNetProvider;
+use blitz_traits::shell::{ColorScheme, Viewport};
+use std::sync::Arc;
+
+/// A 64x32 GIF (a red pixel in the top left corner of a transparent image)
+const GIF_64X32: &[u8] = &[
+    b'G', b'I', b'F', b'8', b'9', b'a', // Header
+    0x40, 0x00, 0x20, 0x00, 0x80, 0x00, 0x00, // Logical screen descriptor
+    0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF, // Global color table (red, blue)
+    0x2C, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, // Image descriptor
+    0x02, 0x02, 0x44, 0x01, 0x00, // Red pixel
+    0x3B, // Trailer
+];
+
+fn load(html: &str, scale: f32, image_memory_budget: Option<usize>) -> HtmlDocument {
+    let mut doc = HtmlDocument::from_html(
+        html,
+        DocumentConfig {
+            base_url: Some("http://example.com/".to_string()),
+            net_provider: Some(Arc::new(StaticNetProvider::new(GIF_64X32)) as _),
+            viewport: Some(viewport(600, scale)),
+            image_decoding: ImageDecoding::Inline,
+            image_memory_budget,
+            ..Default::default()
+        },
+    );
+    doc.resolve(0.0);
+    doc
+}
+
+fn viewport(height: u32, scale: f32) -> Viewport {
+    let width = (800.0 * scale) as u32;
+    let height = (height as f32 * scale) as u32;
+    Viewport::new(width, height, scale, ColorScheme::Light)
+}
+
+/// The raster image displayed by the `<img>` element with the given id
+fn image(doc: &HtmlDocument, id: &str) -> RasterImageData {
+    let img = doc.get_element_by_id(id).unwrap();
+    let element = doc.get_node(img).unwrap().element_data().unwrap();
+    let SpecialElementData::Image(image) = &element.special_data else {
+        panic!("image not loaded");
+    };
+    let ImageData::Raster(raster) = &**image else {
+        panic!("expected a raster image");
+    };
+    raster.clone()
+}
+
+fn decoded_size(doc: &HtmlDocument, id: &str) -> (u32, u32) {
+    let image = image(doc, id);
+    assert_eq!((image.natural_width, image.natural_height), (64, 32));
+    (image.width, image.height)
+}
+
+#[test]
+fn images_decode_at_their_displayed_size() {
+    let html = r#"<html><body>
+        <img id="full" src="a.gif">
+        <img id="small" src="a.gif" style="width:16px;height:8px">
+        <img id="between" src="a.gif" style="width:20px;height:10px">
+    </body></html>"#;
+
+    let doc = load(html, 1.0, None);
+    assert_eq!(decoded_size(&doc, "full"), (64, 32));
+    assert_eq!(decoded_size(&doc, "small"), (16, 8));
+    // The decoded size is never smaller than the displayed size
+    assert_eq!(decoded_size(&doc, "between"), (32, 16));
+
+    // Downscaled images keep their natural size for layout
+    let img = doc.get_element_by_id("full").unwrap();
+    let size = doc.get_node(img).unwrap().final_layout().size;
+    assert_eq!((size.width, size.height), (64.0, 32.0));
+}
+
+#[test]
+fn decode_size_accounts_for_scale_and_object_fit() {
+    let html = r#"<html><body>
+        <img id="fill" src="a.gif" style="width:16px;height:8px">
+        <img id="contain" src="a.gif" style="width:16px;height:16px;object-fit:contain">
+        <img id="cover" src="a.gif" style="width:16px;height:16px;object-fit:cover">
+        <img id="none" src="a.gif" style="width:16px;height:16px;object-fit:none">
+    </body></html>"#;
+
+    let doc = load(html, 1.0, None);
+    assert_eq!(decoded_size(&doc, "fill"), (16, 8));
+    assert_eq!(decoded_size(&doc, "contain"), (16, 8));
+    assert_eq!(decoded_size(&doc, "cover"), (32, 16));
+    assert_eq!(decoded_size(&doc, "none"), (64, 32));
+
+    let doc = load(html, 2.0, None);
+    assert_eq!(decoded_size(&doc, "fill"), (32, 16));
+    assert_eq!(decoded_size(&doc, "contain"), (32, 16));
+    assert_eq!(decoded_size(&doc, "cover"), (64, 32));
+}
+
+#[test]
+fn images_with_the_same_url_share_decoded_pixels() {
+    let html = r#"<html><body>
+        <img id="first" src="a.gif" style="width:16px;height:8px">
+        <img id="second" src="a.gif" style="width:16px;height:8px">
+    </body></html>"#;
+
+    let doc = load(html, 1.0, None);
+    let first = image(&doc, "first");
+    let second = image(&doc, "second");
+    assert_eq!((first.width, first.height), (16, 8));
+    assert_eq!(first.data.id(), second.data.id());
+}
+
+const FAR_IMAGE: &str = r#"<html><body style="margin:0">
+    <img id="near" src="near.gif">
+    <div style="height:2000px"></div>
+    <img id="far" src="far.gif">
+</body></html>"#;
+
+#[test]
+fn out_of_view_images_are_evicted_over_budget() {
+    let mut doc = load(FAR_IMAGE, 1.0, Some(0));
+    assert!(!image(&doc, "near").is_evicted());
+    assert!(image(&doc, "far").is_evicted());
+
+    // Evicted images keep their natural size for layout
+    let far = doc.get_element_by_id("far").unwrap();
+    let size = doc.get_node(far).unwrap().final_layout().size;
+    assert_eq!((size.width, size.height), (64.0, 32.0));
+
+    // Images are decoded again when they come into view
+    doc.set_viewport(viewport(3000, 1.0));
+    doc.resolve(0.0);
+    assert!(!image(&doc, "far").is_evicted());
+    assert_eq!(decoded_size(&doc, "far"), (64, 32));
+}
+
+#[test]
+fn images_are_not_evicted_within_budget() {
+    let doc = load(FAR_IMAGE, 1.0, None);
+    assert!(!image(&doc, "near").is_evicted());
+    assert!(!image(&doc, "far").is_evicted());
+}
+
+#[test]
+fn background_images_sharing_an_img_url_are_decoded_in_full() {
+    let html = r#"<html><body>
+        <img id="small" src="a.gif" style="width:16px;height:8px">
+        <div id="background" style="width:64px;height:32px;background-image:url(a.gif)"></div>
+    </body></html>"#;
+
+    let mut doc = load(html, 1.0, None);
+    // Background layers waiting on the `<img>` element's fetch fetch the image again
+    doc.resolve(0.0);
+    assert_eq!(decoded_size(&doc, "small"), (16, 8));
+
+    let div = doc.get_element_by_id("background").unwrap();
+    let element = doc.get_node(div).unwrap().element_data().unwrap();
+    let Some(Some(layer)) = element.background_images.first() else {
+        panic!("background image not loaded");
+    };
+    let ImageData::Raster(raster) = &layer.image else {
+        panic!("expected a raster image");
+    };
+    assert_eq!((raster.width, raster.height), (64, 32));
+}
@@ -0,0 +1,139 @@
+//! Image source selection. `<img>` elements choose the best candidate from
+//! their `srcset` (with `sizes`) or from the `<source>` elements of a parent
+//! `<picture>` (honouring `media` and `type`) for the viewport's scale and
+//! size, and re-select when the viewport changes.
+
+use blitz_dom::{DocumentConfig, ImageDecoding, QualName, local_name, ns};
+use blitz_html::HtmlDocument;
+use blitz_test_harness::ManualNetProvider;
+use blitz_traits::net::Bytes;
+use blitz_traits::shell::{ColorScheme, Viewport};
+use std::sync::Arc;
+
+fn load(html: &str, width: u32, scale: f32) -> (HtmlDocument, Arc<ManualNetProvider>) {
+    let net = Arc::new(ManualNetProvider::default());
+    let doc = HtmlDocument::from_html(
+        html,
+        DocumentConfig {
+            base_url: Some("http://example.com/".to_string()),
+            net_provider: Some(Arc::clone(&net) as _),
+            viewport: Some(viewport(width, scale)),
+            image_decoding: ImageDecoding::Inline,
+            ..Default::default()
+        },
+    );
+    (doc, net)
+}
+
+fn viewport(width: u32, scale: f32) -> Viewport {
+    let physical_width = (width as f32 * scale) as u32;
+    Viewport::new(physical_width, 600, scale, ColorScheme::Light)
+}
+
+const DENSITIES: &str =
+    r#"<html><body><img id="img" src="a.png" srcset="a@2x.png 2x, a@3x.png 3x"></body></html>"#;
+
+#[test]
+fn density_descriptors_match_viewport_scale() {
+    let (_doc, net) = load(DENSITIES, 800, 1.0);
+    assert_eq!(net.take_requested_files(), vec!["a.png"]);
+
+    let (_doc, net) = load(DENSITIES, 800, 2.0);
+    assert_eq!(net.take_requested_files(), vec!["a@2x.png"]);
+
+    // The next largest candidate is used when there is no exact match...
+    let (_doc, net) = load(DENSITIES, 800, 1.5);
+    assert_eq!(net.take_requested_files(), vec!["a@2x.png"]);
+
+    // ...and the largest when every candidate is too small
+    let (_doc, net) = load(DENSITIES, 800, 4.0);
+    assert_eq!(net.take_requested_files(), vec!["a@3x.png"]);
+}
+
+#[test]
+fn width_descriptors_use_sizes() {
+    let html = r#"<html><body>
+        <img src="fallback.png"
+            srcset="small.png 400w, medium.png 800w, large.png 1600w"
+            sizes="(max-width: 500px) 100vw, 400px">
+    </body></html>"#;
+
+    // 400px wide at 1x needs a 400px image
+    let (_doc, net) = load(html, 800, 1.0);
+    assert_eq!(net.take_requested_files(), vec!["small.png"]);
+
+    // 400px wide at 2x needs an 800px image
+    let (_doc, net) = load(html, 800, 2.0);
+    assert_eq!(net.take_requested_files(), vec!["medium.png"]);
+
+    // 450px (100vw) wide at 2x needs a 900px image
+    let (_doc, net) = load(html, 450, 2.0);
+    assert_eq!(net.take_requested_files(), vec!["large.png"]);
+}
+
+#[test]
+fn picture_uses_first_matching_source() {
+    let html = r#"<html><body><picture>
+        <source srcset="wide.png" media="(min-width: 1000px)">
+        <source srcset="image.unknown" type="image/x-unknown">
+        <source srcset="narrow.png, narrow@2x.png 2x">
+        <img src="fallback.png">
+    </picture></body></html>"#;
+
+    let (_doc, net) = load(html, 1200, 1.0);
+    assert_eq!(net.take_requested_files(), vec!["wide.png"]);
+
+    let (_doc, net) = load(html, 800, 2.0);
+    assert_eq!(net.take_requested_files(), vec!["narrow@2x.png"]);
+}
+
+#[test]
+fn viewport_changes_reselect_the_image() {
+    let (mut doc, net) = load(DENSITIES, 800, 1.0);
+    assert_eq!(net.take_requested_files(), vec!["a.png"]);
+
+    doc.set_viewport(viewport(800, 2.0));
+    assert_eq!(net.take_requested_files(), vec!["a@2x.png"]);
+
+    // Resizing without changing the selected candidate doesn't fetch anything
+    doc.set_viewport(viewport(1000, 2.0));
+    assert!(net.take_requested_files().is_empty());
+}
+
+const WIDE_SVG: &[u8] =
+    br#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100"></svg>"#;
+
+fn load_wide_svg() -> HtmlDocument {
+    let (mut doc, net) = load(
+        r#"<html><body style="margin:0"><img id="img" srcset="wide.svg 2x"></body></html>"#,
+        800,
+        2.0,
+    );
+    let (url, handler) = net.take_last_request().unwrap();
+    handler.bytes(url, Bytes::from_static(WIDE_SVG));
+    doc.resolve(0.0);
+    doc
+}
+
+#[test]
+fn natural_size_is_scaled_by_density() {
+    let doc = load_wide_svg();
+
+    let img = doc.get_element_by_id("img").unwrap();
+    let size = doc.get_node(img).unwrap().final_layout().size;
+    assert_eq!((size.width, size.height), (100.0, 50.0));
+}
+
+#[test]
+fn removing_the_source_clears_the_image() {
+    let mut doc = load_wide_svg();
+    let img = doc.get_element_by_id("img").unwrap();
+    doc.mutate()
+        .clear_attribute(img, QualName::new(None, ns!(), local_name!("srcset")));
+    doc.resolve(0.0);
+
+    let node = doc.get_node(img).unwrap();
+    assert_eq!(node.element_data().unwrap().image_status, None);
+    assert!(node.element_data().unwrap().image_data().is_none());
+    assert_eq!(node.final_layout().size.width, 0.0);
+}
@@ -0,0 +1,155 @@
+//! The `placeholder` of text inputs and textareas is painted with the styles of
+//! the `::placeholder` pseudo-element while the input is empty (and not in the
+//! middle of an IME composition), clipped to the input like its value, and is
+//! restyled along with the input. It is also exposed as the input's accessible
+//! description.
+
+use accesskit::NodeId as AccessKitNodeId;
+use anyrender::render_to_buffer;
+use anyrender_vello_cpu::VelloCpuImageRenderer;
+use blitz_paint::paint_scene;
+use blitz_test_harness::{Harness, count_pixels};
+use blitz_traits::events::BlitzImeEvent;
+use keyboard_types::Key;
+
+const WIDTH: u32 = 300;
+const HEIGHT: u32 = 100;
+
+const RED: [u8; 3] = [255, 0, 0];
+
+fn load(placeholder_style: &str, input: &str) -> Harness {
+    let mut harness = Harness::from_html(&format!(
+        r#"<html><head><style>
+            ::placeholder {{ color: #ff0000; {placeholder_style} }}
+            input, textarea {{
+                display: block; height: 60px; padding: 0; border: none;
+                font-size: 40px; color: #0000ff; background: white;
+            }}
+        </style></head><body style="margin:0">{input}</body></html>"#
+    ));
+    harness.set_viewport_size(WIDTH, HEIGHT);
+    harness.pump();
+    harness
+}
+
+fn render(harness: &mut Harness) -> Vec<u8> {
+    let mut doc = harness.base_mut();
+    render_to_buffer::<VelloCpuImageRenderer, _>(
+        |scene| paint_scene(scene, &mut doc, 1.0, WIDTH, HEIGHT, 0, 0),
+        WIDTH,
+        HEIGHT,
+    )
+}
+
+#[test]
+fn placeholder_is_painted_while_empty() {
+    let mut harness = load(
+        "",
+        r#"<input id="search" placeholder="IIII" style="width:200px">"#,
+    );
+    assert!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED) > 0);
+
+    harness.click("#search");
+    harness.type_text("a");
+    assert_eq!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED), 0);
+
+    harness.press(Key::Backspace);
+    assert!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED) > 0);
+
+    // An IME composition hides it too
+    harness.ime(BlitzImeEvent::Preedit(String::from("か"), None));
+    assert_eq!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED), 0);
+    harness.ime(BlitzImeEvent::Preedit(String::new(), None));
+    assert!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED) > 0);
+}
+
+#[test]
+fn input_with_value_doesnt_show_placeholder() {
+    let mut harness = load(
+        "",
+        r#"<input placeholder="IIII" value="x" style="width:200px">"#,
+    );
+    assert_eq!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED), 0);
+}
+
+#[test]
+fn placeholder_is_styled_by_the_pseudo_element() {
+    let mut harness = load(
+        "opacity: 0",
+        r#"<input placeholder="IIII" style="width:200px">"#,
+    );
+    assert_eq!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED), 0);
+
+    // The font is inherited from the input
+    let mut harness = load(
+        "",
+        r#"<input id="input" placeholder="IIII" style="width:200px">"#,
+    );
+    let input_id = harness.node("#input");
+    let doc = harness.base();
+    let input = doc.get_node(input_id).unwrap();
+    let placeholder = input
+        .element_data()
+        .and_then(|el| el.text_input_data())
+        .and_then(|input| input.placeholder.as_ref())
+        .unwrap();
+    assert!(placeholder.layout.height() >= 40.0);
+    drop(doc);
+    assert!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED) > 0);
+}
+
+#[test]
+fn placeholder_is_restyled_with_the_input() {
+    let mut harness = load(
+        "",
+        r#"<style>input:focus::placeholder { opacity: 0 }</style>
+        <input id="search" placeholder="IIII" style="width:200px">"#,
+    );
+    assert!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED) > 0);
+
+    harness.click("#search");
+    assert_eq!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED), 0);
+
+    harness.base_mut().clear_focus();
+    harness.pump();
+    assert!(count_pixels(&render(&mut harness), WIDTH, 0..WIDTH, RED) > 0);
+}
+
+#[test]
+fn placeholder_is_clipped_to_the_input() {
+    let mut harness = load(
+        "",
+        r#"<input placeholder="IIIIIIIIIIIIIIIIIIII" style="width:50px">"#,
+    );
+    let buffer = render(&mut harness);
+    assert!(count_pixels(&buffer, WIDTH, 0..50, RED) > 0);
+    assert_eq!(count_pixels(&buffer, WIDTH, 50..WIDTH, RED), 0);
+}
+
+#[test]
+fn placeholder_is_the_accessible_description() {
+    let harness = Harness::from_html(
+        r#"<html><body>
+            <input id="labelled" aria-label="Find" placeholder="Search the docs">
+            <input id="unlabelled" placeholder="Search the docs">
+        </body></html>"#,
+    );
+    let tree = harness.base().build_accessibility_tree();
+    let node = |selector: &str| {
+        let id = AccessKitNodeId(harness.node(selector).as_u64());
+        tree.nodes
+            .iter()
+            .find(|(node_id, _)| *node_id == id)
+            .map(|(_, node)| node.clone())
+            .unwrap()
+    };
+
+    let labelled = node("#labelled");
+    assert_eq!(labelled.label(), Some("Find"));
+    assert_eq!(labelled.description(), Some("Search the docs"));
+
+    // The placeholder isn't used twice when it is the input's name
+    let unlabelled = node("#unlabelled");
+    assert_eq!(unlabelled.label(), Some("Search the docs"));
+    assert_eq!(unlabelled.description(), None);
+}
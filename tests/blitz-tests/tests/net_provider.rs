@@ -0,0 +1,243 @@
+//! blitz-net's HTTP behaviour against a local server: retries of transient
+//! failures (including failures while reading the body), timeouts, the
+//! redirect limit and the per-host concurrency cap.
+
+use blitz_net::{Provider, ProviderError, RetryPolicy};
+use blitz_traits::net::{Method, Request};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// How the server responds to a request
+enum Reply {
+    /// Send a complete response with the given status line, headers and body
+    Respond(&'static str, Vec<(&'static str, String)>, &'static str),
+    /// Send the headers of a response whose body is cut short
+    TruncatedBody,
+    /// Wait before responding with a 200
+    Delay(Duration),
+}
+
+/// A local HTTP server which answers every request with a [`Reply`] chosen by the index of
+/// the request, handling each connection on its own thread
+struct Server {
+    url: String,
+    requests: Arc<AtomicUsize>,
+    max_in_flight: Arc<AtomicUsize>,
+}
+
+impl Server {
+    fn start(reply: impl Fn(usize) -> Reply + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let reply = Arc::new(reply);
+
+        let server = Self {
+            url,
+            requests: requests.clone(),
+            max_in_flight: max_in_flight.clone(),
+        };
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let requests = requests.clone();
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                let reply = reply.clone();
+                std::thread::spawn(move || {
+                    if !read_request_head(&stream) {
+                        return;
+                    }
+                    let idx = requests.fetch_add(1, Ordering::SeqCst);
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    write_reply(stream, reply(idx));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        server
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+}
+
+/// Read a request's head (requests in these tests have no body). Returns `false` if the
+/// connection was closed first.
+fn read_request_head(stream: &TcpStream) -> bool {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) if line == "\r\n" => return true,
+            Ok(_) => {}
+        }
+    }
+}
+
+fn write_reply(mut stream: TcpStream, reply: Reply) {
+    let response = match reply {
+        Reply::Respond(status, headers, body) => {
+            let headers: String = headers
+                .iter()
+                .map(|(name, value)| format!("{name}: {value}\r\n"))
+                .collect();
+            format!(
+                "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        Reply::TruncatedBody => {
+            "HTTP/1.1 200 OK\r\nContent-Length: 100\r\nConnection: close\r\n\r\npartial".to_string()
+        }
+        Reply::Delay(delay) => {
+            std::thread::sleep(delay);
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string()
+        }
+    };
+    let _ = stream.write_all(response.as_bytes());
+    let _ = stream.flush();
+}
+
+fn ok() -> Reply {
+    Reply::Respond("200 OK", Vec::new(), "ok")
+}
+
+fn unavailable() -> Reply {
+    Reply::Respond("503 Service Unavailable", Vec::new(), "")
+}
+
+fn retries(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(10),
+    }
+}
+
+fn get(url: &str) -> Request {
+    Request::get(url.parse().unwrap())
+}
+
+#[tokio::test]
+async fn transient_statuses_are_retried() {
+    let server = Server::start(|idx| if idx < 2 { unavailable() } else { ok() });
+    let provider = Provider::builder().retry(retries(3)).build().unwrap();
+
+    let (_, body) = provider.fetch_async(get(&server.url)).await.unwrap();
+    assert_eq!(&body[..], b"ok");
+    assert_eq!(server.requests(), 3);
+}
+
+#[tokio::test]
+async fn retries_stop_at_the_limit() {
+    let server = Server::start(|_| unavailable());
+    let provider = Provider::builder().retry(retries(2)).build().unwrap();
+
+    let (response, _) = provider.fetch_response(get(&server.url)).await.unwrap();
+    assert_eq!(response.status.as_u16(), 503);
+    assert_eq!(server.requests(), 3);
+}
+
+#[tokio::test]
+async fn non_idempotent_requests_are_not_retried() {
+    let server = Server::start(|_| unavailable());
+    let provider = Provider::builder().retry(retries(3)).build().unwrap();
+
+    let mut request = get(&server.url);
+    request.method = Method::POST;
+    let (response, _) = provider.fetch_response(request).await.unwrap();
+    assert_eq!(response.status.as_u16(), 503);
+    assert_eq!(server.requests(), 1);
+}
+
+#[tokio::test]
+async fn retry_after_dates_are_honoured() {
+    let server = Server::start(|idx| match idx {
+        0 => Reply::Respond(
+            "429 Too Many Requests",
+            vec![("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT".to_string())],
+            "",
+        ),
+        _ => ok(),
+    });
+    let provider = Provider::builder().retry(retries(1)).build().unwrap();
+
+    let (_, body) = provider.fetch_async(get(&server.url)).await.unwrap();
+    assert_eq!(&body[..], b"ok");
+    assert_eq!(server.requests(), 2);
+}
+
+#[tokio::test]
+async fn failures_while_reading_the_body_are_retried() {
+    let server = Server::start(|idx| if idx == 0 { Reply::TruncatedBody } else { ok() });
+    let provider = Provider::builder().retry(retries(1)).build().unwrap();
+
+    let (_, body) = provider.fetch_async(get(&server.url)).await.unwrap();
+    assert_eq!(&body[..], b"ok");
+    assert_eq!(server.requests(), 2);
+}
+
+#[tokio::test]
+async fn slow_requests_time_out() {
+    let server = Server::start(|_| Reply::Delay(Duration::from_secs(2)));
+    let provider = Provider::builder()
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let result = provider.fetch_async(get(&server.url)).await;
+    assert!(
+        matches!(&result, Err(ProviderError::ReqwestError(e)) if e.is_timeout()),
+        "{result:?}"
+    );
+}
+
+#[tokio::test]
+async fn redirects_are_limited() {
+    let server =
+        Server::start(|_| Reply::Respond("302 Found", vec![("Location", "/next".to_string())], ""));
+    let provider = Provider::builder().max_redirects(2).build().unwrap();
+
+    let result = provider.fetch_async(get(&server.url)).await;
+    assert!(
+        matches!(&result, Err(ProviderError::ReqwestError(e)) if e.is_redirect()),
+        "{result:?}"
+    );
+    // The first request and the two redirects which were followed
+    assert_eq!(server.requests(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_requests_per_host_are_capped() {
+    let server = Server::start(|_| Reply::Delay(Duration::from_millis(50)));
+    let provider = Arc::new(
+        Provider::builder()
+            .per_host_max_concurrent(2)
+            .build()
+            .unwrap(),
+    );
+
+    let fetches: Vec<_> = (0..6)
+        .map(|_| {
+            let provider = provider.clone();
+            let url = server.url.clone();
+            tokio::spawn(async move { provider.fetch_async(get(&url)).await })
+        })
+        .collect();
+    for fetch in fetches {
+        fetch.await.unwrap().unwrap();
+    }
+
+    assert_eq!(server.requests(), 6);
+    assert_eq!(server.max_in_flight.load(Ordering::SeqCst), 2);
+}
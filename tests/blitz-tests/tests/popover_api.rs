@@ -0,0 +1,274 @@
+//! The popover API. Elements with a `popover` attribute are shown and hidden by
+//! `popovertarget` buttons (firing `beforetoggle` and `toggle` events), match
+//! `:popover-open` and are placed in the top layer while open. `auto` and
+//! `hint` popovers are light dismissed by pressing outside of them (even if the
+//! `pointerdown` event is cancelled) or pressing Escape, and popovers opened from
+//! within an open popover nest inside it. Only showing a popover can be cancelled.
+
+use blitz_dom::{Document, EventDriver, EventHandler, LocalName, QualName, ns};
+use blitz_test_harness::{Harness, key_press, mouse_pointer_event};
+use blitz_traits::events::{DomEvent, DomEventData, EventState, UiEvent};
+use blitz_traits::node_id::NodeId;
+use keyboard_types::Key;
+
+const PAGE: &str = r#"<html><head><style>
+    body { margin: 0; height: 400px; }
+    button { display: block; width: 80px; height: 20px; }
+    [popover] {
+        inset: auto; margin: 0; padding: 0; border: none;
+        left: 200px; width: 100px; height: 60px;
+    }
+    #menu { top: 0; }
+    #submenu { top: 100px; }
+    #tooltip { top: 200px; }
+    #notice { top: 300px; }
+</style></head><body>
+    <button id="toggle-menu" popovertarget="menu">Menu</button>
+    <button id="show-menu" popovertarget="menu" popovertargetaction="show">Show</button>
+    <button id="hide-menu" popovertarget="menu" popovertargetaction="hide">Hide</button>
+    <button id="outside">Outside</button>
+    <div id="menu" popover>
+        <button id="toggle-submenu" popovertarget="submenu">More</button>
+    </div>
+    <div id="submenu" popover="auto"><button id="item">Item</button></div>
+    <div id="tooltip" popover="hint">Tooltip</div>
+    <div id="notice" popover="manual">Notice</div>
+</body></html>"#;
+
+fn show(harness: &mut Harness, selector: &str) {
+    let popover_id = harness.node(selector);
+    harness.base_mut().show_popover(popover_id, None);
+    harness.pump();
+}
+
+/// Click the center of an element, returning the names of the events dispatched (including
+/// the `toggle` events queued by the document)
+fn click_recorded(harness: &mut Harness, selector: &str) -> Vec<String> {
+    let (x, y) = harness.center_of(selector);
+    let event = mouse_pointer_event(x, y);
+    let mut names = harness.dispatch_recorded([
+        UiEvent::PointerDown(event.clone()),
+        UiEvent::PointerUp(event),
+    ]);
+    names.extend(
+        harness
+            .base_mut()
+            .take_pending_events()
+            .iter()
+            .map(|event| event.name().to_string()),
+    );
+    harness.pump();
+    names
+}
+
+/// Cancels every `pointerdown` and `mousedown` event
+struct CancelPointerDown;
+
+impl EventHandler for CancelPointerDown {
+    fn handle_event(
+        &mut self,
+        _chain: &[NodeId],
+        event: &mut DomEvent,
+        _doc: &mut dyn Document,
+        event_state: &mut EventState,
+    ) {
+        if matches!(
+            event.data,
+            DomEventData::PointerDown(_) | DomEventData::MouseDown(_)
+        ) {
+            event_state.prevent_default();
+        }
+    }
+}
+
+#[test]
+fn popovertarget_toggles_the_popover() {
+    let mut harness = Harness::from_html(PAGE);
+    assert!(harness.ids(":popover-open").is_empty());
+    assert_eq!(harness.layout_rect("#menu").height, 0.0);
+
+    let names = click_recorded(&mut harness, "#toggle-menu");
+    let toggle_events: Vec<&str> = names
+        .iter()
+        .map(String::as_str)
+        .filter(|name| name.ends_with("toggle"))
+        .collect();
+    assert_eq!(toggle_events, ["beforetoggle", "toggle"]);
+
+    let menu = harness.node("#menu");
+    assert_eq!(harness.ids(":popover-open"), ["menu"]);
+    assert_eq!(harness.base().top_layer(), [menu]);
+    assert_eq!(harness.layout_rect("#menu").height, 60.0);
+    let (x, y) = harness.center_of("#menu");
+    assert_eq!(harness.hit_node(x, y), menu);
+
+    // Clicking the button again hides the popover (rather than light dismissing it and
+    // showing it again)
+    harness.click("#toggle-menu");
+    assert!(harness.ids(":popover-open").is_empty());
+    assert!(harness.base().top_layer().is_empty());
+}
+
+#[test]
+fn popovertargetaction_only_shows_or_hides() {
+    let mut harness = Harness::from_html(PAGE);
+
+    harness.click("#hide-menu");
+    assert!(harness.ids(":popover-open").is_empty());
+    harness.click("#show-menu");
+    assert_eq!(harness.ids(":popover-open"), ["menu"]);
+
+    // Pressing the buttons counts as pressing inside the popover they control, so it isn't
+    // light dismissed
+    harness.click("#show-menu");
+    assert_eq!(harness.ids(":popover-open"), ["menu"]);
+    harness.click("#hide-menu");
+    assert!(harness.ids(":popover-open").is_empty());
+}
+
+#[test]
+fn light_dismiss_closes_auto_popovers() {
+    let mut harness = Harness::from_html(PAGE);
+    show(&mut harness, "#menu");
+    show(&mut harness, "#notice");
+    assert_eq!(harness.ids(":popover-open"), ["menu", "notice"]);
+
+    // Pressing inside the popover keeps it open
+    harness.click("#menu");
+    assert_eq!(harness.ids(":popover-open"), ["menu", "notice"]);
+
+    // Pressing outside closes it, but `manual` popovers stay open
+    let names = click_recorded(&mut harness, "#outside");
+    assert!(names.contains(&"beforetoggle".to_string()), "{names:?}");
+    assert_eq!(harness.ids(":popover-open"), ["notice"]);
+}
+
+#[test]
+fn escape_closes_the_topmost_popover() {
+    let mut harness = Harness::from_html(PAGE);
+    harness.click("#toggle-menu");
+    harness.click("#toggle-submenu");
+    assert_eq!(harness.ids(":popover-open"), ["menu", "submenu"]);
+
+    harness.dispatch_recorded(key_press(Key::Escape));
+    harness.pump();
+    assert_eq!(harness.ids(":popover-open"), ["menu"]);
+
+    harness.dispatch_recorded(key_press(Key::Escape));
+    harness.pump();
+    assert!(harness.ids(":popover-open").is_empty());
+}
+
+#[test]
+fn nested_popovers_form_a_stack() {
+    let mut harness = Harness::from_html(PAGE);
+    harness.click("#toggle-menu");
+    harness.click("#toggle-submenu");
+    // The submenu was opened from inside the menu, so the menu stays open
+    assert_eq!(harness.ids(":popover-open"), ["menu", "submenu"]);
+    assert_eq!(
+        harness.base().top_layer(),
+        [harness.node("#menu"), harness.node("#submenu")]
+    );
+
+    // Pressing inside the submenu keeps both open
+    harness.click("#item");
+    assert_eq!(harness.ids(":popover-open"), ["menu", "submenu"]);
+
+    // Pressing inside the menu (below its button) closes the submenu above it
+    harness.click("#menu");
+    assert_eq!(harness.ids(":popover-open"), ["menu"]);
+
+    // Hiding the menu hides the submenu nested in it
+    harness.click("#toggle-submenu");
+    let menu = harness.node("#menu");
+    harness.base_mut().hide_popover(menu);
+    harness.pump();
+    assert!(harness.ids(":popover-open").is_empty());
+
+    // Showing the submenu from outside of the menu closes the menu
+    harness.click("#toggle-menu");
+    show(&mut harness, "#submenu");
+    assert_eq!(harness.ids(":popover-open"), ["submenu"]);
+}
+
+#[test]
+fn hint_popovers_leave_auto_popovers_open() {
+    let mut harness = Harness::from_html(PAGE);
+    show(&mut harness, "#menu");
+    show(&mut harness, "#tooltip");
+    assert_eq!(harness.ids(":popover-open"), ["menu", "tooltip"]);
+
+    // Escape closes the hint first
+    harness.dispatch_recorded(key_press(Key::Escape));
+    harness.pump();
+    assert_eq!(harness.ids(":popover-open"), ["menu"]);
+}
+
+#[test]
+fn show_and_hide_queue_toggle_events() {
+    let mut harness = Harness::from_html(PAGE);
+    let notice = harness.node("#notice");
+
+    harness.base_mut().show_popover(notice, None);
+    assert!(harness.base().is_popover_open(notice));
+    let names: Vec<String> = harness
+        .base_mut()
+        .take_pending_events()
+        .iter()
+        .map(|event| event.name().to_string())
+        .collect();
+    assert_eq!(names, ["toggle"]);
+
+    // Removing the `popover` attribute hides the popover
+    harness.base_mut().mutate().clear_attribute(
+        notice,
+        QualName::new(None, ns!(), LocalName::from("popover")),
+    );
+    harness.pump();
+    assert!(!harness.base().is_popover_open(notice));
+    assert!(harness.ids(":popover-open").is_empty());
+}
+
+#[test]
+fn light_dismiss_ignores_cancelled_pointerdown() {
+    let mut harness = Harness::from_html(PAGE);
+    show(&mut harness, "#menu");
+
+    let (x, y) = harness.center_of("#outside");
+    let event = mouse_pointer_event(x, y);
+    let mut doc = harness.base_mut();
+    let mut driver = EventDriver::new(&mut *doc, CancelPointerDown);
+    driver.handle_ui_event(UiEvent::PointerDown(event.clone()));
+    driver.handle_ui_event(UiEvent::PointerUp(event));
+    drop(doc);
+    harness.pump();
+    assert!(harness.ids(":popover-open").is_empty());
+}
+
+#[test]
+fn hidden_popovers_queue_uncancelable_beforetoggle_events() {
+    let mut harness = Harness::from_html(PAGE);
+    show(&mut harness, "#menu");
+    harness.base_mut().take_pending_events();
+
+    // Showing the submenu from outside of the menu hides the menu, announcing it with a
+    // `beforetoggle` event which can't be cancelled
+    let submenu = harness.node("#submenu");
+    harness.base_mut().show_popover(submenu, None);
+    let events: Vec<(NodeId, String, bool)> = harness
+        .base_mut()
+        .take_pending_events()
+        .iter()
+        .map(|event| (event.target, event.name().to_string(), event.cancelable))
+        .collect();
+    let menu = harness.node("#menu");
+    assert_eq!(
+        events,
+        [
+            (menu, "beforetoggle".to_string(), false),
+            (menu, "toggle".to_string(), false),
+            (submenu, "toggle".to_string(), false),
+        ]
+    );
+}
@@ -0,0 +1,160 @@
+//! Failed resource loads. A `NetHandler` is told about HTTP error statuses and
+//! network failures: `<img>` elements are marked as broken, `load`/`error`
+//! events are queued for the resource's element, and stylesheets served with a
+//! non-CSS `Content-Type` are ignored.
+
+use blitz_dom::node::Status;
+use blitz_dom::{DocumentConfig, NodeId};
+use blitz_html::HtmlDocument;
+use blitz_test_harness::ManualNetProvider;
+use blitz_traits::events::DomEventData;
+use blitz_traits::net::{
+    Bytes, HeaderMap, NetError, Response, StatusCode,
+    http::header::{CONTENT_TYPE, HeaderValue},
+};
+use std::sync::Arc;
+use style::properties::generated::longhands::visibility::computed_value::T as Visibility;
+use style::values::computed::Display;
+
+fn load(html: &str) -> (HtmlDocument, Arc<ManualNetProvider>) {
+    let net = Arc::new(ManualNetProvider::default());
+    let doc = HtmlDocument::from_html(
+        html,
+        DocumentConfig {
+            base_url: Some("http://example.com/".to_string()),
+            net_provider: Some(Arc::clone(&net) as _),
+            ..Default::default()
+        },
+    );
+    (doc, net)
+}
+
+fn image_status(doc: &HtmlDocument, id: &str) -> Option<Status> {
+    let node_id = doc.get_element_by_id(id).unwrap();
+    doc.get_node(node_id)
+        .unwrap()
+        .element_data()
+        .unwrap()
+        .image_status
+}
+
+/// The names and targets of the events queued by the document
+fn pending_events(doc: &mut HtmlDocument) -> Vec<(&'static str, NodeId)> {
+    doc.take_pending_events()
+        .into_iter()
+        .map(|event| (event.name(), event.target))
+        .collect()
+}
+
+#[test]
+fn image_http_error_marks_image_as_broken() {
+    let (mut doc, net) = load(r#"<html><body><img id="img" src="missing.png"></body></html>"#);
+    assert_eq!(image_status(&doc, "img"), Some(Status::Loading));
+
+    let (url, handler) = net.take_request("missing.png");
+    handler.response(
+        Response::new(url).status(StatusCode::NOT_FOUND),
+        Bytes::from_static(b"Not Found"),
+    );
+    doc.resolve(0.0);
+
+    let img = doc.get_element_by_id("img").unwrap();
+    assert_eq!(image_status(&doc, "img"), Some(Status::Error));
+    assert_eq!(pending_events(&mut doc), vec![("error", img)]);
+}
+
+#[test]
+fn image_network_failure_fires_error_on_every_waiting_image() {
+    let (mut doc, net) = load(
+        r#"<html><body>
+            <img id="a" src="offline.png">
+            <img id="b" src="offline.png">
+        </body></html>"#,
+    );
+
+    // Both images share a single request
+    let (url, handler) = net.take_request("offline.png");
+    assert_eq!(net.pending_requests(), 0);
+    handler.failed(url, NetError::Fetch(String::from("dns error")));
+    doc.resolve(0.0);
+
+    assert_eq!(image_status(&doc, "a"), Some(Status::Error));
+    assert_eq!(image_status(&doc, "b"), Some(Status::Error));
+    let events = pending_events(&mut doc);
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|(name, _)| *name == "error"));
+}
+
+#[test]
+fn aborted_requests_are_not_reported() {
+    let (mut doc, net) = load(r#"<html><body><img id="img" src="slow.png"></body></html>"#);
+
+    let (url, handler) = net.take_request("slow.png");
+    handler.failed(url, NetError::Aborted);
+    doc.resolve(0.0);
+
+    assert_eq!(image_status(&doc, "img"), Some(Status::Loading));
+    assert!(pending_events(&mut doc).is_empty());
+}
+
+#[test]
+fn stylesheet_with_wrong_content_type_is_ignored() {
+    let (mut doc, net) = load(
+        r#"<html><head>
+            <link id="plain" rel="stylesheet" href="plain.css">
+            <link id="css" rel="stylesheet" href="real.css">
+        </head><body><p id="p">text</p></body></html>"#,
+    );
+
+    let (url, handler) = net.take_request("plain.css");
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+    handler.response(
+        Response::new(url).headers(headers),
+        Bytes::from_static(b"#p { display: none }"),
+    );
+
+    let (url, handler) = net.take_request("real.css");
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/css; charset=utf-8"),
+    );
+    handler.response(
+        Response::new(url).headers(headers),
+        Bytes::from_static(b"#p { visibility: hidden }"),
+    );
+    doc.resolve(0.0);
+    assert!(!doc.has_pending_critical_resources());
+
+    let plain = doc.get_element_by_id("plain").unwrap();
+    let css = doc.get_element_by_id("css").unwrap();
+    let events = pending_events(&mut doc);
+    assert_eq!(events.len(), 2, "{events:?}");
+    assert!(events.contains(&("error", plain)));
+    assert!(events.contains(&("load", css)));
+
+    let p = doc.get_element_by_id("p").unwrap();
+    let styles = doc.get_node(p).unwrap().primary_styles().unwrap();
+    assert_ne!(styles.get_box().clone_display(), Display::None);
+    assert_eq!(
+        styles.get_inherited_box().clone_visibility(),
+        Visibility::Hidden
+    );
+}
+
+#[test]
+fn load_event_data_carries_resource_url() {
+    let (mut doc, net) = load(r#"<html><head><link rel="stylesheet" href="a.css"></head></html>"#);
+
+    let (url, handler) = net.take_request("a.css");
+    handler.bytes(url.clone(), Bytes::from_static(b"p { color: red }"));
+    doc.resolve(0.0);
+
+    let events = doc.take_pending_events();
+    assert_eq!(events.len(), 1);
+    let DomEventData::Load(data) = &events[0].data else {
+        panic!("expected a load event");
+    };
+    assert_eq!(data.url, url);
+}
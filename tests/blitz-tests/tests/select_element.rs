@@ -0,0 +1,186 @@
+//! The `<select>` element. Selectedness follows the options' `selected`
+//! attributes until the user picks an option, either by clicking an option in
+//! the drop-down listbox or by keyboard navigation. User changes fire `input`
+//! and `change` events and are reflected in `:checked`.
+
+use blitz_dom::{NodeId, QualName, local_name, ns};
+use blitz_test_harness::{Harness, key_press, mouse_pointer_event};
+use blitz_traits::events::UiEvent;
+use keyboard_types::Key;
+
+const DROP_DOWN: &str = r#"<html><body style="margin:0">
+    <select id="fruit" name="fruit" style="width:120px;">
+        <option value="a">Apple</option>
+        <option value="b" selected>Banana</option>
+        <option value="c" disabled>Cherry</option>
+        <option value="d">Date</option>
+    </select>
+</body></html>"#;
+
+fn select_value(harness: &Harness, selector: &str) -> String {
+    let node_id = harness.node(selector);
+    harness.base().get_node(node_id).unwrap().select_value()
+}
+
+fn open_select(harness: &Harness) -> Option<NodeId> {
+    harness.base().open_select_node_id()
+}
+
+#[test]
+fn selected_attribute_determines_initial_selection() {
+    let harness = Harness::from_html(DROP_DOWN);
+    assert_eq!(select_value(&harness, "#fruit"), "b");
+    assert_eq!(
+        harness.query("option:checked"),
+        harness.query("option[value=b]")
+    );
+
+    // Without a `selected` option, a drop-down select selects its first enabled option
+    let harness = Harness::from_html(
+        r#"<html><body>
+            <select id="s"><option disabled>x</option><option>y</option></select>
+        </body></html>"#,
+    );
+    assert_eq!(select_value(&harness, "#s"), "y");
+
+    // ...but a multiple select selects nothing
+    let harness = Harness::from_html(
+        r#"<html><body>
+            <select id="s" multiple><option>x</option><option>y</option></select>
+        </body></html>"#,
+    );
+    assert_eq!(select_value(&harness, "#s"), "");
+}
+
+#[test]
+fn clicking_option_in_listbox_selects_it() {
+    let mut harness = Harness::from_html(DROP_DOWN);
+    let select = harness.node("#fruit");
+
+    harness.click("#fruit");
+    assert_eq!(harness.focused(), Some(select));
+    assert_eq!(open_select(&harness), Some(select), "open after click");
+
+    // Click the "Date" row of the listbox
+    let date = harness.node("option[value=d]");
+    let row = harness
+        .base()
+        .select_listbox_rows(select)
+        .into_iter()
+        .find(|(option_id, _)| *option_id == date)
+        .map(|(_, row)| row.center())
+        .unwrap();
+    let (x, y) = (row.x as f32, row.y as f32);
+    let names = harness.dispatch_recorded([
+        UiEvent::PointerDown(mouse_pointer_event(x, y)),
+        UiEvent::PointerUp(mouse_pointer_event(x, y)),
+    ]);
+    harness.pump();
+
+    assert!(names.contains(&"input".to_string()), "{names:?}");
+    assert!(names.contains(&"change".to_string()), "{names:?}");
+    assert_eq!(
+        open_select(&harness),
+        None,
+        "closed after picking an option"
+    );
+    assert_eq!(select_value(&harness, "#fruit"), "d");
+    assert_eq!(harness.query("option:checked"), Some(date));
+}
+
+#[test]
+fn clicking_outside_closes_listbox() {
+    let mut harness = Harness::from_html(DROP_DOWN);
+    harness.click("#fruit");
+    assert!(open_select(&harness).is_some());
+
+    harness.click_at(350.0, 350.0);
+    assert_eq!(open_select(&harness), None);
+    assert_eq!(select_value(&harness, "#fruit"), "b", "selection unchanged");
+}
+
+#[test]
+fn arrow_keys_skip_disabled_options() {
+    let mut harness = Harness::from_html(DROP_DOWN);
+    harness.click("#fruit");
+    harness.press(Key::Escape);
+    assert_eq!(open_select(&harness), None);
+
+    // "Cherry" is disabled, so ArrowDown moves from "Banana" straight to "Date"
+    let names = harness.dispatch_recorded(key_press(Key::ArrowDown));
+    assert!(names.contains(&"change".to_string()), "{names:?}");
+    assert_eq!(select_value(&harness, "#fruit"), "d");
+
+    harness.press(Key::Home);
+    assert_eq!(select_value(&harness, "#fruit"), "a");
+    harness.press(Key::End);
+    assert_eq!(select_value(&harness, "#fruit"), "d");
+}
+
+#[test]
+fn open_listbox_commits_highlighted_option_on_enter() {
+    let mut harness = Harness::from_html(DROP_DOWN);
+    harness.click("#fruit");
+    harness.press(Key::ArrowUp);
+
+    // Navigating an open listbox only moves the highlight
+    assert_eq!(select_value(&harness, "#fruit"), "b");
+
+    harness.press(Key::Enter);
+    assert_eq!(open_select(&harness), None);
+    assert_eq!(select_value(&harness, "#fruit"), "a");
+}
+
+#[test]
+fn typeahead_selects_matching_option() {
+    let mut harness = Harness::from_html(DROP_DOWN);
+    harness.click("#fruit");
+    harness.press(Key::Escape);
+
+    harness.type_text("da");
+    assert_eq!(select_value(&harness, "#fruit"), "d");
+}
+
+#[test]
+fn hovering_listbox_moves_highlight() {
+    let mut harness = Harness::from_html(DROP_DOWN);
+    let select = harness.node("#fruit");
+    harness.click("#fruit");
+
+    // Hover the "Apple" row, then commit the highlighted option
+    let apple = harness.node("option[value=a]");
+    let row = harness
+        .base()
+        .select_listbox_rows(select)
+        .into_iter()
+        .find(|(option_id, _)| *option_id == apple)
+        .map(|(_, row)| row.center())
+        .unwrap();
+    harness.move_mouse_to(row.x as f32, row.y as f32);
+    assert_eq!(
+        select_value(&harness, "#fruit"),
+        "b",
+        "hover only highlights"
+    );
+
+    harness.press(Key::Enter);
+    assert_eq!(select_value(&harness, "#fruit"), "a");
+}
+
+#[test]
+fn selected_attribute_changes_update_checked() {
+    let mut harness = Harness::from_html(DROP_DOWN);
+    let apple = harness.node("option[value=a]");
+    let banana = harness.node("option[value=b]");
+    let selected = || QualName::new(None, ns!(), local_name!("selected"));
+
+    {
+        let mut doc = harness.base_mut();
+        let mut mutator = doc.mutate();
+        mutator.clear_attribute(banana, selected());
+        mutator.set_attribute(apple, selected(), "");
+    }
+    harness.pump();
+    assert_eq!(harness.query_all("option:checked"), [apple]);
+    assert_eq!(select_value(&harness, "#fruit"), "a");
+}
@@ -0,0 +1,128 @@
+//! `position: sticky` and `position: fixed`. Sticky boxes stay within the
+//! scrollport of their nearest scroll container (but never leave their
+//! containing block) and fixed boxes stay anchored to the viewport as it
+//! scrolls. Hit-testing agrees with where the boxes are painted.
+
+use blitz_dom::{Point, ScrollBehavior};
+use blitz_test_harness::Harness;
+
+const STICKY_HEADERS: &str = r#"<html><body style="margin:0">
+    <div id="scroller" style="width:200px; height:100px; overflow-y:scroll;">
+        <section id="first" style="height:180px; padding-top:20px;">
+            <div id="header" style="position:sticky; top:5px; height:20px;">Header</div>
+        </section>
+        <section id="second" style="height:400px;">
+            <div id="other" style="position:sticky; top:0; height:20px;">Other</div>
+        </section>
+    </div>
+</body></html>"#;
+
+fn scroll_to(harness: &mut Harness, selector: &str, y: f64) {
+    let node_id = harness.node(selector);
+    harness
+        .base_mut()
+        .scroll_to(node_id, 0.0, y, ScrollBehavior::Instant);
+    harness.pump();
+}
+
+#[test]
+fn sticky_box_sticks_to_scrollport() {
+    let mut harness = Harness::from_html(STICKY_HEADERS);
+    assert_eq!(harness.layout_rect("#header").y, 20.0);
+
+    // Until it comes within `top: 5px` of the scrollport's top edge it scrolls normally...
+    scroll_to(&mut harness, "#scroller", 10.0);
+    assert_eq!(harness.layout_rect("#header").y, 10.0);
+
+    // ...and after that it sticks there
+    scroll_to(&mut harness, "#scroller", 60.0);
+    assert_eq!(harness.layout_rect("#header").y, 5.0);
+
+    let header = harness.node("#header");
+    assert_eq!(harness.base().element_from_point(50.0, 10.0), Some(header));
+}
+
+#[test]
+fn sticky_box_stays_within_containing_block() {
+    let mut harness = Harness::from_html(STICKY_HEADERS);
+
+    // The first section's bottom edge is 10px below the top of the scrollport,
+    // so its header is pushed up by its containing block.
+    scroll_to(&mut harness, "#scroller", 190.0);
+    assert_eq!(harness.layout_rect("#header").y, -10.0);
+    assert_eq!(harness.layout_rect("#other").y, 10.0);
+
+    scroll_to(&mut harness, "#scroller", 250.0);
+    assert_eq!(harness.layout_rect("#other").y, 0.0);
+    let other = harness.node("#other");
+    assert_eq!(harness.base().element_from_point(50.0, 10.0), Some(other));
+}
+
+#[test]
+fn sticky_insets_do_not_offset_layout() {
+    let harness = Harness::from_html(
+        r#"<html><body style="margin:0">
+            <div id="sticky" style="position:sticky; top:50px; left:30px; height:20px;"></div>
+            <div style="height:500px;"></div>
+        </body></html>"#,
+    );
+    // `left` has no effect as the box already fills its containing block's width
+    let rect = harness.layout_rect("#sticky");
+    assert_eq!((rect.x, rect.y), (0.0, 50.0));
+
+    let node_id = harness.node("#sticky");
+    let location = harness
+        .base()
+        .get_node(node_id)
+        .unwrap()
+        .final_layout()
+        .location;
+    assert_eq!((location.x, location.y), (0.0, 0.0));
+}
+
+#[test]
+fn fixed_box_is_anchored_to_viewport() {
+    let mut harness = Harness::from_html(
+        r#"<html><body style="margin:0">
+            <div id="fixed" style="position:fixed; top:10px; left:0; width:50px; height:50px;"></div>
+            <div style="height:2000px;"></div>
+        </body></html>"#,
+    );
+    assert_eq!(harness.layout_rect("#fixed").y, 10.0);
+
+    harness
+        .base_mut()
+        .set_viewport_scroll(Point { x: 0.0, y: 500.0 });
+    harness.pump();
+
+    // In page coordinates the box moves down with the viewport
+    assert_eq!(harness.layout_rect("#fixed").y, 510.0);
+    let fixed = harness.node("#fixed");
+    assert_eq!(harness.hit_node(20.0, 530.0), fixed);
+    assert_ne!(harness.hit(20.0, 30.0).map(|hit| hit.node_id), Some(fixed));
+}
+
+#[test]
+fn fixed_box_ignores_positioned_ancestors() {
+    let mut harness = Harness::from_html(
+        r#"<html><body style="margin:0">
+            <div style="position:relative; margin:100px 0 0 50px; width:200px; height:100px;">
+                <div id="fixed" style="position:fixed; top:10px; right:20px; width:25%; height:50%;"></div>
+            </div>
+            <div style="height:2000px;"></div>
+        </body></html>"#,
+    );
+
+    // The viewport (800x600) is the containing block, not the relatively positioned ancestor
+    let rect = harness.layout_rect("#fixed");
+    assert_eq!((rect.width, rect.height), (200.0, 300.0));
+    assert_eq!((rect.x, rect.y), (580.0, 10.0));
+
+    harness
+        .base_mut()
+        .set_viewport_scroll(Point { x: 0.0, y: 500.0 });
+    harness.pump();
+    assert_eq!(harness.layout_rect("#fixed").y, 510.0);
+    let fixed = harness.node("#fixed");
+    assert_eq!(harness.hit_node(700.0, 600.0), fixed);
+}
@@ -0,0 +1,86 @@
+//! Incremental HTML parsing with `StreamingHtmlDocument`. The document can be
+//! inspected (and rendered) between chunks, chunks may split tags and
+//! multi-byte characters, and the character encoding is taken from the
+//! `Content-Type` header, a byte order mark or a `<meta charset>`.
+
+use blitz_dom::{Document, DocumentConfig};
+use blitz_html::StreamingHtmlDocument;
+
+fn text_of(doc: &StreamingHtmlDocument, id: &str) -> Option<String> {
+    let doc = doc.inner();
+    let node_id = doc.get_element_by_id(id)?;
+    Some(doc.get_node(node_id).unwrap().text_content())
+}
+
+#[test]
+fn nodes_are_appended_as_chunks_arrive() {
+    let mut doc = StreamingHtmlDocument::with_content_type(
+        DocumentConfig::default(),
+        Some("text/html; charset=utf-8"),
+    );
+
+    doc.write(b"<!DOCTYPE html><html><body><p id=\"first\">Hello</p><p id=\"sec");
+    assert_eq!(text_of(&doc, "first").as_deref(), Some("Hello"));
+    assert_eq!(text_of(&doc, "second"), None);
+
+    // A first frame can be produced from the partial document
+    doc.inner_mut().resolve(0.0);
+
+    doc.write(b"ond\">Wor");
+    doc.write(b"ld</p></body></html>");
+    doc.finish();
+    assert!(doc.is_finished());
+    assert_eq!(text_of(&doc, "second").as_deref(), Some("World"));
+}
+
+#[test]
+fn multi_byte_characters_split_across_chunks() {
+    let mut doc = StreamingHtmlDocument::with_content_type(
+        DocumentConfig::default(),
+        Some("text/html; charset=utf-8"),
+    );
+    let html = "<p id=\"p\">caf\u{e9} \u{1F600}</p>".as_bytes();
+    for byte in html {
+        doc.write(std::slice::from_ref(byte));
+    }
+    doc.finish();
+    assert_eq!(text_of(&doc, "p").as_deref(), Some("caf\u{e9} \u{1F600}"));
+}
+
+#[test]
+fn encoding_is_sniffed_from_meta_charset() {
+    let mut doc = StreamingHtmlDocument::new(DocumentConfig::default());
+    // "café" in windows-1252
+    doc.write(b"<html><head><meta charset=\"windows-1252\"></head><body><p id=\"p\">caf\xe9</p>");
+    doc.finish();
+    assert_eq!(text_of(&doc, "p").as_deref(), Some("caf\u{e9}"));
+}
+
+#[test]
+fn byte_order_mark_overrides_content_type() {
+    let mut doc = StreamingHtmlDocument::with_content_type(
+        DocumentConfig::default(),
+        Some("text/html; charset=windows-1252"),
+    );
+    doc.write(b"\xef\xbb\xbf<p id=\"p\">caf\xc3\xa9</p>");
+    doc.finish();
+    assert_eq!(text_of(&doc, "p").as_deref(), Some("caf\u{e9}"));
+}
+
+#[test]
+fn chunks_sent_from_another_thread_are_parsed_on_poll() {
+    let mut doc =
+        StreamingHtmlDocument::with_content_type(DocumentConfig::default(), Some("text/html"));
+    let sender = doc.sender();
+    std::thread::spawn(move || {
+        sender.send(&b"<div id=\"a\">streamed</div>"[..]);
+        sender.finish();
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(text_of(&doc, "a"), None);
+    assert!(doc.poll(None));
+    assert!(doc.is_finished());
+    assert_eq!(text_of(&doc, "a").as_deref(), Some("streamed"));
+}
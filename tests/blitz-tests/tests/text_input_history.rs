@@ -0,0 +1,239 @@
+//! Text input undo/redo. Typing is coalesced into undo steps, IME compositions
+//! are undone as a whole, and setting the value programmatically resets the
+//! history. Every edit (typing, deleting, undo and redo) fires a cancelable
+//! `beforeinput` event with its input type, followed by an `input` event.
+
+use blitz_dom::{Document, EventDriver, EventHandler, LocalName, QualName, ns};
+use blitz_test_harness::{Harness, key_event};
+use blitz_traits::events::{BlitzImeEvent, DomEvent, DomEventData, EventState, UiEvent};
+use blitz_traits::node_id::NodeId;
+use keyboard_types::{Key, KeyState, Modifiers};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[cfg(target_os = "macos")]
+const ACTION: Modifiers = Modifiers::SUPER;
+#[cfg(not(target_os = "macos"))]
+const ACTION: Modifiers = Modifiers::CONTROL;
+
+const INPUT: &str = r#"<html><body style="margin:0">
+    <input id="text" type="text" style="width:200px; height:20px;">
+</body></html>"#;
+
+fn focused_input() -> Harness {
+    let mut harness = Harness::from_html(INPUT);
+    harness.click("#text");
+    harness
+}
+
+fn value(harness: &Harness) -> String {
+    let node_id = harness.node("#text");
+    let doc = harness.base();
+    let element = doc.get_node(node_id).unwrap().element_data().unwrap();
+    element
+        .text_input_data()
+        .unwrap()
+        .editor
+        .raw_text()
+        .to_string()
+}
+
+fn undo(harness: &mut Harness) {
+    harness.press_with(Key::Character("z".into()), ACTION);
+}
+
+fn redo(harness: &mut Harness) {
+    harness.press_with(Key::Character("Z".into()), ACTION | Modifiers::SHIFT);
+}
+
+/// Records `beforeinput` (with their input types) and `input` events, optionally
+/// cancelling the `beforeinput` events
+#[derive(Clone, Default)]
+struct InputRecorder {
+    events: Rc<RefCell<Vec<String>>>,
+    cancel_before_input: bool,
+}
+
+impl EventHandler for InputRecorder {
+    fn handle_event(
+        &mut self,
+        _chain: &[NodeId],
+        event: &mut DomEvent,
+        _doc: &mut dyn Document,
+        event_state: &mut EventState,
+    ) {
+        match &event.data {
+            DomEventData::BeforeInput(data) => {
+                let input_type = data.input_type.as_str();
+                self.events
+                    .borrow_mut()
+                    .push(format!("beforeinput {input_type}"));
+                if self.cancel_before_input {
+                    event_state.prevent_default();
+                }
+            }
+            DomEventData::Input(_) => self.events.borrow_mut().push(String::from("input")),
+            _ => {}
+        }
+    }
+}
+
+/// Press `key` with `modifiers`, returning the recorded input events
+fn press_recorded(
+    harness: &mut Harness,
+    key: impl Into<Key>,
+    modifiers: Modifiers,
+    cancel_before_input: bool,
+) -> Vec<String> {
+    let recorder = InputRecorder {
+        cancel_before_input,
+        ..Default::default()
+    };
+    let events = recorder.events.clone();
+    let mut doc = harness.base_mut();
+    let mut driver = EventDriver::new(&mut *doc, recorder);
+    let key = key.into();
+    driver.handle_ui_event(UiEvent::KeyDown(key_event(
+        key.clone(),
+        KeyState::Pressed,
+        modifiers,
+    )));
+    driver.handle_ui_event(UiEvent::KeyUp(key_event(
+        key,
+        KeyState::Released,
+        modifiers,
+    )));
+    drop(doc);
+    harness.pump();
+    events.take()
+}
+
+#[test]
+fn typing_is_undone_in_one_step() {
+    let mut harness = focused_input();
+    harness.type_text("hello");
+    assert_eq!(value(&harness), "hello");
+
+    undo(&mut harness);
+    assert_eq!(value(&harness), "");
+    redo(&mut harness);
+    assert_eq!(value(&harness), "hello");
+
+    // Moving the caret starts a new undo step
+    harness.press(Key::Home);
+    harness.type_text(">");
+    harness.press(Key::End);
+    harness.press(Key::Backspace);
+    harness.press(Key::Backspace);
+    assert_eq!(value(&harness), ">hel");
+
+    undo(&mut harness);
+    assert_eq!(value(&harness), ">hello");
+    undo(&mut harness);
+    assert_eq!(value(&harness), "hello");
+    undo(&mut harness);
+    assert_eq!(value(&harness), "");
+}
+
+#[test]
+fn ime_composition_is_one_step() {
+    let mut harness = focused_input();
+    harness.type_text("a ");
+    harness.ime(BlitzImeEvent::Preedit("n".into(), Some((1, 1))));
+    harness.ime(BlitzImeEvent::Preedit("ni".into(), Some((2, 2))));
+    harness.ime(BlitzImeEvent::Preedit(String::new(), None));
+    harness.ime(BlitzImeEvent::Commit("你".into()));
+    assert_eq!(value(&harness), "a 你");
+
+    undo(&mut harness);
+    assert_eq!(value(&harness), "a ");
+    undo(&mut harness);
+    assert_eq!(value(&harness), "");
+}
+
+#[test]
+fn setting_value_resets_history() {
+    let mut harness = focused_input();
+    harness.type_text("typed");
+
+    let node_id = harness.node("#text");
+    let name = QualName {
+        prefix: None,
+        ns: ns!(),
+        local: LocalName::from("value"),
+    };
+    harness
+        .base_mut()
+        .mutate()
+        .set_attribute(node_id, name, "set by script");
+    harness.pump();
+    assert_eq!(value(&harness), "set by script");
+
+    undo(&mut harness);
+    assert_eq!(value(&harness), "set by script");
+}
+
+fn character(c: &str) -> Key {
+    Key::Character(c.into())
+}
+
+#[test]
+fn edits_fire_beforeinput() {
+    let mut harness = focused_input();
+
+    let events = press_recorded(&mut harness, character("a"), Modifiers::empty(), false);
+    assert_eq!(events, ["beforeinput insertText", "input"]);
+    let events = press_recorded(&mut harness, character("b"), Modifiers::empty(), false);
+    assert_eq!(events, ["beforeinput insertText", "input"]);
+    assert_eq!(value(&harness), "ab");
+
+    let events = press_recorded(&mut harness, Key::Backspace, Modifiers::empty(), false);
+    assert_eq!(events, ["beforeinput deleteContentBackward", "input"]);
+    assert_eq!(value(&harness), "a");
+
+    harness.press(Key::Home);
+    let events = press_recorded(&mut harness, Key::Delete, Modifiers::empty(), false);
+    assert_eq!(events, ["beforeinput deleteContentForward", "input"]);
+    assert_eq!(value(&harness), "");
+}
+
+#[test]
+fn cancelling_beforeinput_prevents_edits() {
+    let mut harness = focused_input();
+    harness.type_text("abc");
+
+    let events = press_recorded(&mut harness, character("d"), Modifiers::empty(), true);
+    assert_eq!(events, ["beforeinput insertText"]);
+    let events = press_recorded(&mut harness, Key::Backspace, Modifiers::empty(), true);
+    assert_eq!(events, ["beforeinput deleteContentBackward"]);
+    assert_eq!(value(&harness), "abc");
+}
+
+#[test]
+fn history_events_have_input_types() {
+    let mut harness = focused_input();
+    harness.type_text("a");
+
+    let events = press_recorded(&mut harness, character("z"), ACTION, false);
+    assert_eq!(events, ["beforeinput historyUndo", "input"]);
+    assert_eq!(value(&harness), "");
+
+    let redo = character("Z");
+    let events = press_recorded(&mut harness, redo.clone(), ACTION | Modifiers::SHIFT, false);
+    assert_eq!(events, ["beforeinput historyRedo", "input"]);
+    assert_eq!(value(&harness), "a");
+
+    // Nothing to redo, so no events
+    let events = press_recorded(&mut harness, redo, ACTION | Modifiers::SHIFT, false);
+    assert!(events.is_empty(), "{events:?}");
+}
+
+#[test]
+fn cancelling_beforeinput_prevents_undo() {
+    let mut harness = focused_input();
+    harness.type_text("abc");
+
+    let events = press_recorded(&mut harness, character("z"), ACTION, true);
+    assert_eq!(events, ["beforeinput historyUndo"]);
+    assert_eq!(value(&harness), "abc");
+}
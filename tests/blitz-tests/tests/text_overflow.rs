@@ -0,0 +1,118 @@
+//! `text-overflow` and `-webkit-line-clamp`. Lines which overflow a block with
+//! clipped overflow are truncated with an ellipsis (or a custom string), and
+//! line clamping hides the lines after the limit and ellipsizes the last one.
+//! Truncation only affects painting: the text content, selection and
+//! hit-testing still see the original text.
+
+use blitz_dom::node::TextTruncation;
+use blitz_test_harness::Harness;
+
+const LONG_TEXT: &str = "The quick brown fox jumps over the lazy dog again and again";
+const LINE_CLAMP: &str =
+    "display:-webkit-box; -webkit-box-orient:vertical; -webkit-line-clamp:2; overflow:hidden;";
+
+fn truncation(harness: &Harness, selector: &str) -> TextTruncation {
+    let node_id = harness.node(selector);
+    let doc = harness.base();
+    doc.get_node(node_id)
+        .unwrap()
+        .element_data()
+        .unwrap()
+        .inline_layout_data
+        .as_ref()
+        .unwrap()
+        .truncation
+        .clone()
+}
+
+fn long_text(style: &str) -> Harness {
+    Harness::from_html(&format!(
+        r#"<html><body style="margin:0; font-size:20px; line-height:20px;">
+            <div id="text" style="width:100px; {style}">{LONG_TEXT}</div>
+        </body></html>"#
+    ))
+}
+
+#[test]
+fn overflowing_line_is_ellipsized() {
+    let harness = long_text("white-space:nowrap; overflow:hidden; text-overflow:ellipsis;");
+    let truncation = truncation(&harness, "#text");
+    assert_eq!(truncation.visible_lines, None);
+    assert_eq!(truncation.lines.len(), 1);
+    assert!(truncation.ellipsis.is_some());
+
+    let line = truncation.lines[0];
+    assert_eq!(line.line_index, 0);
+    assert!(line.clip_x > 0.0 && line.clip_x < 100.0, "{line:?}");
+}
+
+#[test]
+fn text_overflow_requires_clipped_overflow() {
+    let harness = long_text("white-space:nowrap; text-overflow:ellipsis;");
+    assert!(truncation(&harness, "#text").lines.is_empty());
+
+    let harness = long_text("white-space:nowrap; overflow:hidden; text-overflow:clip;");
+    assert!(truncation(&harness, "#text").lines.is_empty());
+}
+
+#[test]
+fn custom_string_leaves_less_room_for_text() {
+    let ellipsis = long_text("white-space:nowrap; overflow:hidden; text-overflow:ellipsis;");
+    let custom = long_text(r#"white-space:nowrap; overflow:hidden; text-overflow:"[more]";"#);
+    let ellipsis = truncation(&ellipsis, "#text").lines[0];
+    let custom = truncation(&custom, "#text").lines[0];
+    assert!(custom.clip_x < ellipsis.clip_x);
+}
+
+#[test]
+fn line_clamp_hides_lines_and_ellipsizes_the_last() {
+    let harness = long_text(LINE_CLAMP);
+    let truncation = truncation(&harness, "#text");
+    assert_eq!(truncation.visible_lines, Some(2));
+    assert_eq!(truncation.lines.len(), 1);
+    assert_eq!(truncation.lines[0].line_index, 1);
+
+    // Only the visible lines contribute to the height of the block
+    assert_eq!(harness.layout_rect("#text").height, 40.0);
+}
+
+#[test]
+fn line_clamp_requires_a_vertical_webkit_box() {
+    let harness = long_text("-webkit-line-clamp:2; overflow:hidden;");
+    assert_eq!(truncation(&harness, "#text").visible_lines, None);
+
+    let harness = long_text(
+        "display:-webkit-box; -webkit-box-orient:horizontal; -webkit-line-clamp:2; overflow:hidden;",
+    );
+    assert_eq!(truncation(&harness, "#text").visible_lines, None);
+}
+
+#[test]
+fn line_clamp_has_no_effect_on_short_text() {
+    let harness = Harness::from_html(&format!(
+        r#"<html><body style="margin:0; font-size:20px; line-height:20px;">
+            <div id="text" style="{LINE_CLAMP}">Short</div>
+        </body></html>"#
+    ));
+    let truncation = truncation(&harness, "#text");
+    assert_eq!(truncation.visible_lines, None);
+    assert!(truncation.lines.is_empty());
+}
+
+#[test]
+fn truncated_text_is_selected_in_full() {
+    let mut harness = long_text("white-space:nowrap; overflow:hidden; text-overflow:ellipsis;");
+    let node_id = harness.node("#text");
+    assert_eq!(
+        harness.base().get_node(node_id).unwrap().text_content(),
+        LONG_TEXT
+    );
+
+    harness
+        .base_mut()
+        .set_text_selection(node_id, 0, node_id, LONG_TEXT.len());
+    assert_eq!(
+        harness.base().get_selected_text().as_deref(),
+        Some(LONG_TEXT)
+    );
+}
@@ -0,0 +1,100 @@
+//! `text-shadow` is painted beneath the text of inline layouts and text
+//! inputs, once per listed shadow, offset and in the shadow's colour (with
+//! `currentColor` resolved against the element's colour).
+
+use anyrender::render_to_buffer;
+use anyrender_vello_cpu::VelloCpuImageRenderer;
+use blitz_dom::DocumentConfig;
+use blitz_html::{HtmlDocument, HtmlProvider};
+use blitz_paint::paint_scene;
+use blitz_test_harness::count_pixels;
+use blitz_traits::shell::{ColorScheme, Viewport};
+use std::sync::Arc;
+
+const WIDTH: u32 = 300;
+const HEIGHT: u32 = 100;
+
+fn render(html: &str) -> Vec<u8> {
+    let mut doc = HtmlDocument::from_html(
+        html,
+        DocumentConfig {
+            viewport: Some(Viewport::new(WIDTH, HEIGHT, 1.0, ColorScheme::Light)),
+            html_parser_provider: Some(Arc::new(HtmlProvider) as _),
+            ..Default::default()
+        },
+    );
+    doc.resolve(0.0);
+    render_to_buffer::<VelloCpuImageRenderer, _>(
+        |scene| paint_scene(scene, &mut doc, 1.0, WIDTH, HEIGHT, 0, 0),
+        WIDTH,
+        HEIGHT,
+    )
+}
+
+const RED: [u8; 3] = [255, 0, 0];
+const GREEN: [u8; 3] = [0, 255, 0];
+const BLUE: [u8; 3] = [0, 0, 255];
+
+#[test]
+fn shadow_is_painted_at_offset() {
+    // The text itself is confined to the left third; its shadow is in the right two thirds
+    let html = |shadow: &str| {
+        format!(
+            r#"<html><body style="margin:0; font-size:60px; line-height:1;">
+                <span style="color:#0000ff; text-shadow:{shadow};">II</span>
+            </body></html>"#
+        )
+    };
+
+    let buffer = render(&html("none"));
+    assert!(count_pixels(&buffer, WIDTH, 0..100, BLUE) > 0);
+    assert_eq!(count_pixels(&buffer, WIDTH, 100..WIDTH, RED), 0);
+
+    let buffer = render(&html("150px 0 #ff0000"));
+    assert!(count_pixels(&buffer, WIDTH, 0..100, BLUE) > 0);
+    assert!(count_pixels(&buffer, WIDTH, 100..WIDTH, RED) > 0);
+}
+
+#[test]
+fn every_shadow_in_the_list_is_painted() {
+    let buffer = render(
+        r#"<html><body style="margin:0; font-size:60px; line-height:1;">
+            <div style="color:#0000ff; text-shadow:100px 0 #ff0000, 200px 0 #00ff00;">II</div>
+        </body></html>"#,
+    );
+    assert!(count_pixels(&buffer, WIDTH, 100..200, RED) > 0);
+    assert!(count_pixels(&buffer, WIDTH, 200..WIDTH, GREEN) > 0);
+}
+
+#[test]
+fn shadows_are_painted_beneath_the_text_of_every_line() {
+    // The second line's shadow lands exactly on the first line's glyphs
+    let buffer = render(
+        r#"<html><body style="margin:0; font-size:60px; line-height:1;">
+            <span style="color:#0000ff;">II</span><br>
+            <span style="color:#00ff00; text-shadow:0 -60px #ff0000;">II</span>
+        </body></html>"#,
+    );
+    assert!(count_pixels(&buffer, WIDTH, 0..WIDTH, BLUE) > 0);
+}
+
+#[test]
+fn shadow_color_defaults_to_current_color() {
+    let buffer = render(
+        r#"<html><body style="margin:0; font-size:60px; line-height:1; color:#00ff00;">
+            <div style="text-shadow:150px 0 2px;">II</div>
+        </body></html>"#,
+    );
+    assert!(count_pixels(&buffer, WIDTH, 100..WIDTH, GREEN) > 0);
+}
+
+#[test]
+fn text_inputs_paint_shadows() {
+    let buffer = render(
+        r#"<html><body style="margin:0;">
+            <input value="II" style="width:300px; height:90px; border:0; padding:0; font-size:60px;
+                color:#0000ff; background:transparent; text-shadow:150px 0 #ff0000;">
+        </body></html>"#,
+    );
+    assert!(count_pixels(&buffer, WIDTH, 100..WIDTH, RED) > 0);
+}
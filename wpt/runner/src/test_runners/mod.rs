@@ -1,6 +1,6 @@
 use std::{fs, sync::Arc, time::Instant};
 
-use blitz_dom::{BaseDocument, DocumentConfig};
+use blitz_dom::{BaseDocument, DocumentConfig, ImageDecoding};
 use blitz_html::HtmlDocument;
 use log::{debug, warn};
 
@@ -190,6 +190,7 @@ fn parse_and_resolve_document(
             font_ctx: Some(ctx.font_ctx.clone()),
             net_provider: Some(Arc::clone(&ctx.net_provider) as _),
             navigation_provider: Some(Arc::clone(&ctx.navigation_provider)),
+            image_decoding: ImageDecoding::Inline,
             ..Default::default()
         },
     );